@@ -0,0 +1,64 @@
+//! Route matching benchmark
+//!
+//! Builds a router with 500 parameterized routes, as a large API consolidated into
+//! one function would have, and times how long matching a request takes.
+//!
+//! Run with `cargo bench --bench router`.
+
+use http::Method;
+use serverless_rs::{
+    handler_fn, BoxFuture, Context, Request, Response, Result, Router, RouterBuilder,
+};
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+const RESOURCES: usize = 100;
+const ITERATIONS: u32 = 20_000;
+
+fn ok(_req: Request, _ctx: &Context) -> BoxFuture<'_, Result<Response>> {
+    Box::pin(async { Ok(Response::new()) })
+}
+
+/// Five routes per resource: 500 in total
+fn build_router() -> impl Router {
+    (0..RESOURCES)
+        .fold(RouterBuilder::new(), |builder, i| {
+            builder
+                .route(Method::GET, format!("/api/r{}/{{id}}", i), handler_fn(ok))
+                .route(Method::PUT, format!("/api/r{}/{{id}}", i), handler_fn(ok))
+                .route(
+                    Method::GET,
+                    format!("/api/r{}/{{id}}/items", i),
+                    handler_fn(ok),
+                )
+                .route(
+                    Method::GET,
+                    format!("/api/r{}/{{id}}/items/{{item}}", i),
+                    handler_fn(ok),
+                )
+                .route(Method::GET, format!("/api/r{}/files/*", i), handler_fn(ok))
+        })
+        .build()
+}
+
+fn time(router: &impl Router, label: &str, path: &str) {
+    let ctx = Context::new();
+    let req = Request::new().with_method(Method::GET).with_path(path);
+    let route = || futures::executor::block_on(router.route(black_box(req.clone()), &ctx));
+    assert_eq!(route().unwrap().status(), 200, "{}", path);
+
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        black_box(route().unwrap());
+    }
+    let per_request: Duration = start.elapsed() / ITERATIONS;
+    println!("{:<28} {:>10.2?} per request", label, per_request);
+}
+
+fn main() {
+    let router = build_router();
+    println!("500 routes, {} iterations each", ITERATIONS);
+    time(&router, "first resource", "/api/r0/42");
+    time(&router, "last resource", "/api/r99/42/items/7");
+    time(&router, "wildcard", "/api/r50/files/a/b/c.txt");
+}
@@ -0,0 +1,60 @@
+//! Invocation overhead benchmark
+//!
+//! Times a no-op `#[serverless]` function called through its local adapter, which
+//! runs it on the runtime the adapter shares across invocations, against building a
+//! current-thread runtime for every call. Calls made from inside another runtime,
+//! which drive the shared runtime from a scoped thread, are timed too.
+//!
+//! Run with `cargo bench --bench runtime`.
+
+use serverless_rs::{serverless, Context, Request, Response};
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+const ITERATIONS: u32 = 20_000;
+
+#[serverless]
+pub async fn noop(_req: Request, _ctx: &Context) -> serverless_rs::Result<Response> {
+    Ok(Response::new())
+}
+
+fn time(label: &str, mut call: impl FnMut()) {
+    call();
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        call();
+    }
+    let per_call: Duration = start.elapsed() / ITERATIONS;
+    println!("{:<28} {:>10.2?} per call", label, per_call);
+}
+
+fn main() {
+    let ctx = Context::new();
+    let req = Request::new();
+    println!("{} iterations each", ITERATIONS);
+
+    time("shared runtime", || {
+        black_box(noop::local_server::handle_request(black_box(req.clone()), &ctx).unwrap());
+    });
+
+    time("runtime per call", || {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        black_box(
+            runtime
+                .block_on(noop::noop(black_box(req.clone()), &ctx))
+                .unwrap(),
+        );
+    });
+
+    let outer = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap();
+    outer.block_on(async {
+        time("inside another runtime", || {
+            black_box(noop::local_server::handle_request(black_box(req.clone()), &ctx).unwrap());
+        });
+    });
+}
@@ -73,7 +73,7 @@ async fn create_user(_req: Request, ctx: &Context) -> Result<Response> {
 // Simple main function for local execution
 fn main() {
     // Parse command line arguments
-    let (info_requested, _format) = serverless_rs::parse_info_args();
+    let (info_requested, _format, _output) = serverless_rs::parse_info_args();
 
     // If --info flag was provided, display example function metadata and exit
     if info_requested {
@@ -106,7 +106,10 @@ fn main() {
             .add_route(route2)
             .add_metadata("version", "1.0");
 
-        serverless_rs::display_info(&metadata);
+        if let Err(err) = serverless_rs::display_info(&metadata) {
+            eprintln!("Error: {}", err);
+            std::process::exit(1);
+        }
         return;
     }
 
@@ -2,7 +2,7 @@
 Simple example of using the serverless.rs framework.
 */
 
-use serverless_rs::{Context, Request, Response, Result};
+use serverless_rs::prelude::*;
 
 // Simple serverless function without using macros
 async fn hello_world(_req: Request, ctx: &Context) -> Result<Response> {
@@ -7,7 +7,30 @@
 fn pass_tests() {
     let t = trybuild::TestCases::new();
 
-    t.pass("tests/01-basic-handler.rs");
-    t.pass("tests/02-with-route.rs");
-    t.pass("tests/03-with-requirements.rs");
+    t.pass("tests/ui/01-basic-handler.rs");
+    t.pass("tests/ui/02-with-route.rs");
+    t.pass("tests/ui/03-with-requirements.rs");
+    t.pass("tests/ui/04-with-resource-shortcuts.rs");
+    t.pass("tests/ui/05-invoke.rs");
+    t.pass("tests/ui/06-routes.rs");
+    t.pass("tests/ui/07-registry.rs");
+    t.pass("tests/ui/08-route-requirements.rs");
+    t.pass("tests/ui/09-runtime.rs");
+    t.pass("tests/ui/10-route-params.rs");
+    t.pass("tests/ui/11-triggers.rs");
+    t.pass("tests/ui/12-into-response.rs");
+    t.pass("tests/ui/13-extractors.rs");
+    t.pass("tests/ui/15-custom-method.rs");
+    t.pass("tests/ui/17-platforms-only.rs");
+}
+
+#[test]
+fn compile_fail_tests() {
+    let t = trybuild::TestCases::new();
+
+    t.compile_fail("tests/ui/14-invalid-method.rs");
+    t.compile_fail("tests/ui/16-strict-requirements.rs");
+    t.compile_fail("tests/ui/18-unknown-platform.rs");
+    t.compile_fail("tests/ui/19-unknown-requirements-platform.rs");
+    t.compile_fail("tests/ui/20-strict-profile.rs");
 }
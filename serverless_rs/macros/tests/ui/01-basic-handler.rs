@@ -8,12 +8,12 @@ pub async fn handler(req: Request, ctx: &Context) -> Result<Response> {
     Ok(Response::text("Hello, world!"))
 }
 
-async fn main() {
+fn main() {
     // Now the module `basic_handler` generated by the macro is in scope
     let info = basic_handler::function_info();
     let ctx = Context::new();
     let req = Request::new();
-    let result = basic_handler::handler(req, &ctx).await;
+    let result = futures::executor::block_on(basic_handler::handler(req, &ctx));
     assert_eq!(info.name, "basic_handler");
     assert_eq!(
         info.description,
@@ -1,6 +1,6 @@
 //! Test for a serverless function with #[requirements] attribute
 
-use serverless_rs::{Context, Request, Response, Result};
+use serverless_rs::{Context, Platform, Request, Response, Result};
 use serverless_rs_macros::{requirements, serverless};
 
 #[requirements(
@@ -22,8 +22,8 @@ fn main() {
     let reqs = handler_with_requirements::requirements();
 
     // Check that requirements were correctly processed
-    assert!(reqs.platforms.contains(&"aws".to_string()));
-    assert!(reqs.platforms.contains(&"cloudflare".to_string()));
+    assert!(reqs.platforms.contains(&Platform::Aws));
+    assert!(reqs.platforms.contains(&Platform::Cloudflare));
 
     // Check environment variables
     assert!(reqs.environment.contains(&"DATABASE_URL".to_string()));
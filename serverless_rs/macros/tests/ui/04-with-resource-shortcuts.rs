@@ -0,0 +1,45 @@
+//! Test for a serverless function with resource shortcut arguments
+
+use serverless_rs::{Context, Platform, Request, Response, Result};
+use serverless_rs_macros::{requirements, serverless};
+
+#[serverless(platforms(aws), timeout = "30s", memory = "256MB", concurrency = 10)]
+async fn shortcut_handler(_req: Request, _ctx: &Context) -> Result<Response> {
+    Ok(Response::text("Hello, world!"))
+}
+
+#[requirements(recommend(memory = "512MB"), require(cpu = "1x"))]
+#[serverless(timeout = "30s", memory = "256MB")]
+async fn overridden_handler(_req: Request, _ctx: &Context) -> Result<Response> {
+    Ok(Response::text("Hello, world!"))
+}
+
+// Strict mode compares sizes and durations by value
+#[serverless]
+#[requirements(
+    strict,
+    require(memory = "1GB", timeout = "1m"),
+    recommend(memory = "1024MB", timeout = "60s")
+)]
+async fn strict_handler(_req: Request, _ctx: &Context) -> Result<Response> {
+    Ok(Response::text("Hello, world!"))
+}
+
+fn main() {
+    // Shortcut arguments become recommended resources
+    assert!(shortcut_handler::has_requirements());
+    let info = shortcut_handler::function_info();
+    let resources = &info.resources;
+    assert_eq!(resources.get_recommended("timeout").unwrap().value, "30s");
+    assert_eq!(resources.get_recommended("memory").unwrap().value, "256MB");
+    assert_eq!(resources.get_recommended("concurrency").unwrap().value, "10");
+    assert_eq!(resources.platforms, vec![Platform::Aws]);
+
+    // A stacked #[requirements] takes precedence over shortcut arguments
+    let reqs = overridden_handler::requirements();
+    assert_eq!(reqs.get_recommended("timeout").unwrap().value, "30s");
+    assert_eq!(reqs.get_recommended("memory").unwrap().value, "512MB");
+    assert_eq!(reqs.get_required("cpu").unwrap().value, "1x");
+
+    assert!(strict_handler::requirements().lint().is_empty());
+}
@@ -0,0 +1,22 @@
+//! Test for the --invoke entry point generated by the #[serverless] macro
+
+use serverless_rs::{Context, Request, Response, Result};
+use serverless_rs_macros::serverless;
+
+#[serverless]
+pub async fn invoked_handler(req: Request, _ctx: &Context) -> Result<Response> {
+    Ok(Response::text(req.path().unwrap_or_default()))
+}
+
+fn main() {
+    // Without the --invoke flag the generated entry point does nothing
+    assert!(invoked_handler::handle_invoke().is_none());
+
+    // The handler can be driven through the invocation helpers directly
+    let result = serverless_rs::invoke_event(
+        |req: Request, ctx: &Context| serverless_rs::block_on(invoked_handler::invoked_handler(req, ctx)),
+        "unknown",
+        serverless_rs::Value::Null,
+    );
+    assert!(result.is_err());
+}
@@ -0,0 +1,36 @@
+//! Test for composing #[route] functions into one router with routes!
+
+use serverless_rs::{routes, Context, Request, Response, Result, Router};
+use serverless_rs_macros::{route, serverless};
+
+#[route(GET, "/users/{id}")]
+#[serverless]
+pub async fn get_user(req: Request, _ctx: &Context) -> Result<Response> {
+    Ok(Response::text(format!(
+        "user {}",
+        req.path_param("id").cloned().unwrap_or_default()
+    )))
+}
+
+#[route(POST, "/users")]
+#[serverless]
+pub async fn create_user(_req: Request, _ctx: &Context) -> Result<Response> {
+    Ok(Response::text("created").with_status(201))
+}
+
+fn main() {
+    let router = routes![get_user, create_user].build();
+    let ctx = Context::new();
+
+    let req = Request::new().with_method_str("GET").with_path("/users/7");
+    let response = serverless_rs::block_on(router.route(req, &ctx)).unwrap();
+    assert_eq!(response.body(), b"user 7");
+
+    let req = Request::new().with_method_str("POST").with_path("/users");
+    let response = serverless_rs::block_on(router.route(req, &ctx)).unwrap();
+    assert_eq!(response.status(), 201);
+
+    let req = Request::new().with_method_str("DELETE").with_path("/users");
+    let response = serverless_rs::block_on(router.route(req, &ctx)).unwrap();
+    assert_eq!(response.status(), 404);
+}
@@ -0,0 +1,29 @@
+//! Test for the function registry populated by the #[serverless] macro
+
+use serverless_rs::{Context, Request, Response, Result};
+use serverless_rs_macros::serverless;
+
+#[serverless(name = "orders", description = "Order processing")]
+pub async fn orders(_req: Request, _ctx: &Context) -> Result<Response> {
+    Ok(Response::text("orders"))
+}
+
+#[serverless]
+pub async fn payments(_req: Request, _ctx: &Context) -> Result<Response> {
+    Ok(Response::text("payments"))
+}
+
+fn main() {
+    let names: Vec<String> = serverless_rs::all_functions()
+        .into_iter()
+        .map(|info| info.name)
+        .collect();
+    assert_eq!(names, vec!["orders", "payments"]);
+
+    let ctx = Context::new();
+    let response =
+        serverless_rs::block_on(serverless_rs::invoke("payments", Request::new(), &ctx)).unwrap();
+    assert_eq!(response.body(), b"payments");
+
+    assert!(serverless_rs::block_on(serverless_rs::invoke("refunds", Request::new(), &ctx)).is_err());
+}
@@ -0,0 +1,29 @@
+//! Test for per-route requirements declared with #[route(..., requires(...))]
+
+use serverless_rs::{Context, Request, Response, Result};
+use serverless_rs_macros::{route, serverless};
+
+#[route(
+    POST,
+    "/uploads",
+    description = "Upload a file",
+    requires(env(BUCKET_NAME), recommend(memory = "1GB"))
+)]
+#[serverless]
+async fn upload(_req: Request, _ctx: &Context) -> Result<Response> {
+    Ok(Response::new().with_status(201))
+}
+
+fn main() {
+    let route = upload::route_info();
+    assert_eq!(route.method, "POST");
+    assert_eq!(route.path, "/uploads");
+    assert_eq!(route.description, Some("Upload a file".to_string()));
+
+    let requirements = route.requirements.expect("route requirements");
+    assert_eq!(requirements.environment, vec!["BUCKET_NAME"]);
+    assert_eq!(requirements.get_recommended("memory").unwrap().value, "1GB");
+
+    // Function-level requirements are unaffected
+    assert!(!upload::has_requirements());
+}
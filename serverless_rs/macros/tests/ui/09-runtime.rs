@@ -0,0 +1,22 @@
+//! Test selecting the adapters' tokio runtime flavor
+
+use serverless_rs::{Context, Request, Response, Result};
+use serverless_rs_macros::serverless;
+
+#[serverless(name = "pooled", runtime = "multi_thread")]
+pub async fn pooled(_req: Request, _ctx: &Context) -> Result<Response> {
+    Ok(Response::text("pooled"))
+}
+
+#[serverless(name = "single", runtime = "current_thread")]
+pub async fn single(_req: Request, _ctx: &Context) -> Result<Response> {
+    Ok(Response::text("single"))
+}
+
+fn main() {
+    let ctx = Context::new();
+    let result = futures::executor::block_on(pooled::pooled(Request::new(), &ctx)).unwrap();
+    assert_eq!(result.body(), b"pooled");
+    let result = futures::executor::block_on(single::single(Request::new(), &ctx)).unwrap();
+    assert_eq!(result.body(), b"single");
+}
@@ -0,0 +1,31 @@
+//! Test for route parameters declared with #[route(..., path(...), query(...))]
+
+use serverless_rs::{Context, ParamInfo, ParamLocation, Request, Response, Result};
+use serverless_rs_macros::{route, serverless};
+
+#[route(
+    GET,
+    "/users/{id}/posts/{slug}",
+    path(id = "integer"),
+    query(limit = "integer", cursor),
+    required_query(lang)
+)]
+#[serverless]
+async fn list_posts(_req: Request, _ctx: &Context) -> Result<Response> {
+    Ok(Response::text("[]"))
+}
+
+fn main() {
+    let route = list_posts::route_info();
+    assert_eq!(
+        route.params,
+        vec![
+            ParamInfo::path("id").with_type("integer"),
+            ParamInfo::path("slug"),
+            ParamInfo::query("limit").with_type("integer"),
+            ParamInfo::query("cursor"),
+            ParamInfo::query("lang").with_required(true),
+        ]
+    );
+    assert_eq!(route.params[0].location, ParamLocation::Path);
+}
@@ -0,0 +1,14 @@
+//! Test declaring the event sources that invoke a function
+
+use serverless_rs::{Context, Request, Response, Result};
+use serverless_rs_macros::serverless;
+
+#[serverless(triggers(dynamodb, kinesis))]
+pub async fn changes(_req: Request, _ctx: &Context) -> Result<Response> {
+    Ok(Response::new())
+}
+
+fn main() {
+    let info = changes::function_info();
+    assert_eq!(info.triggers, vec!["dynamodb", "kinesis"]);
+}
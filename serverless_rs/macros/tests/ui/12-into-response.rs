@@ -0,0 +1,33 @@
+//! Test for handlers returning types that implement IntoResponse
+
+use serverless_rs::{json, Context, Error, Request, Response, Result, Value};
+use serverless_rs_macros::serverless;
+
+#[serverless]
+pub async fn greet(_req: Request, _ctx: &Context) -> Result<&'static str> {
+    Ok("hello")
+}
+
+#[serverless]
+pub async fn create(_req: Request, _ctx: &Context) -> Result<(u16, Value)> {
+    Ok((201, json!({ "id": 7 })))
+}
+
+#[serverless]
+pub async fn missing(_req: Request, _ctx: &Context) -> Result<Response> {
+    Err(Error::with_status(404, "no such item"))
+}
+
+fn main() {
+    let ctx = Context::new();
+
+    let response = serverless_rs::block_on(serverless_rs::invoke("greet", Request::new(), &ctx)).unwrap();
+    assert_eq!(response.body(), b"hello");
+
+    let response = serverless_rs::block_on(serverless_rs::invoke("create", Request::new(), &ctx)).unwrap();
+    assert_eq!(response.status(), 201);
+    assert_eq!(response.body(), br#"{"id":7}"#);
+
+    let err = serverless_rs::block_on(serverless_rs::invoke("missing", Request::new(), &ctx)).unwrap_err();
+    assert_eq!(err.status(), 404);
+}
@@ -0,0 +1,60 @@
+//! Test for handlers taking FromRequest extractors as arguments
+
+use serde::Deserialize;
+use serverless_rs::extract::{Json, Path, Query};
+use serverless_rs::{Context, Request, Response, Result};
+use serverless_rs_macros::serverless;
+
+#[derive(Deserialize)]
+pub struct NewUser {
+    name: String,
+}
+
+#[derive(Deserialize)]
+pub struct UserId {
+    id: u64,
+}
+
+#[derive(Deserialize)]
+pub struct Greeting {
+    greeting: Option<String>,
+}
+
+#[serverless]
+pub async fn create(Json(body): Json<NewUser>, _ctx: &Context) -> Result<Response> {
+    Ok(Response::text(format!("created {}", body.name)).with_status(201))
+}
+
+#[serverless]
+pub async fn greet(
+    Path(user): Path<UserId>,
+    Query(query): Query<Greeting>,
+    req: Request,
+    _ctx: &Context,
+) -> Result<String> {
+    let greeting = query.greeting.unwrap_or_else(|| "hello".to_string());
+    Ok(format!("{} {} via {}", greeting, user.id, req.path().unwrap_or_default()))
+}
+
+fn main() {
+    let ctx = Context::new();
+    let invoke = |name: &str, req: Request| {
+        serverless_rs::block_on(serverless_rs::invoke(name, req, &ctx)).unwrap()
+    };
+
+    let response = invoke("create", Request::new().with_body(r#"{"name":"ada"}"#));
+    assert_eq!(response.status(), 201);
+    assert_eq!(response.body(), b"created ada");
+
+    let response = invoke("create", Request::new().with_body(r#"{"nom":"ada"}"#));
+    assert_eq!(response.status(), 400);
+
+    let req = Request::new()
+        .with_path("/users/7")
+        .with_path_param("id", "7")
+        .with_query("greeting", "hi");
+    assert_eq!(invoke("greet", req).body(), b"hi 7 via /users/7");
+
+    let response = invoke("greet", Request::new().with_path_param("id", "seven"));
+    assert_eq!(response.status(), 400);
+}
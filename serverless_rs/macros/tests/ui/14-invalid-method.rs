@@ -0,0 +1,12 @@
+//! Test that #[route] rejects unknown HTTP methods
+
+use serverless_rs::{Context, Request, Response, Result};
+use serverless_rs_macros::{route, serverless};
+
+#[route(GTE, "/items")]
+#[serverless]
+async fn list_items(_req: Request, _ctx: &Context) -> Result<Response> {
+    Ok(Response::text("items"))
+}
+
+fn main() {}
@@ -0,0 +1,21 @@
+//! Test for nonstandard methods declared with CUSTOM("...")
+
+use serverless_rs::{Context, Request, Response, Result};
+use serverless_rs_macros::{route, serverless};
+
+#[route(CUSTOM("PURGE"), "/cache")]
+#[serverless]
+async fn purge_cache(_req: Request, _ctx: &Context) -> Result<Response> {
+    Ok(Response::new().with_status(204))
+}
+
+#[route(delete, "/cache/{key}")]
+#[serverless]
+async fn evict(_req: Request, _ctx: &Context) -> Result<Response> {
+    Ok(Response::new().with_status(204))
+}
+
+fn main() {
+    assert_eq!(purge_cache::route_info().method, "PURGE");
+    assert_eq!(evict::route_info().method, "DELETE");
+}
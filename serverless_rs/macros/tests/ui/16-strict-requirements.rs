@@ -0,0 +1,14 @@
+//! Test that #[requirements(strict, ...)] rejects conflicting resource values
+
+#![allow(unexpected_cfgs, unused_imports)]
+
+use serverless_rs::{Context, Request, Response, Result};
+use serverless_rs_macros::{requirements, serverless};
+
+#[serverless]
+#[requirements(strict, require(memory = "256MB"), recommend(memory = "512MB"))]
+async fn resize(_req: Request, _ctx: &Context) -> Result<Response> {
+    Ok(Response::text("resized"))
+}
+
+fn main() {}
@@ -0,0 +1,29 @@
+//! Test generating adapters for selected platforms only
+
+use serverless_rs::{Context, Platform, Request, Response, Result};
+use serverless_rs_macros::serverless;
+
+#[serverless(platforms_only(aws))]
+pub async fn lambda_only(_req: Request, _ctx: &Context) -> Result<Response> {
+    Ok(Response::text("lambda"))
+}
+
+#[serverless(platforms(aws, cloudflare), platforms_only(aws, cloudflare))]
+pub async fn edge_and_lambda(_req: Request, _ctx: &Context) -> Result<Response> {
+    Ok(Response::text("both"))
+}
+
+fn main() {
+    let info = lambda_only::function_info();
+    assert_eq!(info.resources.platforms, vec![Platform::Aws]);
+
+    let info = edge_and_lambda::function_info();
+    assert_eq!(
+        info.resources.platforms,
+        vec![Platform::Aws, Platform::Cloudflare]
+    );
+
+    let ctx = Context::new();
+    let result = futures::executor::block_on(lambda_only::lambda_only(Request::new(), &ctx));
+    assert_eq!(result.unwrap().body(), b"lambda");
+}
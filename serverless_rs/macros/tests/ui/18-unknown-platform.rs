@@ -0,0 +1,11 @@
+//! Test that platforms_only rejects platforms without an adapter
+
+use serverless_rs::{Context, Request, Response, Result};
+use serverless_rs_macros::serverless;
+
+#[serverless(platforms_only(aws, heroku))]
+pub async fn handler(_req: Request, _ctx: &Context) -> Result<Response> {
+    Ok(Response::new())
+}
+
+fn main() {}
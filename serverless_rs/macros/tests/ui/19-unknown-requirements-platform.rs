@@ -0,0 +1,14 @@
+//! Test that #[requirements] rejects misspelled platform names
+
+#![allow(unexpected_cfgs, unused_imports)]
+
+use serverless_rs::{Context, Request, Response, Result};
+use serverless_rs_macros::{requirements, serverless};
+
+#[serverless]
+#[requirements(platforms(aws, cloudflair))]
+async fn edge(_req: Request, _ctx: &Context) -> Result<Response> {
+    Ok(Response::text("edge"))
+}
+
+fn main() {}
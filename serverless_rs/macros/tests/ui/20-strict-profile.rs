@@ -0,0 +1,22 @@
+//! Test that #[requirements(strict, ...)] still rejects conflicting values when a
+//! profile is loaded too
+
+#![allow(unexpected_cfgs, unused_imports)]
+
+use serverless_rs::{Context, Request, Response, Result};
+use serverless_rs_macros::{requirements, serverless};
+
+// Profiles are relative to the crate root, which for trybuild cases is their
+// project under target/tests/trybuild
+#[serverless]
+#[requirements(
+    strict,
+    profile = "../../../../macros/tests/ui/profiles/base.json",
+    require(memory = "256MB"),
+    recommend(memory = "512MB")
+)]
+async fn resize(_req: Request, _ctx: &Context) -> Result<Response> {
+    Ok(Response::text("resized"))
+}
+
+fn main() {}
@@ -0,0 +1,378 @@
+/*!
+Circuit breakers for downstream calls in serverless.rs.
+
+A [`CircuitBreaker`] counts consecutive failures of calls to a downstream service.
+Once they reach the configured threshold the circuit opens, and calls fail straight
+away with `503 Service Unavailable` instead of waiting on a dependency that is down.
+After the open period one trial call is let through: if it succeeds the circuit
+closes again, otherwise it stays open for another period. A trial that never reports
+back, for example because it was cancelled, is replaced by a new one after another
+open period.
+
+Breakers are shared by name across every invocation in the process, so a warm
+instance remembers that a dependency is unhealthy. Get one with
+[`Context::circuit_breaker`](crate::Context::circuit_breaker) or [`circuit_breaker`].
+*/
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock};
+use std::time::{Duration, Instant};
+
+use crate::error::{Error, Result};
+use crate::util;
+
+/// Default number of consecutive failures that opens a circuit
+pub const DEFAULT_FAILURE_THRESHOLD: u32 = 5;
+
+/// Default time an open circuit waits before letting a trial call through
+pub const DEFAULT_OPEN_DURATION: Duration = Duration::from_secs(30);
+
+/// Thresholds for a circuit breaker
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CircuitConfig {
+    /// Consecutive failures that open the circuit
+    pub failure_threshold: u32,
+
+    /// Time the circuit stays open before a trial call is let through
+    pub open_duration: Duration,
+}
+
+impl CircuitConfig {
+    /// Open after `failure_threshold` consecutive failures, for `open_duration`
+    pub fn new(failure_threshold: u32, open_duration: Duration) -> Self {
+        Self {
+            failure_threshold,
+            open_duration,
+        }
+    }
+}
+
+impl Default for CircuitConfig {
+    fn default() -> Self {
+        Self::new(DEFAULT_FAILURE_THRESHOLD, DEFAULT_OPEN_DURATION)
+    }
+}
+
+/// The state of a circuit breaker
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitState {
+    /// Calls go through and failures are counted
+    Closed,
+    /// Calls are rejected until the open period is over
+    Open,
+    /// A trial call is in flight, and other calls are rejected until it finishes or
+    /// the open period passes again
+    HalfOpen,
+}
+
+#[derive(Debug)]
+struct Breaker {
+    config: CircuitConfig,
+    state: CircuitState,
+    failures: u32,
+    /// When the circuit opened, or when the current trial call was let through
+    opened_at: Option<Instant>,
+}
+
+impl Breaker {
+    fn new(config: CircuitConfig) -> Self {
+        Self {
+            config,
+            state: CircuitState::Closed,
+            failures: 0,
+            opened_at: None,
+        }
+    }
+
+    /// Whether an open circuit has waited long enough to try a call
+    ///
+    /// Without a clock, as on `wasm32-unknown-unknown`, the wait is skipped.
+    fn open_elapsed(&self) -> bool {
+        match (self.opened_at, util::stopwatch()) {
+            (Some(opened_at), Some(now)) => now - opened_at >= self.config.open_duration,
+            _ => true,
+        }
+    }
+
+    /// Whether a trial call has gone on long enough to let another one through
+    ///
+    /// With a zero open period every call would become a trial, so trials are never
+    /// replaced; nor are they without a clock. A cancelled [`CircuitBreaker::call`]
+    /// still reports back as a failure.
+    fn trial_elapsed(&self) -> bool {
+        match (self.opened_at, util::stopwatch()) {
+            (Some(started), Some(now)) => {
+                !self.config.open_duration.is_zero() && now - started >= self.config.open_duration
+            }
+            _ => false,
+        }
+    }
+
+    fn open(&mut self) {
+        self.state = CircuitState::Open;
+        self.opened_at = util::stopwatch();
+    }
+}
+
+/// Breakers created so far in this process, by name
+static BREAKERS: OnceLock<Mutex<HashMap<String, CircuitBreaker>>> = OnceLock::new();
+
+fn breakers() -> MutexGuard<'static, HashMap<String, CircuitBreaker>> {
+    BREAKERS
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Returns the process-wide circuit breaker called `name`, creating it if needed
+///
+/// New breakers use [`CircuitConfig::default`] until [`configure`] is called.
+pub fn circuit_breaker(name: &str) -> CircuitBreaker {
+    breakers()
+        .entry(name.to_string())
+        .or_insert_with(|| CircuitBreaker::new(name, CircuitConfig::default()))
+        .clone()
+}
+
+/// Sets the thresholds of the circuit breaker called `name`
+///
+/// Existing handles to the breaker pick up the new thresholds; its state is kept.
+pub fn configure(name: &str, config: CircuitConfig) {
+    circuit_breaker(name).lock().config = config;
+}
+
+/// A handle to a named circuit breaker
+///
+/// Handles are cheap to clone, and every clone shares the same state.
+///
+/// # Examples
+///
+/// ```
+/// use serverless_rs::circuit::{self, CircuitConfig};
+/// use serverless_rs::{Context, Error, Result};
+/// use std::time::Duration;
+///
+/// async fn fetch_price(ctx: &Context) -> Result<u32> {
+///     circuit::configure("pricing", CircuitConfig::new(3, Duration::from_secs(10)));
+///     ctx.circuit_breaker("pricing")
+///         .call(async { Err::<u32, _>(Error::http("pricing service unavailable")) })
+///         .await
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct CircuitBreaker {
+    name: Arc<str>,
+    inner: Arc<Mutex<Breaker>>,
+}
+
+impl CircuitBreaker {
+    /// Creates a breaker that isn't shared with the process-wide registry
+    pub fn new(name: &str, config: CircuitConfig) -> Self {
+        Self {
+            name: name.into(),
+            inner: Arc::new(Mutex::new(Breaker::new(config))),
+        }
+    }
+
+    /// Returns the breaker's name
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the breaker's thresholds
+    pub fn config(&self) -> CircuitConfig {
+        self.lock().config
+    }
+
+    /// Returns the breaker's current state
+    ///
+    /// An open circuit whose open period is over is reported as open until a call
+    /// is let through.
+    pub fn state(&self) -> CircuitState {
+        self.lock().state
+    }
+
+    /// Asks to make a call, failing with status 503 if the circuit is open
+    ///
+    /// When the open period is over the circuit turns half-open and this call is the
+    /// trial; report its outcome with [`record_success`](Self::record_success) or
+    /// [`record_failure`](Self::record_failure). If a trial hasn't reported back
+    /// after another open period, the next call becomes the trial instead.
+    pub fn check(&self) -> Result<()> {
+        let mut breaker = self.lock();
+        let trial = match breaker.state {
+            CircuitState::Closed => return Ok(()),
+            CircuitState::Open => breaker.open_elapsed(),
+            CircuitState::HalfOpen => breaker.trial_elapsed(),
+        };
+        if trial {
+            breaker.state = CircuitState::HalfOpen;
+            breaker.opened_at = util::stopwatch();
+            return Ok(());
+        }
+        Err(Error::with_status(
+            503,
+            format!("Circuit breaker '{}' is open", self.name),
+        ))
+    }
+
+    /// Records a successful call, closing the circuit
+    pub fn record_success(&self) {
+        let mut breaker = self.lock();
+        breaker.state = CircuitState::Closed;
+        breaker.failures = 0;
+        breaker.opened_at = None;
+    }
+
+    /// Records a failed call, opening the circuit at the threshold or after a trial
+    pub fn record_failure(&self) {
+        let mut breaker = self.lock();
+        breaker.failures = breaker.failures.saturating_add(1);
+        if breaker.state == CircuitState::HalfOpen
+            || breaker.failures >= breaker.config.failure_threshold
+        {
+            breaker.open();
+        }
+    }
+
+    /// Runs a downstream call through the breaker
+    ///
+    /// The call isn't made while the circuit is open. Otherwise its outcome is
+    /// recorded and returned unchanged. A call that is dropped before it finishes,
+    /// for example by a timeout, is recorded as a failure.
+    pub async fn call<T, F>(&self, call: F) -> Result<T>
+    where
+        F: Future<Output = Result<T>>,
+    {
+        self.check()?;
+        let mut cancelled = Cancelled {
+            breaker: self,
+            armed: true,
+        };
+        let result = call.await;
+        cancelled.armed = false;
+        match result {
+            Ok(_) => self.record_success(),
+            Err(_) => self.record_failure(),
+        }
+        result
+    }
+
+    /// Closes the circuit and forgets past failures
+    pub fn reset(&self) {
+        self.record_success();
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Breaker> {
+        // The state stays consistent even if a holder panicked
+        self.inner
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Records a failure if a call is dropped before it finishes, so a cancelled trial
+/// doesn't leave the circuit half-open
+struct Cancelled<'a> {
+    breaker: &'a CircuitBreaker,
+    armed: bool,
+}
+
+impl Drop for Cancelled<'_> {
+    fn drop(&mut self) {
+        if self.armed {
+            self.breaker.record_failure();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::FutureExt;
+
+    async fn fail() -> Result<()> {
+        Err(Error::http("downstream failed"))
+    }
+
+    #[tokio::test]
+    async fn test_opens_and_recovers() {
+        let breaker = CircuitBreaker::new("payments", CircuitConfig::new(2, Duration::ZERO));
+        assert!(breaker.call(fail()).await.is_err());
+        assert_eq!(breaker.state(), CircuitState::Closed);
+        assert!(breaker.call(fail()).await.is_err());
+        assert_eq!(breaker.state(), CircuitState::Open);
+
+        // With no open period the next call is a trial, and others wait for it
+        breaker.check().unwrap();
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+        assert_eq!(breaker.check().unwrap_err().status(), 503);
+
+        // A failed trial opens the circuit again straight away
+        breaker.record_failure();
+        assert_eq!(breaker.state(), CircuitState::Open);
+
+        assert_eq!(breaker.call(async { Ok(7) }).await.unwrap(), 7);
+        assert_eq!(breaker.state(), CircuitState::Closed);
+    }
+
+    #[tokio::test]
+    async fn test_rejects_while_open() {
+        let breaker = CircuitBreaker::new("search", CircuitConfig::new(1, Duration::from_secs(60)));
+        assert!(breaker.call(fail()).await.is_err());
+
+        let mut called = false;
+        let err = breaker
+            .call(async {
+                called = true;
+                Ok(())
+            })
+            .await
+            .unwrap_err();
+        assert_eq!(err.status(), 503);
+        assert!(err.to_string().contains("search"));
+        assert!(!called);
+
+        breaker.reset();
+        assert_eq!(breaker.state(), CircuitState::Closed);
+    }
+
+    #[test]
+    fn test_stuck_trial_replaced() {
+        let breaker = CircuitBreaker::new("billing", CircuitConfig::new(1, Duration::ZERO));
+        breaker.record_failure();
+
+        // A cancelled trial counts as a failure
+        let trial = breaker.call(futures::future::pending::<Result<()>>());
+        assert!(trial.now_or_never().is_none());
+        assert_eq!(breaker.state(), CircuitState::Open);
+
+        // A trial that never reports back is replaced after the open period
+        let breaker = CircuitBreaker::new("ledger", CircuitConfig::new(1, Duration::from_secs(60)));
+        breaker.record_failure();
+        breaker.lock().opened_at = Some(Instant::now() - Duration::from_secs(60));
+        breaker.check().unwrap();
+        assert_eq!(breaker.check().unwrap_err().status(), 503);
+        breaker.lock().opened_at = Some(Instant::now() - Duration::from_secs(60));
+        breaker.check().unwrap();
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+    }
+
+    #[test]
+    fn test_shared_by_name() {
+        let first = circuit_breaker("test-shared-inventory");
+        configure(
+            "test-shared-inventory",
+            CircuitConfig::new(1, Duration::from_secs(60)),
+        );
+        assert_eq!(first.config().failure_threshold, 1);
+
+        circuit_breaker("test-shared-inventory").record_failure();
+        assert_eq!(first.state(), CircuitState::Open);
+        assert_eq!(
+            circuit_breaker("test-shared-other").state(),
+            CircuitState::Closed
+        );
+    }
+}
@@ -0,0 +1,308 @@
+/*!
+Request coalescing middleware for serverless.rs.
+
+[`SingleFlightLayer`] runs the handler once for identical `GET` and `HEAD` requests
+that arrive while an earlier one is still in flight, and gives every caller the same
+response. Requests are identical when their method, path, query and body match, and
+so do their credentials: the `Authorization` and `Cookie` headers, plus any others
+named with [`SingleFlightLayer::vary`].
+
+This helps when developing against slow upstreams, for example with the local server
+under load tests, much like a caching proxy in front of a production deployment.
+*/
+
+use async_trait::async_trait;
+use futures::channel::oneshot;
+use http::Method;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::sync::{Mutex, MutexGuard};
+
+use crate::error::{Error, Result};
+use crate::{Context, Handler, Middleware, Request, Response};
+
+/// Outcome shared with coalesced callers
+type Shared = Result<Response>;
+
+/// Middleware that coalesces identical in-flight `GET` and `HEAD` requests
+///
+/// Other methods are passed through, since they may change state. Requests carrying
+/// different credentials are never coalesced, so one user's response isn't handed to
+/// another. Callers waiting on a request that fails get an error with the same
+/// status and message. If the in-flight request is cancelled, waiting callers run
+/// the handler themselves.
+///
+/// # Examples
+///
+/// ```
+/// use serverless_rs::coalesce::SingleFlightLayer;
+/// use serverless_rs::{Context, Request, Response, Result, RouterBuilder};
+///
+/// fn report(_req: Request, _ctx: &Context) -> Result<Response> {
+///     Ok(Response::text("expensive report"))
+/// }
+///
+/// let router = RouterBuilder::new()
+///     .scope("/reports", |reports| {
+///         reports
+///             .layer(SingleFlightLayer::new().vary("X-Tenant"))
+///             .get("/daily", report)
+///     })
+///     .build();
+/// ```
+#[derive(Debug)]
+pub struct SingleFlightLayer {
+    vary: Vec<String>,
+    in_flight: Mutex<HashMap<String, Vec<oneshot::Sender<Shared>>>>,
+}
+
+impl Default for SingleFlightLayer {
+    fn default() -> Self {
+        Self {
+            vary: vec!["Authorization".to_string(), "Cookie".to_string()],
+            in_flight: Mutex::default(),
+        }
+    }
+}
+
+impl SingleFlightLayer {
+    /// Creates a layer with no requests in flight
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only coalesce requests whose `name` header also matches
+    ///
+    /// Use this for headers the response depends on, such as a tenant or API key
+    /// header. `Authorization` and `Cookie` are always compared.
+    pub fn vary(mut self, name: impl Into<String>) -> Self {
+        self.vary.push(name.into());
+        self
+    }
+
+    /// Returns the coalescing key for a request, if its method is coalesced
+    fn key(&self, req: &Request) -> Option<String> {
+        let method = req.method()?;
+        if method != Method::GET && method != Method::HEAD {
+            return None;
+        }
+
+        let mut query: Vec<_> = req.query().iter().collect();
+        query.sort();
+        // Credentials are hashed along with the body rather than kept in the key
+        let mut hasher = DefaultHasher::new();
+        req.body().hash(&mut hasher);
+        for name in &self.vary {
+            req.header(name).hash(&mut hasher);
+        }
+        Some(format!(
+            "{} {} {:?} {:x}",
+            method,
+            req.path().unwrap_or_default(),
+            query,
+            hasher.finish()
+        ))
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<String, Vec<oneshot::Sender<Shared>>>> {
+        // The map stays consistent even if a holder panicked
+        self.in_flight
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Removes a key from the in-flight map when the leading request finishes or is
+/// dropped, so waiters are never left behind
+struct Flight<'a> {
+    layer: &'a SingleFlightLayer,
+    // Taken by `finish`, so the drop doesn't remove a newer leader's entry
+    key: Option<String>,
+}
+
+impl Flight<'_> {
+    /// Share the leading request's outcome with everyone waiting on it
+    fn finish(mut self, result: &Shared) {
+        let Some(key) = self.key.take() else {
+            return;
+        };
+        let waiters = self.layer.lock().remove(&key).unwrap_or_default();
+        for waiter in waiters {
+            let shared = match result {
+                Ok(response) => Ok(response.clone()),
+                Err(err) => Err(Error::with_status(err.status(), err.to_string())),
+            };
+            // A waiter that went away doesn't need the response
+            let _ = waiter.send(shared);
+        }
+    }
+}
+
+impl Drop for Flight<'_> {
+    fn drop(&mut self) {
+        // Dropping the senders wakes the waiters with `Canceled`
+        if let Some(key) = self.key.take() {
+            self.layer.lock().remove(&key);
+        }
+    }
+}
+
+#[async_trait]
+impl Middleware for SingleFlightLayer {
+    async fn handle(&self, req: Request, ctx: &Context, next: &dyn Handler) -> Result<Response> {
+        let key = match self.key(&req) {
+            Some(key) => key,
+            None => return next.handle(req, ctx).await,
+        };
+
+        let waiting = {
+            let mut in_flight = self.lock();
+            match in_flight.get_mut(&key) {
+                Some(waiters) => {
+                    let (sender, receiver) = oneshot::channel();
+                    waiters.push(sender);
+                    Some(receiver)
+                }
+                None => {
+                    in_flight.insert(key.clone(), Vec::new());
+                    None
+                }
+            }
+        };
+
+        if let Some(receiver) = waiting {
+            return match receiver.await {
+                Ok(shared) => shared,
+                // The leading request was cancelled; run the handler ourselves
+                Err(oneshot::Canceled) => next.handle(req, ctx).await,
+            };
+        }
+
+        let flight = Flight {
+            layer: self,
+            key: Some(key),
+        };
+        let result = next.handle(req, ctx).await;
+        flight.finish(&result);
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Counts calls and yields once, so concurrent requests overlap
+    struct Slow {
+        calls: AtomicUsize,
+        status: u16,
+    }
+
+    impl Slow {
+        fn new(status: u16) -> Self {
+            Self {
+                calls: AtomicUsize::new(0),
+                status,
+            }
+        }
+    }
+
+    #[async_trait]
+    impl Handler for Slow {
+        async fn handle(&self, req: Request, _ctx: &Context) -> Result<Response> {
+            let call = self.calls.fetch_add(1, Ordering::SeqCst) + 1;
+            tokio::task::yield_now().await;
+            if self.status >= 400 {
+                return Err(Error::with_status(self.status, "upstream failed"));
+            }
+            let path = req.path().unwrap_or_default();
+            Ok(Response::text(format!("{} call {}", path, call)))
+        }
+    }
+
+    fn get(path: &str) -> Request {
+        Request::new().with_method(Method::GET).with_path(path)
+    }
+
+    #[tokio::test]
+    async fn test_identical_requests_coalesced() {
+        let layer = SingleFlightLayer::new();
+        let handler = Slow::new(200);
+        let ctx = Context::new();
+
+        let (a, b, c) = futures::join!(
+            layer.handle(get("/report"), &ctx, &handler),
+            layer.handle(get("/report"), &ctx, &handler),
+            layer.handle(get("/other"), &ctx, &handler),
+        );
+        assert_eq!(a.unwrap().body(), b"/report call 1");
+        assert_eq!(b.unwrap().body(), b"/report call 1");
+        assert_eq!(c.unwrap().body(), b"/other call 2");
+        assert_eq!(handler.calls.load(Ordering::SeqCst), 2);
+
+        // Once the first request finished, the next one runs the handler again
+        let later = layer.handle(get("/report"), &ctx, &handler).await;
+        assert_eq!(later.unwrap().body(), b"/report call 3");
+        assert!(layer.lock().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_credentials_not_shared() {
+        let layer = SingleFlightLayer::new().vary("X-Tenant");
+        let handler = Slow::new(200);
+        let ctx = Context::new();
+        let as_user = |name: &str| get("/me").with_header("Authorization", name);
+
+        let (a, b, c) = futures::join!(
+            layer.handle(as_user("Bearer alice"), &ctx, &handler),
+            layer.handle(as_user("Bearer bob"), &ctx, &handler),
+            layer.handle(as_user("Bearer alice"), &ctx, &handler),
+        );
+        assert_eq!(a.unwrap().body(), b"/me call 1");
+        assert_eq!(b.unwrap().body(), b"/me call 2");
+        assert_eq!(c.unwrap().body(), b"/me call 1");
+
+        let (a, b) = futures::join!(
+            layer.handle(
+                get("/me").with_header("Cookie", "session=1"),
+                &ctx,
+                &handler
+            ),
+            layer.handle(get("/me").with_header("X-Tenant", "acme"), &ctx, &handler),
+        );
+        assert_eq!(a.unwrap().body(), b"/me call 3");
+        assert_eq!(b.unwrap().body(), b"/me call 4");
+        assert!(layer.lock().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_unsafe_methods_and_errors() {
+        let layer = SingleFlightLayer::new();
+        let ctx = Context::new();
+
+        let handler = Slow::new(200);
+        let post = || {
+            Request::new()
+                .with_method(Method::POST)
+                .with_path("/report")
+        };
+        let (a, b) = futures::join!(
+            layer.handle(post(), &ctx, &handler),
+            layer.handle(post(), &ctx, &handler),
+        );
+        assert_ne!(a.unwrap().body(), b.unwrap().body());
+
+        let failing = Slow::new(503);
+        let (a, b) = futures::join!(
+            layer.handle(get("/report"), &ctx, &failing),
+            layer.handle(get("/report"), &ctx, &failing),
+        );
+        assert_eq!(a.unwrap_err().status(), 503);
+        let b = b.unwrap_err();
+        assert_eq!(b.status(), 503);
+        assert_eq!(b.to_string(), "upstream failed");
+        assert_eq!(failing.calls.load(Ordering::SeqCst), 1);
+    }
+}
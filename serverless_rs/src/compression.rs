@@ -0,0 +1,220 @@
+/*!
+Request body decompression for serverless.rs.
+
+[`DecompressionLayer`] decodes request bodies sent with `Content-Encoding: gzip`,
+`deflate` or `br`, so handlers and the body accessors such as
+[`Request::body_json`] see the plain body. Decompressed bodies are capped in size to
+guard against decompression bombs.
+
+Requires the `compression` feature.
+*/
+
+use async_trait::async_trait;
+use std::io::Read;
+
+use crate::error::Result;
+use crate::{Context, Handler, Middleware, Request, Response};
+
+/// Default limit on the size of a decompressed body: 10 MiB
+pub const DEFAULT_MAX_DECOMPRESSED_SIZE: usize = 10 * 1024 * 1024;
+
+/// Middleware that decompresses request bodies
+///
+/// Encodings listed in `Content-Encoding` are undone in reverse order, after which
+/// the `Content-Encoding` and `Content-Length` headers are removed. Requests are
+/// rejected with:
+///
+/// - `413 Payload Too Large` if the decompressed body exceeds the limit
+/// - `415 Unsupported Media Type` for encodings other than `gzip`, `x-gzip`,
+///   `deflate`, `br` and `identity`
+/// - `400 Bad Request` if the body isn't validly encoded
+///
+/// # Examples
+///
+/// ```
+/// use serverless_rs::compression::DecompressionLayer;
+/// use serverless_rs::{Context, Request, Response, Result, RouterBuilder};
+///
+/// fn upload(req: Request, _ctx: &Context) -> Result<Response> {
+///     let records: Vec<serverless_rs::Value> = req.body_json()?;
+///     Ok(Response::text(format!("{} records", records.len())))
+/// }
+///
+/// let router = RouterBuilder::new()
+///     .scope("/ingest", |ingest| {
+///         ingest
+///             .layer(DecompressionLayer::new().with_max_size(50 * 1024 * 1024))
+///             .post("/records", upload)
+///     })
+///     .build();
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct DecompressionLayer {
+    max_size: usize,
+}
+
+impl Default for DecompressionLayer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DecompressionLayer {
+    /// Decompress bodies up to [`DEFAULT_MAX_DECOMPRESSED_SIZE`]
+    pub fn new() -> Self {
+        Self {
+            max_size: DEFAULT_MAX_DECOMPRESSED_SIZE,
+        }
+    }
+
+    /// Sets the largest decompressed body accepted, in bytes
+    pub fn with_max_size(mut self, max_size: usize) -> Self {
+        self.max_size = max_size;
+        self
+    }
+
+    /// Decode `body` with the given `Content-Encoding` value
+    fn decompress(&self, encodings: &str, body: Vec<u8>) -> std::result::Result<Vec<u8>, Response> {
+        let encodings: Vec<String> = encodings
+            .split(',')
+            .map(|encoding| encoding.trim().to_ascii_lowercase())
+            .filter(|encoding| !encoding.is_empty() && encoding != "identity")
+            .collect();
+
+        encodings.iter().rev().try_fold(body, |body, encoding| {
+            let reader: Box<dyn Read + '_> = match encoding.as_str() {
+                "gzip" | "x-gzip" => Box::new(flate2::read::MultiGzDecoder::new(&body[..])),
+                "deflate" => Box::new(flate2::read::ZlibDecoder::new(&body[..])),
+                "br" => Box::new(brotli::Decompressor::new(&body[..], 4096)),
+                _ => {
+                    return Err(Response::new()
+                        .with_status(415)
+                        .with_body(format!("Unsupported Content-Encoding: {}", encoding)))
+                }
+            };
+
+            // Read one byte past the limit to tell a full body from a truncated one
+            let mut decoded = Vec::new();
+            reader
+                .take(self.max_size as u64 + 1)
+                .read_to_end(&mut decoded)
+                .map_err(|err| {
+                    Response::bad_request().with_body(format!("Invalid {} body: {}", encoding, err))
+                })?;
+            if decoded.len() > self.max_size {
+                return Err(Response::new()
+                    .with_status(413)
+                    .with_body("Payload Too Large"));
+            }
+            Ok(decoded)
+        })
+    }
+}
+
+#[async_trait]
+impl Middleware for DecompressionLayer {
+    async fn handle(&self, req: Request, ctx: &Context, next: &dyn Handler) -> Result<Response> {
+        let Some(encodings) = req.header("Content-Encoding").cloned() else {
+            return next.handle(req, ctx).await;
+        };
+        let body = match req.decoded_body() {
+            Ok(body) => body.into_owned(),
+            Err(_) => return Ok(Response::bad_request()),
+        };
+
+        match self.decompress(&encodings, body) {
+            Ok(body) => {
+                let req = req
+                    .with_body(body)
+                    .with_base64(false)
+                    .without_header("Content-Encoding")
+                    .without_header("Content-Length");
+                next.handle(req, ctx).await
+            }
+            Err(response) => Ok(response),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use std::io::Write;
+
+    fn gzip(data: &[u8]) -> Vec<u8> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    fn brotli(data: &[u8]) -> Vec<u8> {
+        let mut compressed = Vec::new();
+        {
+            let mut writer = brotli::CompressorWriter::new(&mut compressed, 4096, 5, 22);
+            writer.write_all(data).unwrap();
+        }
+        compressed
+    }
+
+    struct Echo;
+
+    #[async_trait]
+    impl Handler for Echo {
+        async fn handle(&self, req: Request, _ctx: &Context) -> Result<Response> {
+            assert!(req.header("Content-Encoding").is_none());
+            assert!(req.header("Content-Length").is_none());
+            let value: serde_json::Value = req.body_json().unwrap_or_default();
+            Ok(Response::text(value["n"].to_string()))
+        }
+    }
+
+    async fn send(layer: &DecompressionLayer, encoding: &str, body: Vec<u8>) -> Response {
+        let req = Request::new()
+            .with_header("content-encoding", encoding)
+            .with_header("Content-Length", body.len().to_string())
+            .with_body(body);
+        layer.handle(req, &Context::new(), &Echo).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_decompression() {
+        let layer = DecompressionLayer::new();
+        let json = br#"{"n": 1}"#;
+
+        assert_eq!(send(&layer, "gzip", gzip(json)).await.body(), b"1");
+        assert_eq!(send(&layer, "br", brotli(json)).await.body(), b"1");
+        assert_eq!(send(&layer, "identity", json.to_vec()).await.body(), b"1");
+        assert_eq!(
+            send(&layer, "br, gzip", gzip(&brotli(json))).await.body(),
+            b"1"
+        );
+
+        // Base64 bodies are decoded first
+        use base64::Engine;
+        let encoded = base64::engine::general_purpose::STANDARD.encode(gzip(json));
+        let req = Request::new()
+            .with_header("Content-Encoding", "gzip")
+            .with_body(encoded)
+            .with_base64(true);
+        let response = layer.handle(req, &Context::new(), &Echo).await.unwrap();
+        assert_eq!(response.body(), b"1");
+
+        assert_eq!(send(&layer, "gzip", json.to_vec()).await.status(), 400);
+        assert_eq!(send(&layer, "zstd", json.to_vec()).await.status(), 415);
+    }
+
+    #[tokio::test]
+    async fn test_decompression_limit() {
+        // A 1 MiB body compresses to a few KiB
+        let bomb = gzip(&vec![b' '; 1024 * 1024]);
+        assert!(bomb.len() < 4096);
+
+        let layer = DecompressionLayer::new().with_max_size(64 * 1024);
+        assert_eq!(send(&layer, "gzip", bomb.clone()).await.status(), 413);
+
+        let layer = DecompressionLayer::new().with_max_size(1024 * 1024);
+        assert_eq!(send(&layer, "gzip", bomb).await.status(), 200);
+    }
+}
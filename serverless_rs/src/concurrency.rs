@@ -0,0 +1,240 @@
+/*!
+Concurrency limiting middleware for serverless.rs.
+
+[`ConcurrencyLimitLayer`] bounds how many requests run their handler at once, as a
+Lambda function's reserved concurrency does in production. Requests over the limit
+either wait for a slot or are rejected with `503 Service Unavailable`, depending on
+the [`Overflow`] policy. This makes it possible to reproduce production throttling
+when load testing locally.
+*/
+
+use async_trait::async_trait;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Poll, Waker};
+
+use crate::error::Result;
+use crate::{Context, Handler, Middleware, Request, Response};
+
+/// What happens to requests arriving while the limit is reached
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Overflow {
+    /// Wait until a running request finishes
+    #[default]
+    Queue,
+    /// Answer immediately with `503 Service Unavailable`, as a throttled Lambda would
+    Reject,
+}
+
+/// Slots in use and the tasks waiting for one
+#[derive(Debug, Default)]
+struct Slots {
+    in_flight: usize,
+    waiting: Vec<Waker>,
+}
+
+/// Middleware that limits how many requests are handled at once
+///
+/// The limit applies to everything the layer wraps, across all routes of the scope.
+/// Clones of the layer share the same slots.
+///
+/// # Examples
+///
+/// ```
+/// use serverless_rs::concurrency::{ConcurrencyLimitLayer, Overflow};
+/// use serverless_rs::{Context, Request, Response, Result, RouterBuilder};
+///
+/// fn report(_req: Request, _ctx: &Context) -> Result<Response> {
+///     Ok(Response::text("done"))
+/// }
+///
+/// // Mirror a reserved concurrency of 10
+/// let router = RouterBuilder::new()
+///     .scope("/", |all| {
+///         all.layer(ConcurrencyLimitLayer::new(10).with_overflow(Overflow::Reject))
+///             .get("/report", report)
+///     })
+///     .build();
+/// ```
+#[derive(Debug, Clone)]
+pub struct ConcurrencyLimitLayer {
+    max_concurrency: usize,
+    overflow: Overflow,
+    slots: Arc<Mutex<Slots>>,
+}
+
+impl ConcurrencyLimitLayer {
+    /// Allow at most `max_concurrency` requests at once, queuing the rest
+    ///
+    /// A limit of zero is treated as one.
+    pub fn new(max_concurrency: usize) -> Self {
+        Self {
+            max_concurrency: max_concurrency.max(1),
+            overflow: Overflow::default(),
+            slots: Arc::default(),
+        }
+    }
+
+    /// Sets whether excess requests queue or are rejected
+    pub fn with_overflow(mut self, overflow: Overflow) -> Self {
+        self.overflow = overflow;
+        self
+    }
+
+    /// Returns the number of requests currently being handled
+    pub fn in_flight(&self) -> usize {
+        self.slots.lock().map(|slots| slots.in_flight).unwrap_or(0)
+    }
+
+    /// Take a slot if one is free
+    fn try_acquire(&self) -> Option<Permit> {
+        let mut slots = self.slots.lock().ok()?;
+        if slots.in_flight >= self.max_concurrency {
+            return None;
+        }
+        slots.in_flight += 1;
+        Some(Permit {
+            slots: self.slots.clone(),
+        })
+    }
+
+    /// Wait for a free slot
+    fn acquire(&self) -> Acquire<'_> {
+        Acquire { layer: self }
+    }
+}
+
+/// A slot held for the duration of a request, freed on drop
+struct Permit {
+    slots: Arc<Mutex<Slots>>,
+}
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        if let Ok(mut slots) = self.slots.lock() {
+            slots.in_flight -= 1;
+            // Waiters race for the free slot; the losers register again
+            for waker in slots.waiting.drain(..) {
+                waker.wake();
+            }
+        }
+    }
+}
+
+/// Future returned by [`ConcurrencyLimitLayer::acquire`]
+struct Acquire<'a> {
+    layer: &'a ConcurrencyLimitLayer,
+}
+
+impl Future for Acquire<'_> {
+    type Output = Permit;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut std::task::Context<'_>) -> Poll<Permit> {
+        if let Some(permit) = self.layer.try_acquire() {
+            return Poll::Ready(permit);
+        }
+        if let Ok(mut slots) = self.layer.slots.lock() {
+            // A slot may have been freed since `try_acquire` released the lock
+            if slots.in_flight < self.layer.max_concurrency {
+                cx.waker().wake_by_ref();
+            } else {
+                slots.waiting.push(cx.waker().clone());
+            }
+        }
+        Poll::Pending
+    }
+}
+
+#[async_trait]
+impl Middleware for ConcurrencyLimitLayer {
+    async fn handle(&self, req: Request, ctx: &Context, next: &dyn Handler) -> Result<Response> {
+        let _permit = match self.overflow {
+            Overflow::Queue => self.acquire().await,
+            Overflow::Reject => match self.try_acquire() {
+                Some(permit) => permit,
+                None => {
+                    return Ok(Response::new()
+                        .with_status(503)
+                        .with_header("Retry-After", "1")
+                        .with_body("Service Unavailable"))
+                }
+            },
+        };
+        next.handle(req, ctx).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::channel::oneshot;
+    use futures::task::noop_waker_ref;
+    use futures::FutureExt;
+
+    /// Blocks the first request until released, answering the others at once
+    struct Gate(Mutex<Option<oneshot::Receiver<()>>>);
+
+    #[async_trait]
+    impl Handler for Gate {
+        async fn handle(&self, _req: Request, _ctx: &Context) -> Result<Response> {
+            let receiver = self.0.lock().unwrap().take();
+            if let Some(receiver) = receiver {
+                receiver.await.ok();
+            }
+            Ok(Response::text("ok"))
+        }
+    }
+
+    fn gate() -> (oneshot::Sender<()>, Gate) {
+        let (sender, receiver) = oneshot::channel();
+        (sender, Gate(Mutex::new(Some(receiver))))
+    }
+
+    fn poll<F: Future + Unpin>(future: &mut F) -> Poll<F::Output> {
+        future.poll_unpin(&mut std::task::Context::from_waker(noop_waker_ref()))
+    }
+
+    #[tokio::test]
+    async fn test_reject_overflow() {
+        let layer = ConcurrencyLimitLayer::new(1).with_overflow(Overflow::Reject);
+        let ctx = Context::new();
+        let (release, handler) = gate();
+
+        let mut first = layer.handle(Request::new(), &ctx, &handler);
+        assert!(poll(&mut first).is_pending());
+        assert_eq!(layer.in_flight(), 1);
+
+        let rejected = layer.handle(Request::new(), &ctx, &handler).await.unwrap();
+        assert_eq!(rejected.status(), 503);
+        assert_eq!(rejected.header("Retry-After").unwrap(), "1");
+
+        release.send(()).unwrap();
+        assert_eq!(first.await.unwrap().status(), 200);
+        assert_eq!(layer.in_flight(), 0);
+
+        let resp = layer.handle(Request::new(), &ctx, &handler).await.unwrap();
+        assert_eq!(resp.status(), 200);
+    }
+
+    #[tokio::test]
+    async fn test_queue_overflow() {
+        let layer = ConcurrencyLimitLayer::new(1);
+        let ctx = Context::new();
+        let (release, handler) = gate();
+
+        let mut first = layer.handle(Request::new(), &ctx, &handler);
+        let mut second = layer.handle(Request::new(), &ctx, &handler);
+        assert!(poll(&mut first).is_pending());
+        assert!(poll(&mut second).is_pending());
+        assert_eq!(layer.in_flight(), 1);
+
+        release.send(()).unwrap();
+        assert!(matches!(poll(&mut first), Poll::Ready(Ok(_))));
+        match poll(&mut second) {
+            Poll::Ready(resp) => assert_eq!(resp.unwrap().status(), 200),
+            Poll::Pending => panic!("queued request should run once a slot frees"),
+        }
+        assert_eq!(layer.in_flight(), 0);
+    }
+}
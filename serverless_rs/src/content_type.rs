@@ -0,0 +1,241 @@
+/*!
+Content type parsing for serverless.rs.
+
+This module provides [`ContentType`], a parsed `Content-Type` header value split
+into its media type and parameters such as `charset` and `boundary`.
+*/
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::error::{Error, Result};
+
+/// A parsed `Content-Type` header value
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContentType {
+    /// Lowercased media type, e.g. `text/html`
+    media_type: String,
+
+    /// Parameters in order, with lowercased names and unquoted values
+    params: Vec<(String, String)>,
+}
+
+impl ContentType {
+    /// Parse a `Content-Type` header value
+    ///
+    /// Returns `None` if the value does not start with a `type/subtype` media type.
+    /// Parameters without a value are skipped, and quoted values are unquoted.
+    pub fn parse(value: &str) -> Option<Self> {
+        let mut parts = split_params(value).into_iter();
+        let media_type = parts.next()?.trim().to_ascii_lowercase();
+        match media_type.split_once('/') {
+            Some((kind, subtype)) if !kind.is_empty() && !subtype.is_empty() => {}
+            _ => return None,
+        }
+
+        let params = parts
+            .filter_map(|param| {
+                let (name, value) = param.split_once('=')?;
+                let name = name.trim().to_ascii_lowercase();
+                if name.is_empty() {
+                    return None;
+                }
+                Some((name, unquote(value.trim())))
+            })
+            .collect();
+
+        Some(Self { media_type, params })
+    }
+
+    /// Returns the media type without parameters, e.g. `multipart/form-data`
+    pub fn media_type(&self) -> &str {
+        &self.media_type
+    }
+
+    /// Returns the top-level type, e.g. `multipart`
+    pub fn kind(&self) -> &str {
+        self.media_type.split('/').next().unwrap_or_default()
+    }
+
+    /// Returns the subtype, e.g. `form-data`
+    pub fn subtype(&self) -> &str {
+        self.media_type.split('/').nth(1).unwrap_or_default()
+    }
+
+    /// Returns whether the media type equals `media_type`, ignoring ASCII case
+    pub fn is(&self, media_type: &str) -> bool {
+        self.media_type.eq_ignore_ascii_case(media_type)
+    }
+
+    /// Returns the parameters in order
+    pub fn params(&self) -> &[(String, String)] {
+        &self.params
+    }
+
+    /// Returns a parameter by name, ignoring ASCII case
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Returns the `charset` parameter, if present
+    pub fn charset(&self) -> Option<&str> {
+        self.param("charset")
+    }
+
+    /// Returns the `boundary` parameter, if present
+    pub fn boundary(&self) -> Option<&str> {
+        self.param("boundary")
+    }
+
+    /// Decode a body according to the declared charset
+    ///
+    /// UTF-8 (the default), US-ASCII and ISO-8859-1 are supported. Any other charset
+    /// is a serialization error naming it.
+    pub fn decode(&self, body: &[u8]) -> Result<String> {
+        let charset = self.charset().unwrap_or("utf-8").to_ascii_lowercase();
+        match charset.as_str() {
+            "utf-8" | "utf8" => String::from_utf8(body.to_vec()).map_err(Error::serialization),
+            "us-ascii" | "ascii" => {
+                if body.is_ascii() {
+                    Ok(String::from_utf8_lossy(body).into_owned())
+                } else {
+                    Err(Error::serialization("Body is not valid US-ASCII"))
+                }
+            }
+            "iso-8859-1" | "latin1" | "latin-1" => {
+                Ok(body.iter().map(|&byte| byte as char).collect())
+            }
+            other => Err(Error::serialization(format!(
+                "Unsupported charset: {}",
+                other
+            ))),
+        }
+    }
+}
+
+impl FromStr for ContentType {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::parse(s).ok_or_else(|| Error::http(format!("Invalid content type: {}", s)))
+    }
+}
+
+impl fmt::Display for ContentType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.media_type)?;
+        for (name, value) in &self.params {
+            let needs_quotes = value.is_empty()
+                || value
+                    .chars()
+                    .any(|c| c.is_ascii_whitespace() || "()<>@,;:\\\"/[]?=".contains(c));
+            if needs_quotes {
+                let escaped = value.replace('\\', "\\\\").replace('"', "\\\"");
+                write!(f, "; {}=\"{}\"", name, escaped)?;
+            } else {
+                write!(f, "; {}={}", name, value)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Split a header value on `;`, ignoring separators inside quoted strings
+fn split_params(value: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut start = 0;
+    let mut in_quotes = false;
+    let mut escaped = false;
+
+    for (i, c) in value.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if in_quotes => escaped = true,
+            '"' => in_quotes = !in_quotes,
+            ';' if !in_quotes => {
+                parts.push(&value[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&value[start..]);
+    parts
+}
+
+/// Remove surrounding quotes and backslash escapes from a parameter value
+fn unquote(value: &str) -> String {
+    match value
+        .strip_prefix('"')
+        .and_then(|inner| inner.strip_suffix('"'))
+    {
+        Some(inner) => {
+            let mut unquoted = String::with_capacity(inner.len());
+            let mut chars = inner.chars();
+            while let Some(c) = chars.next() {
+                if c == '\\' {
+                    if let Some(next) = chars.next() {
+                        unquoted.push(next);
+                    }
+                } else {
+                    unquoted.push(c);
+                }
+            }
+            unquoted
+        }
+        None => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let content_type =
+            ContentType::parse("Multipart/Form-Data; boundary=\"abc;123\"; Charset=UTF-8").unwrap();
+        assert_eq!(content_type.media_type(), "multipart/form-data");
+        assert_eq!(content_type.kind(), "multipart");
+        assert_eq!(content_type.subtype(), "form-data");
+        assert!(content_type.is("multipart/form-data"));
+        assert_eq!(content_type.boundary(), Some("abc;123"));
+        assert_eq!(content_type.charset(), Some("UTF-8"));
+        assert_eq!(content_type.param("missing"), None);
+
+        assert!(ContentType::parse("text").is_none());
+        assert!(ContentType::parse("").is_none());
+        assert!("application/".parse::<ContentType>().is_err());
+    }
+
+    #[test]
+    fn test_display() {
+        let content_type: ContentType = "text/plain;charset=utf-8".parse().unwrap();
+        assert_eq!(content_type.to_string(), "text/plain; charset=utf-8");
+
+        let content_type = ContentType::parse("multipart/mixed; boundary=\"a b\"").unwrap();
+        assert_eq!(
+            content_type.to_string(),
+            "multipart/mixed; boundary=\"a b\""
+        );
+    }
+
+    #[test]
+    fn test_decode() {
+        let utf8 = ContentType::parse("text/plain").unwrap();
+        assert_eq!(utf8.decode("héllo".as_bytes()).unwrap(), "héllo");
+
+        let latin1 = ContentType::parse("text/plain; charset=ISO-8859-1").unwrap();
+        assert_eq!(latin1.decode(&[b'h', 0xE9, b'l']).unwrap(), "hél");
+
+        let ascii = ContentType::parse("text/plain; charset=us-ascii").unwrap();
+        assert!(ascii.decode(&[0xE9]).is_err());
+
+        let shift_jis = ContentType::parse("text/plain; charset=Shift_JIS").unwrap();
+        let err = shift_jis.decode(b"hello").unwrap_err();
+        assert!(err.to_string().contains("Unsupported charset: shift_jis"));
+    }
+}
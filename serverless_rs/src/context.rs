@@ -7,14 +7,24 @@ different serverless platforms.
 
 use serde_json::Value;
 use std::collections::HashMap;
+use std::str::FromStr;
 use std::time::{Duration, SystemTime};
 
+use crate::error::{Error, Result};
+use crate::metrics::{self, MetricUnit};
+use crate::trace;
+use crate::util;
+use crate::{detect_platform, Extensions, Request};
+
 /// A platform-agnostic execution context for serverless functions
 #[derive(Debug, Clone)]
 pub struct Context {
     /// Unique request ID
     request_id: String,
 
+    /// Request ID of the invocation this context was derived from with [`Context::child`]
+    parent_request_id: Option<String>,
+
     /// Function name
     function_name: String,
 
@@ -30,11 +40,36 @@ pub struct Context {
     /// Function execution deadline
     deadline: Option<SystemTime>,
 
+    /// Identifier the function was invoked with, such as a Lambda ARN
+    invoked_arn: Option<String>,
+
+    /// Cloud region the function runs in
+    region: Option<String>,
+
+    /// Cloud account or project that owns the function
+    account_id: Option<String>,
+
+    /// Deployment stage of the API that routed the request
+    stage: Option<String>,
+
     /// Environment variables
     env_vars: HashMap<String, String>,
 
     /// Platform-specific context data
     platform_data: Value,
+
+    /// Typed state attached by routers and middleware
+    extensions: Extensions,
+
+    /// Trace ID of the distributed trace this invocation belongs to
+    trace_id: Option<String>,
+
+    /// ID of the current span within the trace
+    span_id: Option<String>,
+
+    /// OpenTelemetry context carrying the invocation span
+    #[cfg(feature = "otel")]
+    otel: opentelemetry::Context,
 }
 
 impl Context {
@@ -42,16 +77,54 @@ impl Context {
     pub fn new() -> Self {
         Self {
             request_id: String::new(),
+            parent_request_id: None,
             function_name: String::new(),
             function_version: String::new(),
             memory_limit: None,
             remaining_time: None,
             deadline: None,
+            invoked_arn: None,
+            region: None,
+            account_id: None,
+            stage: None,
             env_vars: HashMap::new(),
             platform_data: Value::Null,
+            extensions: Extensions::new(),
+            trace_id: None,
+            span_id: None,
+            #[cfg(feature = "otel")]
+            otel: opentelemetry::Context::new(),
         }
     }
 
+    /// Build a context from the AWS Lambda context object
+    ///
+    /// Maps the request ID, function name and version, memory limit, deadline, and
+    /// the invoked function ARN along with the region and account ID it names.
+    /// See [`context_from_lambda`](crate::platforms::aws::context_from_lambda).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use serverless_rs::{json, Context};
+    ///
+    /// let ctx = Context::from_aws(&json!({
+    ///     "awsRequestId": "8f5a2c1e",
+    ///     "functionName": "orders",
+    ///     "memoryLimitInMB": "512",
+    ///     "getRemainingTimeInMillis": 30000,
+    ///     "invokedFunctionArn": "arn:aws:lambda:eu-west-1:123456789012:function:orders",
+    /// }));
+    /// assert_eq!(ctx.memory_limit(), Some(512));
+    /// assert!(ctx.deadline().is_some());
+    /// assert_eq!(ctx.region(), Some("eu-west-1"));
+    /// assert_eq!(ctx.account_id(), Some("123456789012"));
+    /// ```
+    #[cfg(feature = "aws")]
+    pub fn from_aws(context: &Value) -> Self {
+        crate::platforms::aws::context_from_lambda(context)
+    }
+
     /// Returns the unique request ID
     pub fn request_id(&self) -> &str {
         &self.request_id
@@ -63,6 +136,38 @@ impl Context {
         self
     }
 
+    /// Returns the request ID of the parent invocation, for contexts made with
+    /// [`Context::child`]
+    pub fn parent_request_id(&self) -> Option<&str> {
+        self.parent_request_id.as_deref()
+    }
+
+    /// Derive a context for a sub-task, such as one record of a batch
+    ///
+    /// The child keeps everything but the request ID, which becomes
+    /// `sub_request_id`, and records this context's request ID as its parent. Log
+    /// lines from the child include both, so they can be correlated with the
+    /// invocation.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use serverless_rs::Context;
+    ///
+    /// let ctx = Context::new().with_request_id("req-1");
+    /// let child = ctx.child("req-1:0");
+    /// assert_eq!(child.request_id(), "req-1:0");
+    /// assert_eq!(child.parent_request_id(), Some("req-1"));
+    /// ```
+    pub fn child(&self, sub_request_id: impl Into<String>) -> Self {
+        let mut child = self.clone();
+        child.parent_request_id = Some(std::mem::replace(
+            &mut child.request_id,
+            sub_request_id.into(),
+        ));
+        child
+    }
+
     /// Returns the function name
     pub fn function_name(&self) -> &str {
         &self.function_name
@@ -118,6 +223,105 @@ impl Context {
         self
     }
 
+    /// Returns the identifier the function was invoked with, if available
+    ///
+    /// On AWS this is the invoked function ARN, including any alias or version.
+    pub fn invoked_arn(&self) -> Option<&str> {
+        self.invoked_arn.as_deref()
+    }
+
+    /// Sets the invoked function identifier for this context
+    pub fn with_invoked_arn(mut self, invoked_arn: impl Into<String>) -> Self {
+        self.invoked_arn = Some(invoked_arn.into());
+        self
+    }
+
+    /// Returns the region the function runs in, if available
+    pub fn region(&self) -> Option<&str> {
+        self.region.as_deref()
+    }
+
+    /// Sets the region for this context
+    pub fn with_region(mut self, region: impl Into<String>) -> Self {
+        self.region = Some(region.into());
+        self
+    }
+
+    /// Returns the account that owns the function, if available
+    pub fn account_id(&self) -> Option<&str> {
+        self.account_id.as_deref()
+    }
+
+    /// Sets the account ID for this context
+    pub fn with_account_id(mut self, account_id: impl Into<String>) -> Self {
+        self.account_id = Some(account_id.into());
+        self
+    }
+
+    /// Returns the deployment stage of the API that routed the request, if available
+    pub fn stage(&self) -> Option<&str> {
+        self.stage.as_deref()
+    }
+
+    /// Sets the deployment stage for this context
+    pub fn with_stage(mut self, stage: impl Into<String>) -> Self {
+        self.stage = Some(stage.into());
+        self
+    }
+
+    /// Run `callback` once the time left before the deadline drops below `margin`
+    ///
+    /// Long-running handlers can use this to checkpoint or flush buffers before the
+    /// platform stops them. The callback runs on the timer thread shared by the whole
+    /// process, so it should be quick, and straight away if less than `margin` is
+    /// already left. The timer is cancelled when the returned guard is dropped, so
+    /// hold it until the handler finishes.
+    ///
+    /// Nothing happens for contexts without a [`deadline`](Context::deadline), or on
+    /// targets without a clock and threads.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use serverless_rs::{Context, Request, Response, Result};
+    /// use std::sync::atomic::{AtomicBool, Ordering};
+    /// use std::sync::Arc;
+    /// use std::time::Duration;
+    ///
+    /// fn consume(_req: Request, ctx: &Context) -> Result<Response> {
+    ///     let stop = Arc::new(AtomicBool::new(false));
+    ///     let flag = stop.clone();
+    ///     let _guard = ctx.on_deadline(Duration::from_secs(5), move || {
+    ///         flag.store(true, Ordering::Relaxed);
+    ///     });
+    ///
+    ///     let mut processed = 0;
+    ///     while processed < 100 && !stop.load(Ordering::Relaxed) {
+    ///         processed += 1;
+    ///     }
+    ///     // Commit offsets for the records processed so far
+    ///     Ok(Response::text(processed.to_string()))
+    /// }
+    /// ```
+    pub fn on_deadline<F>(&self, margin: Duration, callback: F) -> DeadlineGuard
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        let (Some(deadline), Some(now), Some(start)) =
+            (self.deadline, util::now(), util::stopwatch())
+        else {
+            return DeadlineGuard { _timer: None };
+        };
+        let wait = deadline
+            .checked_sub(margin)
+            .and_then(|fire_at| fire_at.duration_since(now).ok())
+            .unwrap_or(Duration::ZERO);
+
+        DeadlineGuard {
+            _timer: Some(util::call_at(start + wait, callback)),
+        }
+    }
+
     /// Returns the environment variables
     pub fn env_vars(&self) -> &HashMap<String, String> {
         &self.env_vars
@@ -134,6 +338,48 @@ impl Context {
         self.env_vars.get(name)
     }
 
+    /// Returns an environment variable parsed as `T`
+    ///
+    /// Returns `None` if the variable is not set, or the parse result otherwise.
+    pub fn env_parsed<T: FromStr>(&self, name: &str) -> Option<std::result::Result<T, T::Err>> {
+        self.env_var(name).map(|value| value.parse())
+    }
+
+    /// Returns an environment variable parsed as `T`, or `default` if it is not set
+    /// or fails to parse
+    pub fn env_or<T: FromStr>(&self, name: &str, default: T) -> T {
+        self.env_parsed(name)
+            .and_then(|value| value.ok())
+            .unwrap_or(default)
+    }
+
+    /// Returns an environment variable that the function requires
+    ///
+    /// Returns a requirements error if the variable is not set.
+    pub fn env_required(&self, name: &str) -> Result<&String> {
+        self.env_var(name).ok_or_else(|| {
+            Error::requirements(format!("Missing required environment variable: {}", name))
+        })
+    }
+
+    /// Returns a secret from the configured secret provider
+    ///
+    /// Secrets come from environment variables unless another provider was set with
+    /// [`set_secret_provider`](crate::secrets::set_secret_provider). Values are cached
+    /// for the life of the process; errors are not.
+    pub fn secret(&self, name: &str) -> Result<String> {
+        crate::secrets::resolve(name)
+    }
+
+    /// Returns the process-wide circuit breaker for the downstream called `name`
+    ///
+    /// Breakers remember failures across invocations on the same instance; see
+    /// [`circuit`](crate::circuit) for how they open and recover, and
+    /// [`circuit::configure`](crate::circuit::configure) to change their thresholds.
+    pub fn circuit_breaker(&self, name: &str) -> crate::circuit::CircuitBreaker {
+        crate::circuit::circuit_breaker(name)
+    }
+
     /// Returns the platform-specific context data
     pub fn platform_data(&self) -> &Value {
         &self.platform_data
@@ -146,32 +392,192 @@ impl Context {
     }
 
     /// Gets a typed value from platform-specific data
+    ///
+    /// The path is a dot-separated list of object keys and array indexes, such as
+    /// `aws.records.0.body`. Returns `None` if the path is missing or the value has a
+    /// different type; use [`Context::try_get_platform_data`] to tell these apart.
     pub fn get_platform_data<T: for<'de> serde::Deserialize<'de>>(&self, path: &str) -> Option<T> {
-        let parts: Vec<&str> = path.split('.').collect();
+        self.try_get_platform_data(path).ok()
+    }
+
+    /// Gets a typed value from platform-specific data, explaining any failure
+    ///
+    /// Returns a platform error naming the first missing segment if the path doesn't
+    /// exist, and a serialization error if the value can't be deserialized as `T`.
+    pub fn try_get_platform_data<T: for<'de> serde::Deserialize<'de>>(
+        &self,
+        path: &str,
+    ) -> Result<T> {
         let mut current = &self.platform_data;
 
-        for part in parts {
-            if let Some(obj) = current.as_object() {
-                if let Some(value) = obj.get(part) {
-                    current = value;
-                } else {
-                    return None;
-                }
-            } else {
-                return None;
-            }
+        for part in path.split('.') {
+            let next = match current {
+                Value::Object(obj) => obj.get(part),
+                Value::Array(items) => part.parse::<usize>().ok().and_then(|i| items.get(i)),
+                _ => None,
+            };
+            current = next.ok_or_else(|| {
+                Error::platform(format!(
+                    "No platform data at {}: segment {} not found",
+                    path, part
+                ))
+            })?;
         }
 
-        serde_json::from_value(current.clone()).ok()
+        serde_json::from_value(current.clone()).map_err(|err| {
+            Error::serialization(format!(
+                "Failed to deserialize platform data at {}: {}",
+                path, err
+            ))
+        })
+    }
+
+    /// Returns the typed extensions attached to this context
+    pub fn extensions(&self) -> &Extensions {
+        &self.extensions
+    }
+
+    /// Returns a mutable reference to the typed extensions
+    pub fn extensions_mut(&mut self) -> &mut Extensions {
+        &mut self.extensions
+    }
+
+    /// Returns the extension of the given type, if present
+    pub fn extension<T: Send + Sync + 'static>(&self) -> Option<&T> {
+        self.extensions.get::<T>()
+    }
+
+    /// Attaches a typed extension to this context, replacing any of the same type
+    pub fn with_extension<T: Send + Sync + 'static>(mut self, value: T) -> Self {
+        self.extensions.insert(value);
+        self
+    }
+
+    /// Returns the session loaded by [`SessionLayer`](crate::session::SessionLayer)
+    ///
+    /// Changes made through the returned handle are saved to the session cookie.
+    #[cfg(feature = "session")]
+    pub fn session(&self) -> Option<crate::session::Session> {
+        self.extension::<crate::session::Session>().cloned()
+    }
+
+    /// Returns the trace ID, if the invocation is part of a distributed trace
+    pub fn trace_id(&self) -> Option<&str> {
+        self.trace_id.as_deref()
+    }
+
+    /// Sets the trace ID for this context
+    pub fn with_trace_id(mut self, trace_id: impl Into<String>) -> Self {
+        self.trace_id = Some(trace_id.into());
+        self
+    }
+
+    /// Returns the ID of the current span, if any
+    pub fn span_id(&self) -> Option<&str> {
+        self.span_id.as_deref()
+    }
+
+    /// Sets the span ID for this context
+    pub fn with_span_id(mut self, span_id: impl Into<String>) -> Self {
+        self.span_id = Some(span_id.into());
+        self
+    }
+
+    /// Sets the trace and span IDs from a request's `traceparent` or `X-Amzn-Trace-Id`
+    /// header
+    ///
+    /// The context is returned unchanged if neither header carries a valid trace.
+    pub fn with_trace_from_request(mut self, req: &Request) -> Self {
+        if let Some((trace_id, span_id)) = trace::trace_from_request(req) {
+            self.trace_id = Some(trace_id);
+            self.span_id = Some(span_id);
+        }
+        self
+    }
+
+    /// Returns the OpenTelemetry span of the current invocation
+    ///
+    /// Handlers can use it to add events and attributes. Outside an instrumented
+    /// invocation this is a no-op span.
+    #[cfg(feature = "otel")]
+    pub fn current_span(&self) -> opentelemetry::trace::SpanRef<'_> {
+        use opentelemetry::trace::TraceContextExt;
+        self.otel.span()
+    }
+
+    /// Sets the OpenTelemetry context for this invocation
+    #[cfg(feature = "otel")]
+    pub(crate) fn with_otel_context(mut self, otel: opentelemetry::Context) -> Self {
+        self.otel = otel;
+        self
+    }
+
+    /// Emit a metric to standard output
+    ///
+    /// On AWS Lambda the metric is written as a CloudWatch Embedded Metric Format
+    /// line, so it is recorded without extra API calls. On other platforms it is
+    /// written as a StatsD line, with dimensions as tags.
+    pub fn emit_metric(
+        &self,
+        name: &str,
+        value: f64,
+        unit: MetricUnit,
+        dimensions: &[(&str, &str)],
+    ) {
+        println!(
+            "{}",
+            metrics::format_metric(detect_platform(), name, value, unit, dimensions)
+        );
     }
 
     /// Log a message to the platform-specific logging system
     /// This is a minimal implementation that will be enhanced by platform adapters
     pub fn log(&self, level: &str, message: &str) {
-        println!("[{}] {} - {}", level, self.request_id, message);
+        match &self.parent_request_id {
+            Some(parent) => println!(
+                "[{}] {} (parent {}) - {}",
+                level, self.request_id, parent, message
+            ),
+            None => println!("[{}] {} - {}", level, self.request_id, message),
+        }
+    }
+
+    /// Log a structured entry as a single JSON line
+    ///
+    /// The `level`, `requestId` and, for child contexts, `parentRequestId` are added
+    /// to the fields of an object entry; any other value is logged under `message`.
+    pub fn log_json(&self, level: &str, entry: Value) {
+        println!("{}", self.json_log_line(level, entry));
+    }
+
+    /// Build the JSON line written by [`Context::log_json`]
+    fn json_log_line(&self, level: &str, entry: Value) -> Value {
+        let mut line = serde_json::Map::new();
+        line.insert("level".to_string(), Value::String(level.to_string()));
+        line.insert(
+            "requestId".to_string(),
+            Value::String(self.request_id.clone()),
+        );
+        if let Some(parent) = &self.parent_request_id {
+            line.insert("parentRequestId".to_string(), Value::String(parent.clone()));
+        }
+        match entry {
+            Value::Object(fields) => line.extend(fields),
+            other => {
+                line.insert("message".to_string(), other);
+            }
+        }
+        Value::Object(line)
     }
 }
 
+/// Cancels a [`Context::on_deadline`] callback when dropped
+#[must_use = "the deadline callback is cancelled when the guard is dropped"]
+#[derive(Debug)]
+pub struct DeadlineGuard {
+    _timer: Option<util::Timer>,
+}
+
 impl Default for Context {
     fn default() -> Self {
         Self::new()
@@ -202,6 +608,83 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_env_accessors() {
+        let ctx = Context::new()
+            .with_env_var("PORT", "8080")
+            .with_env_var("DEBUG", "true")
+            .with_env_var("WORKERS", "many");
+
+        assert_eq!(ctx.env_parsed::<u16>("PORT"), Some(Ok(8080)));
+        assert!(ctx.env_parsed::<u32>("WORKERS").unwrap().is_err());
+        assert!(ctx.env_parsed::<u32>("MISSING").is_none());
+
+        assert!(ctx.env_or("DEBUG", false));
+        assert_eq!(ctx.env_or("WORKERS", 4u32), 4);
+        assert_eq!(ctx.env_or("MISSING", 3000u16), 3000);
+
+        assert_eq!(ctx.env_required("PORT").unwrap(), "8080");
+        assert!(matches!(
+            ctx.env_required("DATABASE_URL"),
+            Err(Error::Requirements(_))
+        ));
+    }
+
+    #[test]
+    fn test_trace_from_request() {
+        let req = Request::new().with_header(
+            "traceparent",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+        );
+        let ctx = Context::new().with_trace_from_request(&req);
+        assert_eq!(ctx.trace_id(), Some("4bf92f3577b34da6a3ce929d0e0e4736"));
+        assert_eq!(ctx.span_id(), Some("00f067aa0ba902b7"));
+
+        let ctx = Context::new().with_trace_from_request(&Request::new());
+        assert!(ctx.trace_id().is_none());
+        assert!(ctx.span_id().is_none());
+    }
+
+    #[test]
+    fn test_json_log_line() {
+        let ctx = Context::new().with_request_id("req-1");
+
+        let line = ctx.json_log_line("INFO", json!({ "status": 200 }));
+        assert_eq!(
+            line,
+            json!({ "level": "INFO", "requestId": "req-1", "status": 200 })
+        );
+
+        let line = ctx.json_log_line("WARN", json!("slow response"));
+        assert_eq!(line["message"], "slow response");
+    }
+
+    #[test]
+    fn test_child_context() {
+        let ctx = Context::new()
+            .with_request_id("req-1")
+            .with_function_name("batch")
+            .with_env_var("TABLE", "orders");
+
+        let child = ctx.child("req-1:3");
+        assert_eq!(child.request_id(), "req-1:3");
+        assert_eq!(child.parent_request_id(), Some("req-1"));
+        assert_eq!(child.function_name(), "batch");
+        assert_eq!(child.env_var("TABLE"), Some(&"orders".to_string()));
+        assert_eq!(ctx.parent_request_id(), None);
+
+        let line = child.json_log_line("INFO", json!({ "record": 3 }));
+        assert_eq!(
+            line,
+            json!({
+                "level": "INFO",
+                "requestId": "req-1:3",
+                "parentRequestId": "req-1",
+                "record": 3
+            })
+        );
+    }
+
     #[test]
     fn test_platform_data() {
         let platform_data = json!({
@@ -225,4 +708,92 @@ mod tests {
         let unknown: Option<String> = ctx.get_platform_data("aws.unknown");
         assert!(unknown.is_none());
     }
+
+    #[test]
+    fn test_platform_data_arrays() {
+        // Shaped like an SQS event batch
+        let ctx = Context::new().with_platform_data(json!({
+            "aws": {
+                "records": [
+                    {
+                        "messageId": "m-1",
+                        "body": "first",
+                        "attributes": { "ApproximateReceiveCount": "1" }
+                    },
+                    {
+                        "messageId": "m-2",
+                        "body": "second",
+                        "messageAttributes": {
+                            "tags": { "stringListValues": ["a", "b"] }
+                        }
+                    }
+                ]
+            }
+        }));
+
+        let body: String = ctx.get_platform_data("aws.records.1.body").unwrap();
+        assert_eq!(body, "second");
+        let count: String = ctx
+            .get_platform_data("aws.records.0.attributes.ApproximateReceiveCount")
+            .unwrap();
+        assert_eq!(count, "1");
+        let tag: String = ctx
+            .get_platform_data("aws.records.1.messageAttributes.tags.stringListValues.1")
+            .unwrap();
+        assert_eq!(tag, "b");
+
+        let missing = ctx.try_get_platform_data::<String>("aws.records.2.body");
+        assert!(matches!(missing, Err(Error::Platform(message)) if message.contains("segment 2")));
+        let not_index = ctx.try_get_platform_data::<String>("aws.records.first");
+        assert!(matches!(not_index, Err(Error::Platform(_))));
+        let wrong_type = ctx.try_get_platform_data::<u32>("aws.records.0.body");
+        assert!(matches!(wrong_type, Err(Error::Serialization(_))));
+    }
+
+    #[test]
+    fn test_on_deadline() {
+        use std::sync::mpsc;
+
+        let (fired, receiver) = mpsc::channel();
+        let ctx = Context::new().with_deadline(SystemTime::now() + Duration::from_millis(60));
+        let _guard = ctx.on_deadline(Duration::from_millis(40), move || {
+            fired.send(()).unwrap();
+        });
+        assert!(receiver.recv_timeout(Duration::from_secs(5)).is_ok());
+
+        // Past the margin already, the callback runs straight away
+        let (fired, receiver) = mpsc::channel();
+        let _guard = ctx.on_deadline(Duration::from_secs(60), move || {
+            fired.send(()).unwrap();
+        });
+        assert!(receiver.recv_timeout(Duration::from_secs(5)).is_ok());
+
+        // Dropping the guard cancels the callback
+        let (fired, receiver) = mpsc::channel();
+        let ctx = Context::new().with_deadline(SystemTime::now() + Duration::from_millis(150));
+        drop(ctx.on_deadline(Duration::from_millis(50), move || {
+            fired.send(()).unwrap();
+        }));
+        assert!(receiver.recv_timeout(Duration::from_millis(300)).is_err());
+
+        // Without a deadline there is nothing to wait for
+        let (fired, receiver) = mpsc::channel::<()>();
+        let _guard = Context::new().on_deadline(Duration::ZERO, move || {
+            fired.send(()).unwrap();
+        });
+        assert!(receiver.recv_timeout(Duration::from_millis(50)).is_err());
+    }
+
+    #[test]
+    fn test_extensions() {
+        struct Database(String);
+
+        let ctx = Context::new().with_extension(Database("postgres://admin".to_string()));
+        assert_eq!(ctx.extension::<Database>().unwrap().0, "postgres://admin");
+        assert!(ctx.extension::<String>().is_none());
+
+        // Clones share the attached state
+        let cloned = ctx.clone();
+        assert!(cloned.extensions().contains::<Database>());
+    }
 }
@@ -2,9 +2,14 @@
 Error types for serverless.rs
 */
 
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+use serde_json::Value;
 use std::fmt;
 use thiserror::Error;
 
+/// A boxed error kept as the cause of an [`Error`]
+pub type BoxError = Box<dyn std::error::Error + Send + Sync + 'static>;
+
 /// Error type for serverless.rs
 #[derive(Error, Debug)]
 pub enum Error {
@@ -31,6 +36,18 @@ pub enum Error {
     /// Unexpected error
     #[error("Unexpected error: {0}")]
     Unexpected(String),
+
+    /// Error answered with a specific HTTP status
+    #[error("{message}")]
+    WithStatus {
+        /// HTTP status code to respond with
+        status: u16,
+        /// Message returned in the response body
+        message: String,
+        /// Underlying cause, if any
+        #[source]
+        source: Option<BoxError>,
+    },
 }
 
 impl Error {
@@ -63,7 +80,189 @@ impl Error {
     pub fn unexpected<T: fmt::Display>(err: T) -> Self {
         Self::Unexpected(err.to_string())
     }
+
+    /// Creates an error that the adapters answer with the given HTTP status
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use serverless_rs::{Error, Request, Result};
+    ///
+    /// fn user_id(req: &Request) -> Result<u64> {
+    ///     let id = req.path_param("id").ok_or_else(|| Error::with_status(404, "user not found"))?;
+    ///     id.parse().map_err(|e| Error::wrap(400, e))
+    /// }
+    ///
+    /// let err = user_id(&Request::new()).unwrap_err();
+    /// assert_eq!(err.status(), 404);
+    /// assert_eq!(err.to_string(), "user not found");
+    /// ```
+    pub fn with_status(status: u16, message: impl Into<String>) -> Self {
+        Self::WithStatus {
+            status,
+            message: message.into(),
+            source: None,
+        }
+    }
+
+    /// Wraps an error as its cause, answered with the given HTTP status
+    ///
+    /// The message is the cause's message, and the cause is kept as the error's
+    /// [`source`](std::error::Error::source) for logging.
+    pub fn wrap<E>(status: u16, err: E) -> Self
+    where
+        E: std::error::Error + Send + Sync + 'static,
+    {
+        Self::WithStatus {
+            status,
+            message: err.to_string(),
+            source: Some(Box::new(err)),
+        }
+    }
+
+    /// Returns the HTTP status the adapters respond with for this error
+    ///
+    /// Errors created with [`Error::with_status`] or [`Error::wrap`] carry their own
+    /// status; every other error is a `500 Internal Server Error`.
+    pub fn status(&self) -> u16 {
+        match self {
+            Self::WithStatus { status, .. } => *status,
+            _ => 500,
+        }
+    }
+
+    /// Returns the kind of error, as the `type` of its JSON form
+    ///
+    /// One of `serialization`, `http`, `platform`, `function`, `requirements` or
+    /// `unexpected`. Errors created with [`Error::with_status`] are `http` errors.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Serialization(_) => "serialization",
+            Self::Http(_) | Self::WithStatus { .. } => "http",
+            Self::Platform(_) => "platform",
+            Self::Function(_) => "function",
+            Self::Requirements(_) => "requirements",
+            Self::Unexpected(_) => "unexpected",
+        }
+    }
+
+    /// Returns the error's message, without the prefix naming its kind
+    pub fn message(&self) -> &str {
+        match self {
+            Self::Serialization(message)
+            | Self::Http(message)
+            | Self::Platform(message)
+            | Self::Function(message)
+            | Self::Requirements(message)
+            | Self::Unexpected(message)
+            | Self::WithStatus { message, .. } => message,
+        }
+    }
+
+    /// Returns the JSON form of this error, as the adapters send it in error responses
+    ///
+    /// The shape is `{ "type": ..., "message": ..., "status": ... }`, see
+    /// [`Error::kind`], [`Error::message`] and [`Error::status`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use serverless_rs::{json, Error};
+    ///
+    /// let err = Error::with_status(400, "missing name");
+    /// assert_eq!(
+    ///     err.to_json(),
+    ///     json!({ "type": "http", "message": "missing name", "status": 400 })
+    /// );
+    /// ```
+    pub fn to_json(&self) -> Value {
+        serde_json::json!({
+            "type": self.kind(),
+            "message": self.message(),
+            "status": self.status(),
+        })
+    }
+}
+
+impl Serialize for Error {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let mut error = serializer.serialize_struct("Error", 3)?;
+        error.serialize_field("type", self.kind())?;
+        error.serialize_field("message", self.message())?;
+        error.serialize_field("status", &self.status())?;
+        error.end()
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(err: std::io::Error) -> Self {
+        Self::wrap(500, err)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(err: serde_json::Error) -> Self {
+        Self::wrap(500, err)
+    }
 }
 
 /// Result type for serverless.rs
 pub type Result<T> = std::result::Result<T, Error>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::error::Error as _;
+
+    #[test]
+    fn test_error_status() {
+        let err = Error::with_status(404, "user not found");
+        assert_eq!(err.status(), 404);
+        assert_eq!(err.to_string(), "user not found");
+        assert!(err.source().is_none());
+
+        assert_eq!(Error::function("boom").status(), 500);
+    }
+
+    #[test]
+    fn test_error_json() {
+        let err = Error::with_status(404, "user not found");
+        assert_eq!(
+            serde_json::to_value(&err).unwrap(),
+            serde_json::json!({ "type": "http", "message": "user not found", "status": 404 })
+        );
+        assert_eq!(serde_json::to_value(&err).unwrap(), err.to_json());
+
+        let cases = [
+            (Error::serialization("bad"), "serialization"),
+            (Error::http("bad"), "http"),
+            (Error::platform("bad"), "platform"),
+            (Error::function("bad"), "function"),
+            (Error::requirements("bad"), "requirements"),
+            (Error::unexpected("bad"), "unexpected"),
+        ];
+        for (err, kind) in cases {
+            let json = err.to_json();
+            assert_eq!(json["type"], kind);
+            assert_eq!(json["message"], "bad");
+            assert_eq!(json["status"], 500);
+        }
+    }
+
+    #[test]
+    fn test_error_source() {
+        fn read() -> Result<Vec<u8>> {
+            Ok(std::fs::read("/definitely/not/here")?)
+        }
+
+        let err = read().unwrap_err();
+        assert_eq!(err.status(), 500);
+        let source = err.source().unwrap();
+        assert!(source.downcast_ref::<std::io::Error>().is_some());
+
+        let parse = serde_json::from_str::<u32>("x").unwrap_err();
+        let err = Error::wrap(400, parse);
+        assert_eq!(err.status(), 400);
+        assert!(err.source().unwrap().is::<serde_json::Error>());
+    }
+}
@@ -0,0 +1,129 @@
+/*!
+Typed extension storage for serverless.rs.
+
+This module provides a type-keyed map used to attach shared state and
+middleware-computed data to a [`Context`](crate::Context).
+*/
+
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+/// A type-keyed map holding at most one value per type
+///
+/// Values are stored behind an `Arc`, so cloning an `Extensions` map is cheap and
+/// clones share the stored values.
+///
+/// # Examples
+///
+/// ```
+/// use serverless_rs::Extensions;
+///
+/// struct Database(&'static str);
+///
+/// let mut extensions = Extensions::new();
+/// extensions.insert(Database("postgres://localhost/app"));
+///
+/// assert_eq!(extensions.get::<Database>().unwrap().0, "postgres://localhost/app");
+/// ```
+#[derive(Clone, Default)]
+pub struct Extensions {
+    map: HashMap<TypeId, Arc<dyn Any + Send + Sync>>,
+}
+
+impl Extensions {
+    /// Creates an empty extensions map
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Inserts a value, replacing any existing value of the same type
+    pub fn insert<T: Send + Sync + 'static>(&mut self, value: T) {
+        self.map.insert(TypeId::of::<T>(), Arc::new(value));
+    }
+
+    /// Returns a reference to the value of the given type, if present
+    pub fn get<T: Send + Sync + 'static>(&self) -> Option<&T> {
+        self.map
+            .get(&TypeId::of::<T>())
+            .and_then(|value| value.downcast_ref::<T>())
+    }
+
+    /// Returns whether a value of the given type is present
+    pub fn contains<T: Send + Sync + 'static>(&self) -> bool {
+        self.map.contains_key(&TypeId::of::<T>())
+    }
+
+    /// Removes the value of the given type, returning whether one was present
+    pub fn remove<T: Send + Sync + 'static>(&mut self) -> bool {
+        self.map.remove(&TypeId::of::<T>()).is_some()
+    }
+
+    /// Copies all values from another map, replacing values of the same type
+    pub fn extend(&mut self, other: &Extensions) {
+        for (type_id, value) in &other.map {
+            self.map.insert(*type_id, Arc::clone(value));
+        }
+    }
+
+    /// Returns the number of stored values
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns whether the map is empty
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+}
+
+impl fmt::Debug for Extensions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Extensions")
+            .field("len", &self.map.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    struct Counter(u32);
+
+    #[test]
+    fn test_insert_and_get() {
+        let mut extensions = Extensions::new();
+        assert!(extensions.is_empty());
+
+        extensions.insert(Counter(1));
+        extensions.insert("label");
+        assert_eq!(extensions.len(), 2);
+        assert_eq!(extensions.get::<Counter>(), Some(&Counter(1)));
+        assert_eq!(extensions.get::<&str>(), Some(&"label"));
+        assert!(extensions.get::<String>().is_none());
+
+        // Inserting the same type replaces the value
+        extensions.insert(Counter(2));
+        assert_eq!(extensions.get::<Counter>(), Some(&Counter(2)));
+
+        assert!(extensions.remove::<Counter>());
+        assert!(!extensions.contains::<Counter>());
+    }
+
+    #[test]
+    fn test_extend() {
+        let mut base = Extensions::new();
+        base.insert(Counter(1));
+
+        let mut other = Extensions::new();
+        other.insert(Counter(2));
+        other.insert(42u64);
+
+        base.extend(&other);
+        assert_eq!(base.get::<Counter>(), Some(&Counter(2)));
+        assert_eq!(base.get::<u64>(), Some(&42));
+    }
+}
@@ -0,0 +1,165 @@
+/*!
+Typed handler arguments for serverless.rs.
+
+This module provides the [`FromRequest`] trait and the [`Json`], [`Query`] and
+[`Path`] extractors. Functions marked with `#[serverless]` can take extractors as
+arguments alongside `Request` and `&Context`; the generated wrapper extracts each one
+and returns its rejection, a `400 Bad Request`, if extraction fails.
+
+```
+use serde::Deserialize;
+use serverless_rs::extract::{Json, Path};
+use serverless_rs::{Context, Response, Result};
+
+#[derive(Deserialize)]
+struct NewComment {
+    text: String,
+}
+
+#[derive(Deserialize)]
+struct PostId {
+    id: u64,
+}
+
+// With #[serverless] and #[route(method = "POST", path = "/posts/{id}/comments")]
+async fn comment(
+    Path(post): Path<PostId>,
+    Json(comment): Json<NewComment>,
+    _ctx: &Context,
+) -> Result<Response> {
+    Ok(Response::text(format!("post {}: {}", post.id, comment.text)))
+}
+```
+*/
+
+use serde::de::DeserializeOwned;
+use serde_json::json;
+use std::collections::HashMap;
+
+use crate::{Request, Response};
+
+/// A value that can be extracted from a request
+///
+/// The error is a ready-made response returned to the caller in place of the
+/// handler's, as with [`Request::body_json_or_400`].
+pub trait FromRequest: Sized {
+    /// Extract the value from the request, or return the rejection response
+    fn from_request(req: &Request) -> std::result::Result<Self, Response>;
+}
+
+/// Extracts the JSON body
+///
+/// Rejected like [`Request::body_json_or_400`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Json<T>(pub T);
+
+impl<T: DeserializeOwned> FromRequest for Json<T> {
+    fn from_request(req: &Request) -> std::result::Result<Self, Response> {
+        req.body_json_or_400().map(Json)
+    }
+}
+
+/// Extracts the query parameters into a type with a field per parameter
+///
+/// Values are parsed as in a URL-encoded form, so numbers and booleans can be
+/// read into numeric and `bool` fields. Rejected with
+/// `{"error": "invalid_query", "message"}`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Query<T>(pub T);
+
+impl<T: DeserializeOwned> FromRequest for Query<T> {
+    fn from_request(req: &Request) -> std::result::Result<Self, Response> {
+        deserialize_params(req.query(), "invalid_query").map(Query)
+    }
+}
+
+/// Extracts the path parameters into a type with a field per parameter
+///
+/// Values are parsed as [`Query`] values are. Rejected with
+/// `{"error": "invalid_path", "message"}`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Path<T>(pub T);
+
+impl<T: DeserializeOwned> FromRequest for Path<T> {
+    fn from_request(req: &Request) -> std::result::Result<Self, Response> {
+        deserialize_params(req.path_params(), "invalid_path").map(Path)
+    }
+}
+
+/// Deserialize string parameters as URL-encoded form fields
+fn deserialize_params<T: DeserializeOwned>(
+    params: &HashMap<String, String>,
+    error: &str,
+) -> std::result::Result<T, Response> {
+    serde_urlencoded::to_string(params)
+        .map_err(|err| err.to_string())
+        .and_then(|form| serde_urlencoded::from_str(&form).map_err(|err| err.to_string()))
+        .map_err(|message| {
+            Response::new()
+                .with_status(400)
+                .with_header("Content-Type", "application/json")
+                .with_body(json!({ "error": error, "message": message }).to_string())
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+    use serde_json::Value;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Page {
+        page: u32,
+        sort: Option<String>,
+        desc: bool,
+    }
+
+    fn rejection(resp: Response) -> Value {
+        assert_eq!(resp.status(), 400);
+        serde_json::from_slice(resp.body()).unwrap()
+    }
+
+    #[test]
+    fn test_query_and_path() {
+        let req = Request::new()
+            .with_query("page", "2")
+            .with_query("desc", "true")
+            .with_path_param("id", "42");
+
+        let Query(page) = Query::<Page>::from_request(&req).unwrap();
+        assert_eq!(
+            page,
+            Page {
+                page: 2,
+                sort: None,
+                desc: true
+            }
+        );
+
+        #[derive(Deserialize)]
+        struct Id {
+            id: u64,
+        }
+        let Path(id) = Path::<Id>::from_request(&req).unwrap();
+        assert_eq!(id.id, 42);
+
+        let req = req.with_query("page", "two");
+        let body = rejection(Query::<Page>::from_request(&req).unwrap_err());
+        assert_eq!(body["error"], "invalid_query");
+
+        let body = rejection(Path::<Page>::from_request(&req).unwrap_err());
+        assert_eq!(body["error"], "invalid_path");
+    }
+
+    #[test]
+    fn test_json() {
+        let req = Request::new().with_body(r#"{"page":1,"desc":false}"#);
+        let Json(page) = Json::<Page>::from_request(&req).unwrap();
+        assert_eq!(page.page, 1);
+
+        let req = Request::new().with_body("{");
+        let body = rejection(Json::<Page>::from_request(&req).unwrap_err());
+        assert_eq!(body["error"], "invalid_json");
+    }
+}
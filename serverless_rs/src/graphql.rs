@@ -0,0 +1,195 @@
+/*!
+GraphQL over HTTP support for serverless.rs.
+
+[`Request::graphql_request`] reads a [`GraphQLRequest`] from the query parameters of
+a `GET` request or from the body of a `POST`, and [`Response::graphql`] serializes an
+execution result. The schema is left to the application: anything that executes a
+query and returns a serializable `{ data, errors }` result can sit in between, such
+as an `async-graphql` schema.
+
+Requires the `graphql` feature.
+
+# Examples
+
+```
+use serverless_rs::graphql::GraphQLRequest;
+use serverless_rs::{json, Context, Request, Response, Result, Value};
+
+// Stands in for a schema from a GraphQL library
+fn execute(request: &GraphQLRequest) -> Value {
+    match request.query.contains("hello") {
+        true => json!({ "data": { "hello": "world" } }),
+        false => json!({ "errors": [{ "message": "unknown field" }] }),
+    }
+}
+
+fn graphql(req: Request, _ctx: &Context) -> Result<Response> {
+    let request = req.graphql_request()?;
+    Response::graphql(&execute(&request))
+}
+
+let req = Request::new()
+    .with_method_str("POST")
+    .with_header("Content-Type", "application/json")
+    .with_body(r#"{"query": "{ hello }"}"#);
+let resp = graphql(req, &Context::new()).unwrap();
+assert_eq!(resp.body(), br#"{"data":{"hello":"world"}}"#);
+```
+*/
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::error::{Error, Result};
+use crate::Request;
+
+/// Content type of GraphQL documents sent as the whole request body
+const GRAPHQL_CONTENT_TYPE: &str = "application/graphql";
+
+/// A GraphQL request: the document, its variables and the operation to run
+///
+/// Serializes to the standard `{ query, variables, operationName, extensions }`
+/// shape, which GraphQL libraries accept as their own request type.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GraphQLRequest {
+    /// The GraphQL document
+    pub query: String,
+
+    /// Values for the variables the document declares
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub variables: Option<Map<String, Value>>,
+
+    /// Name of the operation to run, for documents with several
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub operation_name: Option<String>,
+
+    /// Protocol extensions, such as persisted query hashes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extensions: Option<Map<String, Value>>,
+}
+
+impl GraphQLRequest {
+    /// Create a request for a GraphQL document
+    pub fn new(query: impl Into<String>) -> Self {
+        Self {
+            query: query.into(),
+            ..Self::default()
+        }
+    }
+}
+
+/// Read a GraphQL request from query parameters or the body, see
+/// [`Request::graphql_request`]
+pub(crate) fn graphql_request(req: &Request) -> Result<GraphQLRequest> {
+    match req.method_str().as_deref() {
+        Some("GET") | None => from_query(req),
+        Some("POST") => from_body(req),
+        Some(method) => Err(Error::with_status(
+            405,
+            format!("GraphQL requests must use GET or POST, not {}", method),
+        )),
+    }
+}
+
+fn from_query(req: &Request) -> Result<GraphQLRequest> {
+    let query = req
+        .query_param("query")
+        .ok_or_else(|| Error::with_status(400, "Missing GraphQL query parameter"))?;
+    Ok(GraphQLRequest {
+        query: query.clone(),
+        variables: json_param(req, "variables")?,
+        operation_name: req
+            .query_param("operationName")
+            .filter(|name| !name.is_empty())
+            .cloned(),
+        extensions: json_param(req, "extensions")?,
+    })
+}
+
+/// Parse a query parameter holding a JSON object
+fn json_param(req: &Request, name: &str) -> Result<Option<Map<String, Value>>> {
+    match req.query_param(name).filter(|value| !value.is_empty()) {
+        Some(value) => serde_json::from_str(value)
+            .map_err(|err| Error::with_status(400, format!("Invalid GraphQL {}: {}", name, err))),
+        None => Ok(None),
+    }
+}
+
+fn from_body(req: &Request) -> Result<GraphQLRequest> {
+    let is_document = req
+        .content_type()
+        .is_some_and(|content_type| content_type.is(GRAPHQL_CONTENT_TYPE));
+    if is_document {
+        let query = req
+            .body_string()
+            .map_err(|err| Error::with_status(400, format!("Invalid GraphQL document: {}", err)))?;
+        return Ok(GraphQLRequest::new(query));
+    }
+    req.body_json()
+        .map_err(|err| Error::with_status(400, format!("Invalid GraphQL request: {}", err)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_graphql_request() {
+        let req = Request::new()
+            .with_method_str("POST")
+            .with_header("Content-Type", "application/json")
+            .with_body(
+                r#"{"query":"query Q($id: ID!) { user(id: $id) { name } }","variables":{"id":"7"},"operationName":"Q"}"#,
+            );
+        let request = req.graphql_request().unwrap();
+        assert_eq!(request.operation_name.as_deref(), Some("Q"));
+        assert_eq!(request.variables.unwrap()["id"], "7");
+
+        let req = Request::new()
+            .with_method_str("GET")
+            .with_query("query", "{ me { name } }")
+            .with_query("variables", r#"{"first":10}"#);
+        let request = req.graphql_request().unwrap();
+        assert_eq!(request.query, "{ me { name } }");
+        assert_eq!(request.variables.unwrap()["first"], 10);
+        assert_eq!(request.operation_name, None);
+
+        let req = Request::new()
+            .with_method_str("POST")
+            .with_header("Content-Type", "application/graphql")
+            .with_body("{ me { name } }");
+        assert_eq!(
+            req.graphql_request().unwrap(),
+            GraphQLRequest::new("{ me { name } }")
+        );
+
+        // Serializes to the shape GraphQL libraries expect
+        assert_eq!(
+            serde_json::to_value(GraphQLRequest::new("{ me }")).unwrap(),
+            json!({ "query": "{ me }" })
+        );
+    }
+
+    #[test]
+    fn test_graphql_request_errors() {
+        let status = |req: Request| req.graphql_request().unwrap_err().status();
+
+        assert_eq!(status(Request::new().with_method_str("GET")), 400);
+        assert_eq!(
+            status(
+                Request::new()
+                    .with_method_str("GET")
+                    .with_query("query", "{ me }")
+                    .with_query("variables", "not json")
+            ),
+            400
+        );
+        assert_eq!(
+            status(Request::new().with_method_str("POST").with_body("{}")),
+            400
+        );
+        assert_eq!(status(Request::new().with_method_str("PUT")), 405);
+    }
+}
@@ -5,8 +5,14 @@ This module defines the core Handler trait that serverless functions implement.
 */
 
 use async_trait::async_trait;
+use futures::future::{self, BoxFuture, Either};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::time::Duration;
 
-use crate::{error::Result, Context, Request, Response};
+use crate::{
+    error::{Error, Result},
+    Context, Request, Response,
+};
 
 /// Handler trait for implementing serverless functions
 ///
@@ -54,6 +60,213 @@ where
     }
 }
 
+/// A handler backed by a function returning a boxed future
+///
+/// Created by [`handler_fn`].
+pub struct FnHandler<F> {
+    function: F,
+}
+
+/// Create a [`Handler`] from an async function
+///
+/// The function must return a boxed future, which lets it borrow the context. This
+/// is how `#[serverless]` functions are registered with a router.
+///
+/// # Examples
+///
+/// ```
+/// use serverless_rs::{handler_fn, BoxFuture, Context, Request, Response, Result, RouterBuilder};
+///
+/// async fn hello(_req: Request, ctx: &Context) -> Result<Response> {
+///     Ok(Response::text(format!("Hello from {}", ctx.function_name())))
+/// }
+///
+/// fn boxed(req: Request, ctx: &Context) -> BoxFuture<'_, Result<Response>> {
+///     Box::pin(hello(req, ctx))
+/// }
+///
+/// let router = RouterBuilder::new().get("/hello", handler_fn(boxed)).build();
+/// ```
+pub fn handler_fn<F>(function: F) -> FnHandler<F>
+where
+    F: for<'a> Fn(Request, &'a Context) -> BoxFuture<'a, Result<Response>> + Send + Sync + 'static,
+{
+    FnHandler { function }
+}
+
+#[async_trait]
+impl<F> Handler for FnHandler<F>
+where
+    F: for<'a> Fn(Request, &'a Context) -> BoxFuture<'a, Result<Response>> + Send + Sync + 'static,
+{
+    async fn handle(&self, req: Request, ctx: &Context) -> Result<Response> {
+        (self.function)(req, ctx).await
+    }
+}
+
+/// Combinators for post-processing a [`Handler`]'s responses
+///
+/// These are a lightweight alternative to [`Middleware`](crate::Middleware) for
+/// simple cases, such as adding a header to the responses of one route. Implemented
+/// for every handler.
+///
+/// # Examples
+///
+/// ```
+/// use serverless_rs::{Context, Handler, HandlerExt, Request, Response, Result};
+///
+/// fn hello(_req: Request, _ctx: &Context) -> Result<Response> {
+///     Ok(Response::text("Hello!"))
+/// }
+///
+/// let handler = hello
+///     .map_response(|resp| resp.with_header("X-Frame-Options", "DENY"))
+///     .and_then(|resp, ctx| {
+///         Box::pin(async move {
+///             Ok(resp.with_header("X-Function", ctx.function_name()))
+///         })
+///     });
+/// ```
+pub trait HandlerExt: Handler + Sized {
+    /// Transform every successful response with `f`
+    fn map_response<F>(self, f: F) -> MapResponse<Self, F>
+    where
+        F: Fn(Response) -> Response + Send + Sync + 'static,
+    {
+        MapResponse { handler: self, f }
+    }
+
+    /// Run `f` on every successful response, with access to the context
+    ///
+    /// Like [`handler_fn`], `f` returns a boxed future so it can borrow the context.
+    /// An error from `f` becomes the handler's result.
+    fn and_then<F>(self, f: F) -> AndThen<Self, F>
+    where
+        F: for<'a> Fn(Response, &'a Context) -> BoxFuture<'a, Result<Response>>
+            + Send
+            + Sync
+            + 'static,
+    {
+        AndThen { handler: self, f }
+    }
+
+    /// Fail with a `504 Gateway Timeout` error if the handler runs longer than `timeout`
+    ///
+    /// This bounds a single slow route independently of the platform deadline. When
+    /// the time is up the handler's future is dropped and an error with status 504 is
+    /// returned, which the platform adapters answer with a 504 response. The timeout
+    /// is only noticed when the handler yields, so a handler that blocks its thread
+    /// runs to completion.
+    ///
+    /// Timeouts share one background timer thread rather than starting a thread per
+    /// call. On `wasm32-unknown-unknown`, which has neither a clock nor threads, the
+    /// timeout never fires and the handler runs until it completes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use serverless_rs::{Context, HandlerExt, Request, Response, Result, RouterBuilder};
+    /// use std::time::Duration;
+    ///
+    /// fn report(_req: Request, _ctx: &Context) -> Result<Response> {
+    ///     Ok(Response::text("report"))
+    /// }
+    ///
+    /// let router = RouterBuilder::new()
+    ///     .get("/report", report.with_timeout(Duration::from_secs(5)))
+    ///     .build();
+    /// ```
+    fn with_timeout(self, timeout: Duration) -> Timeout<Self> {
+        Timeout {
+            handler: self,
+            timeout,
+        }
+    }
+}
+
+impl<H: Handler> HandlerExt for H {}
+
+/// A handler whose responses are transformed by a function
+///
+/// Created by [`HandlerExt::map_response`].
+pub struct MapResponse<H, F> {
+    handler: H,
+    f: F,
+}
+
+#[async_trait]
+impl<H, F> Handler for MapResponse<H, F>
+where
+    H: Handler,
+    F: Fn(Response) -> Response + Send + Sync + 'static,
+{
+    async fn handle(&self, req: Request, ctx: &Context) -> Result<Response> {
+        self.handler.handle(req, ctx).await.map(&self.f)
+    }
+}
+
+/// A handler whose responses are post-processed by an async function
+///
+/// Created by [`HandlerExt::and_then`].
+pub struct AndThen<H, F> {
+    handler: H,
+    f: F,
+}
+
+#[async_trait]
+impl<H, F> Handler for AndThen<H, F>
+where
+    H: Handler,
+    F: for<'a> Fn(Response, &'a Context) -> BoxFuture<'a, Result<Response>> + Send + Sync + 'static,
+{
+    async fn handle(&self, req: Request, ctx: &Context) -> Result<Response> {
+        let resp = self.handler.handle(req, ctx).await?;
+        (self.f)(resp, ctx).await
+    }
+}
+
+/// A handler that fails if it doesn't complete in time
+///
+/// Created by [`HandlerExt::with_timeout`].
+pub struct Timeout<H> {
+    handler: H,
+    timeout: Duration,
+}
+
+#[async_trait]
+impl<H: Handler> Handler for Timeout<H> {
+    async fn handle(&self, req: Request, ctx: &Context) -> Result<Response> {
+        let handle = self.handler.handle(req, ctx);
+        match future::select(handle, crate::util::sleep(self.timeout)).await {
+            Either::Left((result, _)) => result,
+            Either::Right(((), _)) => Err(Error::with_status(
+                504,
+                format!("Handler timed out after {:?}", self.timeout),
+            )),
+        }
+    }
+}
+
+/// Run a handler invocation, converting a panic into an error
+///
+/// The panic message is logged to stderr and returned as [`Error::Unexpected`], which
+/// the platform adapters turn into a 500 response. Used by the handler wrappers that
+/// `#[serverless]` generates.
+pub fn catch_panic<F>(invoke: F) -> Result<Response>
+where
+    F: FnOnce() -> Result<Response>,
+{
+    catch_unwind(AssertUnwindSafe(invoke)).unwrap_or_else(|payload| {
+        let message = payload
+            .downcast_ref::<&str>()
+            .map(|message| message.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "unknown panic".to_string());
+        eprintln!("[ERROR] Handler panicked: {}", message);
+        Err(Error::unexpected(format!("Handler panicked: {}", message)))
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -137,4 +350,98 @@ mod tests {
             "Hello, world!"
         );
     }
+
+    #[tokio::test]
+    async fn test_handler_fn() {
+        async fn greet(req: Request, _ctx: &Context) -> Result<Response> {
+            let name = req.path_param("name").cloned().unwrap_or_default();
+            Ok(Response::text(format!("Hello, {}!", name)))
+        }
+
+        fn boxed(req: Request, ctx: &Context) -> BoxFuture<'_, Result<Response>> {
+            Box::pin(greet(req, ctx))
+        }
+
+        let handler = handler_fn(boxed);
+        let req = Request::new().with_path_param("name", "Ada");
+        let response = handler.handle(req, &Context::new()).await.unwrap();
+        assert_eq!(response.body(), b"Hello, Ada!");
+    }
+
+    #[tokio::test]
+    async fn test_combinators() {
+        fn hello(req: Request, _ctx: &Context) -> Result<Response> {
+            match req.query_param("fail") {
+                Some(_) => Err(Error::function("failed")),
+                None => Ok(Response::text("Hello!")),
+            }
+        }
+
+        let handler = hello
+            .map_response(|resp| resp.with_header("X-Frame-Options", "DENY"))
+            .and_then(|resp, ctx| {
+                Box::pin(async move { Ok(resp.with_header("X-Function", ctx.function_name())) })
+            });
+
+        let ctx = Context::new().with_function_name("greeter");
+        let response = handler.handle(Request::new(), &ctx).await.unwrap();
+        assert_eq!(response.body(), b"Hello!");
+        assert_eq!(response.header("X-Frame-Options").unwrap(), "DENY");
+        assert_eq!(response.header("X-Function").unwrap(), "greeter");
+
+        // Errors skip the combinators
+        let req = Request::new().with_query("fail", "1");
+        assert!(handler.handle(req, &ctx).await.is_err());
+
+        // An error from `and_then` becomes the result
+        let handler =
+            hello.and_then(|_resp, _ctx| Box::pin(async { Err(Error::function("rejected")) }));
+        assert!(handler.handle(Request::new(), &ctx).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_with_timeout() {
+        struct Slow(Duration);
+
+        #[async_trait]
+        impl Handler for Slow {
+            async fn handle(&self, _req: Request, _ctx: &Context) -> Result<Response> {
+                crate::util::sleep(self.0).await;
+                Ok(Response::text("done"))
+            }
+        }
+
+        let ctx = Context::new();
+        let slow = Slow(Duration::from_secs(5)).with_timeout(Duration::from_millis(20));
+        let err = slow.handle(Request::new(), &ctx).await.unwrap_err();
+        assert_eq!(err.status(), 504);
+        assert!(err.to_string().contains("timed out after 20ms"), "{}", err);
+
+        let fast = Slow(Duration::from_millis(1)).with_timeout(Duration::from_secs(5));
+        let response = fast.handle(Request::new(), &ctx).await.unwrap();
+        assert_eq!(response.body(), b"done");
+
+        // Errors from the handler pass through unchanged
+        fn failing(_req: Request, _ctx: &Context) -> Result<Response> {
+            Err(Error::with_status(409, "conflict"))
+        }
+        let err = failing
+            .with_timeout(Duration::from_secs(5))
+            .handle(Request::new(), &ctx)
+            .await
+            .unwrap_err();
+        assert_eq!(err.status(), 409);
+    }
+
+    #[test]
+    fn test_catch_panic() {
+        let result = catch_panic(|| Ok(Response::text("fine")));
+        assert!(result.is_ok());
+
+        let result = catch_panic(|| panic!("boom {}", 42));
+        match result {
+            Err(Error::Unexpected(message)) => assert_eq!(message, "Handler panicked: boom 42"),
+            other => panic!("expected an unexpected error, got {:?}", other),
+        }
+    }
 }
@@ -0,0 +1,385 @@
+/*!
+Idempotency middleware for serverless.rs.
+
+[`IdempotencyLayer`] reads the `Idempotency-Key` request header and, when a response
+for the same key was stored within the TTL, returns it instead of running the
+handler again. Keys are scoped to the request method and path. While the first
+request with a key is still running, a repeat of it gets `409 Conflict` rather than
+running the handler a second time.
+
+Responses are kept in an [`IdempotencyStore`]. [`MemoryStore`] keeps them in
+process, which suits the local server; production deployments can plug in a
+shared store such as DynamoDB or Workers KV.
+*/
+
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::error::{Error, Result};
+use crate::util;
+use crate::{Context, Handler, Middleware, Request, Response};
+
+/// Header carrying the client's idempotency key
+const IDEMPOTENCY_KEY_HEADER: &str = "Idempotency-Key";
+
+/// Header added to responses replayed from the store
+const REPLAYED_HEADER: &str = "Idempotent-Replayed";
+
+/// Storage for responses keyed by idempotency key
+#[async_trait]
+pub trait IdempotencyStore: Send + Sync + 'static {
+    /// Returns the stored response for `key`, if present and not expired
+    async fn get(&self, key: &str) -> Result<Option<Response>>;
+
+    /// Store a response for `key`, expiring after `ttl`
+    ///
+    /// This replaces any reservation for `key`.
+    async fn put(&self, key: &str, response: &Response, ttl: Duration) -> Result<()>;
+
+    /// Reserves `key` for a request in progress, expiring after `ttl`
+    ///
+    /// Returns `false` if `key` already holds a response or a reservation. This must
+    /// be an atomic put-if-absent, so that of two concurrent requests with the same
+    /// key only one runs the handler.
+    async fn reserve(&self, key: &str, ttl: Duration) -> Result<bool>;
+
+    /// Drops the reservation for `key`, so the request can be retried
+    ///
+    /// A stored response for `key` is kept.
+    async fn release(&self, key: &str) -> Result<()>;
+}
+
+/// A stored response, or a reservation while `response` is `None`, and its expiry
+#[derive(Debug, Clone)]
+struct Entry {
+    response: Option<Response>,
+    expires: Instant,
+}
+
+/// An in-process [`IdempotencyStore`]
+///
+/// It needs a clock, so on `wasm32-unknown-unknown` every call fails with
+/// [`Error::Platform`]; use a store backed by an external service there.
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+    entries: Mutex<HashMap<String, Entry>>,
+}
+
+impl MemoryStore {
+    /// Creates an empty store
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Look up a response at the given instant, dropping expired entries
+    fn get_at(&self, key: &str, now: Instant) -> Result<Option<Response>> {
+        let mut entries = self.lock()?;
+        entries.retain(|_, entry| entry.expires > now);
+        Ok(entries.get(key).and_then(|entry| entry.response.clone()))
+    }
+
+    /// Store a response at the given instant
+    fn put_at(&self, key: &str, response: &Response, ttl: Duration, now: Instant) -> Result<()> {
+        self.lock()?.insert(
+            key.to_string(),
+            Entry {
+                response: Some(response.clone()),
+                expires: now + ttl,
+            },
+        );
+        Ok(())
+    }
+
+    /// Reserve a key at the given instant, unless it is already taken
+    fn reserve_at(&self, key: &str, ttl: Duration, now: Instant) -> Result<bool> {
+        let mut entries = self.lock()?;
+        entries.retain(|_, entry| entry.expires > now);
+        if entries.contains_key(key) {
+            return Ok(false);
+        }
+        entries.insert(
+            key.to_string(),
+            Entry {
+                response: None,
+                expires: now + ttl,
+            },
+        );
+        Ok(true)
+    }
+
+    fn lock(&self) -> Result<std::sync::MutexGuard<'_, HashMap<String, Entry>>> {
+        self.entries
+            .lock()
+            .map_err(|_| Error::unexpected("idempotency store lock poisoned"))
+    }
+}
+
+/// Returns the current instant, failing on targets without a clock such as
+/// `wasm32-unknown-unknown`
+fn stopwatch() -> Result<Instant> {
+    util::stopwatch().ok_or_else(|| {
+        Error::platform("The in-memory idempotency store needs a clock, which this target lacks")
+    })
+}
+
+#[async_trait]
+impl IdempotencyStore for MemoryStore {
+    async fn get(&self, key: &str) -> Result<Option<Response>> {
+        self.get_at(key, stopwatch()?)
+    }
+
+    async fn put(&self, key: &str, response: &Response, ttl: Duration) -> Result<()> {
+        self.put_at(key, response, ttl, stopwatch()?)
+    }
+
+    async fn reserve(&self, key: &str, ttl: Duration) -> Result<bool> {
+        self.reserve_at(key, ttl, stopwatch()?)
+    }
+
+    async fn release(&self, key: &str) -> Result<()> {
+        let mut entries = self.lock()?;
+        if entries
+            .get(key)
+            .is_some_and(|entry| entry.response.is_none())
+        {
+            entries.remove(key);
+        }
+        Ok(())
+    }
+}
+
+/// Middleware that replays responses for repeated idempotency keys
+///
+/// Requests without an `Idempotency-Key` header are passed through. Before running
+/// the handler the key is reserved in the store, and a request whose key is reserved
+/// by one still in progress fails with status 409. Only responses below 500 are
+/// stored; after an error the reservation is dropped, so the request can be retried
+/// with the same key. A request that is cancelled keeps its reservation until the
+/// TTL runs out. Replayed responses carry an `Idempotent-Replayed: true` header.
+///
+/// # Examples
+///
+/// ```
+/// use serverless_rs::idempotency::IdempotencyLayer;
+/// use serverless_rs::{Context, Request, Response, Result, RouterBuilder};
+/// use std::time::Duration;
+///
+/// fn charge(_req: Request, _ctx: &Context) -> Result<Response> {
+///     Ok(Response::text("charged").with_status(201))
+/// }
+///
+/// let router = RouterBuilder::new()
+///     .scope("/payments", |payments| {
+///         payments
+///             .layer(IdempotencyLayer::new(Duration::from_secs(24 * 60 * 60)))
+///             .post("/charges", charge)
+///     })
+///     .build();
+/// ```
+pub struct IdempotencyLayer {
+    ttl: Duration,
+    store: Arc<dyn IdempotencyStore>,
+}
+
+impl IdempotencyLayer {
+    /// Keep responses for `ttl`, using an in-process [`MemoryStore`]
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            store: Arc::new(MemoryStore::new()),
+        }
+    }
+
+    /// Keep responses in the given store
+    pub fn with_store<S: IdempotencyStore>(mut self, store: S) -> Self {
+        self.store = Arc::new(store);
+        self
+    }
+
+    /// Returns the store key for a request, if it has an idempotency key
+    fn key(req: &Request) -> Option<String> {
+        let key = req.header(IDEMPOTENCY_KEY_HEADER)?.trim();
+        if key.is_empty() {
+            return None;
+        }
+        Some(format!(
+            "{} {} {}",
+            req.method_str().unwrap_or_default(),
+            req.path().unwrap_or_default(),
+            key
+        ))
+    }
+}
+
+#[async_trait]
+impl Middleware for IdempotencyLayer {
+    async fn handle(&self, req: Request, ctx: &Context, next: &dyn Handler) -> Result<Response> {
+        let key = match Self::key(&req) {
+            Some(key) => key,
+            None => return next.handle(req, ctx).await,
+        };
+
+        if let Some(response) = self.store.get(&key).await? {
+            return Ok(response.with_header(REPLAYED_HEADER, "true"));
+        }
+
+        if !self.store.reserve(&key, self.ttl).await? {
+            // The other request may have finished since the lookup above
+            if let Some(response) = self.store.get(&key).await? {
+                return Ok(response.with_header(REPLAYED_HEADER, "true"));
+            }
+            return Err(Error::with_status(
+                409,
+                "A request with this Idempotency-Key is still in progress",
+            ));
+        }
+
+        match next.handle(req, ctx).await {
+            Ok(response) if response.status() < 500 => {
+                self.store.put(&key, &response, self.ttl).await?;
+                Ok(response)
+            }
+            result => {
+                self.store.release(&key).await?;
+                result
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{handler_fn, BoxFuture};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    static CALLS: AtomicUsize = AtomicUsize::new(0);
+
+    fn counted(_req: Request, _ctx: &Context) -> Result<Response> {
+        let call = CALLS.fetch_add(1, Ordering::SeqCst) + 1;
+        Ok(Response::text(format!("call {}", call)).with_status(201))
+    }
+
+    fn failing(_req: Request, _ctx: &Context) -> Result<Response> {
+        Ok(Response::text("unavailable").with_status(503))
+    }
+
+    fn erroring(_req: Request, _ctx: &Context) -> Result<Response> {
+        Err(Error::http("downstream failed"))
+    }
+
+    fn yielding(_req: Request, _ctx: &Context) -> BoxFuture<'_, Result<Response>> {
+        Box::pin(async {
+            tokio::task::yield_now().await;
+            Ok(Response::text("charged").with_status(201))
+        })
+    }
+
+    fn post(key: &str) -> Request {
+        Request::new()
+            .with_method_str("POST")
+            .with_path("/charges")
+            .with_header("Idempotency-Key", key)
+    }
+
+    #[test]
+    fn test_memory_store_expires() {
+        let store = MemoryStore::new();
+        let start = Instant::now();
+        let ttl = Duration::from_secs(60);
+
+        store
+            .put_at("a", &Response::text("ok"), ttl, start)
+            .unwrap();
+        assert!(store.get_at("a", start).unwrap().is_some());
+        assert!(store.get_at("b", start).unwrap().is_none());
+        assert!(store.get_at("a", start + ttl).unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_memory_store_reserve() {
+        let store = MemoryStore::new();
+        let start = Instant::now();
+        let ttl = Duration::from_secs(60);
+
+        assert!(store.reserve_at("a", ttl, start).unwrap());
+        assert!(!store.reserve_at("a", ttl, start).unwrap());
+        assert!(store.get_at("a", start).unwrap().is_none());
+        assert!(store.reserve_at("a", ttl, start + ttl).unwrap());
+
+        store.release("a").await.unwrap();
+        assert!(store.reserve("a", ttl).await.unwrap());
+
+        // Releasing keeps a stored response
+        store.put("a", &Response::text("ok"), ttl).await.unwrap();
+        store.release("a").await.unwrap();
+        assert!(store.get("a").await.unwrap().is_some());
+        assert!(!store.reserve("a", ttl).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_requests() {
+        let layer = IdempotencyLayer::new(Duration::from_secs(60));
+        let ctx = Context::new();
+        let handler = handler_fn(yielding);
+
+        let (first, second) = futures::join!(
+            layer.handle(post("concurrent"), &ctx, &handler),
+            layer.handle(post("concurrent"), &ctx, &handler)
+        );
+        assert_eq!(first.unwrap().status(), 201);
+        assert_eq!(second.unwrap_err().status(), 409);
+
+        let replay = layer
+            .handle(post("concurrent"), &ctx, &handler)
+            .await
+            .unwrap();
+        assert_eq!(replay.header(REPLAYED_HEADER).unwrap(), "true");
+    }
+
+    #[tokio::test]
+    async fn test_idempotency_layer() {
+        let layer = IdempotencyLayer::new(Duration::from_secs(60));
+        let ctx = Context::new();
+
+        let first = layer.handle(post("k1"), &ctx, &counted).await.unwrap();
+        assert_eq!(first.status(), 201);
+        assert!(first.header(REPLAYED_HEADER).is_none());
+
+        let replay = layer.handle(post("k1"), &ctx, &counted).await.unwrap();
+        assert_eq!(replay.status(), 201);
+        assert_eq!(replay.body(), first.body());
+        assert_eq!(replay.header(REPLAYED_HEADER).unwrap(), "true");
+
+        // A new key runs the handler again
+        let other = layer.handle(post("k2"), &ctx, &counted).await.unwrap();
+        assert_ne!(other.body(), first.body());
+
+        // Without a key every request runs the handler
+        let req = || Request::new().with_method_str("POST").with_path("/charges");
+        let a = layer.handle(req(), &ctx, &counted).await.unwrap();
+        let b = layer.handle(req(), &ctx, &counted).await.unwrap();
+        assert_ne!(a.body(), b.body());
+    }
+
+    #[tokio::test]
+    async fn test_server_errors_not_stored() {
+        let layer = IdempotencyLayer::new(Duration::from_secs(60));
+        let ctx = Context::new();
+
+        let resp = layer.handle(post("retry"), &ctx, &failing).await.unwrap();
+        assert_eq!(resp.status(), 503);
+
+        let err = layer
+            .handle(post("retry"), &ctx, &erroring)
+            .await
+            .unwrap_err();
+        assert_eq!(err.status(), 500);
+
+        let resp = layer.handle(post("retry"), &ctx, &counted).await.unwrap();
+        assert_eq!(resp.status(), 201);
+        assert!(resp.header(REPLAYED_HEADER).is_none());
+    }
+}
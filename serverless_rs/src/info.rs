@@ -9,12 +9,168 @@ function metadata using the `--info` flag. This mechanism enables:
 3. Platform compatibility validation
 */
 
-use crate::requirements::Requirements;
+use crate::requirements::{Permission, Requirements};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use serde_json::{json, Map, Value};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+
+use crate::error::{Error, Result};
+use crate::util::{parse_duration, parse_size_mb};
+
+/// Build-time environment variable holding the git commit the function was built from
+pub const BUILD_GIT_SHA_ENV: &str = "SERVERLESS_BUILD_GIT_SHA";
+
+/// Build-time environment variable holding the time the function was built
+pub const BUILD_TIMESTAMP_ENV: &str = "SERVERLESS_BUILD_TIMESTAMP";
+
+/// Where a route parameter is passed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ParamLocation {
+    /// A `{name}` segment of the path
+    Path,
+    /// A query string parameter
+    Query,
+}
+
+impl ParamLocation {
+    /// Returns the OpenAPI name of the location
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ParamLocation::Path => "path",
+            ParamLocation::Query => "query",
+        }
+    }
+}
+
+/// A route parameter and its schema type
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ParamInfo {
+    /// Parameter name
+    pub name: String,
+
+    /// Where the parameter is passed
+    pub location: ParamLocation,
+
+    /// Whether the parameter must be present
+    pub required: bool,
+
+    /// JSON schema type: `string`, `integer`, `number` or `boolean`
+    #[serde(rename = "type")]
+    pub param_type: String,
+}
+
+impl ParamInfo {
+    /// Create a required string path parameter
+    pub fn path(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            location: ParamLocation::Path,
+            required: true,
+            param_type: "string".to_string(),
+        }
+    }
+
+    /// Create an optional string query parameter
+    pub fn query(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            location: ParamLocation::Query,
+            required: false,
+            param_type: "string".to_string(),
+        }
+    }
+
+    /// Set the schema type
+    pub fn with_type(mut self, param_type: impl Into<String>) -> Self {
+        self.param_type = param_type.into();
+        self
+    }
+
+    /// Set whether the parameter must be present
+    pub fn with_required(mut self, required: bool) -> Self {
+        self.required = required;
+        self
+    }
+}
+
+/// Convert a name such as `api_handler` or `GET /users/{id}` to `ApiHandler` or
+/// `GetUsersId`, for use as a CloudFormation logical ID
+fn pascal_case(name: &str) -> String {
+    name.split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let mut chars = word.chars();
+            let first = chars.next().map(|c| c.to_ascii_uppercase());
+            first.into_iter().chain(chars).collect::<String>()
+        })
+        .collect()
+}
+
+/// Convert a method and path such as `GET /users/{id}` to `get_users_id`, for use as
+/// an OpenAPI operation ID
+fn operation_id(method: &str, path: &str) -> String {
+    std::iter::once(method)
+        .chain(path.split(|c: char| !c.is_ascii_alphanumeric()))
+        .filter(|word| !word.is_empty())
+        .map(str::to_ascii_lowercase)
+        .collect::<Vec<_>>()
+        .join("_")
+}
+
+/// Quote a string for YAML; JSON strings are valid double-quoted YAML scalars
+fn yaml_string(value: &str) -> String {
+    Value::from(value).to_string()
+}
+
+/// Build an IAM policy document allowing the given permissions
+///
+/// Actions are grouped by resource, then resources with identical actions are
+/// grouped into one statement. Statements, actions and resources are sorted so the
+/// output is stable.
+pub(crate) fn iam_policy<'a>(permissions: impl IntoIterator<Item = &'a Permission>) -> Value {
+    let mut actions_by_resource: BTreeMap<&str, BTreeSet<&str>> = BTreeMap::new();
+    for permission in permissions {
+        actions_by_resource
+            .entry(&permission.resource)
+            .or_default()
+            .insert(&permission.action);
+    }
+
+    let mut resources_by_actions: BTreeMap<BTreeSet<&str>, Vec<&str>> = BTreeMap::new();
+    for (resource, actions) in actions_by_resource {
+        resources_by_actions
+            .entry(actions)
+            .or_default()
+            .push(resource);
+    }
+
+    let statements: Vec<Value> = resources_by_actions
+        .into_iter()
+        .map(|(actions, resources)| {
+            json!({
+                "Effect": "Allow",
+                "Action": actions.into_iter().collect::<Vec<_>>(),
+                "Resource": resources,
+            })
+        })
+        .collect();
+    json!({ "Version": "2012-10-17", "Statement": statements })
+}
+
+/// Returns the names of the `{name}` segments of a path pattern
+fn path_param_names(path: &str) -> impl Iterator<Item = &str> {
+    path.split('/')
+        .filter_map(|segment| segment.strip_prefix('{')?.strip_suffix('}'))
+        .filter(|name| !name.is_empty())
+}
 
 /// HTTP route information
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct RouteInfo {
     /// HTTP method (GET, POST, PUT, DELETE, etc.)
     pub method: String,
@@ -25,30 +181,79 @@ pub struct RouteInfo {
     /// Optional description of what this route does
     #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
+
+    /// Requirements specific to this route, in addition to the function's
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub requirements: Option<Requirements>,
+
+    /// Path and query parameters
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub params: Vec<ParamInfo>,
 }
 
 impl RouteInfo {
     /// Create a new route information
+    ///
+    /// Each `{name}` segment of the path is declared as a required string path
+    /// parameter.
     pub fn new(method: impl Into<String>, path: impl Into<String>) -> Self {
+        let path = path.into();
+        let params = path_param_names(&path).map(ParamInfo::path).collect();
         Self {
             method: method.into(),
-            path: path.into(),
+            path,
             description: None,
+            requirements: None,
+            params,
         }
     }
 
+    /// Declare a parameter, replacing one with the same name and location
+    pub fn with_param(mut self, param: ParamInfo) -> Self {
+        match self
+            .params
+            .iter_mut()
+            .find(|existing| existing.name == param.name && existing.location == param.location)
+        {
+            Some(existing) => *existing = param,
+            None => self.params.push(param),
+        }
+        self
+    }
+
+    /// Returns the declared parameters, plus any undeclared path parameters
+    pub fn parameters(&self) -> Vec<ParamInfo> {
+        let mut params = self.params.clone();
+        for name in path_param_names(&self.path) {
+            let declared = params
+                .iter()
+                .any(|param| param.name == name && param.location == ParamLocation::Path);
+            if !declared {
+                params.push(ParamInfo::path(name));
+            }
+        }
+        params
+    }
+
     /// Add a description to the route
     pub fn with_description(mut self, description: impl Into<String>) -> Self {
         self.description = Some(description.into());
         self
     }
+
+    /// Attach requirements specific to this route
+    pub fn with_requirements(mut self, requirements: Requirements) -> Self {
+        self.requirements = Some(requirements);
+        self
+    }
 }
 
 /// Function metadata for self-documentation
 ///
 /// This structure follows the JSON schema defined in the PRD [TECH-4]
 /// and provides all the necessary information for the self-documentation mechanism.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct FunctionInfo {
     /// Function name
     pub name: String,
@@ -64,6 +269,10 @@ pub struct FunctionInfo {
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub routes: Vec<RouteInfo>,
 
+    /// Event sources that invoke the function, such as `dynamodb` or `kinesis`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub triggers: Vec<String>,
+
     /// Additional metadata about the function
     #[serde(default, skip_serializing_if = "HashMap::is_empty")]
     pub metadata: HashMap<String, String>,
@@ -77,6 +286,7 @@ impl FunctionInfo {
             description: None,
             resources: Requirements::new(),
             routes: Vec::new(),
+            triggers: Vec::new(),
             metadata: HashMap::new(),
         }
     }
@@ -99,17 +309,293 @@ impl FunctionInfo {
         self
     }
 
+    /// Add an event source that invokes the function
+    pub fn add_trigger(mut self, trigger: impl Into<String>) -> Self {
+        let trigger = trigger.into();
+        if !self.triggers.contains(&trigger) {
+            self.triggers.push(trigger);
+        }
+        self
+    }
+
     /// Add custom metadata to the function
     pub fn add_metadata(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
         self.metadata.insert(key.into(), value.into());
         self
     }
 
+    /// Record build metadata for correlating deploys
+    ///
+    /// Adds the given `git_sha` and `build_timestamp`, and `binary_size` as the size in
+    /// bytes of the running executable. Values that aren't available are skipped.
+    ///
+    /// Pass the `SERVERLESS_BUILD_GIT_SHA` and `SERVERLESS_BUILD_TIMESTAMP` variables
+    /// the build pipeline sets with `option_env!`, so they are captured when the
+    /// binary is compiled rather than read where it is deployed. `#[serverless]` does
+    /// this for its functions.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use serverless_rs::FunctionInfo;
+    ///
+    /// let info = FunctionInfo::new("api").with_build_info(
+    ///     option_env!("SERVERLESS_BUILD_GIT_SHA"),
+    ///     option_env!("SERVERLESS_BUILD_TIMESTAMP"),
+    /// );
+    /// assert!(info.metadata.contains_key("binary_size"));
+    /// ```
+    pub fn with_build_info(self, git_sha: Option<&str>, build_timestamp: Option<&str>) -> Self {
+        let binary_size = std::env::current_exe()
+            .and_then(std::fs::metadata)
+            .map(|metadata| metadata.len())
+            .ok();
+        self.with_build_info_from(
+            git_sha.map(str::to_string),
+            build_timestamp.map(str::to_string),
+            binary_size,
+        )
+    }
+
+    /// Record the given build metadata, skipping missing or empty values
+    fn with_build_info_from(
+        mut self,
+        git_sha: Option<String>,
+        build_timestamp: Option<String>,
+        binary_size: Option<u64>,
+    ) -> Self {
+        let entries = [
+            ("git_sha", git_sha),
+            ("build_timestamp", build_timestamp),
+            ("binary_size", binary_size.map(|size| size.to_string())),
+        ];
+        for (key, value) in entries {
+            if let Some(value) = value.filter(|value| !value.is_empty()) {
+                self.metadata.insert(key.to_string(), value);
+            }
+        }
+        self
+    }
+
     /// Export the function information as JSON
-    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+    pub fn to_json(&self) -> std::result::Result<String, serde_json::Error> {
         serde_json::to_string_pretty(self)
     }
 
+    /// Parse function information exported with [`FunctionInfo::to_json`]
+    ///
+    /// Unknown fields and missing required fields are errors, naming the field and
+    /// where it is in the document, so a typo or stale file doesn't load silently.
+    pub fn from_json(json: &str) -> Result<Self> {
+        serde_json::from_str(json)
+            .map_err(|err| Error::serialization(format!("Invalid function information: {}", err)))
+    }
+
+    /// Load function information from a JSON file, such as `--info --output` writes
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path).map_err(|err| {
+            Error::unexpected(format!("Failed to read {}: {}", path.display(), err))
+        })?;
+        serde_json::from_str(&contents).map_err(|err| {
+            Error::serialization(format!(
+                "Invalid function information in {}: {}",
+                path.display(),
+                err
+            ))
+        })
+    }
+
+    /// Generate a minimal OpenAPI 3.0 document from the declared routes
+    ///
+    /// Each route becomes an operation with its description as the summary and its
+    /// [`RouteInfo::parameters`] as parameters. Operation IDs are made from the method
+    /// and path, such as `get_users_id` for `GET /users/{id}`, with a numeric suffix
+    /// where two routes would otherwise share one.
+    pub fn to_openapi(&self) -> Value {
+        let mut paths = Map::new();
+        let mut operation_ids = HashSet::new();
+        for route in &self.routes {
+            let parameters: Vec<Value> = route
+                .parameters()
+                .iter()
+                .map(|param| {
+                    json!({
+                        "name": param.name,
+                        "in": param.location.as_str(),
+                        "required": param.required,
+                        "schema": { "type": param.param_type }
+                    })
+                })
+                .collect();
+
+            let base_id = operation_id(&route.method, &route.path);
+            let mut id = base_id.clone();
+            let mut suffix = 1;
+            while !operation_ids.insert(id.clone()) {
+                suffix += 1;
+                id = format!("{}_{}", base_id, suffix);
+            }
+
+            let mut operation = json!({
+                "operationId": id,
+                "responses": { "200": { "description": "Successful response" } }
+            });
+            if let Some(description) = &route.description {
+                operation["summary"] = json!(description);
+            }
+            if !parameters.is_empty() {
+                operation["parameters"] = json!(parameters);
+            }
+
+            let path_item = paths.entry(route.path.clone()).or_insert_with(|| json!({}));
+            path_item[route.method.to_lowercase()] = operation;
+        }
+
+        let mut info = json!({
+            "title": self.name,
+            "version": self.metadata.get("version").map(String::as_str).unwrap_or("0.1.0"),
+        });
+        if let Some(description) = &self.description {
+            info["description"] = json!(description);
+        }
+
+        json!({
+            "openapi": "3.0.3",
+            "info": info,
+            "paths": paths,
+        })
+    }
+
+    /// Returns the permissions declared for the function and for each of its routes
+    pub(crate) fn all_permissions(&self) -> impl Iterator<Item = &Permission> {
+        let routes = self
+            .routes
+            .iter()
+            .filter_map(|route| route.requirements.as_ref());
+        std::iter::once(&self.resources)
+            .chain(routes)
+            .flat_map(|requirements| &requirements.permissions)
+    }
+
+    /// Generate an IAM policy document granting the declared permissions
+    ///
+    /// Permissions of the function and of its routes are combined, duplicate actions
+    /// are merged, and resources needing the same set of actions share one statement.
+    /// The result can be attached to the function's execution role as is.
+    pub fn to_iam_policy(&self) -> Value {
+        iam_policy(self.all_permissions())
+    }
+
+    /// Generate an AWS SAM `AWS::Serverless::Function` resource as a YAML fragment
+    ///
+    /// The resource is keyed by the function name in PascalCase and targets the
+    /// `provided.al2023` runtime with a `bootstrap` handler. `memory`, `timeout` and
+    /// `ephemeral_storage` set the matching properties, environment variables are
+    /// declared with empty values, permissions become an inline policy, and a VPC hint
+    /// becomes `VpcConfig`. Routes become `Api` events, and the `dynamodb`, `kinesis`,
+    /// `sqs`, `sns` and `schedule` triggers become events whose source is a template
+    /// parameter, such as `!Ref DynamodbStreamArn`, for the template to define.
+    pub fn to_sam(&self) -> String {
+        let resources = &self.resources;
+        let mut out = format!(
+            "{}:\n  Type: AWS::Serverless::Function\n  Properties:\n",
+            pascal_case(&self.name)
+        );
+        out.push_str("    Handler: bootstrap\n    Runtime: provided.al2023\n");
+        if let Some(description) = &self.description {
+            out.push_str(&format!("    Description: {}\n", yaml_string(description)));
+        }
+        let memory = resources
+            .resource("memory")
+            .and_then(|memory| parse_size_mb(&memory.value));
+        if let Some(memory) = memory {
+            out.push_str(&format!("    MemorySize: {}\n", memory));
+        }
+        let timeout = resources
+            .resource("timeout")
+            .and_then(|timeout| parse_duration(&timeout.value));
+        if let Some(timeout) = timeout {
+            let seconds = timeout.as_secs() + u64::from(timeout.subsec_nanos() > 0);
+            out.push_str(&format!("    Timeout: {}\n", seconds));
+        }
+        if let Some(size) = resources.ephemeral_storage_mb() {
+            out.push_str(&format!("    EphemeralStorage:\n      Size: {}\n", size));
+        }
+
+        if !resources.environment.is_empty() {
+            out.push_str("    Environment:\n      Variables:\n");
+            for name in &resources.environment {
+                out.push_str(&format!("        {}: \"\"\n", name));
+            }
+        }
+
+        if !resources.permissions.is_empty() {
+            out.push_str("    Policies:\n      - Statement:\n");
+            for permission in &resources.permissions {
+                out.push_str(&format!(
+                    "          - Effect: Allow\n            Action: {}\n            Resource: {}\n",
+                    yaml_string(&permission.action),
+                    yaml_string(&permission.resource)
+                ));
+            }
+        }
+
+        if let Some(vpc) = resources.vpc() {
+            out.push_str("    VpcConfig:\n      SubnetIds:\n");
+            for subnet in &vpc.subnets {
+                out.push_str(&format!("        - {}\n", yaml_string(subnet)));
+            }
+            out.push_str("      SecurityGroupIds:\n");
+            for group in &vpc.security_groups {
+                out.push_str(&format!("        - {}\n", yaml_string(group)));
+            }
+        }
+
+        let mut events: Vec<(String, String)> = Vec::new();
+        for route in &self.routes {
+            let path = match route.path.strip_suffix('*') {
+                Some(prefix) => format!("{}{{proxy+}}", prefix),
+                None => route.path.clone(),
+            };
+            let properties = format!(
+                "        Type: Api\n        Properties:\n          Path: {}\n          Method: {}\n",
+                yaml_string(&path),
+                route.method.to_lowercase()
+            );
+            let name = pascal_case(&format!("{} {}", route.method.to_lowercase(), route.path));
+            events.push((name, properties));
+        }
+        for trigger in &self.triggers {
+            let event = match trigger.as_str() {
+                "dynamodb" => "DynamoDB\n        Properties:\n          Stream: !Ref DynamodbStreamArn\n          StartingPosition: LATEST",
+                "kinesis" => "Kinesis\n        Properties:\n          Stream: !Ref KinesisStreamArn\n          StartingPosition: LATEST",
+                "sqs" => "SQS\n        Properties:\n          Queue: !Ref SqsQueueArn",
+                "sns" => "SNS\n        Properties:\n          Topic: !Ref SnsTopicArn",
+                "schedule" => "Schedule\n        Properties:\n          Schedule: !Ref ScheduleExpression",
+                _ => continue,
+            };
+            events.push((pascal_case(trigger), format!("        Type: {}\n", event)));
+        }
+        if !events.is_empty() {
+            out.push_str("    Events:\n");
+            let mut seen = Vec::new();
+            for (name, properties) in events {
+                // Event names must be unique within the function
+                let mut unique = name.clone();
+                let mut n = 2;
+                while seen.contains(&unique) {
+                    unique = format!("{}{}", name, n);
+                    n += 1;
+                }
+                out.push_str(&format!("      {}:\n{}", unique, properties));
+                seen.push(unique);
+            }
+        }
+
+        out
+    }
+
     /// Formats the function information for human-readable output
     pub fn format_for_display(&self) -> String {
         let mut output = format!("# Function: {}\n", self.name);
@@ -126,6 +612,37 @@ impl FunctionInfo {
                 if let Some(desc) = &route.description {
                     output.push_str(&format!("  Description: {}\n", desc));
                 }
+                for param in &route.params {
+                    output.push_str(&format!(
+                        "  Param: {} ({}, {}{})\n",
+                        param.name,
+                        param.location.as_str(),
+                        param.param_type,
+                        if param.required { ", required" } else { "" }
+                    ));
+                }
+                if let Some(requirements) = &route.requirements {
+                    for (name, resource) in &requirements.required {
+                        output.push_str(&format!("  Requires: {} = {}\n", name, resource.value));
+                    }
+                    for (name, resource) in &requirements.recommended {
+                        output.push_str(&format!("  Recommends: {} = {}\n", name, resource.value));
+                    }
+                    if !requirements.environment.is_empty() {
+                        output.push_str(&format!(
+                            "  Environment: {}\n",
+                            requirements.environment.join(", ")
+                        ));
+                    }
+                }
+            }
+        }
+
+        // Format event triggers
+        if !self.triggers.is_empty() {
+            output.push_str("\n## Triggers\n");
+            for trigger in &self.triggers {
+                output.push_str(&format!("- {}\n", trigger));
             }
         }
 
@@ -168,6 +685,27 @@ impl FunctionInfo {
             }
         }
 
+        // Format VPC placement
+        if let Some(vpc) = self.resources.vpc() {
+            output.push_str("\n## VPC\n");
+            output.push_str(&format!("- Subnets: {}\n", vpc.subnets.join(", ")));
+            output.push_str(&format!(
+                "- Security groups: {}\n",
+                vpc.security_groups.join(", ")
+            ));
+        }
+
+        // Format permissions
+        if !self.resources.permissions.is_empty() {
+            output.push_str("\n## Permissions\n");
+            for permission in &self.resources.permissions {
+                output.push_str(&format!(
+                    "- {} on {}\n",
+                    permission.action, permission.resource
+                ));
+            }
+        }
+
         // Format metadata
         if !self.metadata.is_empty() {
             output.push_str("\n## Additional Metadata\n");
@@ -182,20 +720,118 @@ impl FunctionInfo {
 
 /// Display function information in the console
 ///
-/// This function handles the output of function metadata in two formats:
-/// 1. JSON format (when --json flag is present)
-/// 2. Human-readable format (default)
-pub fn display_info(info: &FunctionInfo) {
-    // Check if JSON output is requested
-    if check_json_flag() {
-        if let Ok(json) = info.to_json() {
-            println!("{}", json);
-        } else {
-            eprintln!("Error: Failed to serialize function information to JSON");
+/// This function handles the output of function metadata in these formats:
+/// 1. JSON format (when --json or --format=json is present)
+/// 2. OpenAPI format (when --format=openapi is present)
+/// 3. SAM format (when --format=sam is present)
+/// 4. Human-readable format (when --format=text is present)
+///
+/// By default, text is printed to a terminal and JSON everywhere else, so CI
+/// scripts piping `--info` get machine-readable output.
+///
+/// With `--output <file>`, the information is written to that file instead and a
+/// confirmation is printed to stderr. A failed write is returned, so the caller can
+/// exit with a non-zero status.
+pub fn display_info(info: &FunctionInfo) -> Result<()> {
+    warn_requirements(info, "");
+
+    let (_, format, output) = parse_info_args();
+    report_output(
+        write_info(info, format, output.as_deref()),
+        output.as_deref(),
+    )
+}
+
+/// Print requirement problems of a function and its routes on stderr
+///
+/// Each warning is preceded by `prefix`, which names the function when several are
+/// displayed together.
+pub(crate) fn warn_requirements(info: &FunctionInfo, prefix: &str) {
+    if let Err(err) = info.resources.validate() {
+        eprintln!("Warning: {}{}", prefix, err);
+    }
+    for warning in info.resources.lint() {
+        eprintln!("Warning: {}{}", prefix, warning);
+    }
+    for route in &info.routes {
+        for warning in route.requirements.iter().flat_map(Requirements::lint) {
+            eprintln!(
+                "Warning: {}{} {}: {}",
+                prefix, route.method, route.path, warning
+            );
         }
-    } else {
-        // Display human-readable output
-        println!("{}", info.format_for_display());
+    }
+}
+
+/// Write function information in the given format to a file, or to stdout
+///
+/// Parent directories of the file are created as needed. [`OutputFormat::Auto`] is
+/// resolved with [`OutputFormat::resolve`].
+pub fn write_info(info: &FunctionInfo, format: OutputFormat, output: Option<&Path>) -> Result<()> {
+    let content = render_info(info, format.resolve(output))?;
+    write_output(&content, output)
+}
+
+/// Render information for one function in the given, resolved format
+pub(crate) fn render_info(info: &FunctionInfo, format: OutputFormat) -> Result<String> {
+    Ok(match format {
+        OutputFormat::Json => info.to_json().map_err(|err| {
+            Error::serialization(format!(
+                "Failed to serialize function information to JSON: {}",
+                err
+            ))
+        })?,
+        OutputFormat::OpenApi => {
+            serde_json::to_string_pretty(&info.to_openapi()).map_err(|err| {
+                Error::serialization(format!("Failed to serialize OpenAPI document: {}", err))
+            })?
+        }
+        OutputFormat::Sam => info.to_sam(),
+        OutputFormat::IamPolicy => {
+            serde_json::to_string_pretty(&info.to_iam_policy()).map_err(|err| {
+                Error::serialization(format!("Failed to serialize IAM policy: {}", err))
+            })?
+        }
+        // `resolve` never returns `Auto`
+        OutputFormat::Text | OutputFormat::Auto => info.format_for_display(),
+    })
+}
+
+/// Print `content` to stdout, or write it to `output` creating parent directories
+pub(crate) fn write_output(content: &str, output: Option<&Path>) -> Result<()> {
+    let path = match output {
+        Some(path) => path,
+        None => {
+            println!("{}", content);
+            return Ok(());
+        }
+    };
+
+    let write = || -> std::io::Result<()> {
+        if let Some(parent) = path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+        {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, format!("{}\n", content))
+    };
+    write().map_err(|err| Error::unexpected(format!("Failed to write {}: {}", path.display(), err)))
+}
+
+/// Confirm on stderr that info output was written to a file, passing errors on
+pub(crate) fn report_output(result: Result<()>, output: Option<&Path>) -> Result<()> {
+    if let (Ok(()), Some(path)) = (&result, output) {
+        eprintln!("Wrote function information to {}", path.display());
+    }
+    result
+}
+
+/// Print an error from displaying info output and exit with status 1
+pub(crate) fn exit_on_error(result: Result<()>) {
+    if let Err(err) = result {
+        eprintln!("Error: {}", err);
+        std::process::exit(1);
     }
 }
 
@@ -206,14 +842,6 @@ pub fn check_info_flag() -> bool {
     std::env::args().any(|arg| arg == "--info")
 }
 
-/// Parse command-line arguments to check for the --json flag
-///
-/// Returns true if the --json flag is present, false otherwise.
-/// This is used in conjunction with the --info flag to request JSON output.
-fn check_json_flag() -> bool {
-    std::env::args().any(|arg| arg == "--json")
-}
-
 /// Enum representing the requested output format
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum OutputFormat {
@@ -221,6 +849,33 @@ pub enum OutputFormat {
     Json,
     /// Human-readable text output format
     Text,
+    /// OpenAPI 3.0 document generated from the routes
+    OpenApi,
+    /// AWS SAM function resource, see [`FunctionInfo::to_sam`]
+    Sam,
+    /// IAM policy document, see [`FunctionInfo::to_iam_policy`]
+    IamPolicy,
+    /// Text on a terminal, JSON when piped or written to a file (the default)
+    Auto,
+}
+
+impl OutputFormat {
+    /// Pick the concrete format for [`OutputFormat::Auto`]
+    ///
+    /// `Auto` becomes [`OutputFormat::Text`] when printing to a terminal and
+    /// [`OutputFormat::Json`] when stdout is piped or `output` names a file. Other
+    /// formats are returned unchanged.
+    pub fn resolve(self, output: Option<&Path>) -> Self {
+        self.resolve_for(output.is_none() && std::io::stdout().is_terminal())
+    }
+
+    fn resolve_for(self, to_terminal: bool) -> Self {
+        match self {
+            OutputFormat::Auto if to_terminal => OutputFormat::Text,
+            OutputFormat::Auto => OutputFormat::Json,
+            format => format,
+        }
+    }
 }
 
 /// Parse command-line arguments to determine the desired actions
@@ -228,27 +883,127 @@ pub enum OutputFormat {
 /// This function provides more comprehensive argument parsing than the
 /// individual flag check functions. It returns a tuple with:
 /// 1. Whether the --info flag is present
-/// 2. The requested output format (JSON or text)
-pub fn parse_info_args() -> (bool, OutputFormat) {
-    let info_requested = check_info_flag();
-    let format = if check_json_flag() {
-        OutputFormat::Json
-    } else {
-        OutputFormat::Text
-    };
+/// 2. The requested output format (`--json`, or
+///    `--format auto|json|text|openapi|sam|iam-policy`),
+///    [`OutputFormat::Auto`] if none is given
+/// 3. The file to write to instead of stdout (`--output <file>`)
+pub fn parse_info_args() -> (bool, OutputFormat, Option<PathBuf>) {
+    (
+        check_info_flag(),
+        parse_format_from(std::env::args().skip(1)),
+        parse_output_from(std::env::args().skip(1)),
+    )
+}
+
+/// Which functions `--info` shows in a binary hosting several
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InfoSelection {
+    /// Every function (`--info`)
+    All,
+    /// A single function by name (`--info <function_name>`)
+    Function(String),
+    /// Only the function names, one per line (`--info --list`)
+    List,
+}
+
+/// Parse command-line arguments to determine which functions `--info` shows
+///
+/// A name directly after `--info` selects that function, and `--list` prints only
+/// the names. Without either, every function is shown.
+pub fn parse_info_selection() -> InfoSelection {
+    parse_selection_from(std::env::args().skip(1))
+}
+
+/// Determine the function selection from an explicit argument list
+fn parse_selection_from<I>(args: I) -> InfoSelection
+where
+    I: IntoIterator<Item = String>,
+{
+    let mut selection = InfoSelection::All;
+    let mut args = args.into_iter().peekable();
+
+    while let Some(arg) = args.next() {
+        if arg == "--list" {
+            return InfoSelection::List;
+        }
+        if arg == "--info" {
+            if let Some(name) = args.next_if(|next| !next.starts_with('-')) {
+                selection = InfoSelection::Function(name);
+            }
+        }
+    }
+
+    selection
+}
+
+/// Determine the output file from an explicit argument list
+///
+/// Accepts `--output <file>` and `--output=<file>`. The last occurrence wins.
+fn parse_output_from<I>(args: I) -> Option<PathBuf>
+where
+    I: IntoIterator<Item = String>,
+{
+    let mut output = None;
+    let mut args = args.into_iter();
+
+    while let Some(arg) = args.next() {
+        if let Some(value) = arg.strip_prefix("--output=") {
+            output = Some(PathBuf::from(value));
+        } else if arg == "--output" {
+            output = args.next().map(PathBuf::from);
+        }
+    }
 
-    (info_requested, format)
+    output
+}
+
+/// Determine the output format from an explicit argument list
+///
+/// Accepts `--json`, `--format <name>`, and `--format=<name>`. The last occurrence
+/// wins; without one the format is auto, and unknown formats fall back to text.
+fn parse_format_from<I>(args: I) -> OutputFormat
+where
+    I: IntoIterator<Item = String>,
+{
+    let mut format = OutputFormat::Auto;
+    let mut args = args.into_iter();
+
+    while let Some(arg) = args.next() {
+        let name = if arg == "--json" {
+            Some("json".to_string())
+        } else if let Some(value) = arg.strip_prefix("--format=") {
+            Some(value.to_string())
+        } else if arg == "--format" {
+            args.next()
+        } else {
+            None
+        };
+
+        if let Some(name) = name {
+            format = match name.to_ascii_lowercase().as_str() {
+                "json" => OutputFormat::Json,
+                "openapi" => OutputFormat::OpenApi,
+                "sam" => OutputFormat::Sam,
+                "iam-policy" => OutputFormat::IamPolicy,
+                "auto" => OutputFormat::Auto,
+                _ => OutputFormat::Text,
+            };
+        }
+    }
+
+    format
 }
 
 /// Display function information and exit if the --info flag is present
 ///
 /// This is a convenience function that can be called at the start of the
-/// main function to handle the --info flag automatically.
+/// main function to handle the --info flag automatically. If the information
+/// can't be written, the error is printed and the process exits with status 1.
 pub fn handle_info_request(info: &FunctionInfo) -> bool {
-    let (info_requested, _) = parse_info_args();
+    let (info_requested, _, _) = parse_info_args();
 
     if info_requested {
-        display_info(info);
+        exit_on_error(display_info(info));
         true
     } else {
         false
@@ -258,7 +1013,7 @@ pub fn handle_info_request(info: &FunctionInfo) -> bool {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::requirements::Resource;
+    use crate::requirements::{Resource, VpcHint};
 
     #[test]
     fn test_function_info() {
@@ -303,12 +1058,15 @@ mod tests {
             .recommend(Resource::new("memory", "256MB").with_description("Memory limit"))
             .require(Resource::new("cpu", "1x"))
             .platform("aws")
-            .env_var("API_KEY");
+            .env_var("API_KEY")
+            .permission("s3:GetObject", "arn:aws:s3:::bucket/*");
 
         let info = FunctionInfo::new("test_function")
             .with_description("Test function description")
             .with_resources(resources)
             .add_route(RouteInfo::new("GET", "/test").with_description("Test endpoint"))
+            .add_trigger("kinesis")
+            .add_trigger("kinesis")
             .add_metadata("version", "1.0");
 
         let display = info.format_for_display();
@@ -318,6 +1076,7 @@ mod tests {
         assert!(display.contains("Test function description"));
         assert!(display.contains("GET /test"));
         assert!(display.contains("Test endpoint"));
+        assert!(display.contains("## Triggers\n- kinesis\n\n"));
         assert!(display.contains("Required Resources"));
         assert!(display.contains("cpu: 1x"));
         assert!(display.contains("Recommended Resources"));
@@ -327,18 +1086,433 @@ mod tests {
         assert!(display.contains("aws"));
         assert!(display.contains("Environment Variables"));
         assert!(display.contains("API_KEY"));
+        assert!(display.contains("s3:GetObject on arn:aws:s3:::bucket/*"));
         assert!(display.contains("Additional Metadata"));
         assert!(display.contains("version: 1.0"));
+        assert!(!display.contains("## VPC"));
+    }
+
+    #[test]
+    fn test_display_vpc() {
+        let vpc = VpcHint::new()
+            .subnet("subnet-a")
+            .subnet("subnet-b")
+            .security_group("sg-1");
+        let resources = Requirements::new()
+            .require(Resource::ephemeral_storage("4GB"))
+            .require(Resource::vpc(&vpc));
+        let display = FunctionInfo::new("etl")
+            .with_resources(resources)
+            .format_for_display();
+
+        assert!(display.contains("ephemeral_storage: 4GB"));
+        assert!(display.contains("## VPC"));
+        assert!(display.contains("- Subnets: subnet-a, subnet-b"));
+        assert!(display.contains("- Security groups: sg-1"));
+    }
+
+    #[test]
+    fn test_build_info() {
+        let info = FunctionInfo::new("api").with_build_info_from(
+            Some("3f2c1a9".to_string()),
+            Some(String::new()),
+            Some(4_194_304),
+        );
+        assert_eq!(info.metadata.get("git_sha").unwrap(), "3f2c1a9");
+        assert_eq!(info.metadata.get("binary_size").unwrap(), "4194304");
+        assert!(!info.metadata.contains_key("build_timestamp"));
+
+        // The running test binary always has a size
+        let info = FunctionInfo::new("api").with_build_info(Some("3f2c1a9"), None);
+        assert_eq!(info.metadata.get("git_sha").unwrap(), "3f2c1a9");
+        assert!(info.metadata.contains_key("binary_size"));
+    }
+
+    #[test]
+    fn test_route_requirements() {
+        let route = RouteInfo::new("POST", "/uploads").with_requirements(
+            Requirements::new()
+                .recommend(Resource::new("memory", "1GB"))
+                .env_var("BUCKET_NAME"),
+        );
+        let info = FunctionInfo::new("files").add_route(route);
+
+        let json: serde_json::Value = serde_json::from_str(&info.to_json().unwrap()).unwrap();
+        assert_eq!(
+            json["routes"][0]["requirements"]["environment"][0],
+            "BUCKET_NAME"
+        );
+        let display = info.format_for_display();
+        assert!(display.contains("Recommends: memory = 1GB"));
+        assert!(display.contains("Environment: BUCKET_NAME"));
+
+        // Routes without requirements don't serialize the field
+        let json = FunctionInfo::new("plain")
+            .add_route(RouteInfo::new("GET", "/"))
+            .to_json()
+            .unwrap();
+        assert!(!json.contains("requirements"));
+    }
+
+    #[test]
+    fn test_to_openapi() {
+        let info = FunctionInfo::new("users")
+            .with_description("User API")
+            .add_route(RouteInfo::new("GET", "/users/{id}").with_description("Get a user"))
+            .add_route(RouteInfo::new("DELETE", "/users/{id}"))
+            .add_route(RouteInfo::new("POST", "/users"));
+
+        let doc = info.to_openapi();
+        assert_eq!(doc["openapi"], "3.0.3");
+        assert_eq!(doc["info"]["title"], "users");
+        assert_eq!(doc["info"]["description"], "User API");
+
+        let get = &doc["paths"]["/users/{id}"]["get"];
+        assert_eq!(get["summary"], "Get a user");
+        assert_eq!(get["parameters"][0]["name"], "id");
+        assert_eq!(get["parameters"][0]["in"], "path");
+        assert_eq!(get["parameters"][0]["required"], true);
+        assert!(doc["paths"]["/users/{id}"]["delete"].is_object());
+
+        let post = &doc["paths"]["/users"]["post"];
+        assert!(post.get("parameters").is_none());
+        assert!(post["responses"]["200"].is_object());
+    }
+
+    #[test]
+    fn test_openapi_operation_ids() {
+        let info = FunctionInfo::new("users")
+            .add_route(RouteInfo::new("GET", "/users"))
+            .add_route(RouteInfo::new("GET", "/users/{id}"))
+            .add_route(RouteInfo::new("GET", "/users/{id}/"))
+            .add_route(RouteInfo::new("DELETE", "/users/{id}"))
+            .add_route(RouteInfo::new("GET", "/"));
+
+        let doc = info.to_openapi();
+        let id = |path: &str, method: &str| doc["paths"][path][method]["operationId"].clone();
+        assert_eq!(id("/users", "get"), "get_users");
+        assert_eq!(id("/users/{id}", "get"), "get_users_id");
+        assert_eq!(id("/users/{id}/", "get"), "get_users_id_2");
+        assert_eq!(id("/users/{id}", "delete"), "delete_users_id");
+        assert_eq!(id("/", "get"), "get");
+    }
+
+    #[test]
+    fn test_to_sam() {
+        let resources = Requirements::new()
+            .recommend(Resource::new("memory", "1GB"))
+            .require(Resource::new("timeout", "2.5s"))
+            .recommend(Resource::ephemeral_storage("2GB"))
+            .env_var("TABLE_NAME")
+            .permission("dynamodb:GetItem", "arn:aws:dynamodb:*:*:table/users")
+            .recommend(Resource::vpc(
+                &VpcHint::new().subnet("subnet-a").security_group("sg-1"),
+            ));
+        let info = FunctionInfo::new("user_api")
+            .with_description("Users: read & write")
+            .with_resources(resources)
+            .add_route(RouteInfo::new("GET", "/users/{id}"))
+            .add_route(RouteInfo::new("GET", "/users/{id}/"))
+            .add_route(RouteInfo::new("GET", "/static/*"))
+            .add_trigger("kinesis")
+            .add_trigger("carrier-pigeon");
+
+        let expected = r#"UserApi:
+  Type: AWS::Serverless::Function
+  Properties:
+    Handler: bootstrap
+    Runtime: provided.al2023
+    Description: "Users: read & write"
+    MemorySize: 1024
+    Timeout: 3
+    EphemeralStorage:
+      Size: 2048
+    Environment:
+      Variables:
+        TABLE_NAME: ""
+    Policies:
+      - Statement:
+          - Effect: Allow
+            Action: "dynamodb:GetItem"
+            Resource: "arn:aws:dynamodb:*:*:table/users"
+    VpcConfig:
+      SubnetIds:
+        - "subnet-a"
+      SecurityGroupIds:
+        - "sg-1"
+    Events:
+      GetUsersId:
+        Type: Api
+        Properties:
+          Path: "/users/{id}"
+          Method: get
+      GetUsersId2:
+        Type: Api
+        Properties:
+          Path: "/users/{id}/"
+          Method: get
+      GetStatic:
+        Type: Api
+        Properties:
+          Path: "/static/{proxy+}"
+          Method: get
+      Kinesis:
+        Type: Kinesis
+        Properties:
+          Stream: !Ref KinesisStreamArn
+          StartingPosition: LATEST
+"#;
+        assert_eq!(info.to_sam(), expected);
+
+        let minimal = FunctionInfo::new("ping").to_sam();
+        assert_eq!(
+            minimal,
+            "Ping:\n  Type: AWS::Serverless::Function\n  Properties:\n    Handler: bootstrap\n    Runtime: provided.al2023\n"
+        );
+    }
+
+    #[test]
+    fn test_route_params() {
+        let route = RouteInfo::new("GET", "/users/{id}/posts")
+            .with_param(ParamInfo::path("id").with_type("integer"))
+            .with_param(ParamInfo::query("limit").with_type("integer"))
+            .with_param(ParamInfo::query("id").with_required(true));
+        assert_eq!(
+            route.params,
+            vec![
+                ParamInfo::path("id").with_type("integer"),
+                ParamInfo::query("limit").with_type("integer"),
+                ParamInfo::query("id").with_required(true),
+            ]
+        );
+
+        let doc = FunctionInfo::new("posts").add_route(route).to_openapi();
+        let parameters = &doc["paths"]["/users/{id}/posts"]["get"]["parameters"];
+        assert_eq!(
+            parameters[0],
+            json!({ "name": "id", "in": "path", "required": true, "schema": { "type": "integer" } })
+        );
+        assert_eq!(
+            parameters[1],
+            json!({ "name": "limit", "in": "query", "required": false, "schema": { "type": "integer" } })
+        );
+
+        // Routes serialized without params still document their path parameters
+        let json = r#"{"method":"GET","path":"/items/{sku}"}"#;
+        let route: RouteInfo = serde_json::from_str(json).unwrap();
+        assert!(route.params.is_empty());
+        assert_eq!(route.parameters(), vec![ParamInfo::path("sku")]);
+    }
+
+    #[test]
+    fn test_parse_format() {
+        let args = |list: &[&str]| list.iter().map(|a| a.to_string()).collect::<Vec<_>>();
+
+        assert_eq!(parse_format_from(args(&["--info"])), OutputFormat::Auto);
+        assert_eq!(
+            parse_format_from(args(&["--json", "--format=auto"])),
+            OutputFormat::Auto
+        );
+        assert_eq!(
+            parse_format_from(args(&["--format=text"])),
+            OutputFormat::Text
+        );
+        assert_eq!(
+            parse_format_from(args(&["--info", "--json"])),
+            OutputFormat::Json
+        );
+        assert_eq!(
+            parse_format_from(args(&["--info", "--format=openapi"])),
+            OutputFormat::OpenApi
+        );
+        assert_eq!(
+            parse_format_from(args(&["--format", "JSON"])),
+            OutputFormat::Json
+        );
+        assert_eq!(
+            parse_format_from(args(&["--info", "--format=sam"])),
+            OutputFormat::Sam
+        );
+        assert_eq!(
+            parse_format_from(args(&["--info", "--format", "iam-policy"])),
+            OutputFormat::IamPolicy
+        );
+    }
+
+    #[test]
+    fn test_to_iam_policy() {
+        let info = FunctionInfo::new("api")
+            .with_resources(
+                Requirements::new()
+                    .permission("s3:GetObject", "arn:aws:s3:::uploads/*")
+                    .permission("s3:PutObject", "arn:aws:s3:::uploads/*")
+                    .permission("s3:GetObject", "arn:aws:s3:::uploads/*")
+                    .permission("dynamodb:GetItem", "arn:aws:dynamodb:*:*:table/users"),
+            )
+            .add_route(
+                RouteInfo::new("PUT", "/avatars").with_requirements(
+                    Requirements::new()
+                        .permission("s3:PutObject", "arn:aws:s3:::avatars/*")
+                        .permission("s3:GetObject", "arn:aws:s3:::avatars/*"),
+                ),
+            );
+
+        assert_eq!(
+            info.to_iam_policy(),
+            json!({
+                "Version": "2012-10-17",
+                "Statement": [
+                    {
+                        "Effect": "Allow",
+                        "Action": ["dynamodb:GetItem"],
+                        "Resource": ["arn:aws:dynamodb:*:*:table/users"],
+                    },
+                    {
+                        "Effect": "Allow",
+                        "Action": ["s3:GetObject", "s3:PutObject"],
+                        "Resource": ["arn:aws:s3:::avatars/*", "arn:aws:s3:::uploads/*"],
+                    },
+                ],
+            })
+        );
+
+        let empty = FunctionInfo::new("api").to_iam_policy();
+        assert_eq!(empty["Statement"], json!([]));
+    }
+
+    #[test]
+    fn test_resolve_format() {
+        assert_eq!(OutputFormat::Auto.resolve_for(true), OutputFormat::Text);
+        assert_eq!(OutputFormat::Auto.resolve_for(false), OutputFormat::Json);
+        assert_eq!(OutputFormat::Sam.resolve_for(true), OutputFormat::Sam);
+        assert_eq!(OutputFormat::Text.resolve_for(false), OutputFormat::Text);
+
+        // Files never count as a terminal
+        let output = Path::new("info.json");
+        assert_eq!(OutputFormat::Auto.resolve(Some(output)), OutputFormat::Json);
+    }
+
+    #[test]
+    fn test_parse_output() {
+        let args = |list: &[&str]| list.iter().map(|a| a.to_string()).collect::<Vec<_>>();
+
+        assert_eq!(parse_output_from(args(&["--info"])), None);
+        assert_eq!(
+            parse_output_from(args(&["--info", "--output", "infra/api.json"])),
+            Some(PathBuf::from("infra/api.json"))
+        );
+        assert_eq!(
+            parse_output_from(args(&["--output=a.json", "--json"])),
+            Some(PathBuf::from("a.json"))
+        );
+        assert_eq!(parse_output_from(args(&["--output"])), None);
+    }
+
+    #[test]
+    fn test_write_info() {
+        let dir = std::env::temp_dir().join(format!("serverless_rs_info_{}", std::process::id()));
+        let path = dir.join("nested/api.json");
+        let info = FunctionInfo::new("api").add_route(RouteInfo::new("GET", "/"));
+
+        write_info(&info, OutputFormat::Json, Some(&path)).unwrap();
+        let written: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(written["name"], "api");
+
+        // A file where a directory is needed can't be written
+        let blocked = path.join("api.json");
+        let err = write_info(&info, OutputFormat::Text, Some(&blocked)).unwrap_err();
+        assert!(err.to_string().contains("Failed to write"));
+
+        // Failures are passed on, so `--info` can exit with an error
+        let result = write_info(&info, OutputFormat::Text, Some(&blocked));
+        assert!(report_output(result, Some(&blocked)).is_err());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_from_json() {
+        let info = FunctionInfo::new("api")
+            .with_description("Users API")
+            .with_resources(Requirements::new().platform("aws").env_var("DATABASE_URL"))
+            .add_route(
+                RouteInfo::new("GET", "/users/{id}")
+                    .with_param(ParamInfo::query("expand").with_required(false)),
+            )
+            .add_trigger("sqs")
+            .add_metadata("git_sha", "abc123");
+        let json = info.to_json().unwrap();
+        assert_eq!(FunctionInfo::from_json(&json).unwrap(), info);
+
+        let minimal = FunctionInfo::from_json(r#"{"name": "api", "resources": {}}"#).unwrap();
+        assert_eq!(minimal, FunctionInfo::new("api"));
+
+        let err = FunctionInfo::from_json(r#"{"name": "api", "resources": {}, "rotues": []}"#)
+            .unwrap_err();
+        assert!(
+            err.to_string().contains("unknown field `rotues`"),
+            "{}",
+            err
+        );
+
+        let err = FunctionInfo::from_json(r#"{"name": "api"}"#).unwrap_err();
+        assert!(
+            err.to_string().contains("missing field `resources`"),
+            "{}",
+            err
+        );
+
+        let err = FunctionInfo::from_json(
+            r#"{"name": "api", "resources": {}, "routes": [{"method": "GET", "path": "/", "auth": true}]}"#,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("unknown field `auth`"), "{}", err);
+
+        let path =
+            std::env::temp_dir().join(format!("serverless_rs_from_{}.json", std::process::id()));
+        std::fs::write(&path, &json).unwrap();
+        assert_eq!(FunctionInfo::from_path(&path).unwrap(), info);
+        std::fs::write(&path, "{").unwrap();
+        let err = FunctionInfo::from_path(&path).unwrap_err();
+        assert!(err.to_string().contains(&path.display().to_string()));
+        std::fs::remove_file(&path).unwrap();
+        assert!(FunctionInfo::from_path(&path).is_err());
     }
 
     #[test]
     fn test_parse_info_args() {
         // Default case without arguments
-        let (info_requested, format) = parse_info_args();
+        let (info_requested, format, output) = parse_info_args();
         assert!(!info_requested);
-        assert!(matches!(format, OutputFormat::Text));
+        assert!(matches!(format, OutputFormat::Auto));
+        assert!(output.is_none());
 
         // We can't modify env::args() directly, so we'll skip testing other cases
         // This would require integration tests with actual command-line arguments
     }
+
+    #[test]
+    fn test_parse_info_selection() {
+        let args = |args: &[&str]| args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+
+        assert_eq!(parse_selection_from(args(&["--info"])), InfoSelection::All);
+        assert_eq!(
+            parse_selection_from(args(&["--info", "--json"])),
+            InfoSelection::All
+        );
+        assert_eq!(
+            parse_selection_from(args(&["--info", "orders", "--format", "sam"])),
+            InfoSelection::Function("orders".to_string())
+        );
+        assert_eq!(
+            parse_selection_from(args(&["--format", "json", "--info", "--list"])),
+            InfoSelection::List
+        );
+        // Values of other flags are not function names
+        assert_eq!(
+            parse_selection_from(args(&["--output", "info.json", "--info"])),
+            InfoSelection::All
+        );
+    }
 }
@@ -0,0 +1,133 @@
+/*!
+Handler return types for serverless.rs.
+
+This module provides [`IntoResponse`], which lets `#[serverless]` functions return
+plain strings, JSON values or status/body pairs instead of building a [`Response`].
+*/
+
+use serde_json::Value;
+
+use crate::error::{Error, Result};
+use crate::{Problem, Response};
+
+/// A value that can be returned from a handler
+///
+/// Functions marked with `#[serverless]` may return any `Result<T, E>` where `T`
+/// implements `IntoResponse` and `E` converts into [`Error`]. Errors are passed on
+/// unchanged, so adapters still map them to their status codes.
+///
+/// | Type | Response |
+/// |------|----------|
+/// | [`Response`] | Returned as is |
+/// | `String`, `&str` | `200` with a `text/plain` body |
+/// | [`Value`] | `200` with an `application/json` body |
+/// | [`Problem`] | An `application/problem+json` body with the problem's status |
+/// | `(u16, T)` | `T`'s response with the given status |
+///
+/// # Examples
+///
+/// ```
+/// use serverless_rs::{json, IntoResponse, Response};
+///
+/// let response = "hello".into_response().unwrap();
+/// assert_eq!(response.body(), b"hello");
+///
+/// let response = (201, json!({ "id": 7 })).into_response().unwrap();
+/// assert_eq!(response.status(), 201);
+/// assert_eq!(response.header("Content-Type").unwrap(), "application/json");
+/// ```
+pub trait IntoResponse {
+    /// Convert this value into a response
+    fn into_response(self) -> Result<Response>;
+}
+
+impl IntoResponse for Response {
+    fn into_response(self) -> Result<Response> {
+        Ok(self)
+    }
+}
+
+impl IntoResponse for String {
+    fn into_response(self) -> Result<Response> {
+        Ok(Response::text(self))
+    }
+}
+
+impl IntoResponse for &str {
+    fn into_response(self) -> Result<Response> {
+        Ok(Response::text(self))
+    }
+}
+
+impl IntoResponse for Value {
+    fn into_response(self) -> Result<Response> {
+        Response::json(&self)
+    }
+}
+
+impl IntoResponse for Problem {
+    fn into_response(self) -> Result<Response> {
+        Ok(self.into())
+    }
+}
+
+impl<T: IntoResponse> IntoResponse for (u16, T) {
+    fn into_response(self) -> Result<Response> {
+        let (status, body) = self;
+        Ok(body.into_response()?.with_status(status))
+    }
+}
+
+impl<T, E> IntoResponse for std::result::Result<T, E>
+where
+    T: IntoResponse,
+    E: Into<Error>,
+{
+    fn into_response(self) -> Result<Response> {
+        self.map_err(Into::into)?.into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_into_response() {
+        let text = "hello".into_response().unwrap();
+        assert_eq!(text.status(), 200);
+        assert_eq!(text.body(), b"hello");
+        assert_eq!(
+            String::from("hi")
+                .into_response()
+                .unwrap()
+                .header("Content-Type"),
+            text.header("Content-Type")
+        );
+
+        let json = json!({ "ok": true }).into_response().unwrap();
+        assert_eq!(json.header("Content-Type").unwrap(), "application/json");
+        assert_eq!(json.body(), br#"{"ok":true}"#);
+
+        let created = (201, String::from("created")).into_response().unwrap();
+        assert_eq!(created.status(), 201);
+        assert_eq!(created.body(), b"created");
+
+        let problem = Problem::new(422, "Validation failed").into_response();
+        assert_eq!(problem.unwrap().status(), 422);
+    }
+
+    #[test]
+    fn test_result_into_response() {
+        let ok: Result<&str> = Ok("fine");
+        assert_eq!(ok.into_response().unwrap().body(), b"fine");
+
+        let err: Result<Response> = Err(Error::with_status(404, "missing"));
+        assert_eq!(err.into_response().unwrap_err().status(), 404);
+
+        let io: std::result::Result<Value, std::io::Error> =
+            Err(std::io::Error::other("disk full"));
+        assert_eq!(io.into_response().unwrap_err().status(), 500);
+    }
+}
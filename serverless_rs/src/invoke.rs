@@ -0,0 +1,316 @@
+/*!
+Local invocation mechanism for serverless.rs.
+
+This module provides the `--invoke <event.json>` flag, which loads a platform event
+from a file, runs it through the platform adapter's parsing, invokes the handler,
+and prints the platform response as JSON. This allows testing the exact event
+mapping of a platform without deploying.
+
+Use `--platform aws|cloudflare` to select the event format (defaults to `aws`).
+The selected platform's feature must be enabled.
+
+Use `--simulate-timeout 30s` to give the handler a deadline, as a deployed function
+would have, so timeout handling can be exercised locally.
+*/
+
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use crate::error::{Error, Result};
+use crate::util::parse_duration;
+use crate::{Context, Request, Response};
+
+/// Platform used for `--invoke` when `--platform` is not given
+pub const DEFAULT_INVOKE_PLATFORM: &str = "aws";
+
+/// Arguments for the `--invoke` mode
+#[derive(Debug, Clone, PartialEq)]
+pub struct InvokeArgs {
+    /// Path to the JSON event file
+    pub event_path: PathBuf,
+
+    /// Platform whose event format the file uses
+    pub platform: String,
+
+    /// Execution time budget; sets the context's deadline and remaining time
+    pub simulate_timeout: Option<Duration>,
+}
+
+/// Parse command-line arguments for `--invoke <event.json>`, `--platform <name>` and
+/// `--simulate-timeout <duration>`
+///
+/// Returns `None` if `--invoke` is not present. Both `--invoke path` and
+/// `--invoke=path` forms are accepted. Durations such as `30s`, `500ms` or `2m` are
+/// accepted for the timeout; an invalid duration is ignored with a warning.
+pub fn parse_invoke_args() -> Option<InvokeArgs> {
+    parse_invoke_args_from(std::env::args().skip(1))
+}
+
+/// Parse `--invoke` arguments from an explicit argument list
+fn parse_invoke_args_from<I>(args: I) -> Option<InvokeArgs>
+where
+    I: IntoIterator<Item = String>,
+{
+    let mut event_path = None;
+    let mut platform = None;
+    let mut timeout = None;
+    let mut args = args.into_iter();
+
+    while let Some(arg) = args.next() {
+        if let Some(value) = arg.strip_prefix("--invoke=") {
+            event_path = Some(PathBuf::from(value));
+        } else if arg == "--invoke" {
+            event_path = args.next().map(PathBuf::from);
+        } else if let Some(value) = arg.strip_prefix("--platform=") {
+            platform = Some(value.to_string());
+        } else if arg == "--platform" {
+            platform = args.next();
+        } else if let Some(value) = arg.strip_prefix("--simulate-timeout=") {
+            timeout = Some(value.to_string());
+        } else if arg == "--simulate-timeout" {
+            timeout = args.next();
+        }
+    }
+
+    let simulate_timeout = timeout.and_then(|value| {
+        let parsed = parse_duration(&value);
+        if parsed.is_none() {
+            eprintln!("Warning: ignoring invalid --simulate-timeout '{}'", value);
+        }
+        parsed
+    });
+
+    Some(InvokeArgs {
+        event_path: event_path?,
+        platform: platform.unwrap_or_else(|| DEFAULT_INVOKE_PLATFORM.to_string()),
+        simulate_timeout,
+    })
+}
+
+/// Invoke a handler with a platform event, returning the platform response
+///
+/// The event is parsed by the platform adapter exactly as it would be when deployed.
+pub fn invoke_event<F>(handler: F, platform: &str, event: Value) -> Result<Value>
+where
+    F: Fn(Request, &Context) -> Result<Response>,
+{
+    match platform {
+        #[cfg(feature = "aws")]
+        "aws" => {
+            let context = serde_json::json!({ "awsRequestId": "local-invoke" });
+            invoke_aws_event(handler, event, context)
+        }
+        #[cfg(feature = "cloudflare")]
+        "cloudflare" => {
+            use crate::platforms::cloudflare;
+
+            let req = cloudflare::request_from_event(&event)?;
+            let ctx = Context::new().with_request_id("local-invoke");
+            let resp = handler(req, &ctx)?;
+            Ok(cloudflare::response_to_value(&resp))
+        }
+        _ => {
+            let _ = (handler, event);
+            Err(Error::platform(format!(
+                "Cannot invoke with platform '{}': unknown platform or its feature is not enabled",
+                platform
+            )))
+        }
+    }
+}
+
+/// Invoke a handler with an AWS event, as an API Gateway request or a direct invocation
+///
+/// This is also how [`handle_event`](crate::platforms::handle_event) runs events
+/// outside a known platform.
+#[cfg(feature = "aws")]
+pub(crate) fn invoke_aws_event<F>(handler: F, event: Value, context: Value) -> Result<Value>
+where
+    F: Fn(Request, &Context) -> Result<Response>,
+{
+    use crate::platforms::aws;
+
+    if aws::is_api_gateway_event(&event) {
+        aws::handle_api_gateway(handler, event, context)
+    } else {
+        aws::handle_direct_invocation(handler, event, context)
+    }
+}
+
+/// Load an event from a JSON file and invoke a handler with it
+pub fn invoke_from_file<F>(handler: F, platform: &str, path: impl AsRef<Path>) -> Result<Value>
+where
+    F: Fn(Request, &Context) -> Result<Response>,
+{
+    let path = path.as_ref();
+    let contents = std::fs::read(path)
+        .map_err(|e| Error::unexpected(format!("Failed to read {}: {}", path.display(), e)))?;
+    let event = serde_json::from_slice(&contents).map_err(Error::serialization)?;
+    invoke_event(handler, platform, event)
+}
+
+/// Invoke a handler with the event file and options from [`InvokeArgs`]
+///
+/// With a `simulate_timeout`, the handler's context gets a deadline of now plus the
+/// timeout, and the timeout as its remaining time.
+pub fn invoke_with_args<F>(handler: F, args: &InvokeArgs) -> Result<Value>
+where
+    F: Fn(Request, &Context) -> Result<Response>,
+{
+    match args.simulate_timeout {
+        Some(timeout) => {
+            let deadline = SystemTime::now() + timeout;
+            let handler = |req: Request, ctx: &Context| {
+                let ctx = ctx
+                    .clone()
+                    .with_deadline(deadline)
+                    .with_remaining_time(timeout);
+                handler(req, &ctx)
+            };
+            invoke_from_file(handler, &args.platform, &args.event_path)
+        }
+        None => invoke_from_file(handler, &args.platform, &args.event_path),
+    }
+}
+
+/// Invoke the handler and print the response if the --invoke flag is present
+///
+/// This is a convenience function that can be called at the start of the main
+/// function, alongside [`handle_info_request`](crate::handle_info_request). The
+/// response is printed as JSON to stdout.
+///
+/// Returns `None` if the flag is absent. Otherwise the invocation's outcome is
+/// returned, so `main` can report a failed invocation with a nonzero exit status.
+///
+/// # Examples
+///
+/// ```no_run
+/// use serverless_rs::{Context, Request, Response, Result};
+///
+/// fn handler(_req: Request, _ctx: &Context) -> Result<Response> {
+///     Ok(Response::text("Hello"))
+/// }
+///
+/// fn main() -> Result<()> {
+///     if let Some(result) = serverless_rs::handle_invoke_request(handler) {
+///         return result;
+///     }
+///     Ok(())
+/// }
+/// ```
+pub fn handle_invoke_request<F>(handler: F) -> Option<Result<()>>
+where
+    F: Fn(Request, &Context) -> Result<Response>,
+{
+    let args = parse_invoke_args()?;
+    let result = invoke_with_args(handler, &args).and_then(|response| {
+        let json = serde_json::to_string_pretty(&response).map_err(Error::serialization)?;
+        println!("{}", json);
+        Ok(())
+    });
+    Some(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    fn echo(req: Request, _ctx: &Context) -> Result<Response> {
+        Ok(Response::text(req.path().unwrap_or_default()))
+    }
+
+    #[test]
+    fn test_parse_invoke_args() {
+        assert_eq!(parse_invoke_args_from(args(&["--info"])), None);
+        assert_eq!(parse_invoke_args_from(args(&["--invoke"])), None);
+
+        let parsed = parse_invoke_args_from(args(&["--invoke", "event.json"])).unwrap();
+        assert_eq!(parsed.event_path, PathBuf::from("event.json"));
+        assert_eq!(parsed.platform, "aws");
+
+        let parsed =
+            parse_invoke_args_from(args(&["--platform=cloudflare", "--invoke=fetch.json"]))
+                .unwrap();
+        assert_eq!(parsed.event_path, PathBuf::from("fetch.json"));
+        assert_eq!(parsed.platform, "cloudflare");
+        assert_eq!(parsed.simulate_timeout, None);
+
+        let parsed =
+            parse_invoke_args_from(args(&["--invoke", "e.json", "--simulate-timeout", "30s"]))
+                .unwrap();
+        assert_eq!(parsed.simulate_timeout, Some(Duration::from_secs(30)));
+
+        let parsed =
+            parse_invoke_args_from(args(&["--invoke", "e.json", "--simulate-timeout=soon"]))
+                .unwrap();
+        assert_eq!(parsed.simulate_timeout, None);
+    }
+
+    #[test]
+    fn test_invoke_unknown_platform() {
+        let result = invoke_event(echo, "fastly", Value::Null);
+        assert!(matches!(result, Err(Error::Platform(_))));
+    }
+
+    #[test]
+    fn test_invoke_missing_file() {
+        let result = invoke_from_file(echo, "aws", "does/not/exist.json");
+        assert!(result.is_err());
+    }
+
+    #[cfg(feature = "aws")]
+    #[test]
+    fn test_invoke_aws_event() {
+        let event = serde_json::json!({
+            "version": "2.0",
+            "rawPath": "/hello",
+            "requestContext": { "http": { "method": "GET" } }
+        });
+
+        let response = invoke_event(echo, "aws", event).unwrap();
+        assert_eq!(response["statusCode"], 200);
+        assert_eq!(response["body"], "/hello");
+    }
+
+    #[cfg(feature = "aws")]
+    #[test]
+    fn test_invoke_aws_simulated_timeout() {
+        let path = std::env::temp_dir().join(format!(
+            "serverless_rs_invoke_aws_{}.json",
+            std::process::id()
+        ));
+        std::fs::write(&path, r#"{"task":"resize"}"#).unwrap();
+
+        let args = InvokeArgs {
+            event_path: path.clone(),
+            platform: "aws".to_string(),
+            simulate_timeout: Some(Duration::from_secs(30)),
+        };
+        let response = invoke_with_args(
+            |_req: Request, ctx: &Context| {
+                let remaining = ctx.deadline().unwrap().duration_since(SystemTime::now());
+                assert!(remaining.unwrap() <= Duration::from_secs(30));
+                assert_eq!(ctx.remaining_time(), Some(Duration::from_secs(30)));
+                Ok(Response::text("done"))
+            },
+            &args,
+        );
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(response.unwrap(), "done");
+    }
+
+    #[cfg(feature = "cloudflare")]
+    #[test]
+    fn test_invoke_cloudflare_event() {
+        let event = serde_json::json!({ "method": "GET", "url": "https://example.com/hello" });
+
+        let response = invoke_event(echo, "cloudflare", event).unwrap();
+        assert_eq!(response["status"], 200);
+        assert_eq!(response["body"], "/hello");
+    }
+}
@@ -0,0 +1,234 @@
+/*!
+JSON output settings for serverless.rs.
+
+This module provides [`JsonConfig`], which controls how JSON response bodies are
+written: compact or pretty-printed, and with keys kept as they are or converted to
+camelCase. Use it per response with [`Response::json_with`], or for every JSON
+response in a scope as a middleware.
+*/
+
+use async_trait::async_trait;
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use crate::error::{Error, Result};
+use crate::{ContentType, Context, Middleware, Next, Request, Response};
+
+/// How JSON response bodies are written
+///
+/// Converting keys to camelCase lets Rust structs keep snake_case fields while the
+/// client sees camelCase, without a `#[serde(rename)]` on every field. Keys of
+/// nested objects are converted too.
+///
+/// As a middleware, the config rewrites every response whose `Content-Type` is
+/// `application/json` or ends in `+json`. Bodies that don't parse as JSON are left
+/// alone.
+///
+/// # Examples
+///
+/// ```
+/// use serverless_rs::{json, JsonConfig, Response};
+///
+/// let config = JsonConfig::new().with_camel_case(true);
+/// let response = Response::json_with(&json!({ "user_id": 7 }), &config).unwrap();
+/// assert_eq!(response.body(), br#"{"userId":7}"#);
+/// ```
+///
+/// ```
+/// use serverless_rs::{Context, JsonConfig, Request, Response, Result, RouterBuilder};
+///
+/// fn profile(_req: Request, _ctx: &Context) -> Result<Response> {
+///     Response::json(&serverless_rs::json!({ "display_name": "Ada" }))
+/// }
+///
+/// let router = RouterBuilder::new()
+///     .scope("/api", |api| {
+///         api.layer(JsonConfig::new().with_camel_case(true))
+///             .get("/profile", profile)
+///     })
+///     .build();
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct JsonConfig {
+    pretty: bool,
+    camel_case: bool,
+}
+
+impl JsonConfig {
+    /// Creates a config for compact JSON with keys unchanged, as [`Response::json`] writes
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets whether JSON is pretty-printed
+    pub fn with_pretty(mut self, pretty: bool) -> Self {
+        self.pretty = pretty;
+        self
+    }
+
+    /// Sets whether object keys are converted from snake_case to camelCase
+    pub fn with_camel_case(mut self, camel_case: bool) -> Self {
+        self.camel_case = camel_case;
+        self
+    }
+
+    /// Serialize a value with these settings
+    ///
+    /// Converting keys goes through [`serde_json::Value`], which orders object keys
+    /// alphabetically.
+    pub fn to_vec<T: Serialize + ?Sized>(&self, value: &T) -> Result<Vec<u8>> {
+        if !self.camel_case {
+            return self.write(value);
+        }
+        let mut value = serde_json::to_value(value).map_err(Error::serialization)?;
+        camelize_keys(&mut value);
+        self.write(&value)
+    }
+
+    fn write<T: Serialize + ?Sized>(&self, value: &T) -> Result<Vec<u8>> {
+        let body = if self.pretty {
+            serde_json::to_vec_pretty(value)
+        } else {
+            serde_json::to_vec(value)
+        };
+        body.map_err(Error::serialization)
+    }
+}
+
+#[async_trait]
+impl Middleware for JsonConfig {
+    async fn handle(&self, req: Request, ctx: &Context, next: Next<'_>) -> Result<Response> {
+        let response = next.handle(req, ctx).await?;
+        let is_json = response
+            .header("Content-Type")
+            .and_then(|value| ContentType::parse(value))
+            .is_some_and(|content_type| {
+                content_type.is("application/json") || content_type.subtype().ends_with("+json")
+            });
+        if !is_json || response.is_base64() {
+            return Ok(response);
+        }
+
+        match serde_json::from_slice::<Value>(response.body()) {
+            Ok(value) => {
+                let body = self.to_vec(&value)?;
+                Ok(response.with_body(body))
+            }
+            Err(_) => Ok(response),
+        }
+    }
+}
+
+/// Convert the keys of every object in `value` to camelCase
+fn camelize_keys(value: &mut Value) {
+    match value {
+        Value::Object(object) => {
+            let entries = std::mem::take(object);
+            *object = entries
+                .into_iter()
+                .map(|(key, mut value)| {
+                    camelize_keys(&mut value);
+                    (camel_case(&key), value)
+                })
+                .collect::<Map<String, Value>>();
+        }
+        Value::Array(items) => items.iter_mut().for_each(camelize_keys),
+        _ => {}
+    }
+}
+
+/// Convert a snake_case name to camelCase, keeping leading underscores
+fn camel_case(name: &str) -> String {
+    let trimmed = name.trim_start_matches('_');
+    let mut converted = name[..name.len() - trimmed.len()].to_string();
+    let mut upper = false;
+    for c in trimmed.chars() {
+        if c == '_' {
+            upper = true;
+        } else if upper {
+            converted.extend(c.to_uppercase());
+            upper = false;
+        } else {
+            converted.push(c);
+        }
+    }
+    converted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Handler;
+    use serde_json::json;
+
+    #[test]
+    fn test_camel_case() {
+        assert_eq!(camel_case("user_id"), "userId");
+        assert_eq!(camel_case("created_at_ms"), "createdAtMs");
+        assert_eq!(camel_case("name"), "name");
+        assert_eq!(camel_case("alreadyCamel"), "alreadyCamel");
+        assert_eq!(camel_case("_private_key"), "_privateKey");
+    }
+
+    #[test]
+    fn test_to_vec() {
+        let value = json!({ "user_id": 1, "roles": [{ "role_name": "admin" }] });
+
+        let compact = JsonConfig::new().to_vec(&value).unwrap();
+        assert_eq!(compact, serde_json::to_vec(&value).unwrap());
+
+        let camel = JsonConfig::new().with_camel_case(true);
+        assert_eq!(
+            camel.to_vec(&value).unwrap(),
+            br#"{"roles":[{"roleName":"admin"}],"userId":1}"#
+        );
+
+        let pretty = camel
+            .with_pretty(true)
+            .to_vec(&json!({ "a_b": 1 }))
+            .unwrap();
+        assert_eq!(pretty, b"{\n  \"aB\": 1\n}");
+
+        let response = Response::json_pretty(&json!({ "a_b": 1 })).unwrap();
+        assert_eq!(response.header("Content-Type").unwrap(), "application/json");
+        assert_eq!(response.body(), b"{\n  \"a_b\": 1\n}");
+    }
+
+    struct Body(&'static str, &'static str);
+
+    #[async_trait]
+    impl Handler for Body {
+        async fn handle(&self, _req: Request, _ctx: &Context) -> Result<Response> {
+            Ok(Response::new()
+                .with_header("Content-Type", self.0)
+                .with_body(self.1))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_middleware() {
+        let config = JsonConfig::new().with_camel_case(true);
+        let ctx = Context::new();
+        let run = |content_type, body| {
+            let ctx = ctx.clone();
+            async move {
+                config
+                    .handle(Request::new(), &ctx, &Body(content_type, body))
+                    .await
+                    .unwrap()
+            }
+        };
+
+        let json = run("application/json; charset=utf-8", r#"{"first_name":"Ada"}"#).await;
+        assert_eq!(json.body(), br#"{"firstName":"Ada"}"#);
+
+        let problem = run("application/problem+json", r#"{"error_code":1}"#).await;
+        assert_eq!(problem.body(), br#"{"errorCode":1}"#);
+
+        let text = run("text/plain", r#"{"first_name":"Ada"}"#).await;
+        assert_eq!(text.body(), br#"{"first_name":"Ada"}"#);
+
+        let invalid = run("application/json", "{not json").await;
+        assert_eq!(invalid.body(), b"{not json");
+    }
+}
@@ -14,7 +14,7 @@ serverless functions deployable to multiple cloud providers with minimal platfor
 ## Example
 
 ```rust,no_run
-use serverless_rs::{Context, Request, Response, Result};
+use serverless_rs::prelude::*;
 
 // Use the #[serverless] attribute to transform your function
 // (the macro is included but doctest will fail until fully implemented)
@@ -38,12 +38,138 @@ enable the corresponding feature flag:
 - `vercel` - Vercel Functions
 - `local` - Local development server
 
+`detect_platform()` inspects the environment to tell which platform the binary is
+running on, and `run_auto()` hands an event to that platform's adapter. Elsewhere,
+such as on a developer's machine, events are taken to be AWS-shaped, as with
+`--invoke`.
+
 ## Attribute Macros
 
 - `#[serverless]` - Mark a function as a serverless handler
 - `#[route]` - Define an HTTP route
 - `#[requirements]` - Specify resource requirements
 
+## Return Types
+
+`#[serverless]` functions may return any type implementing `IntoResponse` instead of
+`Result<Response>`: `Ok("hello")`, `Ok(json!({...}))` and `Ok((201, body))` all work,
+and errors keep their status codes.
+
+They may also take typed arguments implementing `FromRequest`, such as
+`extract::Json<T>`, `extract::Query<T>` and `extract::Path<T>`, in place of or
+alongside `Request`. A failed extraction returns a `400 Bad Request` without calling
+the function.
+
+## Routing
+
+Functions declared with `#[route]` and `#[serverless]` can be combined into one
+router with the `routes!` macro, which dispatches by each function's declared
+method and path. Path segments written as `{name}` become path parameters, and a
+trailing `*` matches the rest of the path. When several routes match, an exact path
+wins, then routes are compared segment by segment: literal beats `{name}` beats `*`.
+`RouterBuilder::merge()` combines builders assembled separately, rejecting routes
+that both of them define.
+
+## Multi-Function Binaries
+
+Every `#[serverless]` function is registered in a global registry at link time.
+`all_functions()` returns the metadata of every function in the binary,
+`handle_all_info_request()` prints it for `--info`, and `invoke(name, req, ctx)`
+dispatches to a function by name. When a binary hosts several functions, `--info`
+on any of them shows them all: a JSON array, one text section per function, or
+combined OpenAPI, SAM and IAM policy output. `--info <function_name>` shows a single
+function and `--info --list` prints only the names.
+
+## Build Metadata
+
+`FunctionInfo::with_build_info()` records a `git_sha` and `build_timestamp`, plus the
+executable's `binary_size`, in the function's metadata. `#[serverless]` functions
+capture `SERVERLESS_BUILD_GIT_SHA` and `SERVERLESS_BUILD_TIMESTAMP` when they are
+compiled, so `--info` reports the build rather than the environment it runs in.
+
+## Tracing
+
+Platform adapters read the incoming trace from the W3C `traceparent` or AWS
+`X-Amzn-Trace-Id` header and expose it as `Context::trace_id()` and
+`Context::span_id()`. With the `otel` feature enabled, each invocation also runs
+inside an OpenTelemetry span recording the function name, method, path and status,
+available to handlers as `Context::current_span()`.
+
+## Metrics
+
+`Context::emit_metric()` writes a metric to standard output, as a CloudWatch
+Embedded Metric Format line on AWS Lambda and as a StatsD line elsewhere. With the
+`metrics` feature enabled, adapters emit `Duration` and `Errors` for every invocation.
+
+## Testing
+
+With the `testing` feature enabled, `serverless_rs::testing` provides a
+`TestRequest` builder, `invoke()` to run a handler on a tokio runtime of its own, and
+assertions such as `assert_status()` and `assert_json_body()`.
+`assert_response_snapshot()` compares a response against a snapshot file under
+`tests/snapshots`, recording it on the first run.
+
+## Sessions
+
+With the `session` feature enabled, `serverless_rs::session::SessionLayer` keeps
+session data in an HMAC-signed cookie, available to handlers as `Context::session()`.
+For single values such as CSRF tokens, `session::sign()` signs a cookie value and
+`Request::verify_signed_cookie()` checks it.
+
+## Secrets
+
+`Context::secret()` resolves secrets through a `secrets::SecretProvider`, reading
+environment variables by default, and caches them for the life of the process. With
+the `aws` feature enabled, `secrets::SecretsManagerProvider` and
+`secrets::SsmParameterProvider` read from AWS Secrets Manager and SSM Parameter Store
+through the Parameters and Secrets Lambda Extension.
+
+## Circuit Breakers
+
+`Context::circuit_breaker()` returns a breaker shared by name across invocations.
+Wrapping downstream calls in `CircuitBreaker::call()` counts failures; after
+`circuit::configure()`'s threshold is reached, calls fail fast with
+`503 Service Unavailable` until the open period is over and a trial call succeeds.
+
+## MessagePack
+
+With the `msgpack` feature enabled, `Response::msgpack()` and `Request::body_msgpack()`
+read and write `application/msgpack` bodies. `Request::parse_body()` and
+`Response::negotiated()` pick JSON or MessagePack from the `Content-Type` and
+`Accept` headers, so handlers serve both without changes.
+
+## GraphQL
+
+With the `graphql` feature enabled, `Request::graphql_request()` reads the standard
+`{ query, variables, operationName }` request from a `POST` body or `GET` query
+parameters, and `Response::graphql()` returns the execution result, so any GraphQL
+schema can be served from a handler.
+
+## Compressed Requests
+
+With the `compression` feature enabled, `compression::DecompressionLayer` decodes
+request bodies sent with `Content-Encoding: gzip`, `deflate` or `br` before they
+reach the handler. Decompressed bodies over the configured limit are rejected with
+`413 Payload Too Large`.
+
+## WebAssembly
+
+The crate builds for `wasm32-unknown-unknown`, as used by Cloudflare Workers, with
+`cargo build --target wasm32-unknown-unknown --no-default-features --features wasm,cloudflare`.
+That target has no clock, so `Date` headers and duration metrics are skipped,
+`HandlerExt::with_timeout` never fires, open circuit breakers retry without waiting,
+and the in-memory rate limit and idempotency stores return an
+`Error::Platform` instead of reading the clock.
+
+## Local Invocation
+
+Functions generated by `#[serverless]` expose `handle_invoke()`, which handles the
+`--invoke <event.json>` flag: the event is parsed by the platform adapter selected
+with `--platform aws|cloudflare`, the handler is invoked, and the platform response
+is printed as JSON. `--simulate-timeout 30s` gives the handler a deadline, as it
+would have when deployed. The handler runs on the same tokio runtime as the
+adapters, and a failed invocation is returned so `main` can exit nonzero.
+
 ## Resource Requirements
 
 Use the `#[requirements]` attribute to specify recommended and required resources:
@@ -62,30 +188,91 @@ async fn handler(req: Request, ctx: &Context) -> Result<Response> {
     Ok(Response::text("Hello, world!"))
 }
 ```
+
+Shared defaults can live in a JSON or TOML profile, loaded with
+`Requirements::from_path()` or embedded with `#[requirements(profile = "infra/base.json")]`.
+
+`ephemeral_storage` (the size of `/tmp`, e.g. `"2GB"`) and `vpc`
+(`"subnets=a,b;security_groups=c"`, built with `VpcHint`) are recognized resources:
+`Requirements::validate()` checks their values, and `--info` lists the VPC placement.
 */
 
+pub mod circuit;
+pub mod coalesce;
+#[cfg(feature = "compression")]
+pub mod compression;
+pub mod concurrency;
+mod content_type;
 mod context;
 mod error;
+mod extensions;
+pub mod extract;
+#[cfg(feature = "graphql")]
+pub mod graphql;
 mod handler;
+pub mod idempotency;
 mod info;
+mod into_response;
+mod invoke;
+mod json;
+pub mod logging;
+mod metrics;
+mod middleware;
 pub mod platforms;
+pub mod prelude;
+mod problem;
+pub mod ratelimit;
+mod registry;
 mod request;
 mod requirements;
 mod response;
 mod router;
+pub mod secrets;
+#[cfg(feature = "session")]
+pub mod session;
+mod sse;
+#[cfg(feature = "testing")]
+pub mod testing;
+mod trace;
+mod util;
 
 // Re-export main types
-pub use context::Context;
-pub use error::{Error, Result};
-pub use handler::Handler;
+pub use content_type::ContentType;
+pub use context::{Context, DeadlineGuard};
+pub use error::{BoxError, Error, Result};
+pub use extensions::Extensions;
+pub use extract::FromRequest;
+pub use handler::{
+    catch_panic, handler_fn, AndThen, FnHandler, Handler, HandlerExt, MapResponse, Timeout,
+};
 pub use info::{
-    check_info_flag, display_info, handle_info_request, parse_info_args, FunctionInfo,
-    OutputFormat, RouteInfo,
+    check_info_flag, display_info, handle_info_request, parse_info_args, parse_info_selection,
+    write_info, FunctionInfo, InfoSelection, OutputFormat, ParamInfo, ParamLocation, RouteInfo,
+    BUILD_GIT_SHA_ENV, BUILD_TIMESTAMP_ENV,
+};
+pub use into_response::IntoResponse;
+pub use invoke::{
+    handle_invoke_request, invoke_event, invoke_from_file, invoke_with_args, parse_invoke_args,
+    InvokeArgs,
+};
+pub use json::JsonConfig;
+pub use metrics::MetricUnit;
+pub use middleware::{MapResponseLayer, Middleware, Next};
+pub use platforms::{detect_platform, run_auto, Platform};
+pub use problem::Problem;
+pub use registry::{
+    all_functions, display_all_info, find_function, handle_all_info_request, invoke,
+    FunctionRegistration,
 };
 pub use request::Request;
-pub use requirements::{Requirements, Resource};
-pub use response::Response;
-pub use router::Router;
+pub use requirements::{Permission, Requirements, Resource, VpcHint};
+pub use response::{
+    clear_default_headers, set_default_header, set_header_limits, set_server_header, Response,
+    DEFAULT_MAX_HEADER_BYTES, DEFAULT_MAX_HEADER_COUNT,
+};
+pub use router::{Router, RouterBuilder, Scope};
+pub use sse::SseEvent;
+pub use util::{http_date, parse_http_date};
 
 // Re-export macros
 pub use serverless_rs_macros::{requirements, route, serverless};
@@ -93,6 +280,17 @@ pub use serverless_rs_macros::{requirements, route, serverless};
 // Re-export serde_json for use in macros
 pub use serde_json::{json, Value};
 
+// Re-export the boxed future type used by `handler_fn`
+pub use futures::future::BoxFuture;
+
+// Re-export inventory for function registration in macros
+#[doc(hidden)]
+pub use inventory;
+
+// Re-export an executor for use in macros
+#[doc(hidden)]
+pub use futures::executor::block_on;
+
 /// Version of the serverless.rs framework
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
 
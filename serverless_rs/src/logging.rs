@@ -0,0 +1,554 @@
+/*!
+Access logging middleware for serverless.rs.
+
+[`AccessLogLayer`] logs the method, path, status and duration of every request
+through [`Context::log`] or [`Context::log_json`]. Sensitive headers and query
+parameters are redacted before they are logged.
+
+The AWS and Cloudflare adapters can also log one JSON record per invocation, for
+handlers with or without a router; see [`set_invocation_log`].
+
+When the [`DEBUG_BODIES_ENV`] environment variable is set to `1`, the layer also logs
+each request's headers and body at `DEBUG` level, with sensitive fields redacted and
+the body truncated to a configurable size.
+*/
+
+use async_trait::async_trait;
+use serde_json::{json, Map, Value};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use crate::error::Result;
+use crate::{Context, Handler, Middleware, Request, Response};
+
+/// Replacement for redacted values
+const REDACTED: &str = "[REDACTED]";
+
+/// Headers redacted by default
+const DEFAULT_REDACTED_HEADERS: [&str; 3] = ["Authorization", "Cookie", "Proxy-Authorization"];
+
+/// Body fields redacted by default
+const DEFAULT_REDACTED_BODY_KEYS: [&str; 4] = ["password", "secret", "token", "api_key"];
+
+/// Environment variable that enables request body logging when set to `1`
+pub const DEBUG_BODIES_ENV: &str = "SERVERLESS_DEBUG_BODIES";
+
+/// Default number of body bytes logged when [`DEBUG_BODIES_ENV`] is set
+pub const DEFAULT_DEBUG_BODY_BYTES: usize = 2048;
+
+/// Format of access log entries
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AccessLogFormat {
+    /// A single line: `GET /path?query 200 12ms`
+    #[default]
+    Text,
+    /// A JSON object with the method, path, query, headers, status and duration
+    Json,
+}
+
+/// Middleware that logs every request
+///
+/// # Examples
+///
+/// ```
+/// use serverless_rs::logging::{AccessLogFormat, AccessLogLayer};
+/// use serverless_rs::{Context, Request, Response, Result, RouterBuilder};
+///
+/// fn login(_req: Request, _ctx: &Context) -> Result<Response> {
+///     Ok(Response::text("welcome"))
+/// }
+///
+/// let router = RouterBuilder::new()
+///     .scope("/auth", |auth| {
+///         auth.layer(
+///             AccessLogLayer::new()
+///                 .with_format(AccessLogFormat::Json)
+///                 .redact_query_param("token"),
+///         )
+///         .post("/login", login)
+///     })
+///     .build();
+/// ```
+#[derive(Debug, Clone)]
+pub struct AccessLogLayer {
+    format: AccessLogFormat,
+    redacted_headers: Vec<String>,
+    redacted_query_params: Vec<String>,
+    redacted_body_keys: Vec<String>,
+    debug_body_bytes: usize,
+}
+
+impl AccessLogLayer {
+    /// Creates a text access logger redacting `Authorization`, `Cookie` and
+    /// `Proxy-Authorization`, and the body fields `password`, `secret`, `token` and
+    /// `api_key`
+    pub fn new() -> Self {
+        Self {
+            format: AccessLogFormat::default(),
+            redacted_headers: DEFAULT_REDACTED_HEADERS
+                .iter()
+                .map(|name| name.to_string())
+                .collect(),
+            redacted_query_params: Vec::new(),
+            redacted_body_keys: DEFAULT_REDACTED_BODY_KEYS
+                .iter()
+                .map(|key| key.to_string())
+                .collect(),
+            debug_body_bytes: DEFAULT_DEBUG_BODY_BYTES,
+        }
+    }
+
+    /// Sets the log entry format
+    pub fn with_format(mut self, format: AccessLogFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Redacts a header, matched ignoring ASCII case
+    pub fn redact_header(mut self, name: impl Into<String>) -> Self {
+        self.redacted_headers.push(name.into());
+        self
+    }
+
+    /// Redacts a query parameter
+    pub fn redact_query_param(mut self, name: impl Into<String>) -> Self {
+        self.redacted_query_params.push(name.into());
+        self
+    }
+
+    /// Redacts a field of JSON and form bodies, matched ignoring ASCII case
+    ///
+    /// JSON fields are redacted at any depth. Bodies are only logged when
+    /// [`DEBUG_BODIES_ENV`] is set.
+    pub fn redact_body_key(mut self, name: impl Into<String>) -> Self {
+        self.redacted_body_keys.push(name.into());
+        self
+    }
+
+    /// Sets how many bytes of each body are logged when [`DEBUG_BODIES_ENV`] is set
+    pub fn with_debug_body_limit(mut self, max_bytes: usize) -> Self {
+        self.debug_body_bytes = max_bytes;
+        self
+    }
+
+    fn is_redacted_header(&self, name: &str) -> bool {
+        self.redacted_headers
+            .iter()
+            .any(|redacted| redacted.eq_ignore_ascii_case(name))
+    }
+
+    fn is_redacted_body_key(&self, name: &str) -> bool {
+        self.redacted_body_keys
+            .iter()
+            .any(|redacted| redacted.eq_ignore_ascii_case(name))
+    }
+
+    fn is_redacted_query_param(&self, name: &str) -> bool {
+        self.redacted_query_params
+            .iter()
+            .any(|redacted| redacted == name)
+    }
+
+    /// Returns the query parameters sorted by name, with redacted values replaced
+    fn query(&self, req: &Request) -> Vec<(String, String)> {
+        let mut query: Vec<(String, String)> = req
+            .query()
+            .iter()
+            .map(|(name, value)| {
+                let value = if self.is_redacted_query_param(name) {
+                    REDACTED.to_string()
+                } else {
+                    value.clone()
+                };
+                (name.clone(), value)
+            })
+            .collect();
+        query.sort();
+        query
+    }
+
+    /// Returns the headers sorted by name, with redacted values replaced
+    fn headers(&self, req: &Request) -> Vec<(String, String)> {
+        let mut headers: Vec<(String, String)> = req
+            .headers()
+            .iter()
+            .map(|(name, value)| {
+                let value = if self.is_redacted_header(name) {
+                    REDACTED.to_string()
+                } else {
+                    value.clone()
+                };
+                (name.clone(), value)
+            })
+            .collect();
+        headers.sort();
+        headers
+    }
+
+    /// Returns the body with redacted fields replaced, and its size in bytes
+    ///
+    /// JSON and form bodies are redacted field by field; other bodies are logged as
+    /// text, with invalid UTF-8 replaced.
+    fn body(&self, req: &Request) -> (String, usize) {
+        let body = req
+            .decoded_body()
+            .map(|body| body.into_owned())
+            .unwrap_or_else(|_| req.body().to_vec());
+
+        let is_form = req
+            .content_type()
+            .is_some_and(|content_type| content_type.is("application/x-www-form-urlencoded"));
+        let text = if let Ok(mut value) = serde_json::from_slice::<Value>(&body) {
+            self.redact_json(&mut value);
+            value.to_string()
+        } else if let (true, Ok(pairs)) = (
+            is_form,
+            serde_urlencoded::from_bytes::<Vec<(String, String)>>(&body),
+        ) {
+            let pairs: Vec<(String, String)> = pairs
+                .into_iter()
+                .map(|(name, value)| match self.is_redacted_body_key(&name) {
+                    true => (name, REDACTED.to_string()),
+                    false => (name, value),
+                })
+                .collect();
+            serde_urlencoded::to_string(pairs).unwrap_or_default()
+        } else {
+            String::from_utf8_lossy(&body).into_owned()
+        };
+        (text, body.len())
+    }
+
+    /// Replace redacted fields of a JSON value, at any depth
+    fn redact_json(&self, value: &mut Value) {
+        match value {
+            Value::Object(fields) => {
+                for (name, field) in fields.iter_mut() {
+                    if self.is_redacted_body_key(name) {
+                        *field = Value::String(REDACTED.to_string());
+                    } else {
+                        self.redact_json(field);
+                    }
+                }
+            }
+            Value::Array(items) => items.iter_mut().for_each(|item| self.redact_json(item)),
+            _ => {}
+        }
+    }
+
+    /// Build the debug log entry for a request's headers and body
+    fn debug_entry(&self, req: &Request) -> Value {
+        let (mut body, size) = self.body(req);
+        let truncated = body.len() > self.debug_body_bytes;
+        if truncated {
+            let mut end = self.debug_body_bytes;
+            while !body.is_char_boundary(end) {
+                end -= 1;
+            }
+            body.truncate(end);
+        }
+        let headers: Map<String, Value> = self
+            .headers(req)
+            .into_iter()
+            .map(|(name, value)| (name, Value::String(value)))
+            .collect();
+
+        json!({
+            "method": req.method_str(),
+            "path": req.path(),
+            "headers": headers,
+            "body": body,
+            "bodyBytes": size,
+            "truncated": truncated,
+        })
+    }
+
+    /// Format a text log entry
+    fn text_entry(&self, req: &Request, status: u16, duration: Duration) -> String {
+        let mut target = req.path().unwrap_or_else(|| "/".to_string());
+        let query = self.query(req);
+        if !query.is_empty() {
+            let pairs: Vec<String> = query
+                .iter()
+                .map(|(name, value)| format!("{}={}", name, value))
+                .collect();
+            target = format!("{}?{}", target, pairs.join("&"));
+        }
+        format!(
+            "{} {} {} {}ms",
+            req.method_str().unwrap_or_else(|| "-".to_string()),
+            target,
+            status,
+            duration.as_millis()
+        )
+    }
+
+    /// Build a JSON log entry
+    fn json_entry(&self, req: &Request, status: u16, duration: Duration) -> Value {
+        let query: Map<String, Value> = self
+            .query(req)
+            .into_iter()
+            .map(|(name, value)| (name, Value::String(value)))
+            .collect();
+        let headers: Map<String, Value> = self
+            .headers(req)
+            .into_iter()
+            .map(|(name, value)| (name, Value::String(value)))
+            .collect();
+
+        json!({
+            "method": req.method_str(),
+            "path": req.path(),
+            "query": query,
+            "headers": headers,
+            "status": status,
+            "durationMs": duration.as_secs_f64() * 1000.0,
+        })
+    }
+}
+
+impl Default for AccessLogLayer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Middleware for AccessLogLayer {
+    async fn handle(&self, req: Request, ctx: &Context, next: &dyn Handler) -> Result<Response> {
+        let logged = request_line(&req);
+        if debug_bodies_enabled() {
+            let entry = self.debug_entry(&req);
+            match self.format {
+                AccessLogFormat::Text => ctx.log("DEBUG", &format!("request {}", entry)),
+                AccessLogFormat::Json => ctx.log_json("DEBUG", entry),
+            }
+        }
+
+        let start = crate::util::stopwatch();
+        let result = next.handle(req, ctx).await;
+        // Without a clock, as on wasm32-unknown-unknown, durations are logged as zero
+        let duration = start.map(|start| start.elapsed()).unwrap_or_default();
+
+        // The platform adapters answer errors with the error's status
+        let status = match &result {
+            Ok(resp) => resp.status(),
+            Err(err) => err.status(),
+        };
+        match self.format {
+            AccessLogFormat::Text => {
+                ctx.log("INFO", &self.text_entry(&logged, status, duration));
+            }
+            AccessLogFormat::Json => {
+                ctx.log_json("INFO", self.json_entry(&logged, status, duration));
+            }
+        }
+
+        result
+    }
+}
+
+/// Whether the platform adapters log a record for each invocation
+static INVOCATION_LOG: AtomicBool = AtomicBool::new(false);
+
+/// Enables or disables the invocation log written by the platform adapters
+///
+/// When enabled, the AWS and Cloudflare adapters log one JSON record at `INFO` level
+/// through [`Context::log_json`] as each invocation completes, with the `requestId`,
+/// `method`, `path`, `status`, `durationMs`, `responseBytes` and, for failed
+/// invocations, the `error`. Errors are logged with their
+/// [`Error::status`](crate::Error::status). Disabled by default.
+///
+/// # Examples
+///
+/// ```
+/// serverless_rs::logging::set_invocation_log(true);
+/// ```
+pub fn set_invocation_log(enabled: bool) {
+    INVOCATION_LOG.store(enabled, Ordering::Relaxed);
+}
+
+/// Run a handler and log its invocation record, if enabled with [`set_invocation_log`]
+#[cfg_attr(not(any(feature = "aws", feature = "cloudflare")), allow(dead_code))]
+pub(crate) fn logged<F>(req: Request, ctx: &Context, handler: F) -> Result<Response>
+where
+    F: FnOnce(Request, &Context) -> Result<Response>,
+{
+    if !INVOCATION_LOG.load(Ordering::Relaxed) {
+        return handler(req, ctx);
+    }
+
+    let method = req.method_str();
+    let path = req.path();
+    let start = crate::util::stopwatch();
+    let result = handler(req, ctx);
+    let duration = start.map(|start| start.elapsed()).unwrap_or_default();
+
+    ctx.log_json("INFO", invocation_record(method, path, &result, duration));
+    result
+}
+
+/// Build the invocation log record for a completed invocation
+fn invocation_record(
+    method: Option<String>,
+    path: Option<String>,
+    result: &Result<Response>,
+    duration: Duration,
+) -> Value {
+    let mut record = json!({
+        "method": method,
+        "path": path,
+        "durationMs": duration.as_secs_f64() * 1000.0,
+    });
+    match result {
+        Ok(resp) => {
+            record["status"] = json!(resp.status());
+            record["responseBytes"] = json!(resp.body().len());
+        }
+        Err(err) => {
+            record["status"] = json!(err.status());
+            record["responseBytes"] = json!(0);
+            record["error"] = json!(err.to_string());
+        }
+    }
+    record
+}
+
+/// Returns whether [`DEBUG_BODIES_ENV`] is set to `1`
+fn debug_bodies_enabled() -> bool {
+    std::env::var(DEBUG_BODIES_ENV).is_ok_and(|value| value.trim() == "1")
+}
+
+/// Copy the method, URI, query and headers, leaving the body behind
+fn request_line(req: &Request) -> Request {
+    let mut logged = Request::new();
+    if let Some(method) = req.method() {
+        logged = logged.with_method(method.clone());
+    }
+    if let Some(uri) = req.uri() {
+        logged = logged.with_uri(uri.clone());
+    }
+    for (name, value) in req.query() {
+        logged = logged.with_query(name.clone(), value.clone());
+    }
+    for (name, value) in req.headers() {
+        logged = logged.with_header(name.clone(), value.clone());
+    }
+    logged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request() -> Request {
+        Request::new()
+            .with_method_str("GET")
+            .with_path("/account")
+            .with_query("token", "s3cr3t")
+            .with_query("page", "2")
+            .with_header("authorization", "Bearer abc")
+            .with_header("Accept", "application/json")
+    }
+
+    #[test]
+    fn test_text_entry() {
+        let layer = AccessLogLayer::new().redact_query_param("token");
+        let entry = layer.text_entry(&request(), 200, Duration::from_millis(12));
+        assert_eq!(entry, "GET /account?page=2&token=[REDACTED] 200 12ms");
+    }
+
+    #[test]
+    fn test_json_entry() {
+        let layer = AccessLogLayer::new()
+            .with_format(AccessLogFormat::Json)
+            .redact_query_param("token")
+            .redact_header("Accept");
+        let entry = layer.json_entry(&request(), 404, Duration::from_millis(3));
+
+        assert_eq!(entry["method"], "GET");
+        assert_eq!(entry["path"], "/account");
+        assert_eq!(entry["status"], 404);
+        assert_eq!(entry["durationMs"], 3.0);
+        assert_eq!(entry["query"]["page"], "2");
+        assert_eq!(entry["query"]["token"], REDACTED);
+        assert_eq!(entry["headers"]["authorization"], REDACTED);
+        assert_eq!(entry["headers"]["Accept"], REDACTED);
+    }
+
+    #[test]
+    fn test_debug_entry() {
+        let layer = AccessLogLayer::new().redact_body_key("cardNumber");
+        let req = request()
+            .with_method_str("POST")
+            .with_body(r#"{"user":"ana","password":"hunter2","card":{"CardNumber":"4111"}}"#);
+        let entry = layer.debug_entry(&req);
+
+        assert_eq!(entry["method"], "POST");
+        assert_eq!(entry["headers"]["authorization"], REDACTED);
+        assert_eq!(entry["headers"]["Accept"], "application/json");
+        let body: Value = serde_json::from_str(entry["body"].as_str().unwrap()).unwrap();
+        assert_eq!(body["user"], "ana");
+        assert_eq!(body["password"], REDACTED);
+        assert_eq!(body["card"]["CardNumber"], REDACTED);
+        assert_eq!(entry["truncated"], false);
+
+        let form = Request::new()
+            .with_header("Content-Type", "application/x-www-form-urlencoded")
+            .with_body("user=ana&token=abc");
+        let entry = layer.debug_entry(&form);
+        assert_eq!(entry["body"], "user=ana&token=%5BREDACTED%5D");
+
+        // Bodies are cut to the limit on a character boundary
+        let layer = AccessLogLayer::new().with_debug_body_limit(4);
+        let entry = layer.debug_entry(&Request::new().with_body("abcé and more"));
+        assert_eq!(entry["body"], "abc");
+        assert_eq!(entry["bodyBytes"], 14);
+        assert_eq!(entry["truncated"], true);
+    }
+
+    #[test]
+    fn test_invocation_record() {
+        let ok = Ok(Response::text("hello").with_status(201));
+        let record = invocation_record(
+            Some("POST".to_string()),
+            Some("/items".to_string()),
+            &ok,
+            Duration::from_millis(7),
+        );
+        assert_eq!(
+            record,
+            json!({
+                "method": "POST",
+                "path": "/items",
+                "status": 201,
+                "durationMs": 7.0,
+                "responseBytes": 5,
+            })
+        );
+
+        let failed = Err(crate::Error::with_status(503, "database unavailable"));
+        let record = invocation_record(None, None, &failed, Duration::ZERO);
+        assert_eq!(record["status"], 503);
+        assert_eq!(record["responseBytes"], 0);
+        assert!(record["error"]
+            .as_str()
+            .unwrap()
+            .contains("database unavailable"));
+    }
+
+    #[tokio::test]
+    async fn test_passes_result_through() {
+        fn created(_req: Request, _ctx: &Context) -> Result<Response> {
+            Ok(Response::text("created").with_status(201))
+        }
+
+        let layer = AccessLogLayer::new();
+        let response = layer
+            .handle(request(), &Context::new(), &created)
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 201);
+        assert_eq!(response.body(), b"created");
+    }
+}
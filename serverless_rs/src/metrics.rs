@@ -0,0 +1,237 @@
+/*!
+Metrics emission for serverless.rs.
+
+Metrics are written to standard output so they are collected without extra API
+calls: as CloudWatch Embedded Metric Format (EMF) lines on AWS Lambda, and as
+StatsD lines elsewhere. With the `metrics` feature enabled, platform adapters also
+emit an invocation duration and error count for every request.
+*/
+
+use serde_json::{json, Map, Value};
+use std::time::UNIX_EPOCH;
+
+use crate::error::Result;
+use crate::{Context, Platform, Request, Response};
+
+/// CloudWatch namespace for emitted metrics
+const METRICS_NAMESPACE: &str = "serverless_rs";
+
+/// Unit of a metric value
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MetricUnit {
+    /// A plain count
+    Count,
+    /// A duration in milliseconds
+    Milliseconds,
+    /// A duration in seconds
+    Seconds,
+    /// A size in bytes
+    Bytes,
+    /// A percentage
+    Percent,
+    /// A value without a unit
+    None,
+}
+
+impl MetricUnit {
+    /// Returns the CloudWatch name of the unit
+    pub fn name(&self) -> &'static str {
+        match self {
+            MetricUnit::Count => "Count",
+            MetricUnit::Milliseconds => "Milliseconds",
+            MetricUnit::Seconds => "Seconds",
+            MetricUnit::Bytes => "Bytes",
+            MetricUnit::Percent => "Percent",
+            MetricUnit::None => "None",
+        }
+    }
+
+    /// Returns the StatsD metric type for the unit
+    fn statsd_type(&self) -> &'static str {
+        match self {
+            MetricUnit::Count => "c",
+            MetricUnit::Milliseconds => "ms",
+            _ => "g",
+        }
+    }
+}
+
+/// Format a metric as a CloudWatch Embedded Metric Format JSON line
+pub(crate) fn format_emf(
+    namespace: &str,
+    name: &str,
+    value: f64,
+    unit: MetricUnit,
+    dimensions: &[(&str, &str)],
+    timestamp: u128,
+) -> String {
+    let dimension_names: Vec<&str> = dimensions.iter().map(|(key, _)| *key).collect();
+
+    let mut root = Map::new();
+    root.insert(
+        "_aws".to_string(),
+        json!({
+            "Timestamp": timestamp as u64,
+            "CloudWatchMetrics": [{
+                "Namespace": namespace,
+                "Dimensions": [dimension_names],
+                "Metrics": [{ "Name": name, "Unit": unit.name() }],
+            }],
+        }),
+    );
+    for (key, value) in dimensions {
+        root.insert(key.to_string(), Value::String(value.to_string()));
+    }
+    root.insert(name.to_string(), json!(value));
+
+    Value::Object(root).to_string()
+}
+
+/// Format a metric as a StatsD line, with dimensions as DogStatsD tags
+pub(crate) fn format_statsd(
+    name: &str,
+    value: f64,
+    unit: MetricUnit,
+    dimensions: &[(&str, &str)],
+) -> String {
+    let mut line = format!("{}:{}|{}", name, value, unit.statsd_type());
+    if !dimensions.is_empty() {
+        let tags: Vec<String> = dimensions
+            .iter()
+            .map(|(key, value)| format!("{}:{}", key, value))
+            .collect();
+        line.push_str("|#");
+        line.push_str(&tags.join(","));
+    }
+    line
+}
+
+/// Format a metric for the given platform
+pub(crate) fn format_metric(
+    platform: Platform,
+    name: &str,
+    value: f64,
+    unit: MetricUnit,
+    dimensions: &[(&str, &str)],
+) -> String {
+    match platform {
+        Platform::Aws => {
+            let timestamp = crate::util::now()
+                .and_then(|now| now.duration_since(UNIX_EPOCH).ok())
+                .map(|d| d.as_millis())
+                .unwrap_or_default();
+            format_emf(METRICS_NAMESPACE, name, value, unit, dimensions, timestamp)
+        }
+        _ => format_statsd(name, value, unit, dimensions),
+    }
+}
+
+/// Run a handler and emit its invocation duration and error count
+///
+/// Without the `metrics` feature this simply calls the handler.
+#[cfg(not(feature = "metrics"))]
+#[cfg_attr(not(any(feature = "aws", feature = "cloudflare")), allow(dead_code))]
+pub(crate) fn measured<F>(req: Request, ctx: &Context, handler: F) -> Result<Response>
+where
+    F: FnOnce(Request, &Context) -> Result<Response>,
+{
+    handler(req, ctx)
+}
+
+/// Run a handler and emit its invocation duration and error count
+///
+/// `Duration` is emitted in milliseconds, and `Errors` is 1 if the handler returned a
+/// 5xx response or an error with a 5xx [`Error::status`](crate::Error::status) and 0
+/// otherwise. Both carry a `FunctionName` dimension
+/// when the function name is known.
+#[cfg(feature = "metrics")]
+#[cfg_attr(not(any(feature = "aws", feature = "cloudflare")), allow(dead_code))]
+pub(crate) fn measured<F>(req: Request, ctx: &Context, handler: F) -> Result<Response>
+where
+    F: FnOnce(Request, &Context) -> Result<Response>,
+{
+    let start = crate::util::stopwatch();
+    let result = handler(req, ctx);
+
+    let failed = match &result {
+        Ok(resp) => resp.status() >= 500,
+        Err(err) => err.status() >= 500,
+    };
+    let dimensions: &[(&str, &str)] = if ctx.function_name().is_empty() {
+        &[]
+    } else {
+        &[("FunctionName", ctx.function_name())]
+    };
+    // Targets without a clock, such as wasm32-unknown-unknown, can't time the handler
+    if let Some(start) = start {
+        let elapsed = start.elapsed().as_secs_f64() * 1000.0;
+        ctx.emit_metric("Duration", elapsed, MetricUnit::Milliseconds, dimensions);
+    }
+    ctx.emit_metric(
+        "Errors",
+        if failed { 1.0 } else { 0.0 },
+        MetricUnit::Count,
+        dimensions,
+    );
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_emf() {
+        let line = format_emf(
+            "orders",
+            "Latency",
+            12.5,
+            MetricUnit::Milliseconds,
+            &[("FunctionName", "checkout")],
+            1_700_000_000_000,
+        );
+        let value: Value = serde_json::from_str(&line).unwrap();
+
+        let metrics = &value["_aws"]["CloudWatchMetrics"][0];
+        assert_eq!(value["_aws"]["Timestamp"], 1_700_000_000_000u64);
+        assert_eq!(metrics["Namespace"], "orders");
+        assert_eq!(metrics["Dimensions"], json!([["FunctionName"]]));
+        assert_eq!(
+            metrics["Metrics"],
+            json!([{ "Name": "Latency", "Unit": "Milliseconds" }])
+        );
+        assert_eq!(value["FunctionName"], "checkout");
+        assert_eq!(value["Latency"], 12.5);
+    }
+
+    #[test]
+    fn test_format_statsd() {
+        assert_eq!(
+            format_statsd("requests", 1.0, MetricUnit::Count, &[]),
+            "requests:1|c"
+        );
+        assert_eq!(
+            format_statsd(
+                "latency",
+                12.5,
+                MetricUnit::Milliseconds,
+                &[("function", "checkout"), ("region", "eu")]
+            ),
+            "latency:12.5|ms|#function:checkout,region:eu"
+        );
+        assert_eq!(
+            format_statsd("queue_depth", 3.0, MetricUnit::None, &[]),
+            "queue_depth:3|g"
+        );
+    }
+
+    #[test]
+    fn test_format_metric() {
+        let line = format_metric(Platform::Aws, "Errors", 0.0, MetricUnit::Count, &[]);
+        assert!(line.contains("\"CloudWatchMetrics\""));
+
+        let line = format_metric(Platform::Cloudflare, "Errors", 0.0, MetricUnit::Count, &[]);
+        assert_eq!(line, "Errors:0|c");
+    }
+}
@@ -0,0 +1,288 @@
+/*!
+Middleware trait definition for serverless.rs.
+
+This module defines the Middleware trait used to wrap handlers with
+cross-cutting behavior such as authentication, logging, or header injection.
+*/
+
+use async_trait::async_trait;
+use std::sync::Arc;
+
+use crate::{error::Result, Context, Extensions, Handler, Request, Response};
+
+/// Middleware trait for wrapping handlers
+///
+/// A middleware receives the request and context before the wrapped handler and
+/// decides whether to call `next`. It can short-circuit with its own response,
+/// modify the request or context before calling `next`, or post-process the
+/// response returned by `next`.
+///
+/// # Execution order
+///
+/// Middleware stacks run like an onion. Layers added to a scope run in the order
+/// they were added on the way in, and see the response in the reverse order on the
+/// way out: with layers `a`, `b` and `c`, the code before `next` runs in `a`, `b`,
+/// `c`, then the handler runs, then the code after `next` runs in `c`, `b`, `a`.
+/// A nested scope's layers are inside those of the enclosing scope, and layers
+/// passed to `route_with` are innermost. A response transformation such
+/// as compression should therefore be added after the layers, like access logging,
+/// that need to observe the transformed response.
+///
+/// # Examples
+///
+/// ```
+/// use serverless_rs::{Context, Handler, Middleware, Request, Response};
+/// use async_trait::async_trait;
+///
+/// struct RequireAuth;
+///
+/// #[async_trait]
+/// impl Middleware for RequireAuth {
+///     async fn handle(
+///         &self,
+///         req: Request,
+///         ctx: &Context,
+///         next: &dyn Handler,
+///     ) -> serverless_rs::Result<Response> {
+///         if req.header("Authorization").is_none() {
+///             return Ok(Response::new().with_status(401).with_body("Unauthorized"));
+///         }
+///         next.handle(req, ctx).await
+///     }
+/// }
+/// ```
+#[async_trait]
+pub trait Middleware: Send + Sync + 'static {
+    /// Handle a request, calling `next` to continue to the wrapped handler
+    async fn handle(&self, req: Request, ctx: &Context, next: Next<'_>) -> Result<Response>;
+}
+
+/// The rest of the middleware chain, ending with the handler
+///
+/// Calling `next.handle(req, ctx)` runs the remaining middleware and the handler and
+/// returns their response. A middleware that doesn't call it short-circuits the chain.
+pub type Next<'a> = &'a dyn Handler;
+
+/// Middleware that transforms every successful response
+///
+/// The layer counterpart of [`HandlerExt::map_response`](crate::HandlerExt::map_response),
+/// for transformations shared by every route in a scope. Errors are passed
+/// through unchanged.
+///
+/// # Examples
+///
+/// ```
+/// use serverless_rs::{Context, MapResponseLayer, Request, Response, Result, RouterBuilder};
+///
+/// fn hello(_req: Request, _ctx: &Context) -> Result<Response> {
+///     Ok(Response::text("hello"))
+/// }
+///
+/// let router = RouterBuilder::new()
+///     .scope("/api", |api| {
+///         api.layer(MapResponseLayer::new(|resp: Response| {
+///             resp.with_header("X-Content-Type-Options", "nosniff")
+///         }))
+///         .get("/hello", hello)
+///     })
+///     .build();
+/// ```
+pub struct MapResponseLayer<F> {
+    f: F,
+}
+
+impl<F> MapResponseLayer<F>
+where
+    F: Fn(Response) -> Response + Send + Sync + 'static,
+{
+    /// Creates a layer applying `f` to every successful response
+    pub fn new(f: F) -> Self {
+        Self { f }
+    }
+}
+
+#[async_trait]
+impl<F> Middleware for MapResponseLayer<F>
+where
+    F: Fn(Response) -> Response + Send + Sync + 'static,
+{
+    async fn handle(&self, req: Request, ctx: &Context, next: Next<'_>) -> Result<Response> {
+        next.handle(req, ctx).await.map(&self.f)
+    }
+}
+
+/// A handler wrapped by a single middleware
+pub(crate) struct Layered {
+    middleware: Arc<dyn Middleware>,
+    inner: Arc<dyn Handler>,
+}
+
+impl Layered {
+    /// Wrap a handler with a stack of middlewares
+    ///
+    /// The first middleware in the slice is the outermost and runs first.
+    pub(crate) fn wrap(
+        handler: Arc<dyn Handler>,
+        middlewares: &[Arc<dyn Middleware>],
+    ) -> Arc<dyn Handler> {
+        middlewares.iter().rev().fold(handler, |inner, middleware| {
+            Arc::new(Layered {
+                middleware: Arc::clone(middleware),
+                inner,
+            })
+        })
+    }
+}
+
+#[async_trait]
+impl Handler for Layered {
+    async fn handle(&self, req: Request, ctx: &Context) -> Result<Response> {
+        self.middleware.handle(req, ctx, self.inner.as_ref()).await
+    }
+}
+
+/// A handler that attaches shared state to the context before calling the inner handler
+pub(crate) struct WithState {
+    state: Extensions,
+    inner: Arc<dyn Handler>,
+}
+
+impl WithState {
+    /// Wrap a handler so it sees the given state, skipping the wrapper if there is none
+    pub(crate) fn wrap(handler: Arc<dyn Handler>, state: &Extensions) -> Arc<dyn Handler> {
+        if state.is_empty() {
+            handler
+        } else {
+            Arc::new(WithState {
+                state: state.clone(),
+                inner: handler,
+            })
+        }
+    }
+}
+
+#[async_trait]
+impl Handler for WithState {
+    async fn handle(&self, req: Request, ctx: &Context) -> Result<Response> {
+        let mut ctx = ctx.clone();
+        ctx.extensions_mut().extend(&self.state);
+        self.inner.handle(req, &ctx).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Greeting;
+
+    #[async_trait]
+    impl Handler for Greeting {
+        async fn handle(&self, _req: Request, ctx: &Context) -> Result<Response> {
+            let name = ctx.extension::<&str>().copied().unwrap_or("world");
+            Ok(Response::text(format!("Hello, {}!", name)))
+        }
+    }
+
+    struct Tag(&'static str);
+
+    #[async_trait]
+    impl Middleware for Tag {
+        async fn handle(
+            &self,
+            req: Request,
+            ctx: &Context,
+            next: &dyn Handler,
+        ) -> Result<Response> {
+            let response = next.handle(req, ctx).await?;
+            let tags = match response.header("X-Tags") {
+                Some(existing) => format!("{},{}", existing, self.0),
+                None => self.0.to_string(),
+            };
+            Ok(response.with_header("X-Tags", tags))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_layered_order() {
+        let handler = Layered::wrap(
+            Arc::new(Greeting),
+            &[Arc::new(Tag("outer")), Arc::new(Tag("inner"))],
+        );
+
+        let response = handler
+            .handle(Request::new(), &Context::new())
+            .await
+            .unwrap();
+        // The inner middleware post-processes the response first
+        assert_eq!(response.header("X-Tags"), Some(&"inner,outer".to_string()));
+    }
+
+    /// Records when it runs relative to the rest of the chain
+    struct Record(&'static str, Arc<std::sync::Mutex<Vec<String>>>);
+
+    #[async_trait]
+    impl Middleware for Record {
+        async fn handle(&self, req: Request, ctx: &Context, next: Next<'_>) -> Result<Response> {
+            self.1.lock().unwrap().push(format!("before {}", self.0));
+            let response = next.handle(req, ctx).await;
+            self.1.lock().unwrap().push(format!("after {}", self.0));
+            response
+        }
+    }
+
+    #[tokio::test]
+    async fn test_onion_order() {
+        let calls = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let layers: Vec<Arc<dyn Middleware>> = ["a", "b", "c"]
+            .into_iter()
+            .map(|name| Arc::new(Record(name, calls.clone())) as Arc<dyn Middleware>)
+            .collect();
+        let handler = Layered::wrap(Arc::new(Greeting), &layers);
+
+        handler
+            .handle(Request::new(), &Context::new())
+            .await
+            .unwrap();
+        assert_eq!(
+            *calls.lock().unwrap(),
+            ["before a", "before b", "before c", "after c", "after b", "after a"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_map_response_layer() {
+        let handler = Layered::wrap(
+            Arc::new(Greeting),
+            &[
+                Arc::new(Tag("outer")),
+                Arc::new(MapResponseLayer::new(|resp: Response| {
+                    resp.with_header("X-Tags", "mapped")
+                })),
+            ],
+        );
+
+        let response = handler
+            .handle(Request::new(), &Context::new())
+            .await
+            .unwrap();
+        // The outer layer sees the transformed response
+        assert_eq!(response.header("X-Tags"), Some(&"mapped,outer".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_with_state() {
+        let mut state = Extensions::new();
+        state.insert("admin");
+        let handler = WithState::wrap(Arc::new(Greeting), &state);
+
+        let response = handler
+            .handle(Request::new(), &Context::new())
+            .await
+            .unwrap();
+        assert_eq!(
+            std::str::from_utf8(response.body()).unwrap(),
+            "Hello, admin!"
+        );
+    }
+}
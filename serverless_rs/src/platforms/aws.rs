@@ -2,7 +2,1265 @@
 AWS Lambda adapter for serverless.rs.
 
 This module provides the adapter for deploying serverless.rs functions to AWS Lambda.
+It maps API Gateway (REST v1 and HTTP v2) events and direct invocations to the
+platform-agnostic [`Request`], and [`Response`]s back to the Lambda response format.
+API Gateway WebSocket events are dispatched to a [`WebSocketHandler`].
+
+Functions triggered by DynamoDB Streams or Kinesis can read their records from the
+raw event with [`dynamodb_records`] and [`kinesis_records`]. SQS-triggered functions
+read messages with [`sqs_messages`] and report the ones that failed with
+[`batch_item_failures`], so only those are retried.
+
+Warm-up pings are answered by [`lambda_handler`] without running the handler. An
+event is a warm-up ping if it is a JSON object whose sentinel key (`"warmup"` by
+default, see [`set_warmup_sentinel`]) is `true`, or if its `source` is
+`"serverless-plugin-warmup"`.
+
+API Gateway only Base64 encodes bodies of its configured binary media types. Register
+the same list with [`set_binary_media_types`] so binary responses such as images are
+encoded to match.
 */
 
-// This file will be expanded in Step 4 per the execution plan
-// Currently just a placeholder for the project structure
+use async_trait::async_trait;
+use base64::Engine;
+use serde::de::DeserializeOwned;
+use serde_json::{json, Map, Value};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::error::{Error, Result};
+use crate::logging::logged;
+use crate::metrics::measured;
+use crate::trace::traced;
+use crate::util::parse_query_string;
+use crate::{Context, Request, Response};
+
+/// Returns whether an event is an API Gateway proxy event (REST v1 or HTTP v2)
+pub fn is_api_gateway_event(event: &Value) -> bool {
+    event.get("httpMethod").is_some()
+        || event
+            .pointer("/requestContext/http/method")
+            .and_then(Value::as_str)
+            .is_some()
+}
+
+/// Returns whether an event is an API Gateway WebSocket event
+pub fn is_websocket_event(event: &Value) -> bool {
+    event.pointer("/requestContext/connectionId").is_some()
+        && event.pointer("/requestContext/routeKey").is_some()
+}
+
+/// Default key marking an event as a warm-up ping
+pub const DEFAULT_WARMUP_SENTINEL: &str = "warmup";
+
+/// Hook called for warm-up pings
+type WarmupHook = Arc<dyn Fn(&Context) + Send + Sync>;
+
+/// Custom sentinel key for warm-up pings, if configured
+static WARMUP_SENTINEL: RwLock<Option<String>> = RwLock::new(None);
+
+/// Hook called for warm-up pings, if configured
+static WARMUP_HOOK: RwLock<Option<WarmupHook>> = RwLock::new(None);
+
+/// Set the key that marks an event as a warm-up ping (`"warmup"` by default)
+pub fn set_warmup_sentinel(key: impl Into<String>) {
+    if let Ok(mut sentinel) = WARMUP_SENTINEL.write() {
+        *sentinel = Some(key.into());
+    }
+}
+
+/// Register a hook that runs for warm-up pings, e.g. to prime connection pools
+pub fn on_warmup<F>(hook: F)
+where
+    F: Fn(&Context) + Send + Sync + 'static,
+{
+    if let Ok(mut current) = WARMUP_HOOK.write() {
+        *current = Some(Arc::new(hook));
+    }
+}
+
+/// Returns whether an event is a warm-up ping
+pub fn is_warmup_event(event: &Value) -> bool {
+    let sentinel = WARMUP_SENTINEL
+        .read()
+        .ok()
+        .and_then(|sentinel| sentinel.clone())
+        .unwrap_or_else(|| DEFAULT_WARMUP_SENTINEL.to_string());
+    is_warmup_event_with(event, &sentinel)
+}
+
+/// Returns whether an event is a warm-up ping marked with the given sentinel key
+fn is_warmup_event_with(event: &Value, sentinel: &str) -> bool {
+    event.get(sentinel).and_then(Value::as_bool) == Some(true)
+        || str_field(event, "source") == Some("serverless-plugin-warmup")
+}
+
+/// Answer a warm-up ping, running the warm-up hook if one is registered
+fn handle_warmup(context: &Value) -> Value {
+    let hook = WARMUP_HOOK.read().ok().and_then(|hook| hook.clone());
+    if let Some(hook) = hook {
+        hook(&context_from_lambda(context));
+    }
+    json!({ "statusCode": 200, "body": "warmed" })
+}
+
+/// Media types whose bodies API Gateway exchanges Base64 encoded
+static BINARY_MEDIA_TYPES: RwLock<Vec<String>> = RwLock::new(Vec::new());
+
+/// Set the binary media types configured on the API Gateway
+///
+/// Entries may use wildcards such as `"image/*"` or `"*/*"`. Responses whose
+/// `Content-Type` matches are Base64 encoded with `isBase64Encoded` set, and request
+/// bodies with a matching `Content-Type` are decoded when the event does not say
+/// whether they are encoded.
+pub fn set_binary_media_types(media_types: &[&str]) {
+    if let Ok(mut current) = BINARY_MEDIA_TYPES.write() {
+        *current = media_types.iter().map(|t| t.to_ascii_lowercase()).collect();
+    }
+}
+
+/// Returns whether a content type matches one of the binary media types
+pub fn is_binary_media_type(content_type: &str) -> bool {
+    BINARY_MEDIA_TYPES
+        .read()
+        .is_ok_and(|types| is_binary_media_type_with(content_type, &types))
+}
+
+/// Returns whether a content type matches one of the given lowercase media types
+fn is_binary_media_type_with(content_type: &str, types: &[String]) -> bool {
+    let media_type = content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+    let Some((kind, _)) = media_type.split_once('/') else {
+        return false;
+    };
+
+    types.iter().any(|pattern| match pattern.as_str() {
+        "*/*" => true,
+        pattern => match pattern.strip_suffix("/*") {
+            Some(pattern_kind) => pattern_kind == kind,
+            None => pattern == media_type,
+        },
+    })
+}
+
+/// Build a [`Request`] from a Lambda event
+///
+/// API Gateway events are mapped to HTTP requests with method, path, query, headers,
+/// path parameters, and body. The body is marked as Base64 encoded if `isBase64Encoded`
+/// is set, or if it is absent and the `Content-Type` is a binary media type;
+/// [`Request::decoded_body`] returns its bytes. Any other event is treated
+/// as a direct invocation and its JSON becomes the request body. In both cases the
+/// original event is available via [`Request::raw_event`].
+pub fn request_from_event(event: &Value) -> Result<Request> {
+    if is_websocket_event(event) {
+        let mut req = Request::new()
+            .with_headers(string_map(event.get("headers")))
+            .with_raw_event(event.clone());
+        for (name, value) in string_map(event.get("queryStringParameters")) {
+            req = req.with_query(name, value);
+        }
+        if let Some(body) = str_field(event, "body") {
+            req = req.with_body(body);
+        }
+        return Ok(req);
+    }
+
+    if !is_api_gateway_event(event) {
+        let body = serde_json::to_vec(event).map_err(Error::serialization)?;
+        return Ok(Request::new()
+            .with_header("Content-Type", "application/json")
+            .with_body(body)
+            .with_raw_event(event.clone()));
+    }
+
+    let (method, path, query) = if let Some(method) = event.get("httpMethod") {
+        // REST API (payload format 1.0)
+        let path = str_field(event, "path").unwrap_or("/");
+        let query = string_map(event.get("queryStringParameters"));
+        (method.as_str().unwrap_or_default(), path.to_string(), query)
+    } else {
+        // HTTP API (payload format 2.0)
+        let method = event
+            .pointer("/requestContext/http/method")
+            .and_then(Value::as_str)
+            .unwrap_or_default();
+        let path = str_field(event, "rawPath").unwrap_or("/");
+        let query = match str_field(event, "rawQueryString") {
+            Some(raw) if !raw.is_empty() => parse_query_string(raw).into_iter().collect(),
+            _ => string_map(event.get("queryStringParameters")),
+        };
+        (method, path.to_string(), query)
+    };
+
+    let mut req = Request::new()
+        .with_method_str(method)
+        .with_path(&path)
+        .with_headers(string_map(event.get("headers")))
+        .with_raw_event(event.clone());
+
+    if let Some(cookies) = event.get("cookies").and_then(Value::as_array) {
+        let cookies: Vec<&str> = cookies.iter().filter_map(Value::as_str).collect();
+        if !cookies.is_empty() {
+            req = req.with_header("Cookie", cookies.join("; "));
+        }
+    }
+    for (name, value) in query {
+        req = req.with_query(name, value);
+    }
+    for (name, value) in string_map(event.get("pathParameters")) {
+        req = req.with_path_param(name, value);
+    }
+
+    if let Some(body) = str_field(event, "body") {
+        let is_base64 = match event.get("isBase64Encoded").and_then(Value::as_bool) {
+            Some(is_base64) => is_base64,
+            None => req
+                .header("Content-Type")
+                .is_some_and(|content_type| is_binary_media_type(content_type)),
+        };
+        req = req.with_body(body).with_base64(is_base64);
+    }
+
+    Ok(req)
+}
+
+/// Build a [`Context`] from the Lambda context object
+///
+/// Maps `awsRequestId`, `functionName`, `functionVersion`, `memoryLimitInMB`, and
+/// `invokedFunctionArn`, taking the region and account ID from the ARN. The ARN is
+/// also stored in platform data under `aws.function.arn`. The
+/// deadline and remaining time are taken from `deadlineMs` or `deadline`, in
+/// milliseconds since the Unix epoch, or else from `getRemainingTimeInMillis` or
+/// `remainingTimeInMillis`.
+///
+/// This is what [`Context::from_aws`] does.
+pub fn context_from_lambda(context: &Value) -> Context {
+    let mut ctx = Context::new()
+        .with_request_id(str_field(context, "awsRequestId").unwrap_or_default())
+        .with_function_name(str_field(context, "functionName").unwrap_or_default())
+        .with_function_version(str_field(context, "functionVersion").unwrap_or_default());
+
+    if let Some(memory) = u64_field(context, "memoryLimitInMB") {
+        ctx = ctx.with_memory_limit(memory as u32);
+    }
+
+    let now = SystemTime::now();
+    let deadline = ["deadlineMs", "deadline"]
+        .into_iter()
+        .find_map(|name| u64_field(context, name))
+        .map(|millis| UNIX_EPOCH + Duration::from_millis(millis));
+    let remaining = ["getRemainingTimeInMillis", "remainingTimeInMillis"]
+        .into_iter()
+        .find_map(|name| u64_field(context, name))
+        .map(Duration::from_millis);
+    let timing = match (deadline, remaining) {
+        (Some(deadline), _) => Some((
+            deadline,
+            deadline.duration_since(now).unwrap_or(Duration::ZERO),
+        )),
+        (None, Some(remaining)) => Some((now + remaining, remaining)),
+        (None, None) => None,
+    };
+    if let Some((deadline, remaining)) = timing {
+        ctx = ctx.with_deadline(deadline).with_remaining_time(remaining);
+    }
+
+    if let Some(arn) = str_field(context, "invokedFunctionArn") {
+        // arn:partition:lambda:region:account-id:function:name[:qualifier]
+        let mut parts = arn.split(':').skip(3);
+        if let Some(region) = parts.next().filter(|region| !region.is_empty()) {
+            ctx = ctx.with_region(region);
+        }
+        if let Some(account_id) = parts.next().filter(|account_id| !account_id.is_empty()) {
+            ctx = ctx.with_account_id(account_id);
+        }
+        ctx = ctx
+            .with_invoked_arn(arn)
+            .with_platform_data(json!({ "aws": { "function": { "arn": arn } } }));
+    }
+
+    ctx
+}
+
+/// Build a [`Context`] for an API Gateway event, adding the API stage
+fn event_context(event: &Value, context: &Value) -> Context {
+    let ctx = context_from_lambda(context);
+    match event
+        .pointer("/requestContext/stage")
+        .and_then(Value::as_str)
+    {
+        Some(stage) => ctx.with_stage(stage),
+        None => ctx,
+    }
+}
+
+/// Convert a [`Response`] into an API Gateway proxy response
+///
+/// Bodies with a binary media type (see [`set_binary_media_types`]) are Base64
+/// encoded. Missing default headers are added with [`Response::with_default_headers`].
+///
+/// Values of repeated headers are joined with `, `. A repeated `Set-Cookie` is sent
+/// both as `multiValueHeaders`, for REST APIs, and as `cookies`, for HTTP APIs.
+pub fn response_to_api_gateway(resp: &Response) -> Value {
+    let mut resp = resp.clone();
+    let is_binary = resp
+        .header("Content-Type")
+        .is_some_and(|content_type| is_binary_media_type(content_type));
+    if is_binary && !resp.is_base64() {
+        let encoded = base64::engine::general_purpose::STANDARD.encode(resp.body());
+        resp = resp.with_body(encoded).with_base64(true);
+    }
+    let resp = resp.with_default_headers();
+    let (headers, cookies) = resp.joined_headers();
+    let mut response = json!({
+        "statusCode": resp.status(),
+        "headers": headers,
+        "body": String::from_utf8_lossy(resp.body()),
+        "isBase64Encoded": resp.is_base64(),
+    });
+    if !cookies.is_empty() {
+        response["multiValueHeaders"] = json!({ "Set-Cookie": cookies });
+        response["cookies"] = json!(cookies);
+    }
+    response
+}
+
+/// Handle an API Gateway event, returning the proxy response
+///
+/// Responses whose headers exceed the limits set with
+/// [`set_header_limits`](crate::set_header_limits) are rejected with an error.
+pub fn handle_api_gateway<F>(handler: F, event: Value, context: Value) -> Result<Value>
+where
+    F: Fn(Request, &Context) -> Result<Response>,
+{
+    let req = request_from_event(&event)?;
+    let ctx = event_context(&event, &context).with_trace_from_request(&req);
+    let resp = traced(req, &ctx, |req, ctx| {
+        logged(req, ctx, |req, ctx| measured(req, ctx, handler))
+    })?
+    .with_default_headers();
+    resp.check_header_limits()?;
+    Ok(response_to_api_gateway(&resp))
+}
+
+/// Handle a direct Lambda invocation, returning the response payload
+///
+/// The response body is returned as JSON if it parses as JSON, or as a string otherwise.
+pub fn handle_direct_invocation<F>(handler: F, event: Value, context: Value) -> Result<Value>
+where
+    F: Fn(Request, &Context) -> Result<Response>,
+{
+    let req = request_from_event(&event)?;
+    let ctx = context_from_lambda(&context).with_trace_from_request(&req);
+    let resp = traced(req, &ctx, |req, ctx| {
+        logged(req, ctx, |req, ctx| measured(req, ctx, handler))
+    })?;
+
+    Ok(serde_json::from_slice(resp.body())
+        .unwrap_or_else(|_| Value::String(String::from_utf8_lossy(resp.body()).into_owned())))
+}
+
+/// Handle any Lambda event, dispatching on the event type
+///
+/// Warm-up pings are answered without running the handler, and WebSocket events get
+/// a status-only response. Errors are converted into a proxy response with the
+/// error's [`Error::status`] and [`Error::to_json`] body for API Gateway and WebSocket
+/// events, and into an `{"error": ...}` payload for direct invocations.
+pub fn lambda_handler<F>(handler: F, event: Value, context: Value) -> Value
+where
+    F: Fn(Request, &Context) -> Result<Response>,
+{
+    if is_warmup_event(&event) {
+        handle_warmup(&context)
+    } else if is_websocket_event(&event) {
+        let ctx = websocket_context(&event, &context);
+        match request_from_event(&event).and_then(|req| handler(req, &ctx)) {
+            Ok(resp) => json!({ "statusCode": resp.status() }),
+            Err(e) => json!({ "statusCode": e.status() }),
+        }
+    } else if is_api_gateway_event(&event) {
+        handle_api_gateway(handler, event, context).unwrap_or_else(|e| {
+            json!({
+                "statusCode": e.status(),
+                "headers": { "Content-Type": "application/json" },
+                "body": e.to_json().to_string(),
+            })
+        })
+    } else {
+        handle_direct_invocation(handler, event, context)
+            .unwrap_or_else(|e| json!({ "error": e.to_string() }))
+    }
+}
+
+/// The lifecycle stage of an API Gateway WebSocket event
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WebSocketEvent {
+    /// A client connected (`$connect`)
+    Connect,
+    /// A client disconnected (`$disconnect`)
+    Disconnect,
+    /// A client sent a message (`$default` or a custom route key)
+    Message,
+}
+
+impl WebSocketEvent {
+    /// Determine the event type from an API Gateway route key
+    pub fn from_route_key(route_key: &str) -> Self {
+        match route_key {
+            "$connect" => Self::Connect,
+            "$disconnect" => Self::Disconnect,
+            _ => Self::Message,
+        }
+    }
+}
+
+/// Handler for API Gateway WebSocket APIs
+///
+/// Each lifecycle stage is dispatched to its own method. The connection ID and route
+/// key are available from the context via [`connection_id`] and [`route_key`].
+/// Only the response status is returned to API Gateway; sending messages to clients
+/// goes through the API Gateway management API.
+///
+/// # Examples
+///
+/// ```
+/// use serverless_rs::platforms::aws::{self, WebSocketHandler};
+/// use serverless_rs::{Context, Request, Response, Result};
+/// use async_trait::async_trait;
+///
+/// struct Chat;
+///
+/// #[async_trait]
+/// impl WebSocketHandler for Chat {
+///     async fn on_message(&self, req: Request, ctx: &Context) -> Result<Response> {
+///         let connection = aws::connection_id(ctx).unwrap_or_default();
+///         ctx.log("INFO", &format!("{} sent {} bytes", connection, req.body().len()));
+///         Ok(Response::new())
+///     }
+/// }
+/// ```
+#[async_trait]
+pub trait WebSocketHandler: Send + Sync + 'static {
+    /// Handle a `$connect` event; return a non-2xx status to reject the connection
+    async fn on_connect(&self, _req: Request, _ctx: &Context) -> Result<Response> {
+        Ok(Response::new())
+    }
+
+    /// Handle a `$disconnect` event
+    async fn on_disconnect(&self, _req: Request, _ctx: &Context) -> Result<Response> {
+        Ok(Response::new())
+    }
+
+    /// Handle a message routed to `$default` or a custom route key
+    async fn on_message(&self, req: Request, ctx: &Context) -> Result<Response>;
+}
+
+/// Build a [`Context`] for a WebSocket event
+///
+/// In addition to the fields mapped by [`context_from_lambda`] and the API stage,
+/// the connection ID, route key, and event type are stored in platform data under
+/// `aws.websocket`.
+pub fn websocket_context(event: &Value, context: &Value) -> Context {
+    let ctx = event_context(event, context);
+    let mut platform_data = ctx.platform_data().clone();
+    platform_data["aws"]["websocket"] = json!({
+        "connectionId": event.pointer("/requestContext/connectionId"),
+        "routeKey": event.pointer("/requestContext/routeKey"),
+        "eventType": event.pointer("/requestContext/eventType"),
+    });
+    ctx.with_platform_data(platform_data)
+}
+
+/// Returns the WebSocket connection ID of the current event
+pub fn connection_id(ctx: &Context) -> Option<String> {
+    ctx.get_platform_data("aws.websocket.connectionId")
+}
+
+/// Returns the WebSocket route key of the current event
+pub fn route_key(ctx: &Context) -> Option<String> {
+    ctx.get_platform_data("aws.websocket.routeKey")
+}
+
+/// Handle an API Gateway WebSocket event, returning the status-only response
+pub async fn handle_websocket<H>(handler: &H, event: Value, context: Value) -> Result<Value>
+where
+    H: WebSocketHandler,
+{
+    let route_key = event
+        .pointer("/requestContext/routeKey")
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+    let kind = WebSocketEvent::from_route_key(route_key);
+    let req = request_from_event(&event)?;
+    let ctx = websocket_context(&event, &context);
+
+    let resp = match kind {
+        WebSocketEvent::Connect => handler.on_connect(req, &ctx).await?,
+        WebSocketEvent::Disconnect => handler.on_disconnect(req, &ctx).await?,
+        WebSocketEvent::Message => handler.on_message(req, &ctx).await?,
+    };
+    Ok(json!({ "statusCode": resp.status() }))
+}
+
+/// A record from a DynamoDB Streams event
+///
+/// Keys and images are decoded from DynamoDB attribute values (`{"S": "a"}`) to
+/// plain JSON (`"a"`). Numbers become JSON numbers, sets become arrays, and binary
+/// values stay Base64 strings.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DynamoDbRecord {
+    /// Unique ID of the record
+    pub event_id: String,
+    /// `INSERT`, `MODIFY` or `REMOVE`
+    pub event_name: String,
+    /// Position of the change in the stream
+    pub sequence_number: Option<String>,
+    /// Primary key of the changed item
+    pub keys: Value,
+    /// The item after the change, if the stream view includes it
+    pub new_image: Option<Value>,
+    /// The item before the change, if the stream view includes it
+    pub old_image: Option<Value>,
+}
+
+/// A record from a Kinesis Data Streams event
+#[derive(Debug, Clone, PartialEq)]
+pub struct KinesisRecord {
+    /// Unique ID of the record
+    pub event_id: String,
+    /// Partition key the producer wrote the record with
+    pub partition_key: String,
+    /// Position of the record in the shard
+    pub sequence_number: String,
+    /// When the record was added to the stream, in seconds since the epoch
+    pub approximate_arrival_timestamp: Option<f64>,
+    /// Decoded record payload
+    pub data: Vec<u8>,
+}
+
+impl KinesisRecord {
+    /// Parse the payload as JSON
+    pub fn data_json<T: DeserializeOwned>(&self) -> Result<T> {
+        serde_json::from_slice(&self.data).map_err(Error::serialization)
+    }
+}
+
+/// Returns the records of an event that came from the given event source
+fn event_records<'a>(event: &'a Value, source: &'a str) -> impl Iterator<Item = &'a Value> {
+    event
+        .get("Records")
+        .and_then(Value::as_array)
+        .map(|records| records.iter())
+        .into_iter()
+        .flatten()
+        .filter(move |record| str_field(record, "eventSource") == Some(source))
+}
+
+/// Iterate the DynamoDB Streams records of an event
+///
+/// Records from other event sources are skipped.
+pub fn dynamodb_records(event: &Value) -> impl Iterator<Item = DynamoDbRecord> + '_ {
+    event_records(event, "aws:dynamodb").map(|record| {
+        let change = record.get("dynamodb").unwrap_or(&Value::Null);
+        DynamoDbRecord {
+            event_id: str_field(record, "eventID").unwrap_or_default().to_string(),
+            event_name: str_field(record, "eventName")
+                .unwrap_or_default()
+                .to_string(),
+            sequence_number: str_field(change, "SequenceNumber").map(str::to_string),
+            keys: change.get("Keys").map(decode_item).unwrap_or(Value::Null),
+            new_image: change.get("NewImage").map(decode_item),
+            old_image: change.get("OldImage").map(decode_item),
+        }
+    })
+}
+
+/// Iterate the Kinesis records of an event, decoding each payload
+///
+/// Records from other event sources are skipped. A record whose payload isn't
+/// valid Base64 yields an error.
+pub fn kinesis_records(event: &Value) -> impl Iterator<Item = Result<KinesisRecord>> + '_ {
+    event_records(event, "aws:kinesis").map(|record| {
+        let kinesis = record.get("kinesis").unwrap_or(&Value::Null);
+        let data = base64::engine::general_purpose::STANDARD
+            .decode(str_field(kinesis, "data").unwrap_or_default())
+            .map_err(|e| Error::platform(format!("Invalid Kinesis record data: {}", e)))?;
+        Ok(KinesisRecord {
+            event_id: str_field(record, "eventID").unwrap_or_default().to_string(),
+            partition_key: str_field(kinesis, "partitionKey")
+                .unwrap_or_default()
+                .to_string(),
+            sequence_number: str_field(kinesis, "sequenceNumber")
+                .unwrap_or_default()
+                .to_string(),
+            approximate_arrival_timestamp: kinesis
+                .get("approximateArrivalTimestamp")
+                .and_then(Value::as_f64),
+            data,
+        })
+    })
+}
+
+/// ID of an SQS message, reported back to Lambda when the message fails
+pub type MessageId = String;
+
+/// A message from an SQS event
+#[derive(Debug, Clone, PartialEq)]
+pub struct SqsMessage {
+    /// Unique ID of the message
+    pub message_id: MessageId,
+    /// Handle used to delete or change the visibility of the message
+    pub receipt_handle: String,
+    /// Message body
+    pub body: String,
+    /// System attributes such as `ApproximateReceiveCount`
+    pub attributes: Value,
+    /// Attributes set by the sender
+    pub message_attributes: Value,
+    /// ARN of the queue the message came from
+    pub event_source_arn: Option<String>,
+}
+
+impl SqsMessage {
+    /// Parse the body as JSON
+    pub fn body_json<T: DeserializeOwned>(&self) -> Result<T> {
+        serde_json::from_str(&self.body).map_err(Error::serialization)
+    }
+}
+
+/// Iterate the SQS messages of an event
+///
+/// Records from other event sources are skipped.
+pub fn sqs_messages(event: &Value) -> impl Iterator<Item = SqsMessage> + '_ {
+    event_records(event, "aws:sqs").map(|record| SqsMessage {
+        message_id: str_field(record, "messageId")
+            .unwrap_or_default()
+            .to_string(),
+        receipt_handle: str_field(record, "receiptHandle")
+            .unwrap_or_default()
+            .to_string(),
+        body: str_field(record, "body").unwrap_or_default().to_string(),
+        attributes: record.get("attributes").cloned().unwrap_or(Value::Null),
+        message_attributes: record
+            .get("messageAttributes")
+            .cloned()
+            .unwrap_or(Value::Null),
+        event_source_arn: str_field(record, "eventSourceARN").map(str::to_string),
+    })
+}
+
+/// Build the partial batch response for an SQS event
+///
+/// Returns `{"batchItemFailures": [{"itemIdentifier": ...}]}` listing the messages
+/// whose result is an error, so Lambda only returns those to the queue. The event
+/// source mapping must have `ReportBatchItemFailures` enabled; without it, a failed
+/// invocation retries the whole batch.
+///
+/// A `#[serverless]` function returns the value as its JSON response body, which is
+/// passed through to Lambda as the invocation result.
+///
+/// # Examples
+///
+/// ```
+/// use serverless_rs::platforms::aws::{batch_item_failures, sqs_messages};
+/// use serverless_rs::{Context, Error, Request, Response, Result};
+///
+/// async fn process(req: Request, _ctx: &Context) -> Result<Response> {
+///     let results = sqs_messages(req.raw_event()).map(|message| {
+///         if message.body.is_empty() {
+///             return Err((message.message_id, Error::function("empty message")));
+///         }
+///         Ok(())
+///     });
+///     Response::json(&batch_item_failures(results))
+/// }
+/// ```
+pub fn batch_item_failures<I>(results: I) -> Value
+where
+    I: IntoIterator<Item = std::result::Result<(), (MessageId, Error)>>,
+{
+    let failures: Vec<Value> = results
+        .into_iter()
+        .filter_map(|result| result.err())
+        .map(|(message_id, _)| json!({ "itemIdentifier": message_id }))
+        .collect();
+    json!({ "batchItemFailures": failures })
+}
+
+/// Process each SQS message of an event, returning the partial batch response
+///
+/// Messages are processed in order with `process`; the ones it fails are listed
+/// as described in [`batch_item_failures`].
+pub fn process_sqs_batch<F>(event: &Value, mut process: F) -> Value
+where
+    F: FnMut(&SqsMessage) -> Result<()>,
+{
+    batch_item_failures(
+        sqs_messages(event)
+            .map(|message| process(&message).map_err(|err| (message.message_id, err))),
+    )
+}
+
+/// Decode a map of DynamoDB attribute values to a JSON object
+fn decode_item(item: &Value) -> Value {
+    match item.as_object() {
+        Some(item) => Value::Object(
+            item.iter()
+                .map(|(name, value)| (name.clone(), decode_attribute(value)))
+                .collect(),
+        ),
+        None => Value::Null,
+    }
+}
+
+/// Decode a DynamoDB attribute value such as `{"N": "42"}` to plain JSON
+fn decode_attribute(value: &Value) -> Value {
+    let Some((kind, inner)) = value.as_object().and_then(|typed| typed.iter().next()) else {
+        return Value::Null;
+    };
+    let number = |n: &Value| {
+        n.as_str()
+            .and_then(|n| serde_json::from_str::<serde_json::Number>(n).ok())
+            .map_or_else(|| n.clone(), Value::Number)
+    };
+    let list = |items: &Value, decode: &dyn Fn(&Value) -> Value| {
+        Value::Array(
+            items
+                .as_array()
+                .map(|items| items.iter().map(decode).collect())
+                .unwrap_or_default(),
+        )
+    };
+    match kind.as_str() {
+        "N" => number(inner),
+        "NS" => list(inner, &number),
+        "M" => decode_item(inner),
+        "L" => list(inner, &decode_attribute),
+        "NULL" => Value::Null,
+        // S, B, BOOL, SS and BS are already plain JSON
+        _ => inner.clone(),
+    }
+}
+
+/// Read a string field from a JSON object
+fn str_field<'a>(value: &'a Value, name: &str) -> Option<&'a str> {
+    value.get(name).and_then(Value::as_str)
+}
+
+/// Returns an unsigned integer field, which may also be given as a string
+fn u64_field(value: &Value, name: &str) -> Option<u64> {
+    match value.get(name)? {
+        Value::Number(n) => n.as_u64(),
+        Value::String(s) => s.parse().ok(),
+        _ => None,
+    }
+}
+
+/// Read a JSON object of scalar values as string pairs, skipping nulls
+fn string_map(value: Option<&Value>) -> Vec<(String, String)> {
+    value
+        .and_then(Value::as_object)
+        .map(Map::iter)
+        .into_iter()
+        .flatten()
+        .filter_map(|(key, value)| match value {
+            Value::String(s) => Some((key.clone(), s.clone())),
+            Value::Null => None,
+            other => Some((key.clone(), other.to_string())),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_api_gateway_v2_request() {
+        let event = json!({
+            "version": "2.0",
+            "rawPath": "/users/42",
+            "rawQueryString": "expand=profile&name=Jane+Doe",
+            "cookies": ["session=abc", "theme=dark"],
+            "headers": { "content-type": "application/json" },
+            "pathParameters": { "id": "42" },
+            "requestContext": { "http": { "method": "POST" } },
+            "body": "eyJuYW1lIjoidGVzdCJ9",
+            "isBase64Encoded": true
+        });
+
+        let req = request_from_event(&event).unwrap();
+        assert_eq!(req.method_str().as_deref(), Some("POST"));
+        assert_eq!(req.path().as_deref(), Some("/users/42"));
+        assert_eq!(req.query_param("name"), Some(&"Jane Doe".to_string()));
+        assert_eq!(req.path_param("id"), Some(&"42".to_string()));
+        assert_eq!(
+            req.header("Content-Type"),
+            Some(&"application/json".to_string())
+        );
+        assert_eq!(
+            req.header("Cookie"),
+            Some(&"session=abc; theme=dark".to_string())
+        );
+        assert_eq!(req.body_string().unwrap(), r#"{"name":"test"}"#);
+        assert_eq!(req.raw_event(), &event);
+    }
+
+    #[test]
+    fn test_api_gateway_v1_request() {
+        let event = json!({
+            "httpMethod": "GET",
+            "path": "/hello",
+            "queryStringParameters": { "name": "Test" },
+            "headers": { "Accept": "text/plain" },
+            "pathParameters": null,
+            "body": null
+        });
+
+        let req = request_from_event(&event).unwrap();
+        assert_eq!(req.method_str().as_deref(), Some("GET"));
+        assert_eq!(req.path().as_deref(), Some("/hello"));
+        assert_eq!(req.query_param("name"), Some(&"Test".to_string()));
+        assert!(req.body().is_empty());
+    }
+
+    #[test]
+    fn test_direct_invocation() {
+        let event = json!({ "command": "sync" });
+        let context = json!({
+            "awsRequestId": "req-1",
+            "functionName": "worker",
+            "functionVersion": "$LATEST",
+            "memoryLimitInMB": "256",
+            "invokedFunctionArn": "arn:aws:lambda:us-east-1:123456789012:function:worker"
+        });
+
+        let result = lambda_handler(
+            |req: Request, ctx: &Context| {
+                let event: Value = req.body_json()?;
+                Response::json(&json!({
+                    "command": event["command"],
+                    "request_id": ctx.request_id(),
+                    "memory": ctx.memory_limit(),
+                }))
+            },
+            event,
+            context,
+        );
+
+        assert_eq!(
+            result,
+            json!({ "command": "sync", "request_id": "req-1", "memory": 256 })
+        );
+    }
+
+    #[test]
+    fn test_context_from_aws() {
+        let ctx = Context::from_aws(&json!({
+            "awsRequestId": "req-1",
+            "functionName": "worker",
+            "functionVersion": "7",
+            "memoryLimitInMB": 1024,
+            "getRemainingTimeInMillis": 15000,
+            "invokedFunctionArn": "arn:aws:lambda:us-east-1:123456789012:function:worker:live"
+        }));
+        assert_eq!(ctx.request_id(), "req-1");
+        assert_eq!(ctx.function_name(), "worker");
+        assert_eq!(ctx.function_version(), "7");
+        assert_eq!(ctx.memory_limit(), Some(1024));
+        assert_eq!(ctx.remaining_time(), Some(Duration::from_secs(15)));
+        let until_deadline = ctx.deadline().unwrap().duration_since(SystemTime::now());
+        assert!(until_deadline.unwrap() <= Duration::from_secs(15));
+        assert_eq!(
+            ctx.get_platform_data::<String>("aws.function.arn")
+                .as_deref(),
+            Some("arn:aws:lambda:us-east-1:123456789012:function:worker:live")
+        );
+        assert_eq!(
+            ctx.invoked_arn(),
+            Some("arn:aws:lambda:us-east-1:123456789012:function:worker:live")
+        );
+        assert_eq!(ctx.region(), Some("us-east-1"));
+        assert_eq!(ctx.account_id(), Some("123456789012"));
+
+        // An absolute deadline wins over the remaining time
+        let deadline = SystemTime::now() + Duration::from_secs(60);
+        let millis = deadline.duration_since(UNIX_EPOCH).unwrap().as_millis() as u64;
+        let ctx = Context::from_aws(&json!({
+            "deadlineMs": millis,
+            "getRemainingTimeInMillis": 1
+        }));
+        let remaining = ctx.remaining_time().unwrap();
+        assert!(remaining > Duration::from_secs(55) && remaining <= Duration::from_secs(60));
+
+        // Deadlines in the past leave no time
+        let ctx = Context::from_aws(&json!({ "deadline": 1000 }));
+        assert_eq!(ctx.remaining_time(), Some(Duration::ZERO));
+
+        let ctx = Context::from_aws(&json!({}));
+        assert!(ctx.deadline().is_none() && ctx.memory_limit().is_none());
+        assert!(ctx.invoked_arn().is_none() && ctx.region().is_none());
+    }
+
+    struct Chat;
+
+    #[async_trait]
+    impl WebSocketHandler for Chat {
+        async fn on_connect(&self, req: Request, _ctx: &Context) -> Result<Response> {
+            match req.query_param("token") {
+                Some(_) => Ok(Response::new()),
+                None => Ok(Response::new().with_status(401)),
+            }
+        }
+
+        async fn on_message(&self, req: Request, ctx: &Context) -> Result<Response> {
+            assert_eq!(connection_id(ctx).as_deref(), Some("abc="));
+            assert_eq!(route_key(ctx).as_deref(), Some("$default"));
+            assert_eq!(ctx.stage(), Some("prod"));
+            assert_eq!(req.body_string().unwrap(), "hi");
+            Ok(Response::text("ignored"))
+        }
+    }
+
+    fn websocket_event(route_key: &str) -> Value {
+        json!({
+            "requestContext": {
+                "routeKey": route_key,
+                "eventType": "MESSAGE",
+                "connectionId": "abc=",
+                "stage": "prod"
+            },
+            "body": "hi"
+        })
+    }
+
+    #[tokio::test]
+    async fn test_websocket_dispatch() {
+        assert!(is_websocket_event(&websocket_event("$default")));
+        assert!(!is_api_gateway_event(&websocket_event("$default")));
+
+        let result = handle_websocket(&Chat, websocket_event("$default"), Value::Null)
+            .await
+            .unwrap();
+        assert_eq!(result, json!({ "statusCode": 200 }));
+
+        let result = handle_websocket(&Chat, websocket_event("$connect"), Value::Null)
+            .await
+            .unwrap();
+        assert_eq!(result["statusCode"], 401);
+
+        let result = handle_websocket(&Chat, websocket_event("$disconnect"), Value::Null)
+            .await
+            .unwrap();
+        assert_eq!(result["statusCode"], 200);
+
+        // Closure handlers receive WebSocket events through lambda_handler
+        let result = lambda_handler(
+            |_req: Request, ctx: &Context| {
+                Ok(Response::new().with_status(match connection_id(ctx) {
+                    Some(_) => 204,
+                    None => 400,
+                }))
+            },
+            websocket_event("$default"),
+            Value::Null,
+        );
+        assert_eq!(result, json!({ "statusCode": 204 }));
+    }
+
+    #[test]
+    fn test_warmup() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        static WARMED: AtomicUsize = AtomicUsize::new(0);
+        on_warmup(|_ctx| {
+            WARMED.fetch_add(1, Ordering::SeqCst);
+        });
+
+        let handler = |_req: Request, _ctx: &Context| -> Result<Response> {
+            panic!("the handler must not run for warm-up pings")
+        };
+
+        let result = lambda_handler(handler, json!({ "warmup": true }), Value::Null);
+        assert_eq!(result["statusCode"], 200);
+        let result = lambda_handler(
+            handler,
+            json!({ "source": "serverless-plugin-warmup" }),
+            Value::Null,
+        );
+        assert_eq!(result["statusCode"], 200);
+        assert_eq!(WARMED.load(Ordering::SeqCst), 2);
+
+        assert!(!is_warmup_event(&json!({ "warmup": false })));
+        assert!(!is_warmup_event(&json!({ "keepWarm": true })));
+
+        assert!(is_warmup_event_with(
+            &json!({ "keepWarm": true }),
+            "keepWarm"
+        ));
+        assert!(!is_warmup_event_with(
+            &json!({ "warmup": true }),
+            "keepWarm"
+        ));
+    }
+
+    #[test]
+    fn test_api_gateway_response() {
+        let event = json!({
+            "httpMethod": "GET",
+            "path": "/hello",
+        });
+
+        let result = lambda_handler(
+            |_req: Request, _ctx: &Context| Ok(Response::text("Hello!").with_status(201)),
+            event.clone(),
+            Value::Null,
+        );
+        assert_eq!(result["statusCode"], 201);
+        assert_eq!(result["body"], "Hello!");
+        assert_eq!(result["isBase64Encoded"], false);
+        assert_eq!(result["headers"]["Content-Length"], "6");
+
+        let result = lambda_handler(
+            |_req: Request, _ctx: &Context| Err(Error::function("boom")),
+            event.clone(),
+            Value::Null,
+        );
+        assert_eq!(result["statusCode"], 500);
+
+        let result = lambda_handler(
+            |_req: Request, _ctx: &Context| Err(Error::with_status(404, "user not found")),
+            event.clone(),
+            Value::Null,
+        );
+        assert_eq!(result["statusCode"], 404);
+        assert_eq!(result["headers"]["Content-Type"], "application/json");
+        let body: Value = serde_json::from_str(result["body"].as_str().unwrap()).unwrap();
+        assert_eq!(
+            body,
+            json!({ "type": "http", "message": "user not found", "status": 404 })
+        );
+
+        // Oversized headers fail locally instead of at the gateway
+        let result = lambda_handler(
+            |_req: Request, _ctx: &Context| {
+                Ok(Response::new().with_header("Set-Cookie", "x".repeat(20 * 1024)))
+            },
+            event,
+            Value::Null,
+        );
+        assert_eq!(result["statusCode"], 500);
+        assert!(result["body"]
+            .as_str()
+            .unwrap()
+            .contains("(largest: Set-Cookie)"));
+    }
+
+    #[test]
+    fn test_repeated_headers() {
+        let resp = Response::text("ok")
+            .append_header("Set-Cookie", "a=1")
+            .append_header("Set-Cookie", "b=2")
+            .append_header("Link", "</a>; rel=preload")
+            .append_header("Link", "</b>; rel=preload");
+        let result = response_to_api_gateway(&resp);
+        assert_eq!(result["cookies"], json!(["a=1", "b=2"]));
+        assert_eq!(
+            result["multiValueHeaders"],
+            json!({ "Set-Cookie": ["a=1", "b=2"] })
+        );
+        assert!(result["headers"].get("Set-Cookie").is_none());
+        assert_eq!(
+            result["headers"]["Link"],
+            "</a>; rel=preload, </b>; rel=preload"
+        );
+
+        // A single cookie stays a plain header
+        let result = response_to_api_gateway(&Response::new().append_header("Set-Cookie", "a=1"));
+        assert_eq!(result["headers"]["Set-Cookie"], "a=1");
+        assert!(result.get("cookies").is_none());
+    }
+
+    #[test]
+    fn test_binary_media_types() {
+        let types = ["image/*".to_string(), "application/x-protobuf".to_string()];
+        assert!(is_binary_media_type_with("image/png", &types));
+        assert!(is_binary_media_type_with(
+            "Application/X-Protobuf; charset=binary",
+            &types
+        ));
+        assert!(!is_binary_media_type_with("text/plain", &types));
+        assert!(!is_binary_media_type_with("imagepng", &types));
+        assert!(is_binary_media_type_with(
+            "text/plain",
+            &["*/*".to_string()]
+        ));
+
+        // The adapters read the configured types, which are restored afterwards
+        struct Restore(Vec<String>);
+        impl Drop for Restore {
+            fn drop(&mut self) {
+                if let Ok(mut types) = BINARY_MEDIA_TYPES.write() {
+                    *types = std::mem::take(&mut self.0);
+                }
+            }
+        }
+        let _restore = Restore(BINARY_MEDIA_TYPES.read().unwrap().clone());
+        set_binary_media_types(&["image/*"]);
+
+        let resp = Response::new()
+            .with_header("Content-Type", "image/png")
+            .with_body(vec![0x89, b'P', b'N', b'G']);
+        let result = response_to_api_gateway(&resp);
+        assert_eq!(result["isBase64Encoded"], true);
+        assert_eq!(result["body"], "iVBORw==");
+        assert_eq!(result["headers"]["Content-Length"], "4");
+
+        // Bodies that are already encoded are left alone
+        let resp = Response::new()
+            .with_header("Content-Type", "image/png")
+            .with_body("iVBORw==")
+            .with_base64(true);
+        assert_eq!(response_to_api_gateway(&resp)["body"], "iVBORw==");
+
+        let event = json!({
+            "httpMethod": "POST",
+            "path": "/upload",
+            "headers": { "content-type": "image/png" },
+            "body": "iVBORw=="
+        });
+        let req = request_from_event(&event).unwrap();
+        assert!(req.is_base64());
+        assert_eq!(req.body(), b"iVBORw==");
+        assert_eq!(
+            req.decoded_body().unwrap().as_ref(),
+            &[0x89, b'P', b'N', b'G']
+        );
+    }
+
+    #[test]
+    fn test_dynamodb_records() {
+        let event = json!({
+            "Records": [
+                {
+                    "eventID": "1",
+                    "eventName": "MODIFY",
+                    "eventSource": "aws:dynamodb",
+                    "dynamodb": {
+                        "Keys": { "Id": { "N": "101" } },
+                        "NewImage": {
+                            "Id": { "N": "101" },
+                            "Name": { "S": "Ada" },
+                            "Active": { "BOOL": true },
+                            "Tags": { "SS": ["a", "b"] },
+                            "Scores": { "L": [{ "N": "1.5" }, { "NULL": true }] },
+                            "Address": { "M": { "City": { "S": "London" } } }
+                        },
+                        "OldImage": { "Id": { "N": "101" }, "Name": { "S": "Ad" } },
+                        "SequenceNumber": "222"
+                    }
+                },
+                { "eventID": "2", "eventSource": "aws:kinesis", "kinesis": {} }
+            ]
+        });
+
+        let records: Vec<_> = dynamodb_records(&event).collect();
+        assert_eq!(records.len(), 1);
+        let record = &records[0];
+        assert_eq!(record.event_name, "MODIFY");
+        assert_eq!(record.sequence_number.as_deref(), Some("222"));
+        assert_eq!(record.keys, json!({ "Id": 101 }));
+        assert_eq!(
+            record.new_image,
+            Some(json!({
+                "Id": 101,
+                "Name": "Ada",
+                "Active": true,
+                "Tags": ["a", "b"],
+                "Scores": [1.5, null],
+                "Address": { "City": "London" }
+            }))
+        );
+        assert_eq!(record.old_image, Some(json!({ "Id": 101, "Name": "Ad" })));
+    }
+
+    #[test]
+    fn test_kinesis_records() {
+        let event = json!({
+            "Records": [
+                {
+                    "eventID": "shardId-000:1",
+                    "eventSource": "aws:kinesis",
+                    "kinesis": {
+                        "partitionKey": "user-1",
+                        "sequenceNumber": "4959",
+                        "approximateArrivalTimestamp": 1545084650.987,
+                        "data": "eyJjbGlja3MiOjN9"
+                    }
+                },
+                {
+                    "eventID": "shardId-000:2",
+                    "eventSource": "aws:kinesis",
+                    "kinesis": { "partitionKey": "user-2", "data": "not base64!" }
+                }
+            ]
+        });
+
+        let records: Vec<_> = kinesis_records(&event).collect();
+        let record = records[0].as_ref().unwrap();
+        assert_eq!(record.partition_key, "user-1");
+        assert_eq!(record.sequence_number, "4959");
+        assert_eq!(record.approximate_arrival_timestamp, Some(1545084650.987));
+        assert_eq!(record.data_json::<Value>().unwrap(), json!({ "clicks": 3 }));
+        assert!(matches!(records[1], Err(Error::Platform(_))));
+
+        assert_eq!(kinesis_records(&json!({ "command": "sync" })).count(), 0);
+    }
+
+    #[test]
+    fn test_sqs_batch() {
+        let event = json!({
+            "Records": [
+                {
+                    "eventSource": "aws:sqs",
+                    "messageId": "m-1",
+                    "receiptHandle": "r-1",
+                    "body": "{\"order\": 1}",
+                    "attributes": { "ApproximateReceiveCount": "1" },
+                    "messageAttributes": {},
+                    "eventSourceARN": "arn:aws:sqs:us-east-1:123456789012:orders"
+                },
+                { "eventSource": "aws:sqs", "messageId": "m-2", "body": "not json" },
+                { "eventSource": "aws:sqs", "messageId": "m-3", "body": "{\"order\": 3}" }
+            ]
+        });
+
+        let messages: Vec<_> = sqs_messages(&event).collect();
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[0].receipt_handle, "r-1");
+        assert_eq!(messages[0].attributes["ApproximateReceiveCount"], "1");
+        assert_eq!(
+            messages[0].event_source_arn.as_deref(),
+            Some("arn:aws:sqs:us-east-1:123456789012:orders")
+        );
+        assert_eq!(messages[2].body_json::<Value>().unwrap()["order"], 3);
+
+        let mut processed = Vec::new();
+        let response = process_sqs_batch(&event, |message| {
+            processed.push(message.message_id.clone());
+            message.body_json::<Value>().map(|_| ())
+        });
+        assert_eq!(processed, ["m-1", "m-2", "m-3"]);
+        assert_eq!(
+            response,
+            json!({ "batchItemFailures": [{ "itemIdentifier": "m-2" }] })
+        );
+
+        let all_ok = batch_item_failures(vec![Ok(()), Ok(())]);
+        assert_eq!(all_ok, json!({ "batchItemFailures": [] }));
+    }
+}
@@ -2,7 +2,169 @@
 Cloudflare Workers adapter for serverless.rs.
 
 This module provides the adapter for deploying serverless.rs functions to Cloudflare Workers.
+Fetch events are exchanged as JSON: the worker shim passes `{ method, url, headers, body }`
+and receives `{ status, headers, body, bodyEncoding }` back.
+
+When a response sets more than one cookie, the reply also has a `cookies` array with
+every `Set-Cookie` value, while `headers` keeps only the first. A shim that knows
+about `cookies` should append each of them to the response headers in place of the
+single one; an older shim still sets the first cookie.
 */
 
-// This file will be expanded in Step 5 per the execution plan
-// Currently just a placeholder for the project structure
+use http::Uri;
+use serde_json::{json, Value};
+
+use crate::error::{Error, Result};
+use crate::logging::logged;
+use crate::metrics::measured;
+use crate::trace::traced;
+use crate::util::parse_query_string;
+use crate::{Context, Request, Response};
+
+/// Build a [`Request`] from a fetch event
+///
+/// The `url` is split into path and query parameters. A `bodyEncoding` of `"base64"`
+/// marks the body as Base64 encoded, for [`Request::decoded_body`]. The original event is available via
+/// [`Request::raw_event`].
+pub fn request_from_event(event: &Value) -> Result<Request> {
+    let mut req = Request::new().with_raw_event(event.clone());
+
+    if let Some(method) = event.get("method").and_then(Value::as_str) {
+        req = req.with_method_str(method);
+    }
+
+    if let Some(url) = event.get("url").and_then(Value::as_str) {
+        let uri: Uri = url.parse().map_err(Error::http)?;
+        let path_and_query = uri.path_and_query().map(|pq| pq.as_str()).unwrap_or("/");
+        req = req.with_path(path_and_query);
+        if let Some(query) = uri.query() {
+            for (name, value) in parse_query_string(query) {
+                req = req.with_query(name, value);
+            }
+        }
+    }
+
+    if let Some(headers) = event.get("headers").and_then(Value::as_object) {
+        req = req.with_headers(
+            headers
+                .iter()
+                .filter_map(|(name, value)| Some((name.clone(), value.as_str()?))),
+        );
+    }
+
+    if let Some(body) = event.get("body").and_then(Value::as_str) {
+        let is_base64 = event.get("bodyEncoding").and_then(Value::as_str) == Some("base64");
+        req = req.with_body(body).with_base64(is_base64);
+    }
+
+    Ok(req)
+}
+
+/// Convert a [`Response`] into the fetch response JSON
+///
+/// Missing default headers are added with [`Response::with_default_headers`]. Values
+/// of repeated headers are joined with `, `, except for a repeated `Set-Cookie`,
+/// whose values are listed in `cookies` to be appended one by one; the first is also
+/// kept in `headers` for shims that don't read `cookies`.
+pub fn response_to_value(resp: &Response) -> Value {
+    let resp = resp.clone().with_default_headers();
+    let (mut headers, cookies) = resp.joined_headers();
+    if let Some(first) = cookies.first() {
+        headers.insert("Set-Cookie".to_string(), first.clone());
+    }
+    let mut value = json!({
+        "status": resp.status(),
+        "headers": headers,
+        "body": String::from_utf8_lossy(resp.body()),
+        "bodyEncoding": if resp.is_base64() { "base64" } else { "utf-8" },
+    });
+    if !cookies.is_empty() {
+        value["cookies"] = json!(cookies);
+    }
+    value
+}
+
+/// Handle a fetch event, returning the fetch response JSON
+///
+/// The worker bindings in `env` are exposed as the context's platform data. Errors,
+/// including responses over the header limits set with
+/// [`set_header_limits`](crate::set_header_limits), are converted into a response with
+/// the error's [`Error::status`] and its [`Error::to_json`] body.
+pub fn handle_fetch<F>(handler: F, request: Value, env: Value) -> Value
+where
+    F: Fn(Request, &Context) -> Result<Response>,
+{
+    let ctx = Context::new()
+        .with_request_id("cf-request-id")
+        .with_platform_data(env);
+
+    let result = request_from_event(&request).and_then(|req| {
+        let ctx = ctx.with_trace_from_request(&req);
+        traced(req, &ctx, |req, ctx| {
+            logged(req, ctx, |req, ctx| measured(req, ctx, handler))
+        })
+    });
+    let result = result.and_then(|resp| {
+        let resp = resp.with_default_headers();
+        resp.check_header_limits()?;
+        Ok(resp)
+    });
+    match result {
+        Ok(resp) => response_to_value(&resp),
+        Err(err) => json!({
+            "status": err.status(),
+            "headers": { "Content-Type": "application/json" },
+            "body": err.to_json().to_string(),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_from_event() {
+        let event = json!({
+            "method": "POST",
+            "url": "https://example.com/api/items?sort=name&q=a%20b",
+            "headers": { "content-type": "text/plain" },
+            "body": "aGVsbG8=",
+            "bodyEncoding": "base64"
+        });
+
+        let req = request_from_event(&event).unwrap();
+        assert_eq!(req.method_str().as_deref(), Some("POST"));
+        assert_eq!(req.path().as_deref(), Some("/api/items"));
+        assert_eq!(req.query_param("q"), Some(&"a b".to_string()));
+        assert_eq!(req.header("Content-Type"), Some(&"text/plain".to_string()));
+        assert_eq!(req.body_string().unwrap(), "hello");
+    }
+
+    #[test]
+    fn test_handle_fetch() {
+        let event = json!({ "method": "GET", "url": "https://example.com/hello" });
+        let result = handle_fetch(
+            |req: Request, _ctx: &Context| Ok(Response::text(req.path().unwrap_or_default())),
+            event,
+            Value::Null,
+        );
+        assert_eq!(result["status"], 200);
+        assert_eq!(result["body"], "/hello");
+        assert_eq!(result["bodyEncoding"], "utf-8");
+        assert!(result.get("cookies").is_none());
+    }
+
+    #[test]
+    fn test_repeated_headers() {
+        let resp = Response::new()
+            .append_header("Set-Cookie", "a=1")
+            .append_header("Set-Cookie", "b=2")
+            .append_header("Vary", "Accept")
+            .append_header("Vary", "Origin");
+        let value = response_to_value(&resp);
+        assert_eq!(value["cookies"], json!(["a=1", "b=2"]));
+        assert_eq!(value["headers"]["Set-Cookie"], "a=1");
+        assert_eq!(value["headers"]["Vary"], "Accept, Origin");
+    }
+}
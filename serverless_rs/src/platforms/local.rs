@@ -3,7 +3,202 @@ Local development server for serverless.rs.
 
 This module provides a local development server for testing serverless functions
 without deploying them to a cloud provider.
+
+The server itself is yet to be written; this module currently holds the HTTP/1.1
+request handling it builds on, such as [`read_body`].
 */
 
-// This file will be expanded in Step 6 per the execution plan
-// Currently just a placeholder for the project structure
+use std::collections::HashMap;
+use std::io::{BufRead, Read};
+
+use crate::error::{Error, Result};
+
+/// Default limit on the size of a request body: 6 MB, as for a Lambda invocation
+pub const DEFAULT_MAX_BODY_SIZE: usize = 6 * 1024 * 1024;
+
+/// Read an HTTP/1.1 request body that follows the headers on `reader`
+///
+/// The body is framed as RFC 9112 describes: a `Transfer-Encoding` ending in
+/// `chunked` takes precedence and its chunks are decoded, otherwise `Content-Length`
+/// gives the size, and a request with neither has no body. Chunk extensions and
+/// trailer fields are skipped.
+///
+/// Bodies over `max_size` bytes, counting the decoded size of chunked bodies, are
+/// an error with status 413. Malformed framing, an unsupported transfer coding, or
+/// a connection closed before the body ends are an error with status 400.
+pub fn read_body<R: BufRead>(
+    reader: &mut R,
+    headers: &HashMap<String, String>,
+    max_size: usize,
+) -> Result<Vec<u8>> {
+    if let Some(encoding) = header(headers, "Transfer-Encoding") {
+        let last = encoding.rsplit(',').next().unwrap_or_default().trim();
+        if !last.eq_ignore_ascii_case("chunked") {
+            return Err(Error::with_status(
+                400,
+                format!("Unsupported Transfer-Encoding: {}", encoding),
+            ));
+        }
+        return read_chunked(reader, max_size);
+    }
+
+    let length = match header(headers, "Content-Length") {
+        Some(length) => length
+            .trim()
+            .parse::<usize>()
+            .map_err(|_| Error::with_status(400, format!("Invalid Content-Length: {}", length)))?,
+        None => return Ok(Vec::new()),
+    };
+    if length > max_size {
+        return Err(too_large(max_size));
+    }
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).map_err(incomplete)?;
+    Ok(body)
+}
+
+/// Decode a chunked body, enforcing `max_size` on the decoded bytes
+fn read_chunked<R: BufRead>(reader: &mut R, max_size: usize) -> Result<Vec<u8>> {
+    let mut body = Vec::new();
+    loop {
+        let line = read_line(reader)?;
+        let size = line.split(';').next().unwrap_or_default().trim();
+        let size = usize::from_str_radix(size, 16)
+            .map_err(|_| Error::with_status(400, format!("Invalid chunk size: {}", line)))?;
+        if size == 0 {
+            break;
+        }
+        if size > max_size - body.len() {
+            return Err(too_large(max_size));
+        }
+
+        let start = body.len();
+        body.resize(start + size, 0);
+        reader.read_exact(&mut body[start..]).map_err(incomplete)?;
+        if !read_line(reader)?.is_empty() {
+            return Err(Error::with_status(400, "Chunk is longer than its size"));
+        }
+    }
+
+    // Skip trailer fields up to the blank line ending the body
+    while !read_line(reader)?.is_empty() {}
+    Ok(body)
+}
+
+/// Read one CRLF or LF terminated line, without the line ending
+fn read_line<R: BufRead>(reader: &mut R) -> Result<String> {
+    let mut line = Vec::new();
+    let read = reader
+        .by_ref()
+        .take(8 * 1024)
+        .read_until(b'\n', &mut line)
+        .map_err(incomplete)?;
+    if read == 0 || !line.ends_with(b"\n") {
+        return Err(incomplete(std::io::ErrorKind::UnexpectedEof.into()));
+    }
+    let line = String::from_utf8_lossy(&line);
+    Ok(line.trim_end_matches(['\r', '\n']).to_string())
+}
+
+/// Returns a header value by name, ignoring ASCII case
+fn header<'a>(headers: &'a HashMap<String, String>, name: &str) -> Option<&'a str> {
+    headers
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.as_str())
+}
+
+fn too_large(max_size: usize) -> Error {
+    Error::with_status(
+        413,
+        format!("Request body exceeds the limit of {} bytes", max_size),
+    )
+}
+
+fn incomplete(err: std::io::Error) -> Error {
+    Error::with_status(400, format!("Incomplete request body: {}", err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_read_chunked_body() {
+        let chunked = headers(&[("transfer-encoding", "chunked")]);
+        let mut wire: &[u8] =
+            b"7\r\n{\"name\"\r\n9;ext=1\r\n:\"widget\"\r\n1\r\n}\r\n0\r\nX-Checksum: abc\r\n\r\nNEXT";
+        let body = read_body(&mut wire, &chunked, DEFAULT_MAX_BODY_SIZE).unwrap();
+        assert_eq!(body, br#"{"name":"widget"}"#);
+        // The reader is left at the start of the next request
+        assert_eq!(wire, b"NEXT");
+
+        // Chunked framing wins over Content-Length
+        let both = headers(&[("Transfer-Encoding", "chunked"), ("Content-Length", "3")]);
+        let mut wire: &[u8] = b"5\r\nhello\r\n0\r\n\r\n";
+        assert_eq!(read_body(&mut wire, &both, 1024).unwrap(), b"hello");
+
+        // The limit applies to the total decoded size
+        let mut wire: &[u8] = b"4\r\nabcd\r\n4\r\nefgh\r\n0\r\n\r\n";
+        let err = read_body(&mut wire, &chunked, 6).unwrap_err();
+        assert_eq!(err.status(), 413);
+
+        for wire in [
+            &b"zz\r\nabc\r\n0\r\n\r\n"[..],
+            b"3\r\nabcdef\r\n0\r\n\r\n",
+            b"5\r\nabc",
+        ] {
+            let err = read_body(&mut &wire[..], &chunked, 1024).unwrap_err();
+            assert_eq!(err.status(), 400, "{:?}", String::from_utf8_lossy(wire));
+        }
+
+        let gzip = headers(&[("Transfer-Encoding", "gzip")]);
+        assert_eq!(
+            read_body(&mut &b""[..], &gzip, 1024).unwrap_err().status(),
+            400
+        );
+    }
+
+    #[test]
+    fn test_read_sized_body() {
+        let sized = headers(&[("Content-Length", "5")]);
+        let mut wire: &[u8] = b"helloNEXT";
+        assert_eq!(read_body(&mut wire, &sized, 1024).unwrap(), b"hello");
+        assert_eq!(wire, b"NEXT");
+
+        assert_eq!(
+            read_body(&mut &b"hel"[..], &sized, 1024)
+                .unwrap_err()
+                .status(),
+            400
+        );
+        assert_eq!(
+            read_body(&mut &b"hello"[..], &sized, 4)
+                .unwrap_err()
+                .status(),
+            413
+        );
+
+        let invalid = headers(&[("Content-Length", "five")]);
+        assert_eq!(
+            read_body(&mut &b""[..], &invalid, 1024)
+                .unwrap_err()
+                .status(),
+            400
+        );
+
+        // Without framing headers a request has no body
+        let mut wire: &[u8] = b"NEXT";
+        assert!(read_body(&mut wire, &HashMap::new(), 1024)
+            .unwrap()
+            .is_empty());
+        assert_eq!(wire, b"NEXT");
+    }
+}
@@ -1,7 +1,8 @@
 /*!
 Platform-specific adapters for serverless.rs.
 
-This module contains adapters for different serverless platforms.
+This module contains adapters for different serverless platforms, along with
+platform detection so one binary can pick its adapter at runtime.
 */
 
 // AWS Lambda adapter
@@ -27,3 +28,291 @@ pub mod gcp;
 // Local development server
 #[cfg(feature = "local")]
 pub mod local;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::error::{Error, Result};
+use crate::{Context, Request, Response};
+
+/// A serverless platform supported by serverless.rs
+///
+/// Platforms are serialized as their names, such as `"aws"`. Names serverless.rs
+/// doesn't know are kept as [`Platform::Custom`] when deserializing or converting
+/// from a string, while [`FromStr`] rejects them.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Platform {
+    /// AWS Lambda
+    Aws,
+    /// Cloudflare Workers
+    Cloudflare,
+    /// Azure Functions
+    Azure,
+    /// Google Cloud Functions
+    Gcp,
+    /// Vercel Functions
+    Vercel,
+    /// Local development server
+    Local,
+    /// Another platform, by name, which has no adapter in serverless.rs
+    Custom(String),
+}
+
+impl Platform {
+    /// All platforms with an adapter
+    pub const ALL: [Platform; 6] = [
+        Platform::Aws,
+        Platform::Cloudflare,
+        Platform::Azure,
+        Platform::Gcp,
+        Platform::Vercel,
+        Platform::Local,
+    ];
+
+    /// Returns the platform's name, matching its feature flag
+    pub fn name(&self) -> &str {
+        match self {
+            Platform::Aws => "aws",
+            Platform::Cloudflare => "cloudflare",
+            Platform::Azure => "azure",
+            Platform::Gcp => "gcp",
+            Platform::Vercel => "vercel",
+            Platform::Local => "local",
+            Platform::Custom(name) => name,
+        }
+    }
+
+    /// Returns whether this is a platform with an adapter, rather than a custom one
+    pub fn is_known(&self) -> bool {
+        !matches!(self, Platform::Custom(_))
+    }
+}
+
+impl From<&str> for Platform {
+    /// Returns the known platform with this name, ignoring case, or a custom one
+    ///
+    /// This never fails, so a misspelled name becomes a custom platform; use
+    /// [`str::parse`] to reject unknown names instead.
+    fn from(name: &str) -> Self {
+        name.parse()
+            .unwrap_or_else(|_| Platform::Custom(name.to_string()))
+    }
+}
+
+impl From<String> for Platform {
+    fn from(name: String) -> Self {
+        Platform::from(name.as_str())
+    }
+}
+
+impl Serialize for Platform {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.name())
+    }
+}
+
+impl<'de> Deserialize<'de> for Platform {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        String::deserialize(deserializer).map(Platform::from)
+    }
+}
+
+impl fmt::Display for Platform {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Platform {
+    type Err = Error;
+
+    fn from_str(name: &str) -> Result<Self> {
+        Platform::ALL
+            .into_iter()
+            .find(|platform| platform.name().eq_ignore_ascii_case(name))
+            .ok_or_else(|| Error::platform(format!("Unknown platform: {}", name)))
+    }
+}
+
+/// Detect the platform this process is running on from its environment variables
+///
+/// Falls back to [`Platform::Local`] when no platform is recognized.
+pub fn detect_platform() -> Platform {
+    detect_platform_from(std::env::vars())
+}
+
+/// Detect the platform from an explicit set of environment variables
+fn detect_platform_from<I>(vars: I) -> Platform
+where
+    I: IntoIterator<Item = (String, String)>,
+{
+    let names: Vec<String> = vars.into_iter().map(|(name, _)| name).collect();
+    let has = |name: &str| names.iter().any(|n| n == name);
+
+    // Vercel runs on Lambda, so it must be checked before AWS
+    if has("VERCEL") {
+        Platform::Vercel
+    } else if has("AWS_LAMBDA_RUNTIME_API") || has("AWS_LAMBDA_FUNCTION_NAME") {
+        Platform::Aws
+    } else if has("FUNCTIONS_WORKER_RUNTIME") {
+        Platform::Azure
+    } else if has("FUNCTION_TARGET") || has("K_SERVICE") {
+        Platform::Gcp
+    } else if names.iter().any(|name| name.starts_with("CF_")) {
+        Platform::Cloudflare
+    } else {
+        Platform::Local
+    }
+}
+
+/// Handle a platform event with the adapter for the given platform
+///
+/// [`Platform::Local`] events are taken to be AWS-shaped, as with `--invoke`, so they
+/// need the `aws` feature too. Returns a platform error if the platform has no event
+/// adapter or its feature is not enabled.
+pub fn handle_event<F>(
+    platform: Platform,
+    handler: F,
+    event: Value,
+    context: Value,
+) -> Result<Value>
+where
+    F: Fn(Request, &Context) -> Result<Response>,
+{
+    match platform {
+        #[cfg(feature = "aws")]
+        Platform::Aws => Ok(aws::lambda_handler(handler, event, context)),
+        #[cfg(feature = "cloudflare")]
+        Platform::Cloudflare => Ok(cloudflare::handle_fetch(handler, event, context)),
+        #[cfg(feature = "aws")]
+        Platform::Local => crate::invoke::invoke_aws_event(handler, event, context),
+        _ => {
+            let _ = (handler, event, context);
+            Err(Error::platform(format!(
+                "No event adapter for platform '{}' (unsupported or feature not enabled)",
+                platform
+            )))
+        }
+    }
+}
+
+/// Handle a platform event with the adapter for the detected platform
+///
+/// This lets a single binary serve events on any provider without a different `main`
+/// per platform, and run them locally, where [`detect_platform`] falls back to
+/// [`Platform::Local`].
+pub fn run_auto<F>(handler: F, event: Value, context: Value) -> Result<Value>
+where
+    F: Fn(Request, &Context) -> Result<Response>,
+{
+    handle_event(detect_platform(), handler, event, context)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(names: &[&str]) -> Vec<(String, String)> {
+        names
+            .iter()
+            .map(|name| (name.to_string(), "1".to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_detect_platform() {
+        assert_eq!(
+            detect_platform_from(vars(&["AWS_LAMBDA_RUNTIME_API"])),
+            Platform::Aws
+        );
+        assert_eq!(
+            detect_platform_from(vars(&["AWS_LAMBDA_FUNCTION_NAME", "VERCEL"])),
+            Platform::Vercel
+        );
+        assert_eq!(
+            detect_platform_from(vars(&["FUNCTIONS_WORKER_RUNTIME"])),
+            Platform::Azure
+        );
+        assert_eq!(detect_platform_from(vars(&["K_SERVICE"])), Platform::Gcp);
+        assert_eq!(
+            detect_platform_from(vars(&["CF_PAGES"])),
+            Platform::Cloudflare
+        );
+        assert_eq!(detect_platform_from(vars(&["HOME"])), Platform::Local);
+    }
+
+    #[test]
+    fn test_platform_names() {
+        for platform in Platform::ALL {
+            assert_eq!(platform.name().parse::<Platform>().unwrap(), platform);
+        }
+        assert_eq!("AWS".parse::<Platform>().unwrap(), Platform::Aws);
+        assert!("fastly".parse::<Platform>().is_err());
+
+        // Conversions and serialization keep unknown names as custom platforms
+        assert_eq!(Platform::from("Cloudflare"), Platform::Cloudflare);
+        let fastly = Platform::from("fastly");
+        assert_eq!(fastly, Platform::Custom("fastly".to_string()));
+        assert!(!fastly.is_known());
+        assert_eq!(fastly.to_string(), "fastly");
+        assert_eq!(
+            serde_json::to_value([Platform::Aws, fastly.clone()]).unwrap(),
+            serde_json::json!(["aws", "fastly"])
+        );
+        let parsed: Vec<Platform> = serde_json::from_str(r#"["aws", "fastly"]"#).unwrap();
+        assert_eq!(parsed, vec![Platform::Aws, fastly]);
+    }
+
+    #[test]
+    fn test_handle_event_without_adapter() {
+        let result = handle_event(
+            Platform::Azure,
+            |_req: Request, _ctx: &Context| Ok(Response::new()),
+            Value::Null,
+            Value::Null,
+        );
+        assert!(matches!(result, Err(Error::Platform(_))));
+    }
+
+    #[cfg(feature = "aws")]
+    #[test]
+    fn test_handle_aws_event() {
+        let event = serde_json::json!({ "httpMethod": "GET", "path": "/ping" });
+        let result = handle_event(
+            Platform::Aws,
+            |_req: Request, _ctx: &Context| Ok(Response::text("pong")),
+            event,
+            Value::Null,
+        )
+        .unwrap();
+        assert_eq!(result["body"], "pong");
+    }
+
+    #[cfg(feature = "aws")]
+    #[test]
+    fn test_handle_local_event() {
+        // Outside a known platform, events are run as with `--invoke`
+        let platform = detect_platform_from(vars(&["HOME"]));
+        let event = serde_json::json!({ "httpMethod": "GET", "path": "/ping" });
+        let result = handle_event(
+            platform,
+            |req: Request, _ctx: &Context| Ok(Response::text(req.path().unwrap_or_default())),
+            event,
+            Value::Null,
+        )
+        .unwrap();
+        assert_eq!(result["statusCode"], 200);
+        assert_eq!(result["body"], "/ping");
+
+        let result = handle_event(
+            Platform::Local,
+            |_req: Request, _ctx: &Context| Response::json(&serde_json::json!({ "ok": true })),
+            serde_json::json!({ "order": 7 }),
+            Value::Null,
+        )
+        .unwrap();
+        assert_eq!(result, serde_json::json!({ "ok": true }));
+    }
+}
@@ -0,0 +1,24 @@
+/*!
+The serverless.rs prelude.
+
+Glob-import it to bring the types, traits and macros most functions use, including
+`#[serverless]` and `#[route]`, into scope:
+
+```
+use serverless_rs::prelude::*;
+
+fn hello(req: Request, _ctx: &Context) -> Result<Response> {
+    let name = req.query_param("name").map_or("world", String::as_str);
+    Response::json(&json!({ "message": format!("Hello, {}!", name) }))
+}
+
+let router = RouterBuilder::new().get("/hello", hello).build();
+```
+
+Everything here is also exported from the crate root.
+*/
+
+pub use crate::{
+    json, requirements, route, serverless, Context, Error, FromRequest, Handler, HandlerExt,
+    IntoResponse, Middleware, Request, Response, Result, Router, RouterBuilder, Value,
+};
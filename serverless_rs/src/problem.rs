@@ -0,0 +1,171 @@
+/*!
+Problem details for serverless.rs.
+
+This module provides [`Problem`], an RFC 7807 problem details object, which
+[`Response::problem_json`] sends as an `application/problem+json` body.
+*/
+
+use serde_json::{Map, Value};
+
+use crate::Response;
+
+/// Members defined by RFC 7807, which extension members can't replace
+const STANDARD_MEMBERS: [&str; 5] = ["type", "title", "status", "detail", "instance"];
+
+/// An RFC 7807 problem details object
+///
+/// # Examples
+///
+/// ```
+/// use serverless_rs::{Problem, Response};
+///
+/// let problem = Problem::new(403, "Insufficient credit")
+///     .with_type("https://example.com/probs/out-of-credit")
+///     .with_detail("Your balance is 30, but that costs 50.")
+///     .with_instance("/account/12345/msgs/abc")
+///     .with_extension("balance", 30);
+///
+/// let response = Response::problem_json(&problem);
+/// assert_eq!(response.status(), 403);
+/// assert_eq!(response.header("Content-Type").unwrap(), "application/problem+json");
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Problem {
+    /// URI identifying the problem type
+    problem_type: String,
+
+    /// Short, human-readable summary of the problem type
+    title: String,
+
+    /// HTTP status code
+    status: u16,
+
+    /// Explanation specific to this occurrence
+    detail: Option<String>,
+
+    /// URI identifying this occurrence
+    instance: Option<String>,
+
+    /// Additional members
+    extensions: Map<String, Value>,
+}
+
+impl Problem {
+    /// Creates a problem with the given status and title, and type `about:blank`
+    pub fn new(status: u16, title: impl Into<String>) -> Self {
+        Self {
+            problem_type: "about:blank".to_string(),
+            title: title.into(),
+            status,
+            detail: None,
+            instance: None,
+            extensions: Map::new(),
+        }
+    }
+
+    /// Returns the status code
+    pub fn status(&self) -> u16 {
+        self.status
+    }
+
+    /// Sets the problem type URI
+    pub fn with_type(mut self, problem_type: impl Into<String>) -> Self {
+        self.problem_type = problem_type.into();
+        self
+    }
+
+    /// Sets the occurrence-specific explanation
+    pub fn with_detail(mut self, detail: impl Into<String>) -> Self {
+        self.detail = Some(detail.into());
+        self
+    }
+
+    /// Sets the URI identifying this occurrence
+    pub fn with_instance(mut self, instance: impl Into<String>) -> Self {
+        self.instance = Some(instance.into());
+        self
+    }
+
+    /// Adds an extension member
+    ///
+    /// Extensions named like a standard member (`type`, `title`, `status`, `detail`
+    /// or `instance`) are ignored.
+    pub fn with_extension(mut self, name: impl Into<String>, value: impl Into<Value>) -> Self {
+        let name = name.into();
+        if !STANDARD_MEMBERS.contains(&name.as_str()) {
+            self.extensions.insert(name, value.into());
+        }
+        self
+    }
+
+    /// Returns the problem as a JSON object
+    pub fn to_json(&self) -> Value {
+        let mut object = self.extensions.clone();
+        object.insert("type".to_string(), Value::from(self.problem_type.as_str()));
+        object.insert("title".to_string(), Value::from(self.title.as_str()));
+        object.insert("status".to_string(), Value::from(self.status));
+        if let Some(detail) = &self.detail {
+            object.insert("detail".to_string(), Value::from(detail.as_str()));
+        }
+        if let Some(instance) = &self.instance {
+            object.insert("instance".to_string(), Value::from(instance.as_str()));
+        }
+        Value::Object(object)
+    }
+}
+
+impl From<Problem> for Response {
+    fn from(problem: Problem) -> Self {
+        Response::problem_json(&problem)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_problem_json() {
+        let problem = Problem::new(422, "Validation failed")
+            .with_detail("name is required")
+            .with_instance("/users")
+            .with_extension("errors", json!([{ "field": "name" }]))
+            .with_extension("status", 200);
+
+        assert_eq!(
+            problem.to_json(),
+            json!({
+                "type": "about:blank",
+                "title": "Validation failed",
+                "status": 422,
+                "detail": "name is required",
+                "instance": "/users",
+                "errors": [{ "field": "name" }],
+            })
+        );
+    }
+
+    #[test]
+    fn test_problem_response() {
+        let response = Response::problem(404, "Not Found", "No user with id 7");
+        assert_eq!(response.status(), 404);
+        assert_eq!(
+            response.header("Content-Type").unwrap(),
+            "application/problem+json"
+        );
+        let body: Value = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(
+            body,
+            json!({
+                "type": "about:blank",
+                "title": "Not Found",
+                "status": 404,
+                "detail": "No user with id 7",
+            })
+        );
+
+        let response: Response = Problem::new(503, "Unavailable").into();
+        assert_eq!(response.status(), 503);
+    }
+}
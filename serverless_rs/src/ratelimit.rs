@@ -0,0 +1,389 @@
+/*!
+Rate limiting middleware for serverless.rs.
+
+[`RateLimitLayer`] applies a token bucket per client, keyed by the client IP the
+platform saw or by a configurable header, and answers requests over the limit with
+`429 Too Many Requests` and a `Retry-After` header.
+
+Buckets are kept in a [`RateLimitStore`]. [`MemoryStore`] keeps them in process,
+which suits the local server and single-instance deployments; since serverless
+instances don't share memory, production deployments can plug in an external store.
+*/
+
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::error::{Error, Result};
+use crate::request::parse_ip;
+use crate::util;
+use crate::{Context, Handler, Middleware, Request, Response};
+
+/// The number of requests allowed per period
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Quota {
+    /// Bucket size, i.e. the largest burst allowed
+    pub capacity: u32,
+
+    /// Time to refill the bucket from empty
+    pub period: Duration,
+}
+
+impl Quota {
+    /// Allow `capacity` requests per `period`
+    pub fn new(capacity: u32, period: Duration) -> Self {
+        Self { capacity, period }
+    }
+
+    /// Allow `capacity` requests per second
+    pub fn per_second(capacity: u32) -> Self {
+        Self::new(capacity, Duration::from_secs(1))
+    }
+
+    /// Allow `capacity` requests per minute
+    pub fn per_minute(capacity: u32) -> Self {
+        Self::new(capacity, Duration::from_secs(60))
+    }
+
+    /// Returns the time it takes to refill one token
+    fn token_interval(&self) -> Duration {
+        self.period / self.capacity.max(1)
+    }
+}
+
+/// The outcome of taking a token from a bucket
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RateLimitDecision {
+    /// The request is allowed, with the given number of tokens left
+    Allowed {
+        /// Tokens left in the bucket
+        remaining: u32,
+    },
+    /// The request is over the limit
+    Limited {
+        /// Time until a token is available
+        retry_after: Duration,
+    },
+}
+
+/// Storage for rate limit buckets
+///
+/// Implementations must take tokens atomically, since concurrent invocations may
+/// share a bucket.
+#[async_trait]
+pub trait RateLimitStore: Send + Sync + 'static {
+    /// Take one token from the bucket for `key`, creating a full bucket if needed
+    async fn acquire(&self, key: &str, quota: &Quota) -> Result<RateLimitDecision>;
+}
+
+/// A token bucket's state
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// An in-process [`RateLimitStore`]
+///
+/// Buckets that have sat idle for a whole period are full again, so they are dropped
+/// rather than kept for every client ever seen.
+///
+/// It needs a clock, so on `wasm32-unknown-unknown` every call fails with
+/// [`Error::Platform`]; use a store backed by an external service there.
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+    buckets: Mutex<HashMap<String, Bucket>>,
+}
+
+impl MemoryStore {
+    /// Creates an empty store
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Take a token at the given instant
+    fn acquire_at(&self, key: &str, quota: &Quota, now: Instant) -> Result<RateLimitDecision> {
+        let mut buckets = self
+            .buckets
+            .lock()
+            .map_err(|_| Error::unexpected("rate limit store lock poisoned"))?;
+        buckets.retain(|_, bucket| now.saturating_duration_since(bucket.updated) < quota.period);
+
+        let capacity = quota.capacity as f64;
+        let refill_rate = capacity / quota.period.as_secs_f64().max(f64::EPSILON);
+        let bucket = buckets.entry(key.to_string()).or_insert(Bucket {
+            tokens: capacity,
+            updated: now,
+        });
+
+        let elapsed = now.saturating_duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * refill_rate).min(capacity);
+        bucket.updated = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(RateLimitDecision::Allowed {
+                remaining: bucket.tokens.floor() as u32,
+            })
+        } else {
+            let missing = 1.0 - bucket.tokens;
+            let retry_after = quota.token_interval().mul_f64(missing);
+            Ok(RateLimitDecision::Limited { retry_after })
+        }
+    }
+}
+
+/// Returns the current instant, failing on targets without a clock such as
+/// `wasm32-unknown-unknown`
+fn stopwatch() -> Result<Instant> {
+    util::stopwatch().ok_or_else(|| {
+        Error::platform("The in-memory rate limit store needs a clock, which this target lacks")
+    })
+}
+
+#[async_trait]
+impl RateLimitStore for MemoryStore {
+    async fn acquire(&self, key: &str, quota: &Quota) -> Result<RateLimitDecision> {
+        self.acquire_at(key, quota, stopwatch()?)
+    }
+}
+
+/// Middleware that limits the request rate per client
+///
+/// By default clients are told apart by the IP address the platform saw, rather than
+/// by the first hop of `X-Forwarded-For`, which the client controls.
+///
+/// # Examples
+///
+/// ```
+/// use serverless_rs::ratelimit::{Quota, RateLimitLayer};
+/// use serverless_rs::{Context, Request, Response, Result, RouterBuilder};
+///
+/// fn search(_req: Request, _ctx: &Context) -> Result<Response> {
+///     Ok(Response::text("results"))
+/// }
+///
+/// let router = RouterBuilder::new()
+///     .scope("/api", |api| {
+///         api.layer(RateLimitLayer::new(Quota::per_minute(60)).with_key_header("X-Api-Key"))
+///             .get("/search", search)
+///     })
+///     .build();
+/// ```
+pub struct RateLimitLayer {
+    quota: Quota,
+    key_header: Option<String>,
+    store: Arc<dyn RateLimitStore>,
+}
+
+impl RateLimitLayer {
+    /// Limit each client to the given quota, using an in-process [`MemoryStore`]
+    pub fn new(quota: Quota) -> Self {
+        Self {
+            quota,
+            key_header: None,
+            store: Arc::new(MemoryStore::new()),
+        }
+    }
+
+    /// Key buckets by the value of a header instead of the client IP
+    pub fn with_key_header(mut self, name: impl Into<String>) -> Self {
+        self.key_header = Some(name.into());
+        self
+    }
+
+    /// Keep buckets in the given store
+    pub fn with_store<S: RateLimitStore>(mut self, store: S) -> Self {
+        self.store = Arc::new(store);
+        self
+    }
+
+    /// Returns the bucket key for a request
+    ///
+    /// Requests without the key header or a known client IP share one bucket.
+    fn key(&self, req: &Request) -> String {
+        let key = match &self.key_header {
+            Some(header) => req
+                .header(header)
+                .and_then(|value| value.split(',').next())
+                .map(|value| value.trim().to_string())
+                .filter(|value| !value.is_empty()),
+            None => client_ip(req).map(|ip| ip.to_string()),
+        };
+        key.unwrap_or_else(|| "unknown".to_string())
+    }
+}
+
+/// Returns the client IP as seen by the platform, which the client can't choose
+///
+/// Unlike [`Request::remote_ip`], which prefers the first hop of `X-Forwarded-For`,
+/// this checks the source IP of an API Gateway event, then `CF-Connecting-IP`, which
+/// Cloudflare overwrites, and last the rightmost hop of `X-Forwarded-For`, the one
+/// added by the nearest proxy.
+fn client_ip(req: &Request) -> Option<IpAddr> {
+    let event = req.raw_event();
+    let source = event
+        .pointer("/requestContext/http/sourceIp")
+        .or_else(|| event.pointer("/requestContext/identity/sourceIp"))
+        .and_then(|value| value.as_str());
+    let forwarded = req
+        .header("X-Forwarded-For")
+        .and_then(|value| value.rsplit(',').next());
+    [
+        source,
+        req.header("CF-Connecting-IP").map(String::as_str),
+        forwarded,
+    ]
+    .into_iter()
+    .flatten()
+    .find_map(parse_ip)
+}
+
+#[async_trait]
+impl Middleware for RateLimitLayer {
+    async fn handle(&self, req: Request, ctx: &Context, next: &dyn Handler) -> Result<Response> {
+        match self.store.acquire(&self.key(&req), &self.quota).await? {
+            RateLimitDecision::Allowed { .. } => next.handle(req, ctx).await,
+            RateLimitDecision::Limited { retry_after } => {
+                let seconds = retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0);
+                Ok(Response::new()
+                    .with_status(429)
+                    .with_header("Retry-After", seconds.max(1).to_string())
+                    .with_body("Too Many Requests"))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ok(_req: Request, _ctx: &Context) -> Result<Response> {
+        Ok(Response::text("ok"))
+    }
+
+    #[test]
+    fn test_memory_store_refills() {
+        let store = MemoryStore::new();
+        let quota = Quota::per_second(2);
+        let start = Instant::now();
+
+        assert_eq!(
+            store.acquire_at("a", &quota, start).unwrap(),
+            RateLimitDecision::Allowed { remaining: 1 }
+        );
+        assert_eq!(
+            store.acquire_at("a", &quota, start).unwrap(),
+            RateLimitDecision::Allowed { remaining: 0 }
+        );
+        assert_eq!(
+            store.acquire_at("a", &quota, start).unwrap(),
+            RateLimitDecision::Limited {
+                retry_after: Duration::from_millis(500)
+            }
+        );
+
+        // Other keys have their own bucket
+        assert!(matches!(
+            store.acquire_at("b", &quota, start).unwrap(),
+            RateLimitDecision::Allowed { .. }
+        ));
+
+        // Half a second refills one token
+        let later = start + Duration::from_millis(500);
+        assert_eq!(
+            store.acquire_at("a", &quota, later).unwrap(),
+            RateLimitDecision::Allowed { remaining: 0 }
+        );
+    }
+
+    #[test]
+    fn test_memory_store_prunes_idle_buckets() {
+        let store = MemoryStore::new();
+        let quota = Quota::per_second(2);
+        let start = Instant::now();
+
+        store.acquire_at("a", &quota, start).unwrap();
+        store
+            .acquire_at("b", &quota, start + Duration::from_millis(500))
+            .unwrap();
+        assert_eq!(store.buckets.lock().unwrap().len(), 2);
+
+        // After a whole period "a" is full again and can be forgotten
+        let later = start + Duration::from_secs(1);
+        store.acquire_at("c", &quota, later).unwrap();
+        let buckets = store.buckets.lock().unwrap();
+        assert!(!buckets.contains_key("a"));
+        assert!(buckets.contains_key("b"));
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_layer() {
+        let layer = RateLimitLayer::new(Quota::per_minute(1));
+        let ctx = Context::new();
+        let req = || Request::new().with_header("X-Forwarded-For", "203.0.113.7, 10.0.0.1");
+
+        let resp = layer.handle(req(), &ctx, &ok).await.unwrap();
+        assert_eq!(resp.status(), 200);
+
+        let resp = layer.handle(req(), &ctx, &ok).await.unwrap();
+        assert_eq!(resp.status(), 429);
+        assert_eq!(resp.header("Retry-After").unwrap(), "60");
+
+        let other = Request::new().with_header("X-Forwarded-For", "198.51.100.2");
+        let resp = layer.handle(other, &ctx, &ok).await.unwrap();
+        assert_eq!(resp.status(), 200);
+    }
+
+    #[tokio::test]
+    async fn test_spoofed_forwarded_for() {
+        let layer = RateLimitLayer::new(Quota::per_minute(1));
+        let ctx = Context::new();
+
+        // Only the hop added by the nearest proxy counts
+        let req = |spoofed: &str| {
+            Request::new().with_header("X-Forwarded-For", format!("{}, 192.0.2.1", spoofed))
+        };
+        let resp = layer.handle(req("203.0.113.7"), &ctx, &ok).await.unwrap();
+        assert_eq!(resp.status(), 200);
+        let resp = layer.handle(req("203.0.113.8"), &ctx, &ok).await.unwrap();
+        assert_eq!(resp.status(), 429);
+
+        // The source IP of an API Gateway event wins over any header
+        let req = |spoofed: &str| {
+            Request::new()
+                .with_header("X-Forwarded-For", spoofed)
+                .with_header("CF-Connecting-IP", spoofed)
+                .with_raw_event(serde_json::json!({
+                    "requestContext": { "http": { "sourceIp": "198.51.100.9" } }
+                }))
+        };
+        let resp = layer.handle(req("203.0.113.9"), &ctx, &ok).await.unwrap();
+        assert_eq!(resp.status(), 200);
+        let resp = layer.handle(req("203.0.113.10"), &ctx, &ok).await.unwrap();
+        assert_eq!(resp.status(), 429);
+    }
+
+    #[tokio::test]
+    async fn test_key_header() {
+        let layer = RateLimitLayer::new(Quota::per_minute(1)).with_key_header("X-Api-Key");
+        let ctx = Context::new();
+
+        let req = Request::new()
+            .with_header("X-Api-Key", "alpha")
+            .with_header("X-Forwarded-For", "203.0.113.7");
+        assert_eq!(layer.handle(req, &ctx, &ok).await.unwrap().status(), 200);
+
+        // Same IP, different key
+        let req = Request::new()
+            .with_header("X-Api-Key", "beta")
+            .with_header("X-Forwarded-For", "203.0.113.7");
+        assert_eq!(layer.handle(req, &ctx, &ok).await.unwrap().status(), 200);
+
+        let req = Request::new().with_header("X-Api-Key", "alpha");
+        assert_eq!(layer.handle(req, &ctx, &ok).await.unwrap().status(), 429);
+    }
+}
@@ -0,0 +1,250 @@
+/*!
+Function registry for serverless.rs.
+
+This module collects every `#[serverless]` function linked into a binary, so one
+binary can host many handlers: `--info` can list all of them and a dispatcher can
+invoke them by name.
+*/
+
+use futures::future::BoxFuture;
+
+use crate::error::{Error, Result};
+use crate::info::{
+    exit_on_error, iam_policy, render_info, report_output, warn_requirements, write_output,
+};
+use crate::{Context, FunctionInfo, InfoSelection, OutputFormat, Request, Response};
+
+/// A function registered by the `#[serverless]` macro
+///
+/// Registrations are collected at link time with the `inventory` crate.
+pub struct FunctionRegistration {
+    /// Name of the function, matching `FunctionInfo::name`
+    pub name: &'static str,
+
+    /// Returns the function's metadata
+    pub info: fn() -> FunctionInfo,
+
+    /// Invokes the function
+    pub invoke: for<'a> fn(Request, &'a Context) -> BoxFuture<'a, Result<Response>>,
+}
+
+inventory::collect!(FunctionRegistration);
+
+/// Returns the registration for a function by name
+pub fn find_function(name: &str) -> Option<&'static FunctionRegistration> {
+    inventory::iter::<FunctionRegistration>
+        .into_iter()
+        .find(|registration| registration.name == name)
+}
+
+/// Returns the metadata of every function in this binary, sorted by name
+pub fn all_functions() -> Vec<FunctionInfo> {
+    let mut functions: Vec<FunctionInfo> = inventory::iter::<FunctionRegistration>
+        .into_iter()
+        .map(|registration| (registration.info)())
+        .collect();
+    functions.sort_by(|a, b| a.name.cmp(&b.name));
+    functions
+}
+
+/// Invoke a function in this binary by name
+///
+/// Returns a function error if no function with that name is registered.
+pub async fn invoke(name: &str, req: Request, ctx: &Context) -> Result<Response> {
+    let registration = find_function(name)
+        .ok_or_else(|| Error::function(format!("Unknown function: {}", name)))?;
+    (registration.invoke)(req, ctx).await
+}
+
+/// Display information for several functions, such as every function in this binary
+///
+/// This is the multi-function counterpart of [`display_info`](crate::display_info).
+/// With `--json`, the functions are printed as a JSON array, as they are by default
+/// when stdout is not a terminal. With `--format=openapi`,
+/// the routes of all functions are combined into one OpenAPI document. With
+/// `--format=sam`, the SAM resources of all functions are printed one after another,
+/// ready to go under a template's `Resources`. With `--format=iam-policy`, the
+/// permissions of all functions are combined into one IAM policy, since they share
+/// an execution role. Text output has one section per function. With
+/// `--output <file>`, the output is written to that file instead.
+///
+/// `--info <function_name>` shows only that function, as
+/// [`display_info`](crate::display_info) would, and `--info --list` prints the
+/// function names one per line.
+///
+/// Requirement warnings are printed on stderr for each function shown. Errors, such
+/// as an unknown function or a failed write, are returned.
+pub fn display_all_info(functions: &[FunctionInfo]) -> Result<()> {
+    let selection = crate::parse_info_selection();
+    let (_, format, output) = crate::parse_info_args();
+    let result = render_selection(functions, &selection, format.resolve(output.as_deref()))
+        .and_then(|content| write_output(&content, output.as_deref()));
+    report_output(result, output.as_deref())
+}
+
+/// Render the functions picked by `selection` in the given, resolved format
+fn render_selection(
+    functions: &[FunctionInfo],
+    selection: &InfoSelection,
+    format: OutputFormat,
+) -> Result<String> {
+    match selection {
+        InfoSelection::List => {
+            let names: Vec<&str> = functions.iter().map(|info| info.name.as_str()).collect();
+            Ok(names.join("\n"))
+        }
+        InfoSelection::Function(name) => {
+            let info = functions
+                .iter()
+                .find(|info| &info.name == name)
+                .ok_or_else(|| Error::function(format!("Unknown function: {}", name)))?;
+            warn_requirements(info, "");
+            render_info(info, format)
+        }
+        InfoSelection::All => {
+            for info in functions {
+                warn_requirements(info, &format!("{}: ", info.name));
+            }
+            render_all_info(functions, format)
+        }
+    }
+}
+
+/// Render information for several functions in the given format
+fn render_all_info(functions: &[FunctionInfo], format: OutputFormat) -> Result<String> {
+    match format {
+        OutputFormat::Json => serde_json::to_string_pretty(functions).map_err(|err| {
+            Error::serialization(format!(
+                "Failed to serialize function information to JSON: {}",
+                err
+            ))
+        }),
+        OutputFormat::OpenApi => {
+            let mut document = serde_json::json!({
+                "openapi": "3.0.3",
+                "info": { "title": "serverless.rs functions", "version": "0.1.0" },
+                "paths": {},
+            });
+            for function in functions {
+                let function_document = function.to_openapi();
+                let paths = function_document["paths"].as_object().into_iter().flatten();
+                for (path, operations) in paths {
+                    let operations = operations.as_object().into_iter().flatten();
+                    for (method, operation) in operations {
+                        document["paths"][path][method] = operation.clone();
+                    }
+                }
+            }
+            serde_json::to_string_pretty(&document).map_err(|err| {
+                Error::serialization(format!("Failed to serialize OpenAPI document: {}", err))
+            })
+        }
+        OutputFormat::Sam => Ok(functions.iter().map(FunctionInfo::to_sam).collect()),
+        OutputFormat::IamPolicy => {
+            let permissions = functions.iter().flat_map(FunctionInfo::all_permissions);
+            serde_json::to_string_pretty(&iam_policy(permissions)).map_err(|err| {
+                Error::serialization(format!("Failed to serialize IAM policy: {}", err))
+            })
+        }
+        // Callers resolve `Auto` first
+        OutputFormat::Text | OutputFormat::Auto => {
+            let sections: Vec<String> = functions
+                .iter()
+                .map(FunctionInfo::format_for_display)
+                .collect();
+            Ok(sections.join("\n"))
+        }
+    }
+}
+
+/// Display information for every function and exit if the --info flag is present
+///
+/// This is the multi-function counterpart of
+/// [`handle_info_request`](crate::handle_info_request), and likewise exits with
+/// status 1 if the information can't be shown.
+pub fn handle_all_info_request() -> bool {
+    if crate::check_info_flag() {
+        exit_on_error(display_all_info(&all_functions()));
+        true
+    } else {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ping(_req: Request, _ctx: &Context) -> BoxFuture<'_, Result<Response>> {
+        Box::pin(async { Ok(Response::text("pong")) })
+    }
+
+    inventory::submit! {
+        FunctionRegistration {
+            name: "registry_test_ping",
+            info: || FunctionInfo::new("registry_test_ping"),
+            invoke: ping,
+        }
+    }
+
+    #[test]
+    fn test_all_functions() {
+        let functions = all_functions();
+        assert!(functions.iter().any(|f| f.name == "registry_test_ping"));
+        assert!(find_function("registry_test_ping").is_some());
+        assert!(find_function("missing").is_none());
+    }
+
+    #[test]
+    fn test_render_all_info() {
+        let functions = [
+            FunctionInfo::new("orders").with_description("Order API"),
+            FunctionInfo::new("reports"),
+        ];
+
+        let json = render_all_info(&functions, OutputFormat::Json).unwrap();
+        let parsed: Vec<FunctionInfo> = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, functions);
+
+        let text = render_all_info(&functions, OutputFormat::Text).unwrap();
+        assert!(text.contains(&functions[0].format_for_display()));
+        assert!(text.contains(&functions[1].format_for_display()));
+
+        let sam = render_all_info(&functions, OutputFormat::Sam).unwrap();
+        assert_eq!(sam, functions[0].to_sam() + &functions[1].to_sam());
+    }
+
+    #[test]
+    fn test_render_selection() {
+        let functions = [FunctionInfo::new("orders"), FunctionInfo::new("reports")];
+
+        let list = render_selection(&functions, &InfoSelection::List, OutputFormat::Json);
+        assert_eq!(list.unwrap(), "orders\nreports");
+
+        let selected = InfoSelection::Function("reports".to_string());
+        let json = render_selection(&functions, &selected, OutputFormat::Json).unwrap();
+        assert_eq!(FunctionInfo::from_json(&json).unwrap(), functions[1]);
+
+        let missing = InfoSelection::Function("billing".to_string());
+        let err = render_selection(&functions, &missing, OutputFormat::Text).unwrap_err();
+        assert!(matches!(err, Error::Function(_)));
+
+        let all = render_selection(&functions, &InfoSelection::All, OutputFormat::Json);
+        assert_eq!(
+            all.unwrap(),
+            render_all_info(&functions, OutputFormat::Json).unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_invoke_by_name() {
+        let ctx = Context::new();
+        let response = invoke("registry_test_ping", Request::new(), &ctx)
+            .await
+            .unwrap();
+        assert_eq!(response.body(), b"pong");
+
+        let result = invoke("missing", Request::new(), &ctx).await;
+        assert!(matches!(result, Err(Error::Function(_))));
+    }
+}
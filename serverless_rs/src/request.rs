@@ -5,13 +5,21 @@ This module provides a unified request abstraction that works across
 different serverless platforms.
 */
 
+use base64::Engine;
 use http::{Method, Uri};
 use serde::Deserialize;
 use serde_json::Value;
+use std::borrow::Cow;
 use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
 use std::str::FromStr;
+use std::time::SystemTime;
 
 use crate::error::{Error, Result};
+#[cfg(feature = "msgpack")]
+use crate::response::MSGPACK_CONTENT_TYPE;
+use crate::util::{parse_http_date, parse_query_string};
+use crate::{ContentType, Extensions, Response};
 
 /// A platform-agnostic request that can be handled by serverless functions
 #[derive(Debug, Clone)]
@@ -34,8 +42,14 @@ pub struct Request {
     /// Request body as raw bytes
     body: Vec<u8>,
 
+    /// Whether the body is Base64 encoded
+    is_base64: bool,
+
     /// Original platform-specific event data
     raw_event: Value,
+
+    /// Typed state attached by middleware for this request
+    extensions: Extensions,
 }
 
 impl Request {
@@ -48,10 +62,39 @@ impl Request {
             query: HashMap::new(),
             path_params: HashMap::new(),
             body: Vec::new(),
+            is_base64: false,
             raw_event: Value::Null,
+            extensions: Extensions::new(),
         }
     }
 
+    /// Creates a request from an [`http::Request`]
+    ///
+    /// The query string is parsed into query parameters as the platform adapters do.
+    /// Header names are lowercase, as the `http` crate stores them, and repeated
+    /// headers are joined with `, `.
+    pub fn from_http(req: http::Request<Vec<u8>>) -> Self {
+        let (parts, body) = req.into_parts();
+        let mut request = Self::new().with_method(parts.method).with_body(body);
+
+        if let Some(query) = parts.uri.query() {
+            request.query = parse_query_string(query);
+        }
+        request.uri = Some(parts.uri);
+
+        for name in parts.headers.keys() {
+            let values: Vec<String> = parts
+                .headers
+                .get_all(name)
+                .iter()
+                .map(|value| String::from_utf8_lossy(value.as_bytes()).into_owned())
+                .collect();
+            request = request.with_header(name.as_str(), values.join(", "));
+        }
+
+        request
+    }
+
     /// Returns the HTTP method for this request, if available
     pub fn method(&self) -> Option<&Method> {
         self.method.as_ref()
@@ -105,15 +148,68 @@ impl Request {
         &self.headers
     }
 
-    /// Sets a header for this request
+    /// Sets a header for this request, replacing any value it already has
+    ///
+    /// Header names are compared ignoring ASCII case, as in [`Request::without_header`].
     pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
-        self.headers.insert(name.into(), value.into());
+        let name = name.into();
+        self.headers
+            .retain(|existing, _| !existing.eq_ignore_ascii_case(&name));
+        self.headers.insert(name, value.into());
         self
     }
 
-    /// Returns a header value by name
+    /// Sets several headers, as [`Request::with_header`] does for each
+    ///
+    /// Headers already on the request are kept unless one of the new headers has the
+    /// same name, ignoring ASCII case.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use serverless_rs::Request;
+    ///
+    /// let req = Request::new()
+    ///     .with_header("Accept", "text/html")
+    ///     .with_headers([("accept", "application/json"), ("X-Request-Id", "abc")]);
+    /// assert_eq!(req.header("accept").unwrap(), "application/json");
+    /// assert_eq!(req.headers().len(), 2);
+    /// ```
+    pub fn with_headers<I, K, V>(mut self, headers: I) -> Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: Into<String>,
+        V: Into<String>,
+    {
+        for (name, value) in headers {
+            self = self.with_header(name, value);
+        }
+        self
+    }
+
+    /// Removes a header, ignoring ASCII case
+    pub fn without_header(mut self, name: &str) -> Self {
+        self.headers
+            .retain(|key, _| !key.eq_ignore_ascii_case(name));
+        self
+    }
+
+    /// Returns the `If-Modified-Since` header as a time
+    ///
+    /// Returns `None` if the header is missing or not a valid HTTP date.
+    pub fn if_modified_since(&self) -> Option<SystemTime> {
+        self.header("If-Modified-Since")
+            .and_then(|value| parse_http_date(value))
+    }
+
+    /// Returns a header value by name, ignoring ASCII case
     pub fn header(&self, name: &str) -> Option<&String> {
-        self.headers.get(name)
+        self.headers.get(name).or_else(|| {
+            self.headers
+                .iter()
+                .find(|(key, _)| key.eq_ignore_ascii_case(name))
+                .map(|(_, value)| value)
+        })
     }
 
     /// Returns the query parameters for this request
@@ -143,11 +239,130 @@ impl Request {
         self
     }
 
+    /// Returns whether the client accepts the given content type
+    ///
+    /// The `Accept` header is parsed with quality values; `*/*` and `type/*` wildcards
+    /// are honored and a `q=0` entry excludes a type. Without an `Accept` header every
+    /// content type is accepted.
+    pub fn accepts(&self, content_type: &str) -> bool {
+        self.accept_quality(content_type) > 0.0
+    }
+
+    /// Returns the option the client prefers, based on the `Accept` header
+    ///
+    /// Options are ranked by the quality of the most specific matching media range;
+    /// ties go to the earlier option. Returns `None` if no option is acceptable.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use serverless_rs::Request;
+    ///
+    /// let req = Request::new().with_header("Accept", "text/csv;q=0.9, application/json");
+    /// let preferred = req.preferred_content_type(&["text/csv", "application/json"]);
+    /// assert_eq!(preferred, Some("application/json"));
+    /// ```
+    pub fn preferred_content_type<'a>(&self, options: &[&'a str]) -> Option<&'a str> {
+        let mut best: Option<(&'a str, f32)> = None;
+        for option in options {
+            let quality = self.accept_quality(option);
+            if quality > 0.0 && best.is_none_or(|(_, best_quality)| quality > best_quality) {
+                best = Some((option, quality));
+            }
+        }
+        best.map(|(option, _)| option)
+    }
+
+    /// Returns the quality the `Accept` header assigns to a content type
+    fn accept_quality(&self, content_type: &str) -> f32 {
+        let Some(accept) = self.header("Accept") else {
+            return 1.0;
+        };
+        let content_type = content_type
+            .split(';')
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_ascii_lowercase();
+        let (kind, subtype) = content_type.split_once('/').unwrap_or((&content_type, ""));
+
+        // The most specific matching range wins: type/subtype, then type/*, then */*
+        let mut best: Option<(u8, f32)> = None;
+        for range in accept.split(',') {
+            let mut params = range.split(';');
+            let media_range = params
+                .next()
+                .unwrap_or_default()
+                .trim()
+                .to_ascii_lowercase();
+            let Some((range_kind, range_subtype)) = media_range.split_once('/') else {
+                continue;
+            };
+            let quality = params
+                .filter_map(|param| param.trim().strip_prefix("q="))
+                .find_map(|q| q.trim().parse::<f32>().ok())
+                .unwrap_or(1.0)
+                .clamp(0.0, 1.0);
+
+            let specificity = match (range_kind, range_subtype) {
+                ("*", "*") => 1,
+                (k, "*") if k == kind => 2,
+                (k, st) if k == kind && st == subtype => 3,
+                _ => continue,
+            };
+            if best.is_none_or(|(best_specificity, _)| specificity > best_specificity) {
+                best = Some((specificity, quality));
+            }
+        }
+
+        best.map_or(0.0, |(_, quality)| quality)
+    }
+
+    /// Returns the parsed `Content-Type` header, if present and valid
+    pub fn content_type(&self) -> Option<ContentType> {
+        self.header("Content-Type")
+            .and_then(|value| ContentType::parse(value))
+    }
+
     /// Returns a path parameter by name
     pub fn path_param(&self, name: &str) -> Option<&String> {
         self.path_params.get(name)
     }
 
+    /// Returns the value of a cookie from the `Cookie` header
+    pub fn cookie(&self, name: &str) -> Option<&str> {
+        self.header("Cookie")?
+            .split(';')
+            .filter_map(|pair| pair.trim().split_once('='))
+            .find(|(key, _)| *key == name)
+            .map(|(_, value)| value)
+    }
+
+    /// Returns the payload of a cookie signed with [`sign`](crate::session::sign)
+    ///
+    /// Returns `None` if the cookie is missing or its HMAC-SHA256 signature doesn't
+    /// match `key`. The signature is compared in constant time. Useful for CSRF tokens
+    /// and other small signed values that don't need a [`SessionLayer`].
+    ///
+    /// [`SessionLayer`]: crate::session::SessionLayer
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use serverless_rs::{session, Request};
+    ///
+    /// let key = b"0123456789abcdef0123456789abcdef";
+    /// let token = session::sign(key, "csrf-4f2a");
+    /// let req = Request::new().with_header("Cookie", format!("csrf={}", token));
+    ///
+    /// assert_eq!(req.verify_signed_cookie("csrf", key).as_deref(), Some("csrf-4f2a"));
+    /// assert_eq!(req.verify_signed_cookie("csrf", b"another key"), None);
+    /// ```
+    #[cfg(feature = "session")]
+    pub fn verify_signed_cookie(&self, name: &str, key: &[u8]) -> Option<String> {
+        crate::session::verify(key, self.cookie(name)?).map(str::to_string)
+    }
+
     /// Returns the raw body bytes for this request
     pub fn body(&self) -> &[u8] {
         &self.body
@@ -159,14 +374,136 @@ impl Request {
         self
     }
 
-    /// Returns the body as a string if it's valid UTF-8
+    /// Returns whether the body is Base64 encoded
+    ///
+    /// Platform adapters set this when the event delivered a Base64 encoded body, as
+    /// API Gateway does for binary media types.
+    pub fn is_base64(&self) -> bool {
+        self.is_base64
+    }
+
+    /// Sets whether the body is Base64 encoded
+    pub fn with_base64(mut self, is_base64: bool) -> Self {
+        self.is_base64 = is_base64;
+        self
+    }
+
+    /// Returns the body as raw bytes, decoding it if it is Base64 encoded
+    ///
+    /// Invalid Base64 is a serialization error. The string and JSON accessors read
+    /// the body through this method.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use serverless_rs::Request;
+    ///
+    /// let req = Request::new().with_body("iVBORw==").with_base64(true);
+    /// assert_eq!(req.decoded_body().unwrap().as_ref(), &[0x89, b'P', b'N', b'G']);
+    ///
+    /// let req = Request::new().with_body("plain text");
+    /// assert_eq!(req.decoded_body().unwrap().as_ref(), b"plain text");
+    /// ```
+    pub fn decoded_body(&self) -> Result<Cow<'_, [u8]>> {
+        if !self.is_base64 {
+            return Ok(Cow::Borrowed(&self.body));
+        }
+        base64::engine::general_purpose::STANDARD
+            .decode(&self.body)
+            .map(Cow::Owned)
+            .map_err(Error::serialization)
+    }
+
+    /// Returns the body as a string
+    ///
+    /// The body is decoded with the `charset` declared in the `Content-Type` header,
+    /// defaulting to UTF-8. An unsupported charset is a serialization error.
     pub fn body_string(&self) -> Result<String> {
-        String::from_utf8(self.body.clone()).map_err(Error::serialization)
+        let body = self.decoded_body()?;
+        match self.content_type() {
+            Some(content_type) => content_type.decode(&body),
+            None => String::from_utf8(body.into_owned()).map_err(Error::serialization),
+        }
     }
 
     /// Parse the body as JSON into the given type
+    ///
+    /// The error message names the path of the offending field (e.g. `items[0].id`)
+    /// along with the line and column.
     pub fn body_json<T: for<'de> Deserialize<'de>>(&self) -> Result<T> {
-        serde_json::from_slice(&self.body).map_err(Error::serialization)
+        parse_json_body(&self.decoded_body()?).map_err(|err| match json_error_path(&err) {
+            Some(path) => Error::serialization(format!("{}: {}", path, err.inner())),
+            None => Error::serialization(err.inner()),
+        })
+    }
+
+    /// Parse the body as MessagePack into the given type
+    #[cfg(feature = "msgpack")]
+    pub fn body_msgpack<T: for<'de> Deserialize<'de>>(&self) -> Result<T> {
+        rmp_serde::from_slice(&self.decoded_body()?).map_err(Error::serialization)
+    }
+
+    /// Read a GraphQL request
+    ///
+    /// `GET` requests take `query`, `variables`, `operationName` and `extensions`
+    /// from the query parameters, with `variables` and `extensions` JSON-encoded.
+    /// `POST` requests carry them as a JSON body, or just the document with
+    /// `Content-Type: application/graphql`. Malformed requests are an error with
+    /// status 400, and other methods one with status 405.
+    #[cfg(feature = "graphql")]
+    pub fn graphql_request(&self) -> Result<crate::graphql::GraphQLRequest> {
+        crate::graphql::graphql_request(self)
+    }
+
+    /// Parse the body into the given type, in the format named by `Content-Type`
+    ///
+    /// With the `msgpack` feature, `application/msgpack` bodies are parsed with
+    /// [`Request::body_msgpack`]. Any other body is parsed as JSON.
+    pub fn parse_body<T: for<'de> Deserialize<'de>>(&self) -> Result<T> {
+        #[cfg(feature = "msgpack")]
+        if self
+            .content_type()
+            .is_some_and(|content_type| content_type.media_type() == MSGPACK_CONTENT_TYPE)
+        {
+            return self.body_msgpack();
+        }
+        self.body_json()
+    }
+
+    /// Parse the body as JSON, or return a ready-made 400 response
+    ///
+    /// The response body is a JSON object describing the validation error:
+    /// `{"error": "invalid_json", "message", "path", "line", "column"}`, where `path`
+    /// is `null` if the error is not tied to a field. A body that is not valid Base64
+    /// gets `"error": "invalid_base64"` and `null` position fields.
+    pub fn body_json_or_400<T: for<'de> Deserialize<'de>>(
+        &self,
+    ) -> std::result::Result<T, Response> {
+        let body = match self.decoded_body() {
+            Ok(body) => parse_json_body(&body).map_err(|err| {
+                let inner = err.inner();
+                serde_json::json!({
+                    "error": "invalid_json",
+                    "message": inner.to_string(),
+                    "path": json_error_path(&err),
+                    "line": inner.line(),
+                    "column": inner.column(),
+                })
+            }),
+            Err(err) => Err(serde_json::json!({
+                "error": "invalid_base64",
+                "message": err.to_string(),
+                "path": null,
+                "line": null,
+                "column": null,
+            })),
+        };
+        body.map_err(|body| {
+            Response::new()
+                .with_status(400)
+                .with_header("Content-Type", "application/json")
+                .with_body(body.to_string())
+        })
     }
 
     /// Returns the raw event data for this request
@@ -179,6 +516,101 @@ impl Request {
         self.raw_event = event;
         self
     }
+
+    /// Returns the typed extensions attached to this request
+    ///
+    /// Request extensions hold state for a single invocation, such as the caller
+    /// resolved by an authentication middleware, while [`Context`](crate::Context)
+    /// extensions hold shared state. A cloned request shares the values stored so
+    /// far, but values inserted afterwards are only seen by the copy they were
+    /// inserted into. Extensions aren't part of the platform event, so they never
+    /// reach the response or the logs.
+    pub fn extensions(&self) -> &Extensions {
+        &self.extensions
+    }
+
+    /// Returns a mutable reference to the typed extensions
+    pub fn extensions_mut(&mut self) -> &mut Extensions {
+        &mut self.extensions
+    }
+
+    /// Returns the extension of the given type, if present
+    pub fn extension<T: Send + Sync + 'static>(&self) -> Option<&T> {
+        self.extensions.get::<T>()
+    }
+
+    /// Attaches a typed extension, replacing any existing value of the same type
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use serverless_rs::Request;
+    ///
+    /// struct TenantId(String);
+    ///
+    /// let req = Request::new().with_extension(TenantId("acme".to_string()));
+    /// assert_eq!(req.extension::<TenantId>().unwrap().0, "acme");
+    /// ```
+    pub fn with_extension<T: Send + Sync + 'static>(mut self, value: T) -> Self {
+        self.extensions.insert(value);
+        self
+    }
+
+    /// Returns the client IP address
+    ///
+    /// Checked in order: the first hop of `X-Forwarded-For`, `CF-Connecting-IP`,
+    /// `True-Client-IP`, then the source IP in an API Gateway event
+    /// (`requestContext.identity.sourceIp` or `requestContext.http.sourceIp`).
+    /// These headers are set by the platform's proxy; behind other proxies they can
+    /// be supplied by the client, so don't rely on them for authentication.
+    pub fn remote_ip(&self) -> Option<IpAddr> {
+        let forwarded = self
+            .header("X-Forwarded-For")
+            .and_then(|value| value.split(',').next());
+        let headers = [
+            forwarded,
+            self.header("CF-Connecting-IP").map(String::as_str),
+            self.header("True-Client-IP").map(String::as_str),
+        ];
+        let event = [
+            self.raw_event.pointer("/requestContext/identity/sourceIp"),
+            self.raw_event.pointer("/requestContext/http/sourceIp"),
+        ];
+        headers
+            .into_iter()
+            .chain(event.into_iter().map(|value| value.and_then(Value::as_str)))
+            .flatten()
+            .find_map(parse_ip)
+    }
+}
+
+/// Parse an IP address, optionally with a port as in `203.0.113.7:443` or `[::1]:443`
+pub(crate) fn parse_ip(value: &str) -> Option<IpAddr> {
+    let value = value.trim();
+    value
+        .parse::<IpAddr>()
+        .or_else(|_| value.parse::<SocketAddr>().map(|addr| addr.ip()))
+        .ok()
+}
+
+/// Deserialize a JSON body, tracking the path to any failing field
+fn parse_json_body<T: for<'de> Deserialize<'de>>(
+    body: &[u8],
+) -> std::result::Result<T, serde_path_to_error::Error<serde_json::Error>> {
+    let deserializer = &mut serde_json::Deserializer::from_slice(body);
+    serde_path_to_error::deserialize(deserializer)
+}
+
+/// Returns the path of the field a JSON error refers to
+///
+/// Syntax errors and errors at the document root are not tied to a field.
+fn json_error_path(err: &serde_path_to_error::Error<serde_json::Error>) -> Option<String> {
+    let path = err.path().to_string();
+    if err.inner().is_syntax() || err.inner().is_eof() || path == "." {
+        None
+    } else {
+        Some(path)
+    }
 }
 
 impl Default for Request {
@@ -192,6 +624,116 @@ mod tests {
     use super::*;
     use http::Method;
 
+    #[test]
+    fn test_if_modified_since() {
+        let req = Request::new().with_header("if-modified-since", "Sun, 06 Nov 1994 08:49:37 GMT");
+        let since = req.if_modified_since().unwrap();
+        assert_eq!(crate::http_date(since), "Sun, 06 Nov 1994 08:49:37 GMT");
+
+        let req = Request::new().with_header("If-Modified-Since", "yesterday");
+        assert!(req.if_modified_since().is_none());
+        assert!(Request::new().if_modified_since().is_none());
+    }
+
+    #[test]
+    fn test_extensions() {
+        struct UserId(u64);
+
+        let mut req = Request::new().with_extension(UserId(7));
+        assert_eq!(req.extension::<UserId>().unwrap().0, 7);
+        assert!(req.extension::<String>().is_none());
+
+        let copy = req.clone();
+        req.extensions_mut().insert("admin".to_string());
+        assert_eq!(copy.extension::<UserId>().unwrap().0, 7);
+        assert!(copy.extension::<String>().is_none());
+        assert_eq!(req.extension::<String>().unwrap(), "admin");
+    }
+
+    #[test]
+    fn test_content_type() {
+        let req = Request::new()
+            .with_header("content-type", "text/plain; charset=ISO-8859-1")
+            .with_body(vec![b'c', b'a', b'f', 0xE9]);
+        let content_type = req.content_type().unwrap();
+        assert_eq!(content_type.media_type(), "text/plain");
+        assert_eq!(content_type.charset(), Some("ISO-8859-1"));
+        assert_eq!(req.body_string().unwrap(), "café");
+
+        let req = Request::new()
+            .with_header("Content-Type", "text/plain; charset=koi8-r")
+            .with_body("hello");
+        assert!(matches!(req.body_string(), Err(Error::Serialization(_))));
+
+        assert!(Request::new().content_type().is_none());
+    }
+
+    #[test]
+    fn test_cookie() {
+        let req = Request::new().with_header("Cookie", "theme=dark; csrf=abc.def;lang=en");
+        assert_eq!(req.cookie("csrf"), Some("abc.def"));
+        assert_eq!(req.cookie("lang"), Some("en"));
+        assert_eq!(req.cookie("missing"), None);
+        assert_eq!(Request::new().cookie("theme"), None);
+    }
+
+    #[test]
+    fn test_remote_ip() {
+        let ip = |value: &str| Some(value.parse::<IpAddr>().unwrap());
+
+        let req = Request::new()
+            .with_header("X-Forwarded-For", "203.0.113.7, 10.0.0.1")
+            .with_header("CF-Connecting-IP", "198.51.100.2");
+        assert_eq!(req.remote_ip(), ip("203.0.113.7"));
+
+        let req = Request::new()
+            .with_header("X-Forwarded-For", "unknown")
+            .with_header("CF-Connecting-IP", "2001:db8::1");
+        assert_eq!(req.remote_ip(), ip("2001:db8::1"));
+
+        let req = Request::new().with_header("True-Client-IP", "[2001:db8::2]:443");
+        assert_eq!(req.remote_ip(), ip("2001:db8::2"));
+
+        let req = Request::new().with_raw_event(serde_json::json!({
+            "requestContext": { "identity": { "sourceIp": "192.0.2.10" } }
+        }));
+        assert_eq!(req.remote_ip(), ip("192.0.2.10"));
+
+        let req = Request::new().with_raw_event(serde_json::json!({
+            "requestContext": { "http": { "sourceIp": "192.0.2.11" } }
+        }));
+        assert_eq!(req.remote_ip(), ip("192.0.2.11"));
+
+        assert_eq!(Request::new().remote_ip(), None);
+    }
+
+    #[test]
+    fn test_from_http() {
+        let http_req = http::Request::builder()
+            .method("POST")
+            .uri("https://example.com/search?q=a%20b&page=2")
+            .header("Content-Type", "application/json")
+            .header("Accept", "text/html")
+            .header("Accept", "application/json")
+            .body(b"{}".to_vec())
+            .unwrap();
+        let req = Request::from_http(http_req);
+
+        assert_eq!(req.method(), Some(&Method::POST));
+        assert_eq!(req.path().as_deref(), Some("/search"));
+        assert_eq!(req.query_param("q"), Some(&"a b".to_string()));
+        assert_eq!(req.query_param("page"), Some(&"2".to_string()));
+        assert_eq!(
+            req.headers().get("content-type"),
+            Some(&"application/json".to_string())
+        );
+        assert_eq!(
+            req.header("Accept"),
+            Some(&"text/html, application/json".to_string())
+        );
+        assert_eq!(req.body(), b"{}");
+    }
+
     #[test]
     fn test_request_builder() {
         let req = Request::new()
@@ -211,6 +753,11 @@ mod tests {
         assert_eq!(req.query_param("page"), Some(&"1".to_string()));
         assert_eq!(req.path_param("id"), Some(&"123".to_string()));
         assert_eq!(req.body_string().unwrap(), r#"{"name":"test"}"#);
+
+        // Setting a header replaces it whatever case it was set with
+        let req = req.with_headers([("content-type", "text/plain"), ("X-Trace", "1")]);
+        assert_eq!(req.header("Content-Type").unwrap(), "text/plain");
+        assert_eq!(req.headers().len(), 2);
     }
 
     #[test]
@@ -230,4 +777,100 @@ mod tests {
             }
         );
     }
+
+    #[test]
+    fn test_base64_body() {
+        // {"name":"test"}
+        let req = Request::new()
+            .with_body("eyJuYW1lIjoidGVzdCJ9")
+            .with_base64(true);
+        assert!(req.is_base64());
+        assert_eq!(req.decoded_body().unwrap().as_ref(), br#"{"name":"test"}"#);
+        assert_eq!(req.body_string().unwrap(), r#"{"name":"test"}"#);
+        assert_eq!(req.body_json::<Value>().unwrap()["name"], "test");
+
+        let req = Request::new().with_body("not base64!").with_base64(true);
+        assert!(matches!(req.decoded_body(), Err(Error::Serialization(_))));
+        assert!(req.body_json::<Value>().is_err());
+        let resp = req.body_json_or_400::<Value>().unwrap_err();
+        assert_eq!(resp.status(), 400);
+        let body: Value = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(body["error"], "invalid_base64");
+        assert!(body["line"].is_null());
+    }
+
+    #[test]
+    fn test_body_json_errors() {
+        #[derive(Debug, Deserialize)]
+        #[allow(dead_code)]
+        struct Item {
+            id: u32,
+        }
+
+        #[derive(Debug, Deserialize)]
+        #[allow(dead_code)]
+        struct Order {
+            items: Vec<Item>,
+        }
+
+        let req = Request::new().with_body(r#"{"items":[{"id":1},{"id":"two"}]}"#);
+        let err = req.body_json::<Order>().unwrap_err().to_string();
+        assert!(err.starts_with("Serialization error: items[1].id: invalid type"));
+        assert!(err.contains("line 1 column"));
+
+        let resp = req.body_json_or_400::<Order>().unwrap_err();
+        assert_eq!(resp.status(), 400);
+        let body: Value = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(body["error"], "invalid_json");
+        assert_eq!(body["path"], "items[1].id");
+        assert_eq!(body["line"], 1);
+        assert_eq!(body["column"], 30);
+
+        let req = Request::new().with_body("{");
+        let resp = req.body_json_or_400::<Order>().unwrap_err();
+        let body: Value = serde_json::from_slice(resp.body()).unwrap();
+        assert!(body["path"].is_null());
+
+        let req = Request::new().with_body(r#"{"items":[{"id":7}]}"#);
+        assert_eq!(req.body_json_or_400::<Order>().unwrap().items[0].id, 7);
+    }
+
+    #[test]
+    fn test_content_negotiation() {
+        let req = Request::new().with_header(
+            "accept",
+            "text/csv;q=0.5, application/json, text/*;q=0.2, image/png;q=0",
+        );
+        assert!(req.accepts("application/json"));
+        assert!(req.accepts("text/csv"));
+        assert!(req.accepts("text/html"));
+        assert!(!req.accepts("image/png"));
+        assert!(!req.accepts("application/xml"));
+
+        assert_eq!(
+            req.preferred_content_type(&["text/csv", "application/json"]),
+            Some("application/json")
+        );
+        assert_eq!(
+            req.preferred_content_type(&["text/html", "text/csv"]),
+            Some("text/csv")
+        );
+        assert_eq!(req.preferred_content_type(&["image/png"]), None);
+
+        // Without an Accept header, the first option wins
+        let req = Request::new();
+        assert!(req.accepts("application/json"));
+        assert_eq!(
+            req.preferred_content_type(&["text/csv", "application/json"]),
+            Some("text/csv")
+        );
+
+        // Wildcards match anything not listed more specifically
+        let req = Request::new().with_header("Accept", "*/*;q=0.1, application/json");
+        assert_eq!(
+            req.preferred_content_type(&["text/csv", "application/json"]),
+            Some("application/json")
+        );
+        assert!(req.accepts("text/csv"));
+    }
 }
@@ -6,7 +6,24 @@ resource requirements for serverless functions.
 */
 
 use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
 use std::collections::HashMap;
+use std::fmt;
+use std::ops::RangeInclusive;
+use std::path::Path;
+
+use crate::error::{Error, Result};
+use crate::util::{parse_duration, parse_size_mb};
+use crate::Platform;
+
+/// Resource name for the size of the function's writable `/tmp` storage
+pub(crate) const EPHEMERAL_STORAGE: &str = "ephemeral_storage";
+
+/// Resource name for the VPC the function must be attached to
+pub(crate) const VPC: &str = "vpc";
+
+/// Ephemeral storage sizes accepted by AWS Lambda, in MB
+const EPHEMERAL_STORAGE_RANGE: RangeInclusive<u64> = 512..=10240;
 
 /// Resource specification for serverless functions
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -32,6 +49,16 @@ impl Resource {
         }
     }
 
+    /// Create an ephemeral storage resource, such as `"2GB"`
+    pub fn ephemeral_storage(size: impl Into<String>) -> Self {
+        Self::new(EPHEMERAL_STORAGE, size)
+    }
+
+    /// Create a VPC resource from a placement hint
+    pub fn vpc(hint: &VpcHint) -> Self {
+        Self::new(VPC, hint.to_string())
+    }
+
     /// Add a description to the resource
     pub fn with_description(mut self, description: impl Into<String>) -> Self {
         self.description = Some(description.into());
@@ -39,8 +66,98 @@ impl Resource {
     }
 }
 
+/// Subnets and security groups a function should be attached to
+///
+/// Values are usually placeholders, such as `${var.private_subnet_ids}`, filled in by
+/// the deployment tooling. As a resource value it is written as
+/// `subnets=a,b;security_groups=c`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VpcHint {
+    /// Subnet IDs or placeholders
+    pub subnets: Vec<String>,
+
+    /// Security group IDs or placeholders
+    pub security_groups: Vec<String>,
+}
+
+impl VpcHint {
+    /// Create an empty VPC hint
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a subnet
+    pub fn subnet(mut self, subnet: impl Into<String>) -> Self {
+        self.subnets.push(subnet.into());
+        self
+    }
+
+    /// Add a security group
+    pub fn security_group(mut self, security_group: impl Into<String>) -> Self {
+        self.security_groups.push(security_group.into());
+        self
+    }
+
+    /// Parse a hint written as `subnets=a,b;security_groups=c`
+    pub fn parse(value: &str) -> Result<Self> {
+        let mut hint = Self::new();
+        for part in value.split(';').filter(|part| !part.trim().is_empty()) {
+            let (key, list) = part
+                .split_once('=')
+                .ok_or_else(|| Error::requirements(format!("Invalid VPC hint: {}", value)))?;
+            let items = list
+                .split(',')
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .map(str::to_string);
+            match key.trim() {
+                "subnets" => hint.subnets.extend(items),
+                "security_groups" => hint.security_groups.extend(items),
+                other => {
+                    return Err(Error::requirements(format!(
+                        "Unknown VPC hint key: {}",
+                        other
+                    )))
+                }
+            }
+        }
+        Ok(hint)
+    }
+}
+
+impl fmt::Display for VpcHint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "subnets={};security_groups={}",
+            self.subnets.join(","),
+            self.security_groups.join(",")
+        )
+    }
+}
+
+/// A coarse permission hint, such as an IAM action on a resource pattern
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Permission {
+    /// Action the function performs (e.g., "s3:GetObject")
+    pub action: String,
+
+    /// Resource or resource pattern the action applies to (e.g., "arn:aws:s3:::uploads/*")
+    pub resource: String,
+}
+
+impl Permission {
+    /// Create a new permission hint
+    pub fn new(action: impl Into<String>, resource: impl Into<String>) -> Self {
+        Self {
+            action: action.into(),
+            resource: resource.into(),
+        }
+    }
+}
+
 /// Requirements specification for serverless functions
-#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct Requirements {
     /// Recommended resources for optimal function performance
     #[serde(default)]
@@ -52,11 +169,15 @@ pub struct Requirements {
 
     /// Supported platforms for this function
     #[serde(default)]
-    pub platforms: Vec<String>,
+    pub platforms: Vec<Platform>,
 
     /// Environment variables used by this function
     #[serde(default)]
     pub environment: Vec<String>,
+
+    /// Permissions the function needs, for generating access policies
+    #[serde(default)]
+    pub permissions: Vec<Permission>,
 }
 
 impl Requirements {
@@ -65,6 +186,48 @@ impl Requirements {
         Self::default()
     }
 
+    /// Parse a requirements profile from JSON
+    pub fn from_json(json: &str) -> Result<Self> {
+        serde_json::from_str(json).map_err(Error::serialization)
+    }
+
+    /// Parse a requirements profile from TOML
+    pub fn from_toml(toml: &str) -> Result<Self> {
+        toml::from_str(toml).map_err(Error::serialization)
+    }
+
+    /// Parse a requirements profile, picking the format from the path's extension
+    ///
+    /// Files ending in `.toml` are parsed as TOML and anything else as JSON. This is
+    /// what `#[requirements(profile = "...")]` uses for profiles embedded at build time.
+    pub fn from_profile(path: impl AsRef<Path>, contents: &str) -> Result<Self> {
+        let is_toml = path
+            .as_ref()
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("toml"));
+        if is_toml {
+            Self::from_toml(contents)
+        } else {
+            Self::from_json(contents)
+        }
+    }
+
+    /// Load a requirements profile from a JSON or TOML file
+    ///
+    /// Shared defaults can be kept in one profile and merged with each function's own
+    /// requirements via [`Requirements::merge`].
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path).map_err(|err| {
+            Error::requirements(format!(
+                "Failed to read requirements profile {}: {}",
+                path.display(),
+                err
+            ))
+        })?;
+        Self::from_profile(path, &contents)
+    }
+
     /// Add a recommended resource
     pub fn recommend(mut self, resource: Resource) -> Self {
         self.recommended.insert(resource.name.clone(), resource);
@@ -78,7 +241,26 @@ impl Requirements {
     }
 
     /// Add a supported platform
-    pub fn platform(mut self, platform: impl Into<String>) -> Self {
+    ///
+    /// Names are not checked: they are converted with [`Platform::from`], so a name
+    /// serverless.rs doesn't know, including a misspelled one, becomes a
+    /// [`Platform::Custom`] platform that [`Requirements::lint`] warns about. Parse
+    /// the name with [`str::parse`] first to reject unknown platforms.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use serverless_rs::{Platform, Requirements};
+    ///
+    /// let requirements = Requirements::new().platform("cloudflair");
+    /// assert!(requirements.supports_platform(Platform::Custom("cloudflair".into())));
+    ///
+    /// assert!("cloudflair".parse::<Platform>().is_err());
+    /// let requirements = Requirements::new().platform("cloudflare".parse::<Platform>()?);
+    /// assert!(requirements.supports_platform(Platform::Cloudflare));
+    /// # Ok::<(), serverless_rs::Error>(())
+    /// ```
+    pub fn platform(mut self, platform: impl Into<Platform>) -> Self {
         self.platforms.push(platform.into());
         self
     }
@@ -89,6 +271,47 @@ impl Requirements {
         self
     }
 
+    /// Add a permission hint for an action on a resource
+    pub fn permission(mut self, action: impl Into<String>, resource: impl Into<String>) -> Self {
+        let permission = Permission::new(action, resource);
+        if !self.permissions.contains(&permission) {
+            self.permissions.push(permission);
+        }
+        self
+    }
+
+    /// Merge another requirements specification into this one
+    ///
+    /// Resources from `other` override resources with the same name, platforms are
+    /// unioned, and environment variables and permissions are deduplicated. Order is
+    /// preserved, with entries from `self` first.
+    pub fn merge(mut self, other: Requirements) -> Requirements {
+        self.recommended.extend(other.recommended);
+        self.required.extend(other.required);
+
+        for platform in other.platforms {
+            if !self.platforms.contains(&platform) {
+                self.platforms.push(platform);
+            }
+        }
+
+        let mut environment = Vec::new();
+        for name in self.environment.into_iter().chain(other.environment) {
+            if !environment.contains(&name) {
+                environment.push(name);
+            }
+        }
+        self.environment = environment;
+
+        for permission in other.permissions {
+            if !self.permissions.contains(&permission) {
+                self.permissions.push(permission);
+            }
+        }
+
+        self
+    }
+
     /// Get a recommended resource by name
     pub fn get_recommended(&self, name: &str) -> Option<&Resource> {
         self.recommended.get(name)
@@ -100,8 +323,134 @@ impl Requirements {
     }
 
     /// Check if a platform is supported
-    pub fn supports_platform(&self, platform: &str) -> bool {
-        self.platforms.contains(&platform.to_string())
+    ///
+    /// Like [`Requirements::platform`], names are converted without being checked.
+    pub fn supports_platform(&self, platform: impl Into<Platform>) -> bool {
+        self.platforms.contains(&platform.into())
+    }
+
+    /// Get a resource by name, preferring the required one over the recommended one
+    pub(crate) fn resource(&self, name: &str) -> Option<&Resource> {
+        self.get_required(name)
+            .or_else(|| self.get_recommended(name))
+    }
+
+    /// Returns the declared ephemeral storage size in MB, if valid
+    pub fn ephemeral_storage_mb(&self) -> Option<u64> {
+        parse_size_mb(&self.resource(EPHEMERAL_STORAGE)?.value)
+    }
+
+    /// Returns the declared VPC placement, if valid
+    pub fn vpc(&self) -> Option<VpcHint> {
+        VpcHint::parse(&self.resource(VPC)?.value).ok()
+    }
+
+    /// Returns warnings for resources whose required and recommended values conflict
+    ///
+    /// A resource that is both required and recommended with different values is
+    /// reported, since one of the two is usually a mistake. The warning says so
+    /// explicitly when the recommended value is below the required floor, comparing
+    /// `memory` and `ephemeral_storage` as sizes, `timeout` as a duration, and other
+    /// resources as plain numbers. Warnings are sorted by resource name, followed by
+    /// one for each [`Platform::Custom`] platform, which may be a misspelled name.
+    ///
+    /// These are warnings rather than errors; `#[requirements(strict, ...)]` turns
+    /// conflicting values in the attribute into a compile error.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use serverless_rs::{Requirements, Resource};
+    ///
+    /// let requirements = Requirements::new()
+    ///     .require(Resource::new("memory", "512MB"))
+    ///     .recommend(Resource::new("memory", "256MB"));
+    /// assert_eq!(
+    ///     requirements.lint(),
+    ///     ["recommended memory 256MB is below the required 512MB"]
+    /// );
+    /// ```
+    pub fn lint(&self) -> Vec<String> {
+        let mut names: Vec<&String> = self.required.keys().collect();
+        names.sort();
+
+        let mut warnings = Vec::new();
+        for name in names {
+            let (Some(required), Some(recommended)) =
+                (self.get_required(name), self.get_recommended(name))
+            else {
+                continue;
+            };
+            if required.value.trim() == recommended.value.trim() {
+                continue;
+            }
+            let warning = match compare_values(name, &recommended.value, &required.value) {
+                Some(Ordering::Equal) => continue,
+                Some(Ordering::Less) => format!(
+                    "recommended {} {} is below the required {}",
+                    name, recommended.value, required.value
+                ),
+                _ => format!(
+                    "{} is required as {} but recommended as {}",
+                    name, required.value, recommended.value
+                ),
+            };
+            warnings.push(warning);
+        }
+        for platform in self
+            .platforms
+            .iter()
+            .filter(|platform| !platform.is_known())
+        {
+            warnings.push(format!(
+                "platform {} is not one serverless.rs has an adapter for",
+                platform
+            ));
+        }
+        warnings
+    }
+
+    /// Check the values of recognized resources
+    ///
+    /// `ephemeral_storage` must be a size between 512MB and 10GB, and `vpc` must name
+    /// at least one subnet and one security group. Other resources aren't checked.
+    pub fn validate(&self) -> Result<()> {
+        for resource in self.required.values().chain(self.recommended.values()) {
+            match resource.name.as_str() {
+                EPHEMERAL_STORAGE => match parse_size_mb(&resource.value) {
+                    Some(size) if EPHEMERAL_STORAGE_RANGE.contains(&size) => {}
+                    _ => {
+                        return Err(Error::requirements(format!(
+                            "ephemeral_storage must be between 512MB and 10GB, got {}",
+                            resource.value
+                        )))
+                    }
+                },
+                VPC => {
+                    let hint = VpcHint::parse(&resource.value)?;
+                    if hint.subnets.is_empty() || hint.security_groups.is_empty() {
+                        return Err(Error::requirements(
+                            "vpc must list at least one subnet and one security group",
+                        ));
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Compare two values of the named resource, if both can be parsed
+fn compare_values(name: &str, a: &str, b: &str) -> Option<Ordering> {
+    match name {
+        "memory" | EPHEMERAL_STORAGE => Some(parse_size_mb(a)?.cmp(&parse_size_mb(b)?)),
+        "timeout" => Some(parse_duration(a)?.cmp(&parse_duration(b)?)),
+        _ => a
+            .trim()
+            .parse::<f64>()
+            .ok()?
+            .partial_cmp(&b.trim().parse::<f64>().ok()?),
     }
 }
 
@@ -109,6 +458,71 @@ impl Requirements {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_storage_and_vpc() {
+        let hint = VpcHint::new()
+            .subnet("${var.subnet_a}")
+            .subnet("subnet-0b1")
+            .security_group("sg-123");
+        let requirements = Requirements::new()
+            .recommend(Resource::ephemeral_storage("512MB"))
+            .require(Resource::ephemeral_storage("2GB"))
+            .require(Resource::vpc(&hint));
+
+        assert!(requirements.validate().is_ok());
+        assert_eq!(requirements.ephemeral_storage_mb(), Some(2048));
+        assert_eq!(requirements.vpc(), Some(hint.clone()));
+        assert_eq!(
+            requirements.get_required(VPC).unwrap().value,
+            "subnets=${var.subnet_a},subnet-0b1;security_groups=sg-123"
+        );
+        assert_eq!(
+            VpcHint::parse("subnets=${var.subnet_a}, subnet-0b1; security_groups=sg-123").unwrap(),
+            hint
+        );
+
+        let too_small = Requirements::new().recommend(Resource::ephemeral_storage("128MB"));
+        assert!(matches!(too_small.validate(), Err(Error::Requirements(_))));
+
+        let no_groups = Requirements::new().require(Resource::new(VPC, "subnets=subnet-1"));
+        assert!(no_groups.validate().is_err());
+        assert!(VpcHint::parse("zones=a").is_err());
+    }
+
+    #[test]
+    fn test_lint() {
+        let requirements = Requirements::new()
+            .require(Resource::new("memory", "1GB"))
+            .recommend(Resource::new("memory", "512MB"))
+            .require(Resource::new("timeout", "30s"))
+            .recommend(Resource::new("timeout", "1m"))
+            .require(Resource::new("concurrency", "10"))
+            .recommend(Resource::new("concurrency", "10.0"))
+            .require(Resource::new("cpu", "1x"))
+            .recommend(Resource::new("cpu", "2x"))
+            .recommend(Resource::new("ephemeral_storage", "2GB"));
+
+        assert_eq!(
+            requirements.lint(),
+            [
+                "cpu is required as 1x but recommended as 2x",
+                "recommended memory 512MB is below the required 1GB",
+                "timeout is required as 30s but recommended as 1m",
+            ]
+        );
+        assert!(Requirements::new()
+            .require(Resource::new("memory", "128MB"))
+            .platform("aws")
+            .lint()
+            .is_empty());
+
+        let misspelled = Requirements::new().platform("cloudflair");
+        assert_eq!(
+            misspelled.lint(),
+            ["platform cloudflair is not one serverless.rs has an adapter for"]
+        );
+    }
+
     #[test]
     fn test_resource_creation() {
         let resource =
@@ -145,6 +559,112 @@ mod tests {
         assert!(!requirements.supports_platform("azure"));
     }
 
+    #[test]
+    fn test_merge() {
+        let base = Requirements::new()
+            .recommend(Resource::new("memory", "128MB"))
+            .recommend(Resource::new("timeout", "30s"))
+            .require(Resource::new("cpu", "1x"))
+            .platform("aws")
+            .env_var("DATABASE_URL");
+        let handler = Requirements::new()
+            .recommend(Resource::new("memory", "512MB"))
+            .require(Resource::new("cpu", "2x").with_description("Image processing"))
+            .platform("cloudflare")
+            .platform("aws")
+            .env_var("API_KEY")
+            .env_var("DATABASE_URL");
+
+        let merged = base.merge(handler);
+
+        // The other specification overrides by name
+        assert_eq!(merged.get_recommended("memory").unwrap().value, "512MB");
+        assert_eq!(merged.get_recommended("timeout").unwrap().value, "30s");
+        assert_eq!(merged.get_required("cpu").unwrap().value, "2x");
+        assert_eq!(
+            merged.get_required("cpu").unwrap().description,
+            Some("Image processing".to_string())
+        );
+
+        assert_eq!(merged.platforms, vec![Platform::Aws, Platform::Cloudflare]);
+        assert_eq!(merged.environment, vec!["DATABASE_URL", "API_KEY"]);
+    }
+
+    #[test]
+    fn test_permissions() {
+        let requirements = Requirements::new()
+            .permission("s3:GetObject", "arn:aws:s3:::uploads/*")
+            .permission("s3:GetObject", "arn:aws:s3:::uploads/*")
+            .permission("dynamodb:PutItem", "arn:aws:dynamodb:*:*:table/orders");
+        assert_eq!(requirements.permissions.len(), 2);
+
+        let merged = requirements.merge(
+            Requirements::new()
+                .permission("dynamodb:PutItem", "arn:aws:dynamodb:*:*:table/orders")
+                .permission("sqs:SendMessage", "*"),
+        );
+        assert_eq!(merged.permissions.len(), 3);
+        assert_eq!(
+            merged.permissions[2],
+            Permission::new("sqs:SendMessage", "*")
+        );
+
+        let json = serde_json::to_value(&merged).unwrap();
+        assert_eq!(json["permissions"][0]["action"], "s3:GetObject");
+        assert_eq!(json["permissions"][0]["resource"], "arn:aws:s3:::uploads/*");
+
+        // Profiles written before permissions existed still deserialize
+        let old: Requirements = serde_json::from_str(r#"{"platforms":["aws"]}"#).unwrap();
+        assert!(old.permissions.is_empty());
+    }
+
+    #[test]
+    fn test_profiles() {
+        let json = Requirements::from_json(
+            r#"{
+                "recommended": { "memory": { "name": "memory", "value": "256MB" } },
+                "environment": ["DATABASE_URL"]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(json.get_recommended("memory").unwrap().value, "256MB");
+        assert_eq!(json.environment, vec!["DATABASE_URL"]);
+
+        let toml = Requirements::from_profile(
+            "infra/base.toml",
+            r#"
+                platforms = ["aws"]
+
+                [recommended.timeout]
+                name = "timeout"
+                value = "30s"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(toml.get_recommended("timeout").unwrap().value, "30s");
+        assert!(toml.supports_platform("aws"));
+
+        assert!(matches!(
+            Requirements::from_profile("base.json", "not json"),
+            Err(Error::Serialization(_))
+        ));
+        assert!(matches!(
+            Requirements::from_path("does/not/exist.json"),
+            Err(Error::Requirements(_))
+        ));
+    }
+
+    #[test]
+    fn test_from_path() {
+        let path =
+            std::env::temp_dir().join(format!("serverless_rs_profile_{}.json", std::process::id()));
+        std::fs::write(&path, r#"{"required":{"cpu":{"name":"cpu","value":"1x"}}}"#).unwrap();
+        let requirements = Requirements::from_path(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(requirements.get_required("cpu").unwrap().value, "1x");
+    }
+
     #[test]
     fn test_serialization() {
         let requirements = Requirements::new()
@@ -5,10 +5,104 @@ This module provides a unified response abstraction that works across
 different serverless platforms.
 */
 
+use base64::Engine;
 use serde::Serialize;
 use std::collections::HashMap;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::RwLock;
+use std::time::{Duration, SystemTime};
 
 use crate::error::{Error, Result};
+use crate::util::{self, http_date, parse_http_date};
+use crate::{IntoResponse, JsonConfig, Problem, Request, SseEvent, VERSION};
+
+/// Whether adapters add a `Server` header to responses
+static SERVER_HEADER: AtomicBool = AtomicBool::new(true);
+
+/// Enables or disables the `Server: serverless.rs/<VERSION>` response header
+///
+/// The header is enabled by default. This affects all responses normalized with
+/// [`Response::with_default_headers`] after the call.
+pub fn set_server_header(enabled: bool) {
+    SERVER_HEADER.store(enabled, Ordering::Relaxed);
+}
+
+/// Default limit on the total size of response headers, matching API Gateway
+pub const DEFAULT_MAX_HEADER_BYTES: usize = 10 * 1024;
+
+/// Default limit on the number of response headers
+pub const DEFAULT_MAX_HEADER_COUNT: usize = 100;
+
+/// Limit on the total size of response headers checked by adapters
+static MAX_HEADER_BYTES: AtomicUsize = AtomicUsize::new(DEFAULT_MAX_HEADER_BYTES);
+
+/// Limit on the number of response headers checked by adapters
+static MAX_HEADER_COUNT: AtomicUsize = AtomicUsize::new(DEFAULT_MAX_HEADER_COUNT);
+
+/// Sets the response header limits enforced by [`Response::check_header_limits`]
+///
+/// The defaults are [`DEFAULT_MAX_HEADER_BYTES`] and [`DEFAULT_MAX_HEADER_COUNT`].
+/// Lower them to match a stricter proxy in front of the function.
+pub fn set_header_limits(max_bytes: usize, max_count: usize) {
+    MAX_HEADER_BYTES.store(max_bytes, Ordering::Relaxed);
+    MAX_HEADER_COUNT.store(max_count, Ordering::Relaxed);
+}
+
+/// Content type of MessagePack bodies
+#[cfg(feature = "msgpack")]
+pub(crate) const MSGPACK_CONTENT_TYPE: &str = "application/msgpack";
+
+/// Serialization formats offered by [`Response::negotiated`], JSON first
+#[cfg(feature = "msgpack")]
+const SERIALIZED_CONTENT_TYPES: &[&str] = &["application/json", MSGPACK_CONTENT_TYPE];
+#[cfg(not(feature = "msgpack"))]
+const SERIALIZED_CONTENT_TYPES: &[&str] = &["application/json"];
+
+/// `Cache-Control` directives accepted by [`Response::with_cache_control`], and whether
+/// each takes a number of seconds
+const CACHE_DIRECTIVES: [(&str, bool); 13] = [
+    ("max-age", true),
+    ("s-maxage", true),
+    ("stale-while-revalidate", true),
+    ("stale-if-error", true),
+    ("public", false),
+    ("private", false),
+    ("no-cache", false),
+    ("no-store", false),
+    ("no-transform", false),
+    ("must-revalidate", false),
+    ("proxy-revalidate", false),
+    ("must-understand", false),
+    ("immutable", false),
+];
+
+/// Headers added to every response by adapters, in the order they were set
+static DEFAULT_HEADERS: RwLock<Vec<(String, String)>> = RwLock::new(Vec::new());
+
+/// Adds a header to every response normalized with [`Response::with_default_headers`]
+///
+/// Use this for headers such as `Strict-Transport-Security` or `X-Content-Type-Options`
+/// that every response must carry. Handlers can still set their own value, which is
+/// kept. Setting the same header again replaces its default value.
+pub fn set_default_header(name: impl Into<String>, value: impl Into<String>) {
+    if let Ok(mut headers) = DEFAULT_HEADERS.write() {
+        add_default_header(&mut headers, name.into(), value.into());
+    }
+}
+
+/// Add a header to a default header list, replacing one with the same name
+fn add_default_header(headers: &mut Vec<(String, String)>, name: String, value: String) {
+    headers.retain(|(existing, _)| !existing.eq_ignore_ascii_case(&name));
+    headers.push((name, value));
+}
+
+/// Removes all headers added with [`set_default_header`]
+pub fn clear_default_headers() {
+    if let Ok(mut headers) = DEFAULT_HEADERS.write() {
+        headers.clear();
+    }
+}
 
 /// A platform-agnostic response from serverless functions
 #[derive(Debug, Clone)]
@@ -16,14 +110,20 @@ pub struct Response {
     /// HTTP status code
     status: u16,
 
-    /// Response headers
+    /// Response headers, holding the first value of repeated headers
     headers: HashMap<String, String>,
 
+    /// Further values of repeated headers, in the order they were appended
+    appended_headers: Vec<(String, String)>,
+
     /// Response body as raw bytes
     body: Vec<u8>,
 
     /// Whether the response is Base64 encoded
     is_base64: bool,
+
+    /// Whether a missing `Content-Type` is guessed from the body
+    sniff_content_type: bool,
 }
 
 impl Response {
@@ -32,8 +132,10 @@ impl Response {
         Self {
             status: 200,
             headers: HashMap::new(),
+            appended_headers: Vec::new(),
             body: Vec::new(),
             is_base64: false,
+            sniff_content_type: false,
         }
     }
 
@@ -49,19 +151,375 @@ impl Response {
     }
 
     /// Returns the headers for this response
+    ///
+    /// Repeated headers appear once, with their first value; use
+    /// [`Response::header_values`] or [`Response::header_pairs`] to see every value.
     pub fn headers(&self) -> &HashMap<String, String> {
         &self.headers
     }
 
-    /// Sets a header for this response
+    /// Sets a header for this response, replacing any values it already has
+    ///
+    /// Header names are compared ignoring ASCII case, so `content-type` replaces an
+    /// existing `Content-Type`.
     pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
-        self.headers.insert(name.into(), value.into());
+        let name = name.into();
+        self.headers
+            .retain(|existing, _| !existing.eq_ignore_ascii_case(&name));
+        self.appended_headers
+            .retain(|(existing, _)| !existing.eq_ignore_ascii_case(&name));
+        self.headers.insert(name, value.into());
+        self
+    }
+
+    /// Sets several headers, as [`Response::with_header`] does for each
+    ///
+    /// Headers already on the response are kept unless one of the new headers has
+    /// the same name, whose values it replaces.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use serverless_rs::Response;
+    ///
+    /// let resp = Response::text("ok").with_headers([
+    ///     ("Cache-Control", "no-store"),
+    ///     ("X-Frame-Options", "DENY"),
+    /// ]);
+    /// assert_eq!(resp.header("Content-Type").unwrap(), "text/plain");
+    /// assert_eq!(resp.header("x-frame-options").unwrap(), "DENY");
+    /// ```
+    pub fn with_headers<I, K, V>(mut self, headers: I) -> Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: Into<String>,
+        V: Into<String>,
+    {
+        for (name, value) in headers {
+            self = self.with_header(name, value);
+        }
         self
     }
 
-    /// Returns a header value by name
+    /// Adds a header value, keeping any values the header already has
+    ///
+    /// Use this for headers that may repeat, such as `Set-Cookie`, `Vary` or `Link`.
+    /// The platform adapters send repeated `Set-Cookie` headers separately and join
+    /// the values of other repeated headers with `, `.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use serverless_rs::Response;
+    ///
+    /// let resp = Response::text("welcome")
+    ///     .append_header("Set-Cookie", "theme=dark")
+    ///     .append_header("Set-Cookie", "lang=en");
+    /// assert_eq!(resp.header_values("set-cookie"), ["theme=dark", "lang=en"]);
+    /// assert_eq!(resp.header("Set-Cookie").unwrap(), "theme=dark");
+    /// ```
+    pub fn append_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        let name = name.into();
+        if self.header(&name).is_some() {
+            self.appended_headers.push((name, value.into()));
+        } else {
+            self.headers.insert(name, value.into());
+        }
+        self
+    }
+
+    /// Returns every value of a header, ignoring ASCII case, in the order they were set
+    pub fn header_values(&self, name: &str) -> Vec<&String> {
+        self.header_pairs()
+            .into_iter()
+            .filter(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value)
+            .collect()
+    }
+
+    /// Returns every header name and value, including each value of repeated headers
+    pub fn header_pairs(&self) -> Vec<(&String, &String)> {
+        self.headers
+            .iter()
+            .chain(
+                self.appended_headers
+                    .iter()
+                    .map(|(name, value)| (name, value)),
+            )
+            .collect()
+    }
+
+    /// Returns the headers as sent by the platform adapters, and any repeated cookies
+    ///
+    /// Values of repeated headers are joined with `, `, which is equivalent for list
+    /// headers. `Set-Cookie` can't be joined, so when it repeats its values are
+    /// returned separately and left out of the map.
+    #[cfg_attr(not(any(feature = "aws", feature = "cloudflare")), allow(dead_code))]
+    pub(crate) fn joined_headers(&self) -> (HashMap<String, String>, Vec<String>) {
+        let mut headers = self.headers.clone();
+        let cookies = self.header_values("Set-Cookie");
+        let cookies: Vec<String> = if cookies.len() > 1 {
+            headers.retain(|name, _| !name.eq_ignore_ascii_case("Set-Cookie"));
+            cookies.into_iter().cloned().collect()
+        } else {
+            Vec::new()
+        };
+        for (name, value) in &self.appended_headers {
+            if let Some((_, joined)) = headers
+                .iter_mut()
+                .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            {
+                joined.push_str(", ");
+                joined.push_str(value);
+            }
+        }
+        (headers, cookies)
+    }
+
+    /// Returns a header value by name, ignoring ASCII case
     pub fn header(&self, name: &str) -> Option<&String> {
-        self.headers.get(name)
+        self.headers.get(name).or_else(|| {
+            self.headers
+                .iter()
+                .find(|(key, _)| key.eq_ignore_ascii_case(name))
+                .map(|(_, value)| value)
+        })
+    }
+
+    /// Sets the `ETag` header for this response
+    ///
+    /// The tag is quoted if it isn't already a quoted or weak (`W/"..."`) entity tag.
+    pub fn with_etag(self, tag: impl AsRef<str>) -> Self {
+        let tag = tag.as_ref();
+        let tag = if tag.starts_with('"') || tag.starts_with("W/\"") {
+            tag.to_string()
+        } else {
+            format!("\"{}\"", tag)
+        };
+        self.with_header("ETag", tag)
+    }
+
+    /// Sets the `Last-Modified` header for this response
+    pub fn with_last_modified(self, time: SystemTime) -> Self {
+        self.with_header("Last-Modified", http_date(time))
+    }
+
+    /// Sets the `Expires` header for this response
+    ///
+    /// `Cache-Control: max-age` takes precedence over `Expires` in caches that
+    /// support both; see [`Response::cacheable`].
+    pub fn with_expires(self, time: SystemTime) -> Self {
+        self.with_header("Expires", http_date(time))
+    }
+
+    /// Sets the `Cache-Control` header, after checking its directives
+    ///
+    /// Directives are separated by commas. `max-age`, `s-maxage`,
+    /// `stale-while-revalidate` and `stale-if-error` need a number of seconds;
+    /// `private` and `no-cache` may name header fields; other directives take no
+    /// value. Unknown directives and `public` together with `private` are rejected
+    /// with an HTTP error, since CDNs ignore directives they don't understand.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use serverless_rs::Response;
+    ///
+    /// let response = Response::text("hi")
+    ///     .with_cache_control("public, max-age=60, stale-while-revalidate=30")
+    ///     .unwrap();
+    /// assert_eq!(
+    ///     response.header("Cache-Control").unwrap(),
+    ///     "public, max-age=60, stale-while-revalidate=30"
+    /// );
+    ///
+    /// assert!(Response::new().with_cache_control("max-age=soon").is_err());
+    /// ```
+    pub fn with_cache_control(self, directives: &str) -> Result<Self> {
+        let mut names = Vec::new();
+        for directive in directives.split(',').map(str::trim) {
+            let (name, value) = match directive.split_once('=') {
+                Some((name, value)) => (name.trim(), Some(value.trim())),
+                None => (directive, None),
+            };
+            let name = name.to_ascii_lowercase();
+            let Some((_, takes_seconds)) =
+                CACHE_DIRECTIVES.iter().find(|(known, _)| *known == name)
+            else {
+                return Err(Error::http(format!(
+                    "Unknown Cache-Control directive: {:?}",
+                    directive
+                )));
+            };
+            let valid = match (takes_seconds, value) {
+                (true, Some(seconds)) => seconds.parse::<u64>().is_ok(),
+                (true, None) => false,
+                (false, None) => true,
+                (false, Some(fields)) => {
+                    matches!(name.as_str(), "private" | "no-cache")
+                        && fields.len() > 1
+                        && fields.starts_with('"')
+                        && fields.ends_with('"')
+                }
+            };
+            if !valid {
+                return Err(Error::http(format!(
+                    "Invalid Cache-Control directive: {:?}",
+                    directive
+                )));
+            }
+            names.push(name);
+        }
+        if names.iter().any(|name| name == "public") && names.iter().any(|name| name == "private") {
+            return Err(Error::http(
+                "Cache-Control can't be both public and private",
+            ));
+        }
+        Ok(self.with_header("Cache-Control", directives.trim()))
+    }
+
+    /// Lets browsers and shared caches such as CDNs keep the response for `max_age`
+    ///
+    /// Sets `Cache-Control: public, max-age=<seconds>`, rounding down to whole seconds.
+    pub fn cacheable(self, max_age: Duration) -> Self {
+        self.with_header(
+            "Cache-Control",
+            format!("public, max-age={}", max_age.as_secs()),
+        )
+    }
+
+    /// Forbids every cache from storing the response, with `Cache-Control: no-store`
+    pub fn no_store(self) -> Self {
+        self.with_header("Cache-Control", "no-store")
+    }
+
+    /// Applies conditional request headers to this response
+    ///
+    /// If the request is a `GET` or `HEAD` and this is a 200 response, returns
+    /// `304 Not Modified` with no body when `If-None-Match` matches the `ETag`, or,
+    /// when `If-None-Match` is absent, when `If-Modified-Since` is not earlier than
+    /// `Last-Modified`. Otherwise the response is returned unchanged.
+    pub fn conditional(mut self, req: &Request) -> Self {
+        let is_safe = matches!(req.method_str().as_deref(), Some("GET") | Some("HEAD"));
+        if !is_safe || self.status != 200 || !self.is_not_modified(req) {
+            return self;
+        }
+
+        self.headers
+            .retain(|name, _| !name.eq_ignore_ascii_case("Content-Length"));
+        self.appended_headers
+            .retain(|(name, _)| !name.eq_ignore_ascii_case("Content-Length"));
+        self.status = 304;
+        self.body = Vec::new();
+        self.is_base64 = false;
+        self
+    }
+
+    /// Returns whether the request's validators match this response
+    fn is_not_modified(&self, req: &Request) -> bool {
+        if let Some(if_none_match) = req.header("If-None-Match") {
+            let Some(etag) = self.header("ETag") else {
+                return false;
+            };
+            let etag = etag.trim_start_matches("W/");
+            return if_none_match
+                .split(',')
+                .map(str::trim)
+                .any(|candidate| candidate == "*" || candidate.trim_start_matches("W/") == etag);
+        }
+
+        if let Some(since) = req.if_modified_since() {
+            let modified = self
+                .header("Last-Modified")
+                .and_then(|value| parse_http_date(value));
+            if let Some(modified) = modified {
+                return modified <= since;
+            }
+        }
+
+        false
+    }
+
+    /// Adds default headers that are missing from this response
+    ///
+    /// Sets `Content-Length` from the (decoded) body size, `Date` to the current time
+    /// (skipped on targets without a clock, such as `wasm32-unknown-unknown`),
+    /// `Server: serverless.rs/<VERSION>` unless disabled with [`set_server_header`], and
+    /// the headers registered with [`set_default_header`].
+    /// Headers already set by the handler are never overridden. `Content-Length` is
+    /// not added to `204 No Content` and `304 Not Modified` responses.
+    pub fn with_default_headers(self) -> Self {
+        match DEFAULT_HEADERS.read() {
+            Ok(defaults) => self.with_default_headers_from(&defaults),
+            Err(_) => self.with_default_headers_from(&[]),
+        }
+    }
+
+    /// Adds missing default headers, taking the registered ones from `defaults`
+    fn with_default_headers_from(mut self, defaults: &[(String, String)]) -> Self {
+        if self.header("Content-Length").is_none() && !matches!(self.status, 204 | 304) {
+            let length = if self.is_base64 {
+                base64::engine::general_purpose::STANDARD
+                    .decode(&self.body)
+                    .map(|body| body.len())
+                    .unwrap_or(self.body.len())
+            } else {
+                self.body.len()
+            };
+            self = self.with_header("Content-Length", length.to_string());
+        }
+        if let (None, Some(now)) = (self.header("Date"), util::now()) {
+            self = self.with_header("Date", http_date(now));
+        }
+        if self.header("Server").is_none() && SERVER_HEADER.load(Ordering::Relaxed) {
+            self = self.with_header("Server", format!("serverless.rs/{}", VERSION));
+        }
+        for (name, value) in defaults {
+            if self.header(name).is_none() {
+                self.headers.insert(name.clone(), value.clone());
+            }
+        }
+        self
+    }
+
+    /// Check the headers against the limits set with [`set_header_limits`]
+    ///
+    /// Gateways reject responses with oversized headers with an opaque error, so the
+    /// platform adapters run this check first and fail with a [`Error::Platform`]
+    /// naming the limit instead. Each header value counts as `name: value` plus a
+    /// line break, so repeated headers count once per value.
+    pub fn check_header_limits(&self) -> Result<()> {
+        self.check_header_limits_with(
+            MAX_HEADER_BYTES.load(Ordering::Relaxed),
+            MAX_HEADER_COUNT.load(Ordering::Relaxed),
+        )
+    }
+
+    /// Check the headers against the given limits
+    fn check_header_limits_with(&self, max_bytes: usize, max_count: usize) -> Result<()> {
+        let headers = self.header_pairs();
+        if headers.len() > max_count {
+            return Err(Error::platform(format!(
+                "Response has {} headers, over the limit of {}",
+                headers.len(),
+                max_count
+            )));
+        }
+        let total: usize = headers
+            .iter()
+            .map(|(name, value)| name.len() + value.len() + 4)
+            .sum();
+        if total > max_bytes {
+            let (largest, _) = headers
+                .iter()
+                .max_by_key(|(name, value)| name.len() + value.len())
+                .expect("headers are non-empty when over the size limit");
+            return Err(Error::platform(format!(
+                "Response headers total {} bytes, over the limit of {} bytes (largest: {})",
+                total, max_bytes, largest
+            )));
+        }
+        Ok(())
     }
 
     /// Returns the raw body bytes for this response
@@ -70,11 +528,58 @@ impl Response {
     }
 
     /// Sets the body for this response
+    ///
+    /// With [`with_content_type_sniffing`](Self::with_content_type_sniffing) enabled,
+    /// a `Content-Type` is also guessed from the body if the response has none.
     pub fn with_body(mut self, body: impl Into<Vec<u8>>) -> Self {
         self.body = body.into();
+        if self.sniff_content_type {
+            self.sniff();
+        }
+        self
+    }
+
+    /// Guesses a missing `Content-Type` from the body, now and whenever it is set
+    ///
+    /// Bodies that parse as JSON are `application/json`, UTF-8 text starting with `<`
+    /// is `text/html`, other UTF-8 text is `text/plain`, and anything else is
+    /// `application/octet-stream`. An explicit `Content-Type` is never replaced, and
+    /// empty bodies are left without one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use serverless_rs::Response;
+    ///
+    /// let resp = Response::new()
+    ///     .with_content_type_sniffing()
+    ///     .with_body(r#"{"id":1}"#);
+    /// assert_eq!(resp.header("Content-Type").unwrap(), "application/json");
+    /// ```
+    pub fn with_content_type_sniffing(mut self) -> Self {
+        self.sniff_content_type = true;
+        self.sniff();
         self
     }
 
+    /// Sets `Content-Type` from the body's contents if the response has none
+    fn sniff(&mut self) {
+        if self.body.is_empty() || self.header("Content-Type").is_some() {
+            return;
+        }
+        let content_type = if serde_json::from_slice::<serde::de::IgnoredAny>(&self.body).is_ok() {
+            "application/json"
+        } else {
+            match std::str::from_utf8(&self.body) {
+                Ok(text) if text.trim_start().starts_with('<') => "text/html",
+                Ok(_) => "text/plain",
+                Err(_) => "application/octet-stream",
+            }
+        };
+        self.headers
+            .insert("Content-Type".to_string(), content_type.to_string());
+    }
+
     /// Returns whether the body is Base64 encoded
     pub fn is_base64(&self) -> bool {
         self.is_base64
@@ -88,13 +593,208 @@ impl Response {
 
     /// Creates a response with a JSON body
     pub fn json<T: Serialize>(value: &T) -> Result<Self> {
-        let body = serde_json::to_vec(value).map_err(Error::serialization)?;
+        Self::new().with_json_body(value)
+    }
+
+    /// Sets a JSON body on this response, keeping its status and headers
+    ///
+    /// The value is serialized into the existing body buffer. `Content-Type` is set
+    /// to `application/json` unless the response already has one.
+    pub fn with_json_body<T: Serialize>(mut self, value: &T) -> Result<Self> {
+        self.body.clear();
+        serde_json::to_writer(&mut self.body, value).map_err(Error::serialization)?;
+        self.is_base64 = false;
+
+        if self.header("Content-Type").is_none() {
+            self.headers
+                .insert("Content-Type".to_string(), "application/json".to_string());
+        }
+        Ok(self)
+    }
+
+    /// Creates a response with a GraphQL execution result
+    ///
+    /// `result` is anything serializing to the `{ data, errors }` response shape,
+    /// such as a GraphQL library's response type. The response has status 200 even
+    /// when the result holds errors, as GraphQL over HTTP specifies for
+    /// `application/json`.
+    #[cfg(feature = "graphql")]
+    pub fn graphql<T: Serialize>(result: &T) -> Result<Self> {
+        Self::json(result)
+    }
+
+    /// Creates a response with a pretty-printed JSON body
+    pub fn json_pretty<T: Serialize>(value: &T) -> Result<Self> {
+        Self::json_with(value, &JsonConfig::new().with_pretty(true))
+    }
+
+    /// Creates a JSON response written with the given [`JsonConfig`]
+    pub fn json_with<T: Serialize>(value: &T, config: &JsonConfig) -> Result<Self> {
+        Ok(Self::new()
+            .with_header("Content-Type", "application/json")
+            .with_body(config.to_vec(value)?))
+    }
+
+    /// Creates a MessagePack response
+    ///
+    /// The value is serialized with field names, like JSON, with
+    /// `Content-Type: application/msgpack`. The body is binary, so on API Gateway
+    /// register `application/msgpack` with
+    /// [`set_binary_media_types`](crate::platforms::aws::set_binary_media_types).
+    #[cfg(feature = "msgpack")]
+    pub fn msgpack<T: Serialize>(value: &T) -> Result<Self> {
+        let body = rmp_serde::to_vec_named(value).map_err(Error::serialization)?;
+        Ok(Self::new()
+            .with_header("Content-Type", MSGPACK_CONTENT_TYPE)
+            .with_body(body))
+    }
+
+    /// Creates a response in the format the client prefers
+    ///
+    /// With the `msgpack` feature, clients whose `Accept` header prefers
+    /// `application/msgpack` get a MessagePack body. Everyone else gets JSON.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use serverless_rs::{json, Request, Response};
+    ///
+    /// let req = Request::new().with_header("Accept", "application/json");
+    /// let resp = Response::negotiated(&req, &json!({ "id": 7 })).unwrap();
+    /// assert_eq!(resp.header("Content-Type").unwrap(), "application/json");
+    /// ```
+    pub fn negotiated<T: Serialize>(req: &Request, value: &T) -> Result<Self> {
+        match req.preferred_content_type(SERIALIZED_CONTENT_TYPES) {
+            #[cfg(feature = "msgpack")]
+            Some(MSGPACK_CONTENT_TYPE) => Self::msgpack(value),
+            _ => Self::json(value),
+        }
+    }
+
+    /// Creates a newline-delimited JSON (JSON Lines) response
+    ///
+    /// Each item is serialized on its own line, each line ending in `\n`, with
+    /// `Content-Type: application/x-ndjson`. Like [`Response::sse`], the lines are
+    /// collected into the body since serverless platforms buffer responses.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use serverless_rs::{json, Response};
+    ///
+    /// let response = Response::ndjson((1..=2).map(|id| json!({ "id": id }))).unwrap();
+    /// assert_eq!(response.body(), b"{\"id\":1}\n{\"id\":2}\n");
+    /// ```
+    pub fn ndjson<I, T>(items: I) -> Result<Self>
+    where
+        I: IntoIterator<Item = T>,
+        T: Serialize,
+    {
+        let mut body = Vec::new();
+        for item in items {
+            serde_json::to_writer(&mut body, &item).map_err(Error::serialization)?;
+            body.push(b'\n');
+        }
+        Ok(Self::new()
+            .with_header("Content-Type", "application/x-ndjson")
+            .with_body(body))
+    }
+
+    /// Creates a newline-delimited JSON response by collecting an async stream of items
+    pub async fn ndjson_stream<S, T>(items: S) -> Result<Self>
+    where
+        S: futures::Stream<Item = T>,
+        T: Serialize,
+    {
+        use futures::StreamExt;
+
+        Self::ndjson(items.collect::<Vec<_>>().await)
+    }
+
+    /// Creates a JSON array response from an async stream of items
+    ///
+    /// Each item is serialized as it arrives, with commas between items, and the
+    /// items themselves are dropped once written. The whole serialized array is
+    /// buffered, though, and platform adapters send the body once it is complete, so
+    /// this does not bound memory for large streams. Use this over
+    /// [`Response::ndjson_stream`] for clients that expect a single JSON array.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use serverless_rs::{json, Response};
+    ///
+    /// let items = futures::stream::iter((1..=2).map(|id| json!({ "id": id })));
+    /// let response = futures::executor::block_on(Response::json_stream(items)).unwrap();
+    /// assert_eq!(response.body(), b"[{\"id\":1},{\"id\":2}]");
+    /// ```
+    pub async fn json_stream<S, T>(items: S) -> Result<Self>
+    where
+        S: futures::Stream<Item = T>,
+        T: Serialize,
+    {
+        use futures::StreamExt;
 
+        let mut body = vec![b'['];
+        futures::pin_mut!(items);
+        while let Some(item) = items.next().await {
+            if body.len() > 1 {
+                body.push(b',');
+            }
+            serde_json::to_writer(&mut body, &item).map_err(Error::serialization)?;
+        }
+        body.push(b']');
         Ok(Self::new()
             .with_header("Content-Type", "application/json")
             .with_body(body))
     }
 
+    /// Sets the body to the contents of a reader, keeping the status and headers
+    ///
+    /// Platform adapters send complete bodies, so the reader is read to the end into
+    /// the body buffer, which is reused. Any Base64 flag is cleared. Use this to proxy
+    /// a download or send a generated report without copying it through a `Vec`
+    /// first.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use serverless_rs::Response;
+    ///
+    /// let report: &[u8] = b"id,total\n1,42\n";
+    /// let response = Response::new()
+    ///     .with_header("Content-Type", "text/csv")
+    ///     .with_body_stream_from_reader(report)
+    ///     .unwrap();
+    /// assert_eq!(response.body(), report);
+    /// ```
+    pub fn with_body_stream_from_reader<R: std::io::Read>(mut self, mut reader: R) -> Result<Self> {
+        self.body.clear();
+        reader
+            .read_to_end(&mut self.body)
+            .map_err(|e| Error::http(format!("Failed to read response body: {}", e)))?;
+        self.is_base64 = false;
+        Ok(self)
+    }
+
+    /// Sets the body to the contents of an async reader, keeping the status and headers
+    ///
+    /// The async counterpart of [`Response::with_body_stream_from_reader`].
+    pub async fn with_body_stream_from_async_reader<R>(mut self, mut reader: R) -> Result<Self>
+    where
+        R: futures::io::AsyncRead + Unpin,
+    {
+        use futures::io::AsyncReadExt;
+
+        self.body.clear();
+        reader
+            .read_to_end(&mut self.body)
+            .await
+            .map_err(|e| Error::http(format!("Failed to read response body: {}", e)))?;
+        self.is_base64 = false;
+        Ok(self)
+    }
+
     /// Creates a response with a text body
     pub fn text<T: AsRef<str>>(text: T) -> Self {
         Self::new()
@@ -109,18 +809,161 @@ impl Response {
             .with_body(html.as_ref().as_bytes().to_vec())
     }
 
-    /// Creates a redirect response
+    /// Creates a Server-Sent Events response from a sequence of events
+    ///
+    /// Sets `Content-Type: text/event-stream` and `Cache-Control: no-cache`. Serverless
+    /// platforms buffer responses, so all events are collected into the body; use
+    /// [`Response::sse_stream`] to collect events from an async stream.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use serverless_rs::{Response, SseEvent};
+    ///
+    /// let response = Response::sse((1..=3).map(|step| {
+    ///     SseEvent::new(format!("{}/3", step)).with_event("progress")
+    /// }));
+    /// assert_eq!(response.header("Content-Type").unwrap(), "text/event-stream");
+    /// ```
+    pub fn sse<I>(events: I) -> Self
+    where
+        I: IntoIterator<Item = SseEvent>,
+    {
+        let body: String = events.into_iter().map(|event| event.to_string()).collect();
+        Self::new()
+            .with_header("Content-Type", "text/event-stream")
+            .with_header("Cache-Control", "no-cache")
+            .with_body(body)
+    }
+
+    /// Creates a Server-Sent Events response by collecting an async stream of events
+    pub async fn sse_stream<S>(events: S) -> Self
+    where
+        S: futures::Stream<Item = SseEvent>,
+    {
+        use futures::StreamExt;
+
+        Self::sse(events.collect::<Vec<_>>().await)
+    }
+
+    /// Creates a response with the contents of a file
+    ///
+    /// The `Content-Type` is guessed from the file extension and `Content-Length` is set
+    /// to the file size. Binary content is Base64 encoded so it can pass through
+    /// platforms that only accept text bodies.
+    pub fn file(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let contents = std::fs::read(path)
+            .map_err(|e| Error::http(format!("Failed to read {}: {}", path.display(), e)))?;
+        let content_type = content_type_for_path(path);
+        let is_text = is_text_content_type(content_type) && std::str::from_utf8(&contents).is_ok();
+
+        let mut response = Self::new()
+            .with_header("Content-Type", content_type)
+            .with_header("Content-Length", contents.len().to_string());
+        if let Ok(modified) = std::fs::metadata(path).and_then(|meta| meta.modified()) {
+            response = response.with_last_modified(modified);
+        }
+
+        if is_text {
+            Ok(response.with_body(contents))
+        } else {
+            Ok(response
+                .with_body(base64::engine::general_purpose::STANDARD.encode(&contents))
+                .with_base64(true))
+        }
+    }
+
+    /// Creates an RFC 7807 `application/problem+json` response
+    ///
+    /// Use [`Response::problem_json`] with a [`Problem`] to set the type, instance or
+    /// extension members.
+    pub fn problem(status: u16, title: impl Into<String>, detail: impl Into<String>) -> Self {
+        Self::problem_json(&Problem::new(status, title).with_detail(detail))
+    }
+
+    /// Creates an `application/problem+json` response from a [`Problem`]
+    pub fn problem_json(problem: &Problem) -> Self {
+        Self::new()
+            .with_status(problem.status())
+            .with_header("Content-Type", "application/problem+json")
+            .with_body(problem.to_json().to_string())
+    }
+
+    /// Creates a response from a handler's result, mapping errors to responses
+    ///
+    /// `Ok` values are converted with [`IntoResponse`]. Errors, including a failed
+    /// conversion, become a response with the error's [`Error::status`] and its
+    /// [`Error::to_json`] body, as the platform adapters send them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use serverless_rs::{Error, Response};
+    ///
+    /// let ok = Response::from_result(Ok("done"));
+    /// assert_eq!(ok.body(), b"done");
+    ///
+    /// let err = Response::from_result::<&str>(Err(Error::with_status(404, "no such user")));
+    /// assert_eq!(err.status(), 404);
+    /// assert_eq!(err.header("Content-Type").unwrap(), "application/json");
+    /// ```
+    pub fn from_result<T: IntoResponse>(result: Result<T>) -> Self {
+        match result.and_then(IntoResponse::into_response) {
+            Ok(resp) => resp,
+            Err(err) => Self::new()
+                .with_status(err.status())
+                .with_header("Content-Type", "application/json")
+                .with_body(err.to_json().to_string()),
+        }
+    }
+
+    /// Creates a redirect response (302 Found)
     pub fn redirect(location: impl Into<String>) -> Self {
         Self::new()
             .with_status(302)
             .with_header("Location", location.into())
     }
 
+    /// Creates a permanent redirect response (301 Moved Permanently)
+    pub fn redirect_permanent(location: impl Into<String>) -> Self {
+        Self::redirect(location).with_status(301)
+    }
+
+    /// Creates a redirect response that switches to GET (303 See Other)
+    pub fn redirect_see_other(location: impl Into<String>) -> Self {
+        Self::redirect(location).with_status(303)
+    }
+
+    /// Creates a temporary redirect that preserves the method (307 Temporary Redirect)
+    pub fn redirect_temporary(location: impl Into<String>) -> Self {
+        Self::redirect(location).with_status(307)
+    }
+
+    /// Creates a redirect response with the given 3xx status
+    ///
+    /// Use 308 for permanent redirects that preserve the method and body. Returns an
+    /// HTTP error if the status is not in the 3xx range.
+    pub fn redirect_with_status(status: u16, location: impl Into<String>) -> Result<Self> {
+        if !(300..400).contains(&status) {
+            return Err(Error::http(format!(
+                "Invalid redirect status {}: expected a 3xx status",
+                status
+            )));
+        }
+        Ok(Self::redirect(location).with_status(status))
+    }
+
     /// Creates a "not found" response
     pub fn not_found() -> Self {
         Self::new().with_status(404).with_body("Not Found")
     }
 
+    /// Creates a "forbidden" response
+    pub fn forbidden() -> Self {
+        Self::new().with_status(403).with_body("Forbidden")
+    }
+
     /// Creates a "bad request" response
     pub fn bad_request() -> Self {
         Self::new().with_status(400).with_body("Bad Request")
@@ -134,6 +977,45 @@ impl Response {
     }
 }
 
+/// Guess the content type of a file from its extension
+fn content_type_for_path(path: &Path) -> &'static str {
+    let extension = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_ascii_lowercase())
+        .unwrap_or_default();
+
+    match extension.as_str() {
+        "html" | "htm" => "text/html",
+        "css" => "text/css",
+        "js" | "mjs" => "application/javascript",
+        "json" | "map" => "application/json",
+        "txt" => "text/plain",
+        "csv" => "text/csv",
+        "xml" => "application/xml",
+        "svg" => "image/svg+xml",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "ico" => "image/x-icon",
+        "woff" => "font/woff",
+        "woff2" => "font/woff2",
+        "wasm" => "application/wasm",
+        "pdf" => "application/pdf",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Returns whether a content type is text that can be sent without encoding
+fn is_text_content_type(content_type: &str) -> bool {
+    content_type.starts_with("text/")
+        || matches!(
+            content_type,
+            "application/javascript" | "application/json" | "application/xml" | "image/svg+xml"
+        )
+}
+
 impl Default for Response {
     fn default() -> Self {
         Self::new()
@@ -143,7 +1025,303 @@ impl Default for Response {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use serde_json::json;
+    use serde_json::{json, Value};
+
+    #[test]
+    fn test_redirects() {
+        let cases = [
+            (Response::redirect("/a"), 302),
+            (Response::redirect_permanent("/a"), 301),
+            (Response::redirect_see_other("/a"), 303),
+            (Response::redirect_temporary("/a"), 307),
+            (Response::redirect_with_status(308, "/a").unwrap(), 308),
+        ];
+        for (response, status) in cases {
+            assert_eq!(response.status(), status);
+            assert_eq!(response.header("Location"), Some(&"/a".to_string()));
+        }
+
+        assert!(matches!(
+            Response::redirect_with_status(200, "/a"),
+            Err(Error::Http(_))
+        ));
+        assert!(Response::redirect_with_status(400, "/a").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_ndjson_response() {
+        let response = Response::ndjson(vec![json!({ "id": 1 }), json!({ "id": 2 })]).unwrap();
+        assert_eq!(
+            response.header("Content-Type"),
+            Some(&"application/x-ndjson".to_string())
+        );
+        assert_eq!(response.body(), b"{\"id\":1}\n{\"id\":2}\n");
+
+        let empty = Response::ndjson(Vec::<Value>::new()).unwrap();
+        assert!(empty.body().is_empty());
+
+        let streamed = Response::ndjson_stream(futures::stream::iter(vec!["a", "b"]))
+            .await
+            .unwrap();
+        assert_eq!(streamed.body(), b"\"a\"\n\"b\"\n");
+    }
+
+    #[tokio::test]
+    async fn test_json_stream_response() {
+        let items = futures::stream::iter((1..=3).map(|id| json!({ "id": id })));
+        let response = Response::json_stream(items).await.unwrap();
+        assert_eq!(
+            response.header("Content-Type"),
+            Some(&"application/json".to_string())
+        );
+        let array: Vec<Value> = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(
+            array,
+            vec![json!({"id": 1}), json!({"id": 2}), json!({"id": 3})]
+        );
+
+        let empty = Response::json_stream(futures::stream::iter(Vec::<Value>::new()))
+            .await
+            .unwrap();
+        assert_eq!(empty.body(), b"[]");
+
+        let single = Response::json_stream(futures::stream::iter(["only"]))
+            .await
+            .unwrap();
+        assert_eq!(single.body(), b"[\"only\"]");
+    }
+
+    #[tokio::test]
+    async fn test_body_from_reader() {
+        // A few megabytes, so reads span many buffer refills
+        let contents: Vec<u8> = (0..4 * 1024 * 1024).map(|i| (i % 251) as u8).collect();
+        let path = std::env::temp_dir().join(format!("serverless_rs_body_{}", std::process::id()));
+        std::fs::write(&path, &contents).unwrap();
+
+        let file = std::fs::File::open(&path).unwrap();
+        let response = Response::new()
+            .with_status(200)
+            .with_header("Content-Type", "application/octet-stream")
+            .with_body("replaced")
+            .with_base64(true)
+            .with_body_stream_from_reader(file)
+            .unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(response.body(), contents.as_slice());
+        assert!(!response.is_base64());
+        assert_eq!(
+            response.header("Content-Type").unwrap(),
+            "application/octet-stream"
+        );
+
+        let reader = futures::io::Cursor::new(contents.clone());
+        let response = Response::new()
+            .with_body_stream_from_async_reader(reader)
+            .await
+            .unwrap();
+        assert_eq!(response.body(), contents.as_slice());
+
+        struct Failing;
+        impl std::io::Read for Failing {
+            fn read(&mut self, _buf: &mut [u8]) -> std::io::Result<usize> {
+                Err(std::io::Error::other("connection reset"))
+            }
+        }
+        let err = Response::new()
+            .with_body_stream_from_reader(Failing)
+            .unwrap_err();
+        assert!(err.to_string().contains("connection reset"));
+    }
+
+    #[tokio::test]
+    async fn test_sse_response() {
+        let response = Response::sse(vec![
+            SseEvent::new("started").with_id("1"),
+            SseEvent::new("{\"done\":true}").with_event("complete"),
+        ]);
+        assert_eq!(
+            response.header("Content-Type"),
+            Some(&"text/event-stream".to_string())
+        );
+        assert_eq!(
+            response.header("Cache-Control"),
+            Some(&"no-cache".to_string())
+        );
+        assert_eq!(
+            std::str::from_utf8(response.body()).unwrap(),
+            "id: 1\ndata: started\n\nevent: complete\ndata: {\"done\":true}\n\n"
+        );
+
+        let events = futures::stream::iter(vec![SseEvent::new("a"), SseEvent::new("b")]);
+        let response = Response::sse_stream(events).await;
+        assert_eq!(response.body(), b"data: a\n\ndata: b\n\n");
+    }
+
+    #[test]
+    fn test_default_headers() {
+        let response = Response::text("Hello").with_default_headers();
+        assert_eq!(response.header("Content-Length"), Some(&"5".to_string()));
+        assert!(parse_http_date(response.header("Date").unwrap()).is_some());
+        assert_eq!(
+            response.header("Server"),
+            Some(&format!("serverless.rs/{}", VERSION))
+        );
+
+        // User-set values are kept
+        let response = Response::text("Hello")
+            .with_header("content-length", "3")
+            .with_header("Server", "custom")
+            .with_default_headers();
+        assert_eq!(response.header("Content-Length"), Some(&"3".to_string()));
+        assert_eq!(response.header("Server"), Some(&"custom".to_string()));
+
+        // Base64 bodies report the decoded size
+        let response = Response::new()
+            .with_body("aGVsbG8=")
+            .with_base64(true)
+            .with_default_headers();
+        assert_eq!(response.header("Content-Length"), Some(&"5".to_string()));
+
+        let response = Response::new().with_status(304).with_default_headers();
+        assert!(response.header("Content-Length").is_none());
+    }
+
+    #[test]
+    fn test_cache_control() {
+        let cache_control = |resp: Response| resp.header("Cache-Control").cloned().unwrap();
+
+        assert_eq!(
+            cache_control(Response::new().cacheable(Duration::from_millis(3_600_900))),
+            "public, max-age=3600"
+        );
+        assert_eq!(cache_control(Response::new().no_store()), "no-store");
+
+        for valid in [
+            "no-cache",
+            "private, max-age=0, must-revalidate",
+            "public, max-age=31536000, immutable",
+            "Public, S-MaxAge=600, stale-if-error=86400",
+            r#"private="Set-Cookie", max-age=60"#,
+        ] {
+            let resp = Response::new().with_cache_control(valid).unwrap();
+            assert_eq!(cache_control(resp), valid);
+        }
+
+        for invalid in [
+            "max-age",
+            "max-age=-1",
+            "max-age=1.5",
+            "public, maxage=60",
+            "public, private",
+            "no-store=true",
+            "",
+            "public,",
+        ] {
+            let result = Response::new().with_cache_control(invalid);
+            assert!(matches!(result, Err(Error::Http(_))), "{:?}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_with_headers() {
+        let forwarded: HashMap<String, String> = [
+            ("X-Request-Id".to_string(), "abc".to_string()),
+            ("Vary".to_string(), "Origin".to_string()),
+        ]
+        .into_iter()
+        .collect();
+        let resp = Response::text("ok")
+            .append_header("Vary", "Accept")
+            .append_header("Vary", "Cookie")
+            .with_headers(forwarded);
+
+        assert_eq!(resp.header("Content-Type").unwrap(), "text/plain");
+        assert_eq!(resp.header("X-Request-Id").unwrap(), "abc");
+        assert_eq!(resp.header_values("Vary"), ["Origin"]);
+    }
+
+    #[test]
+    fn test_append_header() {
+        let resp = Response::new()
+            .append_header("Vary", "Accept")
+            .append_header("vary", "Origin")
+            .with_header("Link", "</a>");
+        assert_eq!(resp.header_values("VARY"), ["Accept", "Origin"]);
+        assert_eq!(resp.header("Vary").unwrap(), "Accept");
+        assert_eq!(resp.headers().len(), 2);
+        assert_eq!(resp.header_pairs().len(), 3);
+
+        // Setting a header replaces every value
+        let resp = resp.with_header("Vary", "Cookie");
+        assert_eq!(resp.header_values("Vary"), ["Cookie"]);
+
+        // Whatever the case the existing header was set with
+        let resp = Response::text("hi").with_header("content-type", "text/html");
+        assert_eq!(resp.header_values("Content-Type"), ["text/html"]);
+        assert_eq!(resp.headers().len(), 1);
+
+        // Each value counts towards the header limits
+        let resp = Response::new()
+            .append_header("Set-Cookie", "a=1")
+            .append_header("Set-Cookie", "b=2");
+        let err = resp.check_header_limits_with(1024, 1).unwrap_err();
+        assert!(err.to_string().contains("Response has 2 headers"));
+    }
+
+    #[test]
+    fn test_header_limits() {
+        let response = Response::text("Hello").with_header("X-Trace", "a".repeat(100));
+        assert!(response.check_header_limits().is_ok());
+        assert!(response.check_header_limits_with(200, 10).is_ok());
+
+        let err = response.check_header_limits_with(100, 10).unwrap_err();
+        assert!(matches!(err, Error::Platform(_)));
+        assert!(err.to_string().contains("over the limit of 100 bytes"));
+        assert!(err.to_string().contains("X-Trace"));
+
+        let err = response.check_header_limits_with(200, 1).unwrap_err();
+        assert!(err.to_string().contains("Response has 2 headers"));
+    }
+
+    #[test]
+    fn test_configured_default_headers() {
+        let mut defaults = Vec::new();
+        for (name, value) in [
+            ("X-Test-Default", "first"),
+            ("x-test-default", "1.2.3"),
+            ("X-Test-Nosniff", "nosniff"),
+        ] {
+            add_default_header(&mut defaults, name.to_string(), value.to_string());
+        }
+        assert_eq!(defaults.len(), 2);
+
+        let response = Response::text("Hello").with_default_headers_from(&defaults);
+        assert_eq!(
+            response.header("X-Test-Default"),
+            Some(&"1.2.3".to_string())
+        );
+        assert_eq!(
+            response.header("X-Test-Nosniff"),
+            Some(&"nosniff".to_string())
+        );
+
+        // Handler-set values are kept
+        let response = Response::text("Hello")
+            .with_header("X-TEST-NOSNIFF", "custom")
+            .with_default_headers_from(&defaults);
+        assert_eq!(
+            response.header("X-Test-Nosniff"),
+            Some(&"custom".to_string())
+        );
+        let nosniff = response
+            .headers()
+            .keys()
+            .filter(|name| name.eq_ignore_ascii_case("X-Test-Nosniff"));
+        assert_eq!(nosniff.count(), 1);
+
+        let response = Response::text("Hello").with_default_headers_from(&[]);
+        assert!(response.header("X-Test-Default").is_none());
+    }
 
     #[test]
     fn test_response_builder() {
@@ -160,6 +1338,62 @@ mod tests {
         assert_eq!(std::str::from_utf8(resp.body()).unwrap(), r#"{"id":123}"#);
     }
 
+    #[test]
+    fn test_content_type_sniffing() {
+        let sniffed = |body: &[u8]| {
+            Response::new()
+                .with_content_type_sniffing()
+                .with_body(body.to_vec())
+                .header("Content-Type")
+                .cloned()
+        };
+        assert_eq!(sniffed(b"[1, 2]").unwrap(), "application/json");
+        assert_eq!(sniffed(b"\"quoted\"").unwrap(), "application/json");
+        assert_eq!(sniffed(b"  <!DOCTYPE html><p>Hi</p>").unwrap(), "text/html");
+        assert_eq!(sniffed(b"plain words").unwrap(), "text/plain");
+        assert_eq!(
+            sniffed(&[0xff, 0xd8, 0xff, 0xe0]).unwrap(),
+            "application/octet-stream"
+        );
+        assert!(sniffed(b"").is_none());
+
+        // Explicit types win, and sniffing is off by default
+        let resp = Response::new()
+            .with_header("content-type", "text/csv")
+            .with_content_type_sniffing()
+            .with_body("a,b");
+        assert_eq!(resp.header("Content-Type").unwrap(), "text/csv");
+        assert!(Response::new()
+            .with_body("{}")
+            .header("Content-Type")
+            .is_none());
+
+        // A body set before enabling sniffing is also sniffed
+        let resp = Response::new().with_body("{}").with_content_type_sniffing();
+        assert_eq!(resp.header("Content-Type").unwrap(), "application/json");
+    }
+
+    #[test]
+    fn test_from_result() {
+        let resp = Response::from_result(Ok((201, json!({ "id": 7 }))));
+        assert_eq!(resp.status(), 201);
+        assert_eq!(resp.body(), br#"{"id":7}"#);
+
+        let resp = Response::from_result::<Response>(Err(Error::with_status(409, "taken")));
+        assert_eq!(resp.status(), 409);
+        let body: Value = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(
+            body,
+            json!({ "type": "http", "message": "taken", "status": 409 })
+        );
+
+        // A value that fails to convert is mapped like any other error
+        let resp = Response::from_result(Ok(Err::<&str, _>(Error::function("boom"))));
+        assert_eq!(resp.status(), 500);
+        let body: Value = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(body["type"], "function");
+    }
+
     #[test]
     fn test_json_response() {
         let data = json!({
@@ -178,6 +1412,75 @@ mod tests {
         assert_eq!(parsed, data);
     }
 
+    #[test]
+    fn test_negotiated_response() {
+        let data = json!({ "id": 123 });
+        for accept in [None, Some("application/json"), Some("image/png")] {
+            let req = match accept {
+                Some(accept) => Request::new().with_header("Accept", accept),
+                None => Request::new(),
+            };
+            let resp = Response::negotiated(&req, &data).unwrap();
+            assert_eq!(resp.header("Content-Type").unwrap(), "application/json");
+        }
+    }
+
+    #[cfg(feature = "msgpack")]
+    #[test]
+    fn test_msgpack_round_trip() {
+        #[derive(Debug, PartialEq, Serialize, serde::Deserialize)]
+        struct User {
+            id: u32,
+            name: String,
+        }
+        let user = User {
+            id: 7,
+            name: "Ada".to_string(),
+        };
+
+        let req = Request::new().with_header("Accept", "application/msgpack, */*;q=0.5");
+        let resp = Response::negotiated(&req, &user).unwrap();
+        assert_eq!(resp.header("Content-Type").unwrap(), "application/msgpack");
+
+        let req = Request::new()
+            .with_header("Content-Type", "application/msgpack")
+            .with_body(resp.body().to_vec());
+        assert_eq!(req.parse_body::<User>().unwrap(), user);
+        assert!(Request::new()
+            .with_body("not msgpack")
+            .body_msgpack::<User>()
+            .is_err());
+
+        let req = Request::new().with_body(r#"{"id":7,"name":"Ada"}"#);
+        assert_eq!(req.parse_body::<User>().unwrap(), user);
+    }
+
+    #[test]
+    fn test_with_json_body() {
+        let resp = Response::new()
+            .with_status(201)
+            .with_header("Set-Cookie", "session=abc")
+            .with_json_body(&json!({ "id": 7 }))
+            .unwrap();
+        assert_eq!(resp.status(), 201);
+        assert_eq!(resp.header("Set-Cookie").unwrap(), "session=abc");
+        assert_eq!(resp.header("Content-Type").unwrap(), "application/json");
+        assert_eq!(resp.body(), br#"{"id":7}"#);
+
+        // An existing content type is kept, and the previous body is replaced
+        let resp = Response::new()
+            .with_body("old")
+            .with_header("content-type", "application/problem+json")
+            .with_json_body(&json!({ "title": "Not Found" }))
+            .unwrap();
+        assert_eq!(
+            resp.header("Content-Type").unwrap(),
+            "application/problem+json"
+        );
+        assert_eq!(resp.headers().len(), 1);
+        assert_eq!(resp.body(), br#"{"title":"Not Found"}"#);
+    }
+
     #[test]
     fn test_convenience_responses() {
         let text = Response::text("Hello, world!");
@@ -203,4 +1506,99 @@ mod tests {
         let internal_error = Response::internal_error();
         assert_eq!(internal_error.status(), 500);
     }
+
+    #[test]
+    fn test_etag_conditional() {
+        let resp = Response::json(&json!({"id": 1})).unwrap().with_etag("v1");
+        assert_eq!(resp.header("ETag"), Some(&"\"v1\"".to_string()));
+
+        // A matching If-None-Match yields 304 with no body
+        let req = Request::new()
+            .with_method_str("GET")
+            .with_header("If-None-Match", "\"v0\", \"v1\"");
+        let not_modified = resp.clone().conditional(&req);
+        assert_eq!(not_modified.status(), 304);
+        assert!(not_modified.body().is_empty());
+        assert_eq!(not_modified.header("ETag"), Some(&"\"v1\"".to_string()));
+
+        // Weak comparison and header name case are ignored
+        let req = Request::new()
+            .with_method_str("GET")
+            .with_header("if-none-match", "W/\"v1\"");
+        assert_eq!(resp.clone().conditional(&req).status(), 304);
+
+        // A non-matching If-None-Match yields the full body
+        let req = Request::new()
+            .with_method_str("GET")
+            .with_header("If-None-Match", "\"v2\"");
+        let full = resp.clone().conditional(&req);
+        assert_eq!(full.status(), 200);
+        assert_eq!(full.body(), resp.body());
+
+        // Unsafe methods are never turned into 304s
+        let req = Request::new()
+            .with_method_str("POST")
+            .with_header("If-None-Match", "\"v1\"");
+        assert_eq!(resp.conditional(&req).status(), 200);
+    }
+
+    #[test]
+    fn test_last_modified_conditional() {
+        let modified = crate::util::parse_http_date("Sun, 06 Nov 1994 08:49:37 GMT").unwrap();
+        let resp = Response::text("report").with_last_modified(modified);
+        assert_eq!(
+            resp.header("Last-Modified"),
+            Some(&"Sun, 06 Nov 1994 08:49:37 GMT".to_string())
+        );
+        let expiring = resp
+            .clone()
+            .with_expires(modified + Duration::from_secs(86_400));
+        assert_eq!(
+            expiring.header("Expires"),
+            Some(&"Mon, 07 Nov 1994 08:49:37 GMT".to_string())
+        );
+
+        let req = Request::new()
+            .with_method_str("GET")
+            .with_header("If-Modified-Since", "Sun, 06 Nov 1994 08:49:37 GMT");
+        assert_eq!(resp.clone().conditional(&req).status(), 304);
+
+        let req = Request::new()
+            .with_method_str("GET")
+            .with_header("If-Modified-Since", "Sat, 05 Nov 1994 08:49:37 GMT");
+        let full = resp.conditional(&req);
+        assert_eq!(full.status(), 200);
+        assert_eq!(std::str::from_utf8(full.body()).unwrap(), "report");
+    }
+
+    #[test]
+    fn test_file_response() {
+        let dir = std::env::temp_dir().join(format!("serverless_rs_file_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let text_path = dir.join("index.html");
+        std::fs::write(&text_path, "<h1>Hello</h1>").unwrap();
+        let text = Response::file(&text_path).unwrap();
+        assert_eq!(text.header("Content-Type"), Some(&"text/html".to_string()));
+        assert_eq!(text.header("Content-Length"), Some(&"14".to_string()));
+        assert!(!text.is_base64());
+        assert_eq!(std::str::from_utf8(text.body()).unwrap(), "<h1>Hello</h1>");
+
+        let binary_path = dir.join("logo.png");
+        std::fs::write(&binary_path, [0x89, b'P', b'N', b'G', 0xff]).unwrap();
+        let binary = Response::file(&binary_path).unwrap();
+        assert_eq!(
+            binary.header("Content-Type"),
+            Some(&"image/png".to_string())
+        );
+        assert_eq!(binary.header("Content-Length"), Some(&"5".to_string()));
+        assert!(binary.is_base64());
+        assert_eq!(std::str::from_utf8(binary.body()).unwrap(), "iVBOR/8=");
+
+        assert!(text.header("Last-Modified").is_some());
+
+        assert!(Response::file(dir.join("missing.txt")).is_err());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
 }
@@ -7,16 +7,66 @@ This module defines the Router trait for HTTP routing in serverless functions.
 use async_trait::async_trait;
 use http::Method;
 use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
 use std::sync::Arc;
 
 use crate::{
     error::{Error, Result},
-    Context, Handler, Request, Response,
+    middleware::{Layered, WithState},
+    util::percent_decode_path,
+    Context, Extensions, Handler, Middleware, Request, Response, RouteInfo,
 };
 
 /// A route handler function
 pub type RouteHandler = Arc<dyn Handler>;
 
+/// Methods a route added with [`RouterBuilder::any`] is registered for
+const ANY_METHODS: [Method; 9] = [
+    Method::GET,
+    Method::POST,
+    Method::PUT,
+    Method::DELETE,
+    Method::PATCH,
+    Method::HEAD,
+    Method::OPTIONS,
+    Method::CONNECT,
+    Method::TRACE,
+];
+
+/// Build a [`RouterBuilder`] from `#[serverless]` functions declared with `#[route]`
+///
+/// Each function is registered with the method and path from its `#[route]`
+/// attribute. Listing a function without a `#[route]` is a compile error. The
+/// returned builder can be extended with further routes before calling `build`.
+///
+/// # Examples
+///
+/// ```ignore
+/// use serverless_rs::{route, routes, serverless, Context, Request, Response, Result, Router};
+///
+/// #[route(GET, "/users/{id}")]
+/// #[serverless]
+/// pub async fn get_user(req: Request, _ctx: &Context) -> Result<Response> {
+///     Ok(Response::text(req.path_param("id").cloned().unwrap_or_default()))
+/// }
+///
+/// #[route(POST, "/users")]
+/// #[serverless]
+/// pub async fn create_user(_req: Request, _ctx: &Context) -> Result<Response> {
+///     Ok(Response::new().with_status(201))
+/// }
+///
+/// let router = routes![get_user, create_user].build();
+/// ```
+#[macro_export]
+macro_rules! routes {
+    ($($($function:ident)::+),* $(,)?) => {{
+        let router = $crate::RouterBuilder::new();
+        $(let router = $($function)::+::register(router);)*
+        router
+    }};
+}
+
 /// Router trait for handling HTTP routes in serverless functions
 ///
 /// This trait defines the routing abstraction for HTTP-based serverless functions.
@@ -61,17 +111,17 @@ pub trait Router: Send + Sync + 'static {
 
 /// A builder for creating routers with route registration
 #[derive(Default)]
-#[allow(dead_code)]
 pub struct RouterBuilder {
     routes: HashMap<(Method, String), RouteHandler>,
+    static_dirs: Vec<(String, PathBuf)>,
 }
 
-#[allow(dead_code)]
 impl RouterBuilder {
     /// Create a new router builder
     pub fn new() -> Self {
         Self {
             routes: HashMap::new(),
+            static_dirs: Vec::new(),
         }
     }
 
@@ -85,6 +135,59 @@ impl RouterBuilder {
         self
     }
 
+    /// Add a route wrapped by middlewares that apply to this route only
+    ///
+    /// The first middleware in `layers` is the outermost and runs first. Middlewares
+    /// of enclosing scopes run before the route's own.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use serverless_rs::{Context, Handler, Middleware, Request, Response, Result, RouterBuilder};
+    /// use async_trait::async_trait;
+    /// use http::Method;
+    /// use std::sync::Arc;
+    ///
+    /// struct RequireAuth;
+    ///
+    /// #[async_trait]
+    /// impl Middleware for RequireAuth {
+    ///     async fn handle(&self, req: Request, ctx: &Context, next: &dyn Handler) -> Result<Response> {
+    ///         match req.header("Authorization") {
+    ///             Some(_) => next.handle(req, ctx).await,
+    ///             None => Ok(Response::new().with_status(401)),
+    ///         }
+    ///     }
+    /// }
+    ///
+    /// fn profile(_req: Request, _ctx: &Context) -> Result<Response> {
+    ///     Ok(Response::text("profile"))
+    /// }
+    ///
+    /// fn health(_req: Request, _ctx: &Context) -> Result<Response> {
+    ///     Ok(Response::text("ok"))
+    /// }
+    ///
+    /// let router = RouterBuilder::new()
+    ///     .route_with(Method::GET, "/profile", profile, &[Arc::new(RequireAuth)])
+    ///     .get("/health", health)
+    ///     .build();
+    /// ```
+    pub fn route_with<H>(
+        mut self,
+        method: Method,
+        path: impl Into<String>,
+        handler: H,
+        layers: &[Arc<dyn Middleware>],
+    ) -> Self
+    where
+        H: Handler,
+    {
+        let handler = Layered::wrap(Arc::new(handler), layers);
+        self.routes.insert((method, path.into()), handler);
+        self
+    }
+
     /// Add a GET route to the router
     pub fn get<H>(self, path: impl Into<String>, handler: H) -> Self
     where
@@ -117,18 +220,495 @@ impl RouterBuilder {
         self.route(Method::DELETE, path, handler)
     }
 
+    /// Add a PATCH route to the router
+    pub fn patch<H>(self, path: impl Into<String>, handler: H) -> Self
+    where
+        H: Handler,
+    {
+        self.route(Method::PATCH, path, handler)
+    }
+
+    /// Add a HEAD route to the router
+    ///
+    /// Without one, `HEAD` requests are only answered for static files.
+    pub fn head<H>(self, path: impl Into<String>, handler: H) -> Self
+    where
+        H: Handler,
+    {
+        self.route(Method::HEAD, path, handler)
+    }
+
+    /// Add an OPTIONS route to the router
+    pub fn options<H>(self, path: impl Into<String>, handler: H) -> Self
+    where
+        H: Handler,
+    {
+        self.route(Method::OPTIONS, path, handler)
+    }
+
+    /// Add a route matching every standard HTTP method
+    ///
+    /// This is the same as adding the route for each of `GET`, `POST`, `PUT`,
+    /// `DELETE`, `PATCH`, `HEAD`, `OPTIONS`, `CONNECT` and `TRACE`. As with other
+    /// routes, a later registration for the same method and path replaces an earlier
+    /// one, so add method-specific routes after `any` to override it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use serverless_rs::{Context, Request, Response, Result, RouterBuilder};
+    ///
+    /// fn proxy(req: Request, _ctx: &Context) -> Result<Response> {
+    ///     Ok(Response::text(format!("proxied {}", req.method().unwrap())))
+    /// }
+    ///
+    /// fn health(_req: Request, _ctx: &Context) -> Result<Response> {
+    ///     Ok(Response::text("ok"))
+    /// }
+    ///
+    /// let router = RouterBuilder::new()
+    ///     .any("/proxy/*", proxy)
+    ///     .get("/proxy/health", health)
+    ///     .build();
+    /// ```
+    pub fn any<H>(mut self, path: impl Into<String>, handler: H) -> Self
+    where
+        H: Handler,
+    {
+        let path = path.into();
+        let handler: RouteHandler = Arc::new(handler);
+        for method in ANY_METHODS {
+            self.routes.insert((method, path.clone()), handler.clone());
+        }
+        self
+    }
+
+    /// Add a route described by a [`RouteInfo`], such as one declared with `#[route]`
+    ///
+    /// # Panics
+    ///
+    /// Panics if the route's method is not a valid HTTP method.
+    pub fn mount<H>(self, route: &RouteInfo, handler: H) -> Self
+    where
+        H: Handler,
+    {
+        let method = Method::from_bytes(route.method.to_ascii_uppercase().as_bytes())
+            .unwrap_or_else(|_| {
+                panic!(
+                    "Invalid HTTP method for route {}: {}",
+                    route.path, route.method
+                )
+            });
+        self.route(method, route.path.clone(), handler)
+    }
+
+    /// Register a group of routes sharing a path prefix, middlewares, and state
+    ///
+    /// Routes registered on the [`Scope`] are mounted under `prefix`, wrapped by the
+    /// scope's middlewares, and see the scope's state in their [`Context`]. Routes
+    /// outside the scope are unaffected.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use serverless_rs::{Context, Handler, Request, Response, RouterBuilder};
+    /// use async_trait::async_trait;
+    ///
+    /// struct AdminDb(&'static str);
+    ///
+    /// struct ListUsers;
+    ///
+    /// #[async_trait]
+    /// impl Handler for ListUsers {
+    ///     async fn handle(&self, _req: Request, ctx: &Context) -> serverless_rs::Result<Response> {
+    ///         let db = ctx.extension::<AdminDb>().unwrap();
+    ///         Ok(Response::text(format!("users from {}", db.0)))
+    ///     }
+    /// }
+    ///
+    /// let router = RouterBuilder::new()
+    ///     .scope("/admin", |admin| {
+    ///         admin
+    ///             .with_state(AdminDb("postgres://admin"))
+    ///             .get("/users", ListUsers)
+    ///     })
+    ///     .build();
+    /// ```
+    pub fn scope<F>(mut self, prefix: impl Into<String>, configure: F) -> Self
+    where
+        F: FnOnce(Scope) -> Scope,
+    {
+        let scope = configure(Scope::new(prefix));
+        for (method, path, handler) in scope.into_routes() {
+            self.routes.insert((method, path), handler);
+        }
+        self
+    }
+
+    /// Combine the routes and static directories of another builder into this one
+    ///
+    /// Unlike [`scope`](Self::scope), no prefix is added, so feature modules can each
+    /// build their routes from the root and be assembled into one router.
+    ///
+    /// # Panics
+    ///
+    /// Panics if both builders have a route for the same method and path, or a
+    /// static directory under the same prefix; see [`try_merge`](Self::try_merge).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use serverless_rs::{Context, Request, Response, Result, RouterBuilder};
+    ///
+    /// fn list_orders(_req: Request, _ctx: &Context) -> Result<Response> {
+    ///     Ok(Response::text("orders"))
+    /// }
+    ///
+    /// fn list_invoices(_req: Request, _ctx: &Context) -> Result<Response> {
+    ///     Ok(Response::text("invoices"))
+    /// }
+    ///
+    /// let orders = RouterBuilder::new().get("/orders", list_orders);
+    /// let billing = RouterBuilder::new().get("/invoices", list_invoices);
+    /// let router = orders.merge(billing).build();
+    /// ```
+    pub fn merge(self, other: RouterBuilder) -> Self {
+        self.try_merge(other)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Combine another builder into this one, failing on conflicting routes
+    ///
+    /// Routes conflict when they have the same method and their paths are the same,
+    /// ignoring the names of `{name}` parameters, since only one of them could ever
+    /// match. Static directories conflict when their prefixes are the same. Nothing
+    /// is overwritten: every conflict is listed in the returned HTTP error.
+    pub fn try_merge(mut self, other: RouterBuilder) -> Result<Self> {
+        let patterns: HashMap<(Method, String), &String> = self
+            .routes
+            .keys()
+            .map(|(method, path)| ((method.clone(), route_shape(path)), path))
+            .collect();
+        let mut conflicts: Vec<String> = other
+            .routes
+            .keys()
+            .filter_map(|(method, path)| {
+                let existing = patterns.get(&(method.clone(), route_shape(path)))?;
+                Some(if *existing == path {
+                    format!("{} {}", method, path)
+                } else {
+                    format!("{} {} (as {})", method, path, existing)
+                })
+            })
+            .collect();
+        conflicts.sort();
+        conflicts.extend(
+            other
+                .static_dirs
+                .iter()
+                .filter(|(prefix, _)| self.static_dirs.iter().any(|(p, _)| p == prefix))
+                .map(|(prefix, _)| format!("static directory {}", prefix)),
+        );
+        if !conflicts.is_empty() {
+            return Err(Error::http(format!(
+                "Conflicting routes when merging routers: {}",
+                conflicts.join(", ")
+            )));
+        }
+
+        self.routes.extend(other.routes);
+        self.static_dirs.extend(other.static_dirs);
+        Ok(self)
+    }
+
+    /// Serve static files from a directory under a path prefix
+    ///
+    /// `GET` and `HEAD` requests for `{prefix}/{file}` that don't match a registered
+    /// route are answered with [`Response::file`] for `{dir}/{file}`, without the body
+    /// for `HEAD`. Paths containing `..` segments are rejected with 403 to prevent
+    /// escaping the directory.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use serverless_rs::RouterBuilder;
+    ///
+    /// let router = RouterBuilder::new().static_dir("/assets", "./public").build();
+    /// ```
+    pub fn static_dir(mut self, prefix: impl Into<String>, dir: impl Into<PathBuf>) -> Self {
+        let prefix = prefix.into().trim_end_matches('/').to_string();
+        self.static_dirs.push((prefix, dir.into()));
+        self
+    }
+
     /// Build the router
     pub fn build(self) -> impl Router {
+        let mut patterns = RouteNode::default();
+        for (method, pattern) in self.routes.keys() {
+            patterns.insert(method, pattern);
+        }
         BuildRouter {
             routes: self.routes,
+            patterns,
+            static_dirs: self.static_dirs,
         }
     }
 }
 
+/// A group of routes sharing a path prefix, middlewares, and state
+///
+/// Created by [`RouterBuilder::scope`]. Scopes can be nested; the outer scope's
+/// middlewares run before the inner scope's, and inner state overrides outer state
+/// of the same type.
+pub struct Scope {
+    prefix: String,
+    routes: Vec<(Method, String, RouteHandler)>,
+    middlewares: Vec<Arc<dyn Middleware>>,
+    state: Extensions,
+}
+
+impl Scope {
+    /// Create a new scope for the given path prefix
+    fn new(prefix: impl Into<String>) -> Self {
+        Self {
+            prefix: prefix.into().trim_end_matches('/').to_string(),
+            routes: Vec::new(),
+            middlewares: Vec::new(),
+            state: Extensions::new(),
+        }
+    }
+
+    /// Add a middleware applied to every route in this scope
+    ///
+    /// Middlewares run in the order they are added, regardless of whether routes
+    /// were registered before or after them.
+    pub fn layer<M>(mut self, middleware: M) -> Self
+    where
+        M: Middleware,
+    {
+        self.middlewares.push(Arc::new(middleware));
+        self
+    }
+
+    /// Attach shared state visible to every route and middleware in this scope
+    pub fn with_state<T: Send + Sync + 'static>(mut self, state: T) -> Self {
+        self.state.insert(state);
+        self
+    }
+
+    /// Add a route to the scope, relative to the scope's prefix
+    pub fn route<H>(mut self, method: Method, path: impl Into<String>, handler: H) -> Self
+    where
+        H: Handler,
+    {
+        let path = self.join(&path.into());
+        self.routes.push((method, path, Arc::new(handler)));
+        self
+    }
+
+    /// Add a route wrapped by middlewares that apply to this route only
+    ///
+    /// The route's middlewares run after the scope's, and see the scope's state.
+    /// See [`RouterBuilder::route_with`].
+    pub fn route_with<H>(
+        mut self,
+        method: Method,
+        path: impl Into<String>,
+        handler: H,
+        layers: &[Arc<dyn Middleware>],
+    ) -> Self
+    where
+        H: Handler,
+    {
+        let path = self.join(&path.into());
+        let handler = Layered::wrap(Arc::new(handler), layers);
+        self.routes.push((method, path, handler));
+        self
+    }
+
+    /// Add a GET route to the scope
+    pub fn get<H>(self, path: impl Into<String>, handler: H) -> Self
+    where
+        H: Handler,
+    {
+        self.route(Method::GET, path, handler)
+    }
+
+    /// Add a POST route to the scope
+    pub fn post<H>(self, path: impl Into<String>, handler: H) -> Self
+    where
+        H: Handler,
+    {
+        self.route(Method::POST, path, handler)
+    }
+
+    /// Add a PUT route to the scope
+    pub fn put<H>(self, path: impl Into<String>, handler: H) -> Self
+    where
+        H: Handler,
+    {
+        self.route(Method::PUT, path, handler)
+    }
+
+    /// Add a DELETE route to the scope
+    pub fn delete<H>(self, path: impl Into<String>, handler: H) -> Self
+    where
+        H: Handler,
+    {
+        self.route(Method::DELETE, path, handler)
+    }
+
+    /// Add a PATCH route to the scope
+    pub fn patch<H>(self, path: impl Into<String>, handler: H) -> Self
+    where
+        H: Handler,
+    {
+        self.route(Method::PATCH, path, handler)
+    }
+
+    /// Add a HEAD route to the scope
+    ///
+    /// Without one, `HEAD` requests are only answered for static files.
+    pub fn head<H>(self, path: impl Into<String>, handler: H) -> Self
+    where
+        H: Handler,
+    {
+        self.route(Method::HEAD, path, handler)
+    }
+
+    /// Add an OPTIONS route to the scope
+    pub fn options<H>(self, path: impl Into<String>, handler: H) -> Self
+    where
+        H: Handler,
+    {
+        self.route(Method::OPTIONS, path, handler)
+    }
+
+    /// Add a route matching every standard HTTP method to the scope
+    ///
+    /// See [`RouterBuilder::any`].
+    pub fn any<H>(mut self, path: impl Into<String>, handler: H) -> Self
+    where
+        H: Handler,
+    {
+        let path = self.join(&path.into());
+        let handler: RouteHandler = Arc::new(handler);
+        for method in ANY_METHODS {
+            self.routes.push((method, path.clone(), handler.clone()));
+        }
+        self
+    }
+
+    /// Register a nested scope under this scope's prefix
+    pub fn scope<F>(mut self, prefix: impl Into<String>, configure: F) -> Self
+    where
+        F: FnOnce(Scope) -> Scope,
+    {
+        let prefix = self.join(&prefix.into());
+        let nested = configure(Scope::new(prefix));
+        self.routes.extend(nested.into_routes());
+        self
+    }
+
+    /// Join a relative path onto the scope prefix
+    fn join(&self, path: &str) -> String {
+        match path.trim_start_matches('/') {
+            "" if self.prefix.is_empty() => "/".to_string(),
+            "" => self.prefix.clone(),
+            relative => format!("{}/{}", self.prefix, relative),
+        }
+    }
+
+    /// Consume the scope, wrapping each route with the scope's middlewares and state
+    fn into_routes(self) -> Vec<(Method, String, RouteHandler)> {
+        let Scope {
+            routes,
+            middlewares,
+            state,
+            ..
+        } = self;
+
+        routes
+            .into_iter()
+            .map(|(method, path, handler)| {
+                let handler = Layered::wrap(handler, &middlewares);
+                (method, path, WithState::wrap(handler, &state))
+            })
+            .collect()
+    }
+}
+
 /// Router implementation created by RouterBuilder
-#[allow(dead_code)]
 struct BuildRouter {
     routes: HashMap<(Method, String), RouteHandler>,
+    patterns: RouteNode,
+    static_dirs: Vec<(String, PathBuf)>,
+}
+
+impl BuildRouter {
+    /// Find a route whose path pattern matches, extracting path parameters
+    ///
+    /// When several patterns match, they are compared segment by segment from the
+    /// left: a literal segment beats a `{name}` parameter, which beats a `*` wildcard.
+    /// This makes the longest literal prefix win among wildcard routes.
+    fn match_pattern(
+        &self,
+        method: &Method,
+        path: &str,
+    ) -> Option<(&RouteHandler, Vec<(String, String)>)> {
+        let segments: Vec<&str> = path.split('/').collect();
+        let mut candidates = Vec::new();
+        self.patterns
+            .find(method, &segments, &mut Vec::new(), &mut candidates);
+
+        candidates
+            .into_iter()
+            // Equally ranked patterns only differ in parameter names; pick one stably
+            .max_by(|(a, _), (b, _)| a.rank.cmp(&b.rank).then_with(|| b.pattern.cmp(&a.pattern)))
+            .and_then(|(endpoint, params)| {
+                let key = (method.clone(), endpoint.pattern.clone());
+                Some((self.routes.get(&key)?, params))
+            })
+    }
+
+    /// Serve a static file if the path falls under a registered static directory
+    fn serve_static(&self, method: &Method, path: &str) -> Option<Response> {
+        if method != Method::GET && method != Method::HEAD {
+            return None;
+        }
+
+        for (prefix, dir) in &self.static_dirs {
+            let Some(relative) = path.strip_prefix(prefix.as_str()) else {
+                continue;
+            };
+            let Some(relative) = relative.strip_prefix('/') else {
+                continue;
+            };
+
+            // Reject anything that could escape the directory
+            let relative = Path::new(relative);
+            if relative
+                .components()
+                .any(|c| !matches!(c, Component::Normal(_)))
+            {
+                return Some(Response::forbidden());
+            }
+
+            let file = dir.join(relative);
+            if !file.is_file() {
+                return Some(Response::not_found());
+            }
+
+            let response = Response::file(file).unwrap_or_else(|_| Response::internal_error());
+            // HEAD gets the headers of the GET response, Content-Length included
+            if method == Method::HEAD {
+                return Some(response.with_body(Vec::new()).with_base64(false));
+            }
+            return Some(response);
+        }
+
+        None
+    }
 }
 
 #[async_trait]
@@ -145,9 +725,18 @@ impl Router for BuildRouter {
             .path()
             .to_string();
 
-        // Find the handler for this route
+        // Find the handler for this route, honoring conditional request headers
         if let Some(handler) = self.routes.get(&(method.clone(), path.clone())) {
-            handler.handle(req, ctx).await
+            let validators = conditional_validators(&req);
+            Ok(handler.handle(req, ctx).await?.conditional(&validators))
+        } else if let Some((handler, params)) = self.match_pattern(&method, &path) {
+            let validators = conditional_validators(&req);
+            let req = params
+                .into_iter()
+                .fold(req, |req, (name, value)| req.with_path_param(name, value));
+            Ok(handler.handle(req, ctx).await?.conditional(&validators))
+        } else if let Some(response) = self.serve_static(&method, &path) {
+            Ok(response.conditional(&req))
         } else {
             // Return 404 if no handler is found
             Ok(Response::not_found())
@@ -155,12 +744,165 @@ impl Router for BuildRouter {
     }
 }
 
-/// Middleware support will be implemented in future versions
-/// We'll keep the router simpler for now to pass compilation
+/// The kind of a route pattern segment, ordered by matching priority
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum SegmentKind {
+    /// A trailing `*` matching the rest of the path
+    Wildcard,
+    /// A `{name}` parameter matching one segment
+    Param,
+    /// A literal segment
+    Literal,
+}
+
+impl SegmentKind {
+    fn of(segment: &str) -> Self {
+        if segment == "*" {
+            SegmentKind::Wildcard
+        } else if is_param(segment) {
+            SegmentKind::Param
+        } else {
+            SegmentKind::Literal
+        }
+    }
+}
+
+/// A route pattern ending at a [`RouteNode`]
+#[derive(Debug)]
+struct Endpoint {
+    method: Method,
+    pattern: String,
+    /// Kinds of the pattern's segments, compared to pick the best match
+    rank: Vec<SegmentKind>,
+}
+
+/// A segment trie of route patterns
+///
+/// Matching walks the path one segment at a time, following only the branches that
+/// can match, so the cost grows with the path length rather than the number of
+/// routes. Candidates are then ranked as [`BuildRouter::match_pattern`] describes.
+#[derive(Debug, Default)]
+struct RouteNode {
+    /// Patterns ending at this node
+    endpoints: Vec<Endpoint>,
+    /// Patterns ending with a `*` after this node
+    wildcards: Vec<Endpoint>,
+    literals: HashMap<String, RouteNode>,
+    /// Children for `{name}` segments, by parameter name
+    params: Vec<(String, RouteNode)>,
+}
+
+impl RouteNode {
+    fn insert(&mut self, method: &Method, pattern: &str) {
+        let segments: Vec<&str> = pattern.split('/').collect();
+        let endpoint = Endpoint {
+            method: method.clone(),
+            pattern: pattern.to_string(),
+            rank: segments
+                .iter()
+                .map(|segment| SegmentKind::of(segment))
+                .collect(),
+        };
+
+        let mut node = self;
+        for (index, segment) in segments.iter().enumerate() {
+            if *segment == "*" && index == segments.len() - 1 {
+                node.wildcards.push(endpoint);
+                return;
+            }
+            node = if is_param(segment) {
+                let name = &segment[1..segment.len() - 1];
+                match node
+                    .params
+                    .iter()
+                    .position(|(existing, _)| existing == name)
+                {
+                    Some(position) => &mut node.params[position].1,
+                    None => {
+                        node.params.push((name.to_string(), RouteNode::default()));
+                        &mut node.params.last_mut().expect("just pushed").1
+                    }
+                }
+            } else {
+                node.literals.entry(segment.to_string()).or_default()
+            };
+        }
+        node.endpoints.push(endpoint);
+    }
+
+    /// Collect every pattern for `method` matching the remaining path segments
+    ///
+    /// `{name}` segments match one non-empty segment. A trailing `*` matches the
+    /// non-empty rest of the path, which is returned as the `*` parameter.
+    fn find<'a>(
+        &'a self,
+        method: &Method,
+        segments: &[&str],
+        params: &mut Vec<(String, String)>,
+        candidates: &mut Vec<(&'a Endpoint, Vec<(String, String)>)>,
+    ) {
+        let Some((segment, rest)) = segments.split_first() else {
+            for endpoint in self.endpoints.iter().filter(|e| e.method == *method) {
+                candidates.push((endpoint, params.clone()));
+            }
+            return;
+        };
+
+        let remaining = segments.join("/");
+        if !remaining.is_empty() {
+            for endpoint in self.wildcards.iter().filter(|e| e.method == *method) {
+                let mut params = params.clone();
+                params.push(("*".to_string(), percent_decode_path(&remaining)));
+                candidates.push((endpoint, params));
+            }
+        }
+
+        if let Some(child) = self.literals.get(*segment) {
+            child.find(method, rest, params, candidates);
+        }
+        if !segment.is_empty() {
+            for (name, child) in &self.params {
+                params.push((name.clone(), percent_decode_path(segment)));
+                child.find(method, rest, params, candidates);
+                params.pop();
+            }
+        }
+    }
+}
+
+/// Returns a route pattern with its parameter names removed, so patterns matching
+/// the same paths compare equal
+fn route_shape(pattern: &str) -> String {
+    pattern
+        .split('/')
+        .map(|segment| if is_param(segment) { "{}" } else { segment })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Returns whether a path segment is a `{name}` parameter
+fn is_param(segment: &str) -> bool {
+    segment.len() > 2 && segment.starts_with('{') && segment.ends_with('}')
+}
+
+/// Copy the method and conditional request headers, leaving the body behind
+fn conditional_validators(req: &Request) -> Request {
+    let mut validators = Request::new();
+    if let Some(method) = req.method() {
+        validators = validators.with_method(method.clone());
+    }
+    for name in ["If-None-Match", "If-Modified-Since"] {
+        if let Some(value) = req.header(name) {
+            validators = validators.with_header(name, value.clone());
+        }
+    }
+    validators
+}
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{handler_fn, BoxFuture};
 
     struct HelloHandler;
 
@@ -222,4 +964,475 @@ mod tests {
         let response = router.route(req, &ctx).await.unwrap();
         assert_eq!(response.status(), 404);
     }
+
+    #[tokio::test]
+    async fn test_merge() {
+        let orders = RouterBuilder::new()
+            .get("/orders", HelloHandler)
+            .static_dir("/assets", "./public");
+        let search = RouterBuilder::new().get("/search", EchoHandler);
+        let router = orders.merge(search).build();
+
+        let ctx = Context::new();
+        for (path, body) in [
+            ("/orders", "Hello, world!"),
+            ("/search", "Hello, stranger!"),
+        ] {
+            let req = Request::new()
+                .with_method(Method::GET)
+                .with_uri(path.parse().unwrap());
+            let response = router.route(req, &ctx).await.unwrap();
+            assert_eq!(response.body(), body.as_bytes());
+        }
+    }
+
+    #[test]
+    fn test_merge_conflicts() {
+        let base = || {
+            RouterBuilder::new()
+                .get("/users/{id}", HelloHandler)
+                .post("/users", HelloHandler)
+                .static_dir("/assets", "./public")
+        };
+
+        // A different method on the same path is not a conflict
+        assert!(base()
+            .try_merge(RouterBuilder::new().put("/users/{id}", EchoHandler))
+            .is_ok());
+
+        let overlapping = RouterBuilder::new()
+            .post("/users", EchoHandler)
+            .get("/users/{user_id}", EchoHandler)
+            .static_dir("/assets", "./other");
+        let err = match base().try_merge(overlapping) {
+            Err(err) => err.to_string(),
+            Ok(_) => panic!("overlapping routes were merged"),
+        };
+        assert!(
+            err.contains("GET /users/{user_id} (as /users/{id})"),
+            "{}",
+            err
+        );
+        assert!(err.contains("POST /users"), "{}", err);
+        assert!(err.contains("static directory /assets"), "{}", err);
+
+        let merged = std::panic::catch_unwind(|| {
+            base().merge(RouterBuilder::new().post("/users", EchoHandler))
+        });
+        assert!(merged.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_mount_with_path_params() {
+        async fn get_user(req: Request, _ctx: &Context) -> Result<Response> {
+            let id = req.path_param("id").cloned().unwrap_or_default();
+            Ok(Response::text(format!("user {}", id)))
+        }
+
+        fn get_user_boxed(req: Request, ctx: &Context) -> BoxFuture<'_, Result<Response>> {
+            Box::pin(get_user(req, ctx))
+        }
+
+        let router = RouterBuilder::new()
+            .mount(
+                &RouteInfo::new("get", "/users/{id}"),
+                handler_fn(get_user_boxed),
+            )
+            .get("/users/me", HelloHandler)
+            .build();
+        let ctx = Context::new();
+
+        let req = Request::new()
+            .with_method(Method::GET)
+            .with_uri("/users/42".parse().unwrap());
+        let response = router.route(req, &ctx).await.unwrap();
+        assert_eq!(response.body(), b"user 42");
+
+        // Exact matches take priority over patterns
+        let req = Request::new()
+            .with_method(Method::GET)
+            .with_uri("/users/me".parse().unwrap());
+        let response = router.route(req, &ctx).await.unwrap();
+        assert_eq!(response.body(), b"Hello, world!");
+
+        // Empty segments and other methods don't match
+        for (method, path) in [(Method::GET, "/users/"), (Method::POST, "/users/42")] {
+            let req = Request::new()
+                .with_method(method)
+                .with_uri(path.parse().unwrap());
+            let response = router.route(req, &ctx).await.unwrap();
+            assert_eq!(response.status(), 404);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_route_priority() {
+        struct Named(&'static str);
+
+        #[async_trait]
+        impl Handler for Named {
+            async fn handle(&self, req: Request, _ctx: &Context) -> Result<Response> {
+                let params = [req.path_param("name"), req.path_param("*")];
+                let params: Vec<&str> = params.iter().flatten().map(|p| p.as_str()).collect();
+                Ok(Response::text(format!("{} {}", self.0, params.join(","))))
+            }
+        }
+
+        let router = RouterBuilder::new()
+            .get("/files/readme", Named("exact"))
+            .get("/files/{name}", Named("param"))
+            .get("/files/*", Named("wildcard"))
+            .get("/files/docs/*", Named("docs"))
+            .get("/*", Named("fallback"))
+            .build();
+        let ctx = Context::new();
+
+        for (path, expected) in [
+            ("/files/readme", "exact "),
+            ("/files/license", "param license"),
+            ("/files/a/b%20c", "wildcard a/b c"),
+            ("/files/docs/guide/intro", "docs guide/intro"),
+            ("/files/docs", "param docs"),
+            ("/images/logo.png", "fallback images/logo.png"),
+        ] {
+            let req = Request::new()
+                .with_method(Method::GET)
+                .with_uri(path.parse().unwrap());
+            let response = router.route(req, &ctx).await.unwrap();
+            assert_eq!(response.body(), expected.as_bytes(), "{}", path);
+        }
+
+        // A literal branch that dead-ends falls back to a parameter one
+        let router = RouterBuilder::new()
+            .get("/files/readme/raw", Named("raw"))
+            .get("/files/{name}/meta", Named("meta"))
+            .build();
+        let req = Request::new()
+            .with_method(Method::GET)
+            .with_uri("/files/readme/meta".parse().unwrap());
+        let response = router.route(req, &ctx).await.unwrap();
+        assert_eq!(response.body(), b"meta readme");
+
+        // Wildcards need a non-empty remainder
+        let router = RouterBuilder::new()
+            .get("/files/*", Named("wildcard"))
+            .build();
+        for path in ["/files", "/files/"] {
+            let req = Request::new()
+                .with_method(Method::GET)
+                .with_uri(path.parse().unwrap());
+            let response = router.route(req, &ctx).await.unwrap();
+            assert_eq!(response.status(), 404, "{}", path);
+        }
+    }
+
+    struct TaggedHandler;
+
+    #[async_trait]
+    impl Handler for TaggedHandler {
+        async fn handle(&self, _req: Request, _ctx: &Context) -> Result<Response> {
+            Ok(Response::text("cached").with_etag("v1"))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_conditional_route() {
+        let router = RouterBuilder::new().get("/cached", TaggedHandler).build();
+        let ctx = Context::new();
+
+        let req = Request::new()
+            .with_method(Method::GET)
+            .with_uri("/cached".parse().unwrap())
+            .with_header("If-None-Match", "\"v1\"");
+        let response = router.route(req, &ctx).await.unwrap();
+        assert_eq!(response.status(), 304);
+        assert!(response.body().is_empty());
+
+        let req = Request::new()
+            .with_method(Method::GET)
+            .with_uri("/cached".parse().unwrap())
+            .with_header("If-None-Match", "\"v2\"");
+        let response = router.route(req, &ctx).await.unwrap();
+        assert_eq!(response.status(), 200);
+        assert_eq!(std::str::from_utf8(response.body()).unwrap(), "cached");
+    }
+
+    struct AdminDb(&'static str);
+
+    struct RequireAuth;
+
+    #[async_trait]
+    impl Middleware for RequireAuth {
+        async fn handle(
+            &self,
+            req: Request,
+            ctx: &Context,
+            next: &dyn Handler,
+        ) -> Result<Response> {
+            if req.header("Authorization").is_none() {
+                return Ok(Response::new().with_status(401).with_body("Unauthorized"));
+            }
+            let response = next.handle(req, ctx).await?;
+            Ok(response.with_header("X-Auth-Checked", "true"))
+        }
+    }
+
+    struct DbHandler;
+
+    #[async_trait]
+    impl Handler for DbHandler {
+        async fn handle(&self, _req: Request, ctx: &Context) -> Result<Response> {
+            let db = ctx.extension::<AdminDb>().map(|db| db.0).unwrap_or("none");
+            Ok(Response::text(db))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_scope() {
+        let router = RouterBuilder::new()
+            .get("/users", DbHandler)
+            .scope("/admin", |admin| {
+                admin
+                    .get("/users", DbHandler)
+                    .layer(RequireAuth)
+                    .with_state(AdminDb("postgres://admin"))
+                    .post("/users", DbHandler)
+                    .scope("/reports", |reports| reports.get("/", DbHandler))
+            })
+            .build();
+        let ctx = Context::new();
+
+        // Every route under /admin runs the auth middleware and sees the scope state
+        for (method, path) in [
+            (Method::GET, "/admin/users"),
+            (Method::POST, "/admin/users"),
+            (Method::GET, "/admin/reports"),
+        ] {
+            let req = Request::new()
+                .with_method(method.clone())
+                .with_uri(path.parse().unwrap());
+            let response = router.route(req, &ctx).await.unwrap();
+            assert_eq!(response.status(), 401, "{} {}", method, path);
+
+            let req = Request::new()
+                .with_method(method.clone())
+                .with_uri(path.parse().unwrap())
+                .with_header("Authorization", "Bearer token");
+            let response = router.route(req, &ctx).await.unwrap();
+            assert_eq!(response.status(), 200, "{} {}", method, path);
+            assert_eq!(response.header("X-Auth-Checked"), Some(&"true".to_string()));
+            assert_eq!(
+                std::str::from_utf8(response.body()).unwrap(),
+                "postgres://admin"
+            );
+        }
+
+        // Sibling routes don't run the scope's middleware or see its state
+        let req = Request::new()
+            .with_method(Method::GET)
+            .with_uri("/users".parse().unwrap());
+        let response = router.route(req, &ctx).await.unwrap();
+        assert_eq!(response.status(), 200);
+        assert!(response.header("X-Auth-Checked").is_none());
+        assert_eq!(std::str::from_utf8(response.body()).unwrap(), "none");
+    }
+
+    /// Appends its name to the request's `X-Layers` header
+    struct Tag(&'static str);
+
+    #[async_trait]
+    impl Middleware for Tag {
+        async fn handle(
+            &self,
+            req: Request,
+            ctx: &Context,
+            next: &dyn Handler,
+        ) -> Result<Response> {
+            let layers = match req.header("X-Layers") {
+                Some(layers) => format!("{},{}", layers, self.0),
+                None => self.0.to_string(),
+            };
+            next.handle(req.with_header("X-Layers", layers), ctx).await
+        }
+    }
+
+    struct LayersHandler;
+
+    #[async_trait]
+    impl Handler for LayersHandler {
+        async fn handle(&self, req: Request, ctx: &Context) -> Result<Response> {
+            let layers = req.header("X-Layers").cloned().unwrap_or_default();
+            let db = ctx.extension::<AdminDb>().map(|db| db.0).unwrap_or("none");
+            Ok(Response::text(format!("{} {}", layers, db)))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_method_shortcuts() {
+        struct Named(&'static str);
+
+        #[async_trait]
+        impl Handler for Named {
+            async fn handle(&self, req: Request, _ctx: &Context) -> Result<Response> {
+                Ok(Response::text(format!(
+                    "{} {}",
+                    self.0,
+                    req.method().unwrap()
+                )))
+            }
+        }
+
+        let router = RouterBuilder::new()
+            .patch("/items/{id}", Named("patch"))
+            .head("/items/{id}", Named("head"))
+            .options("/items/{id}", Named("options"))
+            .any("/proxy/*", Named("any"))
+            .get("/proxy/health", Named("health"))
+            .scope("/v2", |v2| {
+                v2.any("/echo", Named("v2"))
+                    .patch("/echo", Named("v2 patch"))
+            })
+            .build();
+        let ctx = Context::new();
+
+        for (method, path, expected) in [
+            (Method::PATCH, "/items/1", "patch PATCH"),
+            (Method::HEAD, "/items/1", "head HEAD"),
+            (Method::OPTIONS, "/items/1", "options OPTIONS"),
+            (Method::DELETE, "/proxy/a/b", "any DELETE"),
+            (Method::TRACE, "/proxy/a", "any TRACE"),
+            (Method::GET, "/proxy/health", "health GET"),
+            (Method::POST, "/proxy/health", "any POST"),
+            (Method::PUT, "/v2/echo", "v2 PUT"),
+            (Method::PATCH, "/v2/echo", "v2 patch PATCH"),
+        ] {
+            let req = Request::new()
+                .with_method(method)
+                .with_uri(path.parse().unwrap());
+            let response = router.route(req, &ctx).await.unwrap();
+            assert_eq!(response.body(), expected.as_bytes(), "{}", path);
+        }
+
+        let req = Request::new()
+            .with_method(Method::GET)
+            .with_uri("/items/1".parse().unwrap());
+        let response = router.route(req, &ctx).await.unwrap();
+        assert_eq!(response.status(), 404);
+    }
+
+    #[tokio::test]
+    async fn test_route_with() {
+        let router = RouterBuilder::new()
+            .route_with(Method::GET, "/profile", DbHandler, &[Arc::new(RequireAuth)])
+            .get("/public", DbHandler)
+            .scope("/admin", |admin| {
+                admin
+                    .layer(Tag("scope"))
+                    .with_state(AdminDb("postgres://admin"))
+                    .route_with(
+                        Method::GET,
+                        "/layers",
+                        LayersHandler,
+                        &[Arc::new(Tag("first")), Arc::new(Tag("second"))],
+                    )
+            })
+            .build();
+        let ctx = Context::new();
+
+        let get = |path: &str| {
+            Request::new()
+                .with_method(Method::GET)
+                .with_uri(path.parse().unwrap())
+        };
+
+        // The protected route runs its auth layer
+        let response = router.route(get("/profile"), &ctx).await.unwrap();
+        assert_eq!(response.status(), 401);
+        let req = get("/profile").with_header("Authorization", "Bearer token");
+        let response = router.route(req, &ctx).await.unwrap();
+        assert_eq!(response.status(), 200);
+        assert_eq!(response.header("X-Auth-Checked"), Some(&"true".to_string()));
+
+        // The public route on the same router doesn't
+        let response = router.route(get("/public"), &ctx).await.unwrap();
+        assert_eq!(response.status(), 200);
+        assert!(response.header("X-Auth-Checked").is_none());
+
+        // Route layers run in order after the scope's, and see the scope's state
+        let response = router.route(get("/admin/layers"), &ctx).await.unwrap();
+        assert_eq!(
+            std::str::from_utf8(response.body()).unwrap(),
+            "scope,first,second postgres://admin"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_static_dir() {
+        let dir = std::env::temp_dir().join(format!("serverless_rs_static_{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("css")).unwrap();
+        std::fs::write(dir.join("css/site.css"), "body {}").unwrap();
+
+        let router = RouterBuilder::new()
+            .get("/assets/override.css", HelloHandler)
+            .static_dir("/assets", &dir)
+            .build();
+        let ctx = Context::new();
+
+        // Files under the directory are served
+        let req = Request::new()
+            .with_method(Method::GET)
+            .with_uri("/assets/css/site.css".parse().unwrap());
+        let response = router.route(req, &ctx).await.unwrap();
+        assert_eq!(response.status(), 200);
+        assert_eq!(
+            response.header("Content-Type"),
+            Some(&"text/css".to_string())
+        );
+        assert_eq!(std::str::from_utf8(response.body()).unwrap(), "body {}");
+
+        // HEAD requests get the same headers without the body
+        let req = Request::new()
+            .with_method(Method::HEAD)
+            .with_uri("/assets/css/site.css".parse().unwrap());
+        let head = router.route(req, &ctx).await.unwrap();
+        assert_eq!(head.status(), 200);
+        assert_eq!(head.header("Content-Length"), Some(&"7".to_string()));
+        assert_eq!(head.header("Content-Type"), Some(&"text/css".to_string()));
+        assert!(head.body().is_empty());
+
+        // Conditional requests against static files get 304s
+        let last_modified = response.header("Last-Modified").cloned().unwrap();
+        let req = Request::new()
+            .with_method(Method::GET)
+            .with_uri("/assets/css/site.css".parse().unwrap())
+            .with_header("If-Modified-Since", last_modified);
+        let response = router.route(req, &ctx).await.unwrap();
+        assert_eq!(response.status(), 304);
+
+        // Registered routes take precedence
+        let req = Request::new()
+            .with_method(Method::GET)
+            .with_uri("/assets/override.css".parse().unwrap());
+        let response = router.route(req, &ctx).await.unwrap();
+        assert_eq!(
+            std::str::from_utf8(response.body()).unwrap(),
+            "Hello, world!"
+        );
+
+        // Missing files are not found
+        let req = Request::new()
+            .with_method(Method::GET)
+            .with_uri("/assets/missing.js".parse().unwrap());
+        let response = router.route(req, &ctx).await.unwrap();
+        assert_eq!(response.status(), 404);
+
+        // Path traversal is rejected
+        let req = Request::new()
+            .with_method(Method::GET)
+            .with_uri("/assets/../Cargo.toml".parse().unwrap());
+        let response = router.route(req, &ctx).await.unwrap();
+        assert_eq!(response.status(), 403);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
 }
@@ -0,0 +1,318 @@
+/*!
+Secret resolution for serverless.rs.
+
+[`Context::secret`](crate::Context::secret) resolves secrets through the provider set
+with [`set_secret_provider`], [`EnvSecretProvider`] by default. Resolved values are
+cached for the life of the process, so warm invocations don't fetch them again.
+
+With the `aws` feature, [`SecretsManagerProvider`] and [`SsmParameterProvider`] read
+from AWS Secrets Manager and SSM Parameter Store through the AWS Parameters and
+Secrets Lambda Extension, which must be added to the function as a layer.
+*/
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
+
+use crate::error::{Error, Result};
+
+/// A source of secret values
+///
+/// Lookups run on the calling thread and may block, so providers backed by a remote
+/// service should be quick; [`Context::secret`](crate::Context::secret) caches every
+/// value they return.
+pub trait SecretProvider: Send + Sync + 'static {
+    /// Returns the value of the secret called `name`
+    fn get_secret(&self, name: &str) -> Result<String>;
+}
+
+/// A [`SecretProvider`] that reads environment variables
+///
+/// Missing variables are a requirements error.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EnvSecretProvider;
+
+impl SecretProvider for EnvSecretProvider {
+    fn get_secret(&self, name: &str) -> Result<String> {
+        std::env::var(name)
+            .map_err(|_| Error::requirements(format!("Missing required secret: {}", name)))
+    }
+}
+
+/// Provider used by [`resolve`], or `None` for [`EnvSecretProvider`]
+static PROVIDER: RwLock<Option<Arc<dyn SecretProvider>>> = RwLock::new(None);
+
+/// Secrets resolved so far in this process
+static CACHE: OnceLock<Mutex<HashMap<String, String>>> = OnceLock::new();
+
+/// Resolve secrets with `provider` from now on
+///
+/// Secrets cached from the previous provider are discarded.
+pub fn set_secret_provider(provider: impl SecretProvider) {
+    if let Ok(mut current) = PROVIDER.write() {
+        *current = Some(Arc::new(provider));
+    }
+    clear_secret_cache();
+}
+
+/// Forget every cached secret, so the next lookups fetch them again
+///
+/// Call this after rotating a secret to pick up its new value.
+pub fn clear_secret_cache() {
+    if let Ok(mut cache) = cache().lock() {
+        cache.clear();
+    }
+}
+
+fn cache() -> &'static Mutex<HashMap<String, String>> {
+    CACHE.get_or_init(Default::default)
+}
+
+/// Returns the secret called `name`, from the cache or the configured provider
+pub(crate) fn resolve(name: &str) -> Result<String> {
+    if let Some(value) = cache()
+        .lock()
+        .ok()
+        .and_then(|cache| cache.get(name).cloned())
+    {
+        return Ok(value);
+    }
+
+    let provider = PROVIDER.read().ok().and_then(|provider| provider.clone());
+    let value = match provider {
+        Some(provider) => provider.get_secret(name)?,
+        None => EnvSecretProvider.get_secret(name)?,
+    };
+    if let Ok(mut cache) = cache().lock() {
+        cache.insert(name.to_string(), value.clone());
+    }
+    Ok(value)
+}
+
+#[cfg(feature = "aws")]
+pub use self::aws::{SecretsManagerProvider, SsmParameterProvider};
+
+#[cfg(feature = "aws")]
+mod aws {
+    use serde_json::Value;
+    use std::io::{Read, Write};
+    use std::net::{SocketAddr, TcpStream};
+    use std::time::Duration;
+
+    use super::SecretProvider;
+    use crate::error::{Error, Result};
+
+    /// Port the extension listens on unless `PARAMETERS_SECRETS_EXTENSION_HTTP_PORT` is set
+    const DEFAULT_EXTENSION_PORT: u16 = 2773;
+
+    /// How long to wait on the extension before giving up
+    ///
+    /// Lookups block the calling thread, so a stalled extension mustn't hang the
+    /// invocation until the platform kills it.
+    const EXTENSION_TIMEOUT: Duration = Duration::from_secs(5);
+
+    /// A [`SecretProvider`] for AWS Secrets Manager
+    ///
+    /// Secrets are looked up by name or ARN and their `SecretString` is returned.
+    #[derive(Debug, Clone, Copy, Default)]
+    pub struct SecretsManagerProvider;
+
+    impl SecretProvider for SecretsManagerProvider {
+        fn get_secret(&self, name: &str) -> Result<String> {
+            let query = encode_query(&[("secretId", name)])?;
+            let response = extension_get(&format!("/secretsmanager/get?{}", query))?;
+            match response.get("SecretString").and_then(Value::as_str) {
+                Some(value) => Ok(value.to_string()),
+                None => Err(Error::platform(format!(
+                    "Secret {} has no SecretString",
+                    name
+                ))),
+            }
+        }
+    }
+
+    /// A [`SecretProvider`] for AWS Systems Manager Parameter Store
+    ///
+    /// `SecureString` parameters are decrypted.
+    #[derive(Debug, Clone, Copy, Default)]
+    pub struct SsmParameterProvider;
+
+    impl SecretProvider for SsmParameterProvider {
+        fn get_secret(&self, name: &str) -> Result<String> {
+            let query = encode_query(&[("name", name), ("withDecryption", "true")])?;
+            let response = extension_get(&format!("/systemsmanager/parameters/get?{}", query))?;
+            match response.pointer("/Parameter/Value").and_then(Value::as_str) {
+                Some(value) => Ok(value.to_string()),
+                None => Err(Error::platform(format!("Parameter {} has no value", name))),
+            }
+        }
+    }
+
+    fn encode_query(pairs: &[(&str, &str)]) -> Result<String> {
+        serde_urlencoded::to_string(pairs).map_err(Error::serialization)
+    }
+
+    /// Send a GET request to the Parameters and Secrets extension and parse its JSON reply
+    fn extension_get(path: &str) -> Result<Value> {
+        let port = std::env::var("PARAMETERS_SECRETS_EXTENSION_HTTP_PORT")
+            .ok()
+            .and_then(|port| port.parse().ok())
+            .unwrap_or(DEFAULT_EXTENSION_PORT);
+        let token = std::env::var("AWS_SESSION_TOKEN")
+            .map_err(|_| Error::platform("AWS_SESSION_TOKEN is not set"))?;
+
+        let exchange = || -> std::io::Result<Vec<u8>> {
+            let address = SocketAddr::from(([127, 0, 0, 1], port));
+            let mut stream = TcpStream::connect_timeout(&address, EXTENSION_TIMEOUT)?;
+            stream.set_read_timeout(Some(EXTENSION_TIMEOUT))?;
+            stream.set_write_timeout(Some(EXTENSION_TIMEOUT))?;
+            write!(
+                stream,
+                "GET {} HTTP/1.1\r\nHost: localhost\r\nX-Aws-Parameters-Secrets-Token: {}\r\nConnection: close\r\n\r\n",
+                path, token
+            )?;
+            let mut response = Vec::new();
+            stream.read_to_end(&mut response)?;
+            Ok(response)
+        };
+        let response = exchange().map_err(|err| {
+            Error::platform(format!(
+                "Failed to reach the Parameters and Secrets extension on port {}: {}",
+                port, err
+            ))
+        })?;
+        parse_http_response(&response)
+    }
+
+    /// Parse an HTTP/1.1 response, returning its JSON body if the status is 200
+    pub(super) fn parse_http_response(response: &[u8]) -> Result<Value> {
+        let malformed = || Error::platform("Malformed response from the secrets extension");
+        let head_end = response
+            .windows(4)
+            .position(|window| window == b"\r\n\r\n")
+            .ok_or_else(malformed)?;
+        // The body stays as bytes, since a chunk may end partway through a character
+        let head = String::from_utf8_lossy(&response[..head_end]);
+        let body = &response[head_end + 4..];
+        let mut lines = head.lines();
+        let status = lines
+            .next()
+            .and_then(|line| line.split_whitespace().nth(1))
+            .and_then(|status| status.parse::<u16>().ok())
+            .ok_or_else(malformed)?;
+        let chunked = lines.any(|line| {
+            line.split_once(':').is_some_and(|(name, value)| {
+                name.trim().eq_ignore_ascii_case("Transfer-Encoding")
+                    && value.trim().eq_ignore_ascii_case("chunked")
+            })
+        });
+        let body = if chunked {
+            decode_chunked(body)
+        } else {
+            body.to_vec()
+        };
+
+        if status != 200 {
+            return Err(Error::with_status(
+                status,
+                format!(
+                    "Secrets extension returned {}: {}",
+                    status,
+                    String::from_utf8_lossy(&body).trim()
+                ),
+            ));
+        }
+        serde_json::from_slice(&body).map_err(Error::serialization)
+    }
+
+    /// Join the chunks of a chunked transfer-encoded body
+    fn decode_chunked(mut body: &[u8]) -> Vec<u8> {
+        let mut decoded = Vec::new();
+        while let Some(line_end) = body.windows(2).position(|window| window == b"\r\n") {
+            let size = String::from_utf8_lossy(&body[..line_end]);
+            let size = size.split(';').next().unwrap_or_default().trim();
+            let size = usize::from_str_radix(size, 16).unwrap_or(0);
+            let rest = &body[line_end + 2..];
+            if size == 0 || rest.len() < size {
+                break;
+            }
+            decoded.extend_from_slice(&rest[..size]);
+            body = rest[size..].strip_prefix(b"\r\n").unwrap_or(&rest[size..]);
+        }
+        decoded
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Counts lookups, answering with the secret's name reversed
+    #[derive(Default)]
+    struct Reversed {
+        lookups: Arc<Mutex<usize>>,
+    }
+
+    impl SecretProvider for Reversed {
+        fn get_secret(&self, name: &str) -> Result<String> {
+            *self.lookups.lock().unwrap() += 1;
+            match name {
+                "missing" => Err(Error::requirements("no such secret")),
+                name => Ok(name.chars().rev().collect()),
+            }
+        }
+    }
+
+    #[test]
+    fn test_secret_resolution() {
+        // One test owns the global provider, so cases can't race each other
+        std::env::set_var("SERVERLESS_RS_TEST_SECRET", "from env");
+        assert_eq!(resolve("SERVERLESS_RS_TEST_SECRET").unwrap(), "from env");
+        assert!(matches!(
+            resolve("SERVERLESS_RS_TEST_UNSET_SECRET"),
+            Err(Error::Requirements(_))
+        ));
+
+        let provider = Reversed::default();
+        let lookups = provider.lookups.clone();
+        set_secret_provider(provider);
+        assert_eq!(resolve("db-password").unwrap(), "drowssap-bd");
+        assert_eq!(resolve("db-password").unwrap(), "drowssap-bd");
+        assert_eq!(*lookups.lock().unwrap(), 1);
+
+        // Errors aren't cached
+        assert!(resolve("missing").is_err());
+        assert!(resolve("missing").is_err());
+        assert_eq!(*lookups.lock().unwrap(), 3);
+
+        clear_secret_cache();
+        resolve("db-password").unwrap();
+        assert_eq!(*lookups.lock().unwrap(), 4);
+
+        set_secret_provider(EnvSecretProvider);
+        assert_eq!(resolve("SERVERLESS_RS_TEST_SECRET").unwrap(), "from env");
+    }
+
+    #[cfg(feature = "aws")]
+    #[test]
+    fn test_parse_extension_response() {
+        use super::aws::parse_http_response;
+
+        let response = b"HTTP/1.1 200 OK\r\nContent-Type: application/json\r\n\r\n{\"SecretString\":\"s3cr3t\"}";
+        assert_eq!(
+            parse_http_response(response).unwrap()["SecretString"],
+            "s3cr3t"
+        );
+
+        let chunked = b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n8\r\n{\"Name\":\r\n5\r\n\"db\"}\r\n0\r\n\r\n";
+        assert_eq!(parse_http_response(chunked).unwrap()["Name"], "db");
+
+        // A chunk boundary may split a multi-byte character
+        let split = b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\na\r\n{\"Name\":\"\xc3\r\n6\r\n\xa9t\xc3\xa9\"}\r\n0\r\n\r\n";
+        assert_eq!(parse_http_response(split).unwrap()["Name"], "été");
+
+        let denied = b"HTTP/1.1 400 Bad Request\r\n\r\nnot authorized";
+        let err = parse_http_response(denied).unwrap_err();
+        assert_eq!(err.status(), 400);
+        assert!(err.to_string().contains("not authorized"));
+    }
+}
@@ -0,0 +1,376 @@
+/*!
+Cookie sessions for serverless.rs.
+
+This module, enabled with the `session` feature, keeps session data on the client in
+a cookie signed with HMAC-SHA256, so functions don't need a session store.
+[`SessionLayer`] loads the [`Session`] from the request cookie into the context,
+where handlers read and change it through [`Context::session`], and writes the
+cookie back when the session changed.
+
+Session data is signed, not encrypted: clients can read it but not change it. A
+cookie with a missing or invalid signature is dropped, starting an empty session.
+*/
+
+use async_trait::async_trait;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use sha2::Sha256;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+use crate::error::{Error, Result};
+use crate::{Context, Handler, Middleware, Request, Response};
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Sign a value with HMAC-SHA256, returning `value.signature`
+///
+/// The result can be sent as a cookie value if `value` is cookie-safe, such as
+/// URL-safe Base64, and checked with [`Request::verify_signed_cookie`].
+pub fn sign(key: &[u8], value: &str) -> String {
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(value.as_bytes());
+    let signature = URL_SAFE_NO_PAD.encode(mac.finalize().into_bytes());
+    format!("{}.{}", value, signature)
+}
+
+/// Verify a value produced by [`sign`], returning the original value
+///
+/// The signature is compared in constant time.
+pub(crate) fn verify<'a>(key: &[u8], signed: &'a str) -> Option<&'a str> {
+    let (value, signature) = signed.rsplit_once('.')?;
+    let signature = URL_SAFE_NO_PAD.decode(signature).ok()?;
+    let mut mac = HmacSha256::new_from_slice(key).ok()?;
+    mac.update(value.as_bytes());
+    mac.verify_slice(&signature).ok()?;
+    Some(value)
+}
+
+/// Session data shared between the layer and the handler
+#[derive(Debug, Default)]
+struct SessionState {
+    values: Map<String, Value>,
+    changed: bool,
+}
+
+/// Session data for the current request
+///
+/// Cloning a session gives another handle to the same data.
+#[derive(Debug, Clone, Default)]
+pub struct Session {
+    state: Arc<Mutex<SessionState>>,
+}
+
+impl Session {
+    /// Creates an empty session
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a session from existing values, marked unchanged
+    fn from_values(values: Map<String, Value>) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(SessionState {
+                values,
+                changed: false,
+            })),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, SessionState> {
+        // The state stays consistent even if a holder panicked
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Returns a value, if present and of the expected type
+    pub fn get<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        let value = self.lock().values.get(key)?.clone();
+        serde_json::from_value(value).ok()
+    }
+
+    /// Sets a value
+    pub fn insert<T: Serialize>(&self, key: impl Into<String>, value: &T) -> Result<()> {
+        let value = serde_json::to_value(value).map_err(Error::serialization)?;
+        let mut state = self.lock();
+        state.values.insert(key.into(), value);
+        state.changed = true;
+        Ok(())
+    }
+
+    /// Removes a value
+    pub fn remove(&self, key: &str) {
+        let mut state = self.lock();
+        if state.values.remove(key).is_some() {
+            state.changed = true;
+        }
+    }
+
+    /// Removes all values, so the session cookie is deleted
+    pub fn clear(&self) {
+        let mut state = self.lock();
+        if !state.values.is_empty() {
+            state.values.clear();
+            state.changed = true;
+        }
+    }
+
+    /// Returns whether the session has no values
+    pub fn is_empty(&self) -> bool {
+        self.lock().values.is_empty()
+    }
+
+    /// Returns whether the session was changed since it was loaded
+    pub fn is_changed(&self) -> bool {
+        self.lock().changed
+    }
+}
+
+/// Middleware that loads and saves a signed session cookie
+///
+/// Cookies are `HttpOnly`, `Secure` and `SameSite=Lax` by default. When the session
+/// changed, its cookie is appended to any `Set-Cookie` headers the handler set.
+///
+/// # Examples
+///
+/// ```
+/// use serverless_rs::session::SessionLayer;
+/// use serverless_rs::{Context, Request, Response, Result, RouterBuilder};
+///
+/// fn visit(_req: Request, ctx: &Context) -> Result<Response> {
+///     let session = ctx.session().expect("SessionLayer is installed");
+///     let visits = session.get::<u32>("visits").unwrap_or(0) + 1;
+///     session.insert("visits", &visits)?;
+///     Ok(Response::text(format!("visit {}", visits)))
+/// }
+///
+/// let key = std::env::var("SESSION_KEY").unwrap_or_default();
+/// let router = RouterBuilder::new()
+///     .scope("/app", |app| app.layer(SessionLayer::new(key)).get("/visit", visit))
+///     .build();
+/// ```
+#[derive(Clone)]
+pub struct SessionLayer {
+    key: Vec<u8>,
+    cookie_name: String,
+    path: String,
+    max_age: Option<Duration>,
+    secure: bool,
+}
+
+impl SessionLayer {
+    /// Creates a layer signing cookies with `key`
+    ///
+    /// The key should be at least 32 random bytes and kept secret, e.g. in an
+    /// environment variable.
+    pub fn new(key: impl Into<Vec<u8>>) -> Self {
+        Self {
+            key: key.into(),
+            cookie_name: "session".to_string(),
+            path: "/".to_string(),
+            max_age: None,
+            secure: true,
+        }
+    }
+
+    /// Sets the cookie name, `session` by default
+    pub fn with_cookie_name(mut self, name: impl Into<String>) -> Self {
+        self.cookie_name = name.into();
+        self
+    }
+
+    /// Sets the cookie path, `/` by default
+    pub fn with_path(mut self, path: impl Into<String>) -> Self {
+        self.path = path.into();
+        self
+    }
+
+    /// Sets the cookie lifetime; without one the cookie lasts for the browser session
+    pub fn with_max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    /// Sets whether the cookie is only sent over HTTPS, `true` by default
+    pub fn with_secure(mut self, secure: bool) -> Self {
+        self.secure = secure;
+        self
+    }
+
+    /// Load the session from the request, dropping cookies that fail verification
+    fn load(&self, req: &Request) -> Session {
+        let values = req
+            .cookie(&self.cookie_name)
+            .and_then(|cookie| verify(&self.key, cookie))
+            .and_then(|payload| URL_SAFE_NO_PAD.decode(payload).ok())
+            .and_then(|json| serde_json::from_slice::<Map<String, Value>>(&json).ok())
+            .unwrap_or_default();
+        Session::from_values(values)
+    }
+
+    /// Build the `Set-Cookie` value for a changed session
+    fn set_cookie(&self, session: &Session) -> String {
+        let state = session.lock();
+        let (value, max_age) = if state.values.is_empty() {
+            (String::new(), Some(0))
+        } else {
+            let json = Value::Object(state.values.clone()).to_string();
+            let payload = URL_SAFE_NO_PAD.encode(json);
+            (
+                sign(&self.key, &payload),
+                self.max_age.map(|max_age| max_age.as_secs()),
+            )
+        };
+
+        let mut cookie = format!(
+            "{}={}; Path={}; HttpOnly; SameSite=Lax",
+            self.cookie_name, value, self.path
+        );
+        if let Some(max_age) = max_age {
+            cookie.push_str(&format!("; Max-Age={}", max_age));
+        }
+        if self.secure {
+            cookie.push_str("; Secure");
+        }
+        cookie
+    }
+}
+
+#[async_trait]
+impl Middleware for SessionLayer {
+    async fn handle(&self, req: Request, ctx: &Context, next: &dyn Handler) -> Result<Response> {
+        let session = self.load(&req);
+        let ctx = ctx.clone().with_extension(session.clone());
+
+        let response = next.handle(req, &ctx).await?;
+        if session.is_changed() {
+            Ok(response.append_header("Set-Cookie", self.set_cookie(&session)))
+        } else {
+            Ok(response)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: &[u8] = b"0123456789abcdef0123456789abcdef";
+
+    fn login(_req: Request, ctx: &Context) -> Result<Response> {
+        let session = ctx.session().unwrap();
+        session.insert("user", &"ada")?;
+        Ok(Response::text("welcome"))
+    }
+
+    fn whoami(_req: Request, ctx: &Context) -> Result<Response> {
+        let user: Option<String> = ctx.session().unwrap().get("user");
+        Ok(Response::text(
+            user.unwrap_or_else(|| "anonymous".to_string()),
+        ))
+    }
+
+    fn logout(_req: Request, ctx: &Context) -> Result<Response> {
+        ctx.session().unwrap().clear();
+        Ok(Response::text("bye"))
+    }
+
+    /// Returns the `name=value` part of a `Set-Cookie` header
+    fn cookie_pair(resp: &Response) -> String {
+        let set_cookie = resp.header("Set-Cookie").unwrap();
+        set_cookie.split(';').next().unwrap().to_string()
+    }
+
+    #[test]
+    fn test_sign_and_verify() {
+        let signed = sign(KEY, "payload");
+        assert_eq!(verify(KEY, &signed), Some("payload"));
+        assert_eq!(verify(b"another key", &signed), None);
+        assert_eq!(verify(KEY, &signed.replace("payload", "payl0ad")), None);
+        assert_eq!(verify(KEY, "payload"), None);
+    }
+
+    #[test]
+    fn test_verify_signed_cookie() {
+        let token = sign(KEY, "csrf-1");
+        let req = Request::new().with_header("Cookie", format!("a=1; csrf={}", token));
+        assert_eq!(
+            req.verify_signed_cookie("csrf", KEY).as_deref(),
+            Some("csrf-1")
+        );
+        assert_eq!(req.verify_signed_cookie("csrf", b"wrong key"), None);
+        assert_eq!(req.verify_signed_cookie("a", KEY), None);
+
+        let tampered = token.replace("csrf-1", "csrf-2");
+        let req = Request::new().with_header("Cookie", format!("csrf={}", tampered));
+        assert_eq!(req.verify_signed_cookie("csrf", KEY), None);
+    }
+
+    #[tokio::test]
+    async fn test_session_round_trip() {
+        let layer = SessionLayer::new(KEY).with_max_age(Duration::from_secs(3600));
+        let ctx = Context::new();
+
+        let resp = layer.handle(Request::new(), &ctx, &login).await.unwrap();
+        let set_cookie = resp.header("Set-Cookie").unwrap();
+        assert!(set_cookie.starts_with("session="));
+        assert!(set_cookie.contains("; HttpOnly; SameSite=Lax; Max-Age=3600; Secure"));
+
+        let req =
+            Request::new().with_header("Cookie", format!("theme=dark; {}", cookie_pair(&resp)));
+        let resp = layer.handle(req, &ctx, &whoami).await.unwrap();
+        assert_eq!(resp.body(), b"ada");
+        // Unchanged sessions aren't written back
+        assert!(resp.header("Set-Cookie").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_tampered_cookie_dropped() {
+        let layer = SessionLayer::new(KEY);
+        let ctx = Context::new();
+
+        let forged = URL_SAFE_NO_PAD.encode(r#"{"user":"admin"}"#);
+        let signed = sign(b"not the key", &forged);
+        for cookie in [format!("session={}", signed), format!("session={}", forged)] {
+            let req = Request::new().with_header("Cookie", cookie);
+            let resp = layer.handle(req, &ctx, &whoami).await.unwrap();
+            assert_eq!(resp.body(), b"anonymous");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_clear_expires_cookie() {
+        let layer = SessionLayer::new(KEY).with_secure(false);
+        let ctx = Context::new();
+
+        let resp = layer.handle(Request::new(), &ctx, &login).await.unwrap();
+        let req = Request::new().with_header("Cookie", cookie_pair(&resp));
+        let resp = layer.handle(req, &ctx, &logout).await.unwrap();
+        assert_eq!(
+            resp.header("Set-Cookie").unwrap(),
+            "session=; Path=/; HttpOnly; SameSite=Lax; Max-Age=0"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_keeps_handler_cookies() {
+        fn login_with_theme(req: Request, ctx: &Context) -> Result<Response> {
+            Ok(login(req, ctx)?.with_header("Set-Cookie", "theme=dark"))
+        }
+
+        let layer = SessionLayer::new(KEY);
+        let resp = layer
+            .handle(Request::new(), &Context::new(), &login_with_theme)
+            .await
+            .unwrap();
+        let cookies = resp.header_values("Set-Cookie");
+        assert_eq!(cookies.len(), 2);
+        assert_eq!(cookies[0], "theme=dark");
+        assert!(cookies[1].starts_with("session="));
+    }
+}
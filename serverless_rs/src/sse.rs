@@ -0,0 +1,118 @@
+/*!
+Server-Sent Events support for serverless.rs.
+
+This module provides the [`SseEvent`] type used by [`Response::sse`](crate::Response::sse)
+to build `text/event-stream` responses.
+*/
+
+use std::fmt;
+
+/// A single Server-Sent Event
+///
+/// Events are formatted per the SSE specification: each field on its own line,
+/// multi-line data split across several `data:` lines, and a blank line at the end.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SseEvent {
+    /// Event ID, sent as the `id` field
+    id: Option<String>,
+
+    /// Event type, sent as the `event` field
+    event: Option<String>,
+
+    /// Event payload, sent as one or more `data` fields
+    data: String,
+
+    /// Reconnection time in milliseconds, sent as the `retry` field
+    retry: Option<u64>,
+}
+
+impl SseEvent {
+    /// Create an event with the given data
+    pub fn new(data: impl Into<String>) -> Self {
+        Self {
+            data: data.into(),
+            ..Self::default()
+        }
+    }
+
+    /// Create an event with JSON-serialized data
+    pub fn json<T: serde::Serialize>(value: &T) -> crate::Result<Self> {
+        let data = serde_json::to_string(value).map_err(crate::Error::serialization)?;
+        Ok(Self::new(data))
+    }
+
+    /// Set the event ID
+    pub fn with_id(mut self, id: impl Into<String>) -> Self {
+        self.id = Some(id.into());
+        self
+    }
+
+    /// Set the event type
+    pub fn with_event(mut self, event: impl Into<String>) -> Self {
+        self.event = Some(event.into());
+        self
+    }
+
+    /// Set the client reconnection time in milliseconds
+    pub fn with_retry(mut self, retry: u64) -> Self {
+        self.retry = Some(retry);
+        self
+    }
+
+    /// Returns the event ID
+    pub fn id(&self) -> Option<&str> {
+        self.id.as_deref()
+    }
+
+    /// Returns the event type
+    pub fn event(&self) -> Option<&str> {
+        self.event.as_deref()
+    }
+
+    /// Returns the event data
+    pub fn data(&self) -> &str {
+        &self.data
+    }
+}
+
+impl fmt::Display for SseEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Line breaks would end the field early, so they are stripped from single-line fields
+        if let Some(id) = &self.id {
+            writeln!(f, "id: {}", id.replace(['\r', '\n'], ""))?;
+        }
+        if let Some(event) = &self.event {
+            writeln!(f, "event: {}", event.replace(['\r', '\n'], ""))?;
+        }
+        if let Some(retry) = self.retry {
+            writeln!(f, "retry: {}", retry)?;
+        }
+        for line in self.data.split('\n') {
+            writeln!(f, "data: {}", line.strip_suffix('\r').unwrap_or(line))?;
+        }
+        writeln!(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_event_format() {
+        let event = SseEvent::new("line one\nline two")
+            .with_id("42")
+            .with_event("progress")
+            .with_retry(3000);
+        assert_eq!(
+            event.to_string(),
+            "id: 42\nevent: progress\nretry: 3000\ndata: line one\ndata: line two\n\n"
+        );
+
+        assert_eq!(SseEvent::new("").to_string(), "data: \n\n");
+        assert_eq!(
+            SseEvent::new("x").with_event("a\nb").to_string(),
+            "event: ab\ndata: x\n\n"
+        );
+    }
+}
@@ -0,0 +1,699 @@
+/*!
+Test harness for serverless.rs handlers.
+
+This module, enabled with the `testing` feature, provides a [`TestRequest`] builder,
+[`invoke`] to run a handler to completion without setting up an async runtime, and
+assertions for the resulting [`Response`], including snapshot assertions with
+[`assert_response_snapshot`].
+
+Platform events for testing adapters and `--invoke` are generated with
+[`aws_api_gateway_v2_event`], [`aws_api_gateway_v1_event`] and
+[`cloudflare_fetch_event`].
+
+# Examples
+
+```
+use serverless_rs::testing::{assert_json_body, assert_status, invoke, TestRequest};
+use serverless_rs::{json, Context, Request, Response, Result, Value};
+
+fn echo(req: Request, _ctx: &Context) -> Result<Response> {
+    let body: Value = req.body_json()?;
+    Response::json(&body)
+}
+
+let resp = invoke(&echo, TestRequest::post("/echo").json(&json!({ "id": 1 })));
+assert_status(&resp, 200);
+assert_json_body(&resp, &json!({ "id": 1 }));
+```
+*/
+
+use base64::Engine;
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+use std::path::PathBuf;
+
+use crate::util::parse_query_string;
+use crate::{Context, Handler, Request, Response};
+
+/// A builder for requests used in handler tests
+#[derive(Debug, Clone, Default)]
+pub struct TestRequest {
+    request: Request,
+}
+
+impl TestRequest {
+    /// Start a request with the given method and path
+    ///
+    /// The path may include a query string, which is parsed into query parameters.
+    pub fn new(method: &str, path: &str) -> Self {
+        let (path_only, query) = path.split_once('?').unwrap_or((path, ""));
+        let mut request = Request::new().with_method_str(method).with_path(path_only);
+        for (name, value) in parse_query_string(query) {
+            request = request.with_query(name, value);
+        }
+        Self { request }
+    }
+
+    /// Start a `GET` request
+    pub fn get(path: &str) -> Self {
+        Self::new("GET", path)
+    }
+
+    /// Start a `POST` request
+    pub fn post(path: &str) -> Self {
+        Self::new("POST", path)
+    }
+
+    /// Start a `PUT` request
+    pub fn put(path: &str) -> Self {
+        Self::new("PUT", path)
+    }
+
+    /// Start a `PATCH` request
+    pub fn patch(path: &str) -> Self {
+        Self::new("PATCH", path)
+    }
+
+    /// Start a `DELETE` request
+    pub fn delete(path: &str) -> Self {
+        Self::new("DELETE", path)
+    }
+
+    /// Add a header
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.request = self.request.with_header(name, value);
+        self
+    }
+
+    /// Add a query parameter
+    pub fn query(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.request = self.request.with_query(name, value);
+        self
+    }
+
+    /// Add a path parameter, as a router would
+    pub fn path_param(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.request = self.request.with_path_param(name, value);
+        self
+    }
+
+    /// Set the raw body
+    pub fn body(mut self, body: impl Into<Vec<u8>>) -> Self {
+        self.request = self.request.with_body(body);
+        self
+    }
+
+    /// Set a JSON body and the `Content-Type: application/json` header
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value cannot be serialized.
+    pub fn json<T: Serialize>(self, value: &T) -> Self {
+        let body = serde_json::to_vec(value).expect("failed to serialize JSON body");
+        self.header("Content-Type", "application/json").body(body)
+    }
+
+    /// Returns the built request
+    pub fn to_request(&self) -> Request {
+        self.request.clone()
+    }
+}
+
+impl From<TestRequest> for Request {
+    fn from(test_request: TestRequest) -> Self {
+        test_request.request
+    }
+}
+
+/// Run a handler to completion with an empty context
+///
+/// An error is converted into a response with the error's [`Error::status`](crate::Error::status), as the
+/// platform adapters do, so error paths can be asserted like any other response.
+pub fn invoke<H: Handler>(handler: &H, req: impl Into<Request>) -> Response {
+    invoke_with_context(handler, req, &Context::new())
+}
+
+/// Run a handler to completion with the given context
+///
+/// An error is converted into a response with its status, as the platform adapters do.
+/// The handler runs on a current-thread tokio runtime, like the generated adapters, so
+/// it may use tokio timers and spawn tasks, and this may be called from async tests.
+pub fn invoke_with_context<H: Handler>(
+    handler: &H,
+    req: impl Into<Request>,
+    ctx: &Context,
+) -> Response {
+    let req = req.into();
+    let run = || {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .expect("failed to build the test runtime")
+            .block_on(handler.handle(req, ctx))
+    };
+    if tokio::runtime::Handle::try_current().is_err() {
+        return Response::from_result(run());
+    }
+
+    // Blocking a thread that is already driving a runtime panics, so run on a
+    // scoped thread and wait for it instead
+    std::thread::scope(|scope| match scope.spawn(run).join() {
+        Ok(result) => Response::from_result(result),
+        Err(panic) => std::panic::resume_unwind(panic),
+    })
+}
+
+/// Assert that a response has the given status code
+#[track_caller]
+pub fn assert_status(resp: &Response, status: u16) {
+    assert_eq!(
+        resp.status(),
+        status,
+        "unexpected status, body: {}",
+        String::from_utf8_lossy(resp.body())
+    );
+}
+
+/// Assert that a response has a header with the given value
+#[track_caller]
+pub fn assert_header(resp: &Response, name: &str, value: &str) {
+    match resp.header(name) {
+        Some(actual) => assert_eq!(actual, value, "unexpected value for header {}", name),
+        None => panic!("missing header {}", name),
+    }
+}
+
+/// Assert that a response body is JSON equal to `expected`
+#[track_caller]
+pub fn assert_json_body<T: Serialize>(resp: &Response, expected: &T) {
+    let actual: Value = match serde_json::from_slice(resp.body()) {
+        Ok(actual) => actual,
+        Err(err) => panic!(
+            "body is not JSON ({}): {}",
+            err,
+            String::from_utf8_lossy(resp.body())
+        ),
+    };
+    let expected = serde_json::to_value(expected).expect("failed to serialize expected JSON");
+    assert_eq!(actual, expected, "unexpected JSON body");
+}
+
+/// Assert that a response body is the given text
+#[track_caller]
+pub fn assert_text_body(resp: &Response, expected: &str) {
+    assert_eq!(
+        String::from_utf8_lossy(resp.body()),
+        expected,
+        "unexpected body"
+    );
+}
+
+/// Environment variable that, when set, makes snapshot assertions rewrite snapshots
+pub const UPDATE_SNAPSHOTS_ENV: &str = "SERVERLESS_UPDATE_SNAPSHOTS";
+
+/// Headers whose values change from run to run, replaced in snapshots
+const VOLATILE_HEADERS: &[&str] = &["date"];
+
+/// Render a response in the stable text form used by [`assert_response_snapshot`]
+///
+/// The first line holds the status, followed by the headers sorted by lowercased
+/// name, a blank line, and the body. Volatile headers such as `Date` have their
+/// value replaced by `[redacted]`. JSON bodies are pretty-printed, other UTF-8
+/// bodies are written as is, and binary bodies are written as Base64.
+pub fn response_snapshot(resp: &Response) -> String {
+    let mut headers: Vec<(String, &str)> = resp
+        .header_pairs()
+        .into_iter()
+        .map(|(name, value)| {
+            let name = name.to_ascii_lowercase();
+            let value = if VOLATILE_HEADERS.contains(&name.as_str()) {
+                "[redacted]"
+            } else {
+                value.as_str()
+            };
+            (name, value)
+        })
+        .collect();
+    // Repeated headers keep the order of their values
+    headers.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    let mut snapshot = format!("HTTP {}\n", resp.status());
+    for (name, value) in headers {
+        snapshot.push_str(&format!("{}: {}\n", name, value));
+    }
+    snapshot.push('\n');
+
+    let body = resp.body();
+    if resp.is_base64() {
+        snapshot.push_str(&format!("[base64] {}", String::from_utf8_lossy(body)));
+    } else if let Ok(json) = serde_json::from_slice::<Value>(body) {
+        snapshot.push_str(&serde_json::to_string_pretty(&json).unwrap_or_default());
+    } else if let Ok(text) = std::str::from_utf8(body) {
+        snapshot.push_str(text);
+    } else {
+        let encoded = base64::engine::general_purpose::STANDARD.encode(body);
+        snapshot.push_str(&format!("[base64] {}", encoded));
+    }
+    if !snapshot.ends_with('\n') {
+        snapshot.push('\n');
+    }
+    snapshot
+}
+
+/// Assert that a response matches the snapshot stored under `name`
+///
+/// Snapshots are kept in `tests/snapshots/{name}.snap` under the crate being tested,
+/// in the form written by [`response_snapshot`]. A missing snapshot is written and
+/// the assertion passes, so the first run records the expected output; review and
+/// commit the new file. Set the `SERVERLESS_UPDATE_SNAPSHOTS` environment variable
+/// to rewrite snapshots that no longer match instead of failing.
+///
+/// # Examples
+///
+/// ```no_run
+/// use serverless_rs::testing::{assert_response_snapshot, invoke, TestRequest};
+/// use serverless_rs::{json, Context, Request, Response, Result};
+///
+/// fn user(_req: Request, _ctx: &Context) -> Result<Response> {
+///     Response::json(&json!({ "id": 7, "name": "Ada" }))
+/// }
+///
+/// let resp = invoke(&user, TestRequest::get("/users/7"));
+/// assert_response_snapshot(&resp, "get_user");
+/// ```
+#[track_caller]
+pub fn assert_response_snapshot(resp: &Response, name: &str) {
+    let dir = std::env::var_os("CARGO_MANIFEST_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_default()
+        .join("tests")
+        .join("snapshots");
+    assert_snapshot_in(&dir, resp, name);
+}
+
+/// Compare a response against the snapshot `{dir}/{name}.snap`
+#[track_caller]
+fn assert_snapshot_in(dir: &std::path::Path, resp: &Response, name: &str) {
+    let path = dir.join(format!("{}.snap", name));
+    let actual = response_snapshot(resp);
+    let update = std::env::var_os(UPDATE_SNAPSHOTS_ENV).is_some();
+
+    let write = || {
+        std::fs::create_dir_all(dir)
+            .and_then(|()| std::fs::write(&path, &actual))
+            .unwrap_or_else(|err| panic!("failed to write snapshot {}: {}", path.display(), err));
+    };
+
+    match std::fs::read_to_string(&path) {
+        Ok(expected) if expected == actual => {}
+        Ok(_) if update => write(),
+        Ok(expected) => panic!(
+            "response does not match snapshot {}\n\n--- expected\n{}\n+++ actual\n{}\nSet {} to update the snapshot",
+            path.display(),
+            expected,
+            actual,
+            UPDATE_SNAPSHOTS_ENV
+        ),
+        Err(_) => write(),
+    }
+}
+
+/// Account ID used in generated AWS events
+const TEST_ACCOUNT_ID: &str = "123456789012";
+
+/// Source IP used in generated events
+const TEST_SOURCE_IP: &str = "203.0.113.10";
+
+/// User agent used in generated events
+const TEST_USER_AGENT: &str = "serverless-rs-test";
+
+/// Split a path with an optional query string into the path, raw query and pairs
+fn split_target(target: &str) -> (&str, &str, Vec<(String, String)>) {
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let pairs = serde_urlencoded::from_str(query).unwrap_or_default();
+    (path, query, pairs)
+}
+
+/// Returns the body as event text, Base64 encoding it unless it's valid UTF-8
+fn event_body(body: &[u8]) -> (String, bool) {
+    match std::str::from_utf8(body) {
+        Ok(text) => (text.to_string(), false),
+        Err(_) => (base64::engine::general_purpose::STANDARD.encode(body), true),
+    }
+}
+
+/// Returns the headers of a generated event, with a content type for non-empty bodies
+fn event_headers(body: &[u8]) -> Map<String, Value> {
+    let mut headers = Map::new();
+    headers.insert("host".to_string(), json!("api.example.com"));
+    headers.insert("user-agent".to_string(), json!(TEST_USER_AGENT));
+    headers.insert("accept".to_string(), json!("*/*"));
+    if !body.is_empty() {
+        let content_type = match std::str::from_utf8(body) {
+            Ok(text) if serde_json::from_str::<Value>(text).is_ok() => "application/json",
+            Ok(_) => "text/plain",
+            Err(_) => "application/octet-stream",
+        };
+        headers.insert("content-type".to_string(), json!(content_type));
+    }
+    headers
+}
+
+/// Generate an API Gateway HTTP API event (payload format 2.0)
+///
+/// `path` may include a query string, which is set as `rawQueryString` and parsed
+/// into `queryStringParameters`, with repeated parameters joined by commas as API
+/// Gateway does. Bodies that aren't valid UTF-8 are Base64 encoded with
+/// `isBase64Encoded` set, and non-empty bodies get a `content-type` header. Other
+/// fields have fixed, realistic values, and can be changed on the returned JSON.
+///
+/// # Examples
+///
+/// ```
+/// use serverless_rs::testing::aws_api_gateway_v2_event;
+///
+/// let event = aws_api_gateway_v2_event("POST", "/orders?dry_run=1", br#"{"sku":"A1"}"#);
+/// assert_eq!(event["requestContext"]["http"]["method"], "POST");
+/// assert_eq!(event["queryStringParameters"]["dry_run"], "1");
+/// ```
+pub fn aws_api_gateway_v2_event(method: &str, path: &str, body: &[u8]) -> Value {
+    let (path, raw_query, pairs) = split_target(path);
+    let mut query: Map<String, Value> = Map::new();
+    for (name, value) in pairs {
+        match query.get_mut(&name) {
+            Some(Value::String(joined)) => {
+                joined.push(',');
+                joined.push_str(&value);
+            }
+            _ => {
+                query.insert(name, Value::String(value));
+            }
+        }
+    }
+
+    let mut event = json!({
+        "version": "2.0",
+        "routeKey": "$default",
+        "rawPath": path,
+        "rawQueryString": raw_query,
+        "headers": event_headers(body),
+        "requestContext": {
+            "accountId": TEST_ACCOUNT_ID,
+            "apiId": "api-id",
+            "domainName": "api.example.com",
+            "domainPrefix": "api",
+            "http": {
+                "method": method,
+                "path": path,
+                "protocol": "HTTP/1.1",
+                "sourceIp": TEST_SOURCE_IP,
+                "userAgent": TEST_USER_AGENT,
+            },
+            "requestId": "test-request-id",
+            "routeKey": "$default",
+            "stage": "$default",
+            "time": "14/Nov/2023:22:13:20 +0000",
+            "timeEpoch": 1_700_000_000_000u64,
+        },
+        "isBase64Encoded": false,
+    });
+    if !query.is_empty() {
+        event["queryStringParameters"] = Value::Object(query);
+    }
+    if !body.is_empty() {
+        let (body, is_base64) = event_body(body);
+        event["body"] = json!(body);
+        event["isBase64Encoded"] = json!(is_base64);
+    }
+    event
+}
+
+/// Generate an API Gateway REST API event (payload format 1.0)
+///
+/// Like [`aws_api_gateway_v2_event`], but in the REST API shape: query parameters
+/// fill both `queryStringParameters`, keeping the last value, and
+/// `multiValueQueryStringParameters`, and absent values are `null`.
+///
+/// # Examples
+///
+/// ```
+/// use serverless_rs::testing::aws_api_gateway_v1_event;
+///
+/// let event = aws_api_gateway_v1_event("GET", "/users?tag=a&tag=b", b"");
+/// assert_eq!(event["httpMethod"], "GET");
+/// assert_eq!(event["multiValueQueryStringParameters"]["tag"][1], "b");
+/// ```
+pub fn aws_api_gateway_v1_event(method: &str, path: &str, body: &[u8]) -> Value {
+    let (path, _, pairs) = split_target(path);
+    let mut query = Map::new();
+    let mut multi_query: Map<String, Value> = Map::new();
+    for (name, value) in pairs {
+        query.insert(name.clone(), json!(value));
+        match multi_query.get_mut(&name) {
+            Some(Value::Array(values)) => values.push(json!(value)),
+            _ => {
+                multi_query.insert(name, json!([value]));
+            }
+        }
+    }
+    let headers = event_headers(body);
+    let multi_headers: Map<String, Value> = headers
+        .iter()
+        .map(|(name, value)| (name.clone(), json!([value])))
+        .collect();
+    let (body, is_base64) = match body.is_empty() {
+        true => (Value::Null, false),
+        false => {
+            let (body, is_base64) = event_body(body);
+            (json!(body), is_base64)
+        }
+    };
+    let or_null = |map: Map<String, Value>| match map.is_empty() {
+        true => Value::Null,
+        false => Value::Object(map),
+    };
+
+    json!({
+        "resource": "/{proxy+}",
+        "path": path,
+        "httpMethod": method,
+        "headers": headers,
+        "multiValueHeaders": multi_headers,
+        "queryStringParameters": or_null(query),
+        "multiValueQueryStringParameters": or_null(multi_query),
+        "pathParameters": { "proxy": path.trim_start_matches('/') },
+        "stageVariables": null,
+        "requestContext": {
+            "accountId": TEST_ACCOUNT_ID,
+            "apiId": "api-id",
+            "httpMethod": method,
+            "identity": {
+                "sourceIp": TEST_SOURCE_IP,
+                "userAgent": TEST_USER_AGENT,
+            },
+            "path": format!("/prod{}", path),
+            "protocol": "HTTP/1.1",
+            "requestId": "test-request-id",
+            "requestTime": "14/Nov/2023:22:13:20 +0000",
+            "requestTimeEpoch": 1_700_000_000_000u64,
+            "resourcePath": "/{proxy+}",
+            "stage": "prod",
+        },
+        "body": body,
+        "isBase64Encoded": is_base64,
+    })
+}
+
+/// Generate a Cloudflare Workers fetch event, as passed by the worker shim
+///
+/// `path` may include a query string and is appended to `https://example.com`.
+/// Bodies that aren't valid UTF-8 are Base64 encoded with `bodyEncoding` set to
+/// `"base64"`.
+///
+/// # Examples
+///
+/// ```
+/// use serverless_rs::testing::cloudflare_fetch_event;
+///
+/// let event = cloudflare_fetch_event("GET", "/search?q=rust", b"");
+/// assert_eq!(event["url"], "https://example.com/search?q=rust");
+/// ```
+pub fn cloudflare_fetch_event(method: &str, path: &str, body: &[u8]) -> Value {
+    let mut headers = event_headers(body);
+    headers.insert("host".to_string(), json!("example.com"));
+    headers.insert("cf-connecting-ip".to_string(), json!(TEST_SOURCE_IP));
+
+    let mut event = json!({
+        "method": method,
+        "url": format!("https://example.com{}", path),
+        "headers": headers,
+    });
+    if !body.is_empty() {
+        let (body, is_base64) = event_body(body);
+        event["body"] = json!(body);
+        event["bodyEncoding"] = json!(if is_base64 { "base64" } else { "utf-8" });
+    }
+    event
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::{Error, Result};
+    use serde_json::json;
+
+    fn greet(req: Request, ctx: &Context) -> Result<Response> {
+        match req.query_param("name") {
+            Some(name) => Ok(Response::text(format!("Hello, {}!", name))
+                .with_header("X-Function", ctx.function_name())),
+            None => Err(Error::http("missing name")),
+        }
+    }
+
+    #[test]
+    fn test_test_request() {
+        let req = TestRequest::put("/items/42?verbose=1&q=a%20b")
+            .path_param("id", "42")
+            .json(&json!({ "name": "widget" }))
+            .to_request();
+
+        assert_eq!(req.method_str().as_deref(), Some("PUT"));
+        assert_eq!(req.path().as_deref(), Some("/items/42"));
+        assert_eq!(req.query_param("verbose"), Some(&"1".to_string()));
+        assert_eq!(req.query_param("q"), Some(&"a b".to_string()));
+        assert_eq!(req.path_param("id"), Some(&"42".to_string()));
+        assert_eq!(
+            req.header("Content-Type"),
+            Some(&"application/json".to_string())
+        );
+        assert_eq!(req.body_string().unwrap(), r#"{"name":"widget"}"#);
+    }
+
+    #[test]
+    fn test_invoke() {
+        let ctx = Context::new().with_function_name("greeter");
+        let resp = invoke_with_context(&greet, TestRequest::get("/?name=Ada"), &ctx);
+        assert_status(&resp, 200);
+        assert_text_body(&resp, "Hello, Ada!");
+        assert_header(&resp, "X-Function", "greeter");
+
+        let resp = invoke(&greet, TestRequest::get("/"));
+        assert_status(&resp, 500);
+        assert_json_body(
+            &resp,
+            &json!({ "type": "http", "message": "missing name", "status": 500 }),
+        );
+    }
+
+    fn sleepy(_req: Request, _ctx: &Context) -> futures::future::BoxFuture<'_, Result<Response>> {
+        Box::pin(async {
+            tokio::time::sleep(std::time::Duration::from_millis(1)).await;
+            let task = tokio::spawn(async { "slept" });
+            Ok(Response::text(task.await.unwrap()))
+        })
+    }
+
+    #[test]
+    fn test_invoke_on_tokio() {
+        let sleepy = crate::handler_fn(sleepy);
+        assert_text_body(&invoke(&sleepy, TestRequest::get("/")), "slept");
+
+        // Also from inside another runtime, as in a `#[tokio::test]`
+        let outer = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        let resp = outer.block_on(async { invoke(&sleepy, TestRequest::get("/")) });
+        assert_text_body(&resp, "slept");
+    }
+
+    #[test]
+    fn test_assert_json_body() {
+        let resp = Response::json(&json!({ "ok": true, "count": 2 })).unwrap();
+        assert_json_body(&resp, &json!({ "count": 2, "ok": true }));
+    }
+
+    #[test]
+    fn test_response_snapshot() {
+        let resp = Response::json(&json!({ "b": [1, 2], "a": "x" }))
+            .unwrap()
+            .with_status(201)
+            .with_header("X-Version", "3")
+            .with_header("Date", "Tue, 01 Jan 2030 00:00:00 GMT");
+        assert_eq!(
+            response_snapshot(&resp),
+            "HTTP 201\ncontent-type: application/json\ndate: [redacted]\nx-version: 3\n\n{\n  \"a\": \"x\",\n  \"b\": [\n    1,\n    2\n  ]\n}\n"
+        );
+
+        let binary = Response::new().with_body(vec![0xff, 0x00]);
+        assert_eq!(response_snapshot(&binary), "HTTP 200\n\n[base64] /wA=\n");
+    }
+
+    #[test]
+    fn test_assert_response_snapshot() {
+        let dir = std::env::temp_dir().join(format!("serverless_rs_snap_{}", std::process::id()));
+        let resp = Response::text("hello");
+
+        // The first run records the snapshot, later runs compare against it
+        assert_snapshot_in(&dir, &resp, "greeting");
+        assert_eq!(
+            std::fs::read_to_string(dir.join("greeting.snap")).unwrap(),
+            response_snapshot(&resp)
+        );
+        assert_snapshot_in(&dir, &resp, "greeting");
+
+        let changed = std::panic::catch_unwind(|| {
+            assert_snapshot_in(&dir, &Response::text("goodbye"), "greeting")
+        });
+        assert!(changed.is_err());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    #[cfg(feature = "aws")]
+    fn test_aws_events() {
+        use crate::platforms::aws::request_from_event;
+
+        let event = aws_api_gateway_v2_event("POST", "/orders?tag=a&tag=b&q=x%20y", b"\xff\x00");
+        assert_eq!(event["isBase64Encoded"], true);
+        let req = request_from_event(&event).unwrap();
+        assert_eq!(req.method_str().as_deref(), Some("POST"));
+        assert_eq!(req.path().as_deref(), Some("/orders"));
+        assert_eq!(req.query_param("q").unwrap(), "x y");
+        assert_eq!(req.decoded_body().unwrap().as_ref(), b"\xff\x00");
+        assert_eq!(event["queryStringParameters"]["tag"], "a,b");
+
+        let event = aws_api_gateway_v1_event("PUT", "/items/1?verbose=1", br#"{"n":1}"#);
+        let req = request_from_event(&event).unwrap();
+        assert_eq!(req.method_str().as_deref(), Some("PUT"));
+        assert_eq!(req.path().as_deref(), Some("/items/1"));
+        assert_eq!(req.query_param("verbose").unwrap(), "1");
+        assert_eq!(req.header("Content-Type").unwrap(), "application/json");
+        assert_eq!(req.body_json::<Value>().unwrap(), json!({ "n": 1 }));
+
+        let event = aws_api_gateway_v1_event("GET", "/", b"");
+        assert!(event["body"].is_null() && event["queryStringParameters"].is_null());
+    }
+
+    #[test]
+    #[cfg(feature = "cloudflare")]
+    fn test_cloudflare_event() {
+        use crate::platforms::cloudflare::request_from_event;
+
+        let event = cloudflare_fetch_event("POST", "/upload?name=a.bin", &[0xff, 0x01]);
+        assert_eq!(event["bodyEncoding"], "base64");
+        let req = request_from_event(&event).unwrap();
+        assert_eq!(req.query_param("name").unwrap(), "a.bin");
+        assert_eq!(req.decoded_body().unwrap().as_ref(), [0xff, 0x01]);
+        assert_eq!(
+            req.header("content-type").unwrap(),
+            "application/octet-stream"
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "unexpected status")]
+    fn test_assert_status_failure() {
+        assert_status(&Response::text("nope").with_status(404), 200);
+    }
+}
@@ -0,0 +1,254 @@
+/*!
+Distributed tracing support for serverless.rs.
+
+This module parses incoming trace context from W3C `traceparent` and AWS
+`X-Amzn-Trace-Id` headers. With the `otel` feature enabled, platform adapters also
+start an OpenTelemetry span per invocation using the globally installed tracer
+provider.
+*/
+
+use crate::error::Result;
+use crate::{Context, Request, Response};
+
+/// Parse a W3C `traceparent` header into its trace ID and parent span ID
+///
+/// The expected format is `00-<32 hex trace id>-<16 hex span id>-<2 hex flags>`.
+/// All-zero IDs are rejected.
+pub(crate) fn parse_traceparent(value: &str) -> Option<(String, String)> {
+    let parts: Vec<&str> = value.trim().split('-').collect();
+    if parts.len() < 4 || parts[0].len() != 2 || parts[0] == "ff" {
+        return None;
+    }
+
+    let (trace_id, span_id) = (parts[1], parts[2]);
+    if !is_hex_id(trace_id, 32) || !is_hex_id(span_id, 16) {
+        return None;
+    }
+
+    Some((trace_id.to_lowercase(), span_id.to_lowercase()))
+}
+
+/// Parse an AWS X-Ray `X-Amzn-Trace-Id` header into a trace ID and parent span ID
+///
+/// The `Root=1-<8 hex>-<24 hex>` field is converted into a 32 character trace ID.
+/// The header must also carry a `Parent` field.
+pub(crate) fn parse_xray(value: &str) -> Option<(String, String)> {
+    let mut trace_id = None;
+    let mut span_id = None;
+
+    for field in value.split(';') {
+        match field.trim().split_once('=') {
+            Some(("Root", root)) => {
+                let mut parts = root.splitn(3, '-');
+                if parts.next() != Some("1") {
+                    return None;
+                }
+                let id: String = parts.collect();
+                trace_id = Some(id);
+            }
+            Some(("Parent", parent)) => span_id = Some(parent.to_string()),
+            _ => {}
+        }
+    }
+
+    let (trace_id, span_id) = (trace_id?, span_id?);
+    if !is_hex_id(&trace_id, 32) || !is_hex_id(&span_id, 16) {
+        return None;
+    }
+
+    Some((trace_id.to_lowercase(), span_id.to_lowercase()))
+}
+
+/// Extract the trace ID and parent span ID from a request's headers
+///
+/// `traceparent` takes precedence over `X-Amzn-Trace-Id`.
+pub(crate) fn trace_from_request(req: &Request) -> Option<(String, String)> {
+    req.header("traceparent")
+        .and_then(|value| parse_traceparent(value))
+        .or_else(|| {
+            req.header("X-Amzn-Trace-Id")
+                .and_then(|value| parse_xray(value))
+        })
+}
+
+fn is_hex_id(id: &str, len: usize) -> bool {
+    id.len() == len && id.chars().all(|c| c.is_ascii_hexdigit()) && id.chars().any(|c| c != '0')
+}
+
+/// Run a handler inside an invocation span
+///
+/// Without the `otel` feature this simply calls the handler.
+#[cfg(not(feature = "otel"))]
+#[cfg_attr(not(any(feature = "aws", feature = "cloudflare")), allow(dead_code))]
+pub(crate) fn traced<F>(req: Request, ctx: &Context, handler: F) -> Result<Response>
+where
+    F: FnOnce(Request, &Context) -> Result<Response>,
+{
+    handler(req, ctx)
+}
+
+/// Run a handler inside an invocation span
+///
+/// The span is a child of the incoming trace context, if any, and records the
+/// function name, HTTP method, path and response status. 5xx responses and errors
+/// with a 5xx [`Error::status`](crate::Error::status) mark the span as failed.
+#[cfg(feature = "otel")]
+#[cfg_attr(not(any(feature = "aws", feature = "cloudflare")), allow(dead_code))]
+pub(crate) fn traced<F>(req: Request, ctx: &Context, handler: F) -> Result<Response>
+where
+    F: FnOnce(Request, &Context) -> Result<Response>,
+{
+    use opentelemetry::trace::{
+        SpanContext, SpanId, SpanKind, Status, TraceContextExt, TraceFlags, TraceId, TraceState,
+        Tracer,
+    };
+    use opentelemetry::KeyValue;
+
+    let mut parent = opentelemetry::Context::new();
+    if let (Some(trace_id), Some(span_id)) = (ctx.trace_id(), ctx.span_id()) {
+        if let (Ok(trace_id), Ok(span_id)) =
+            (TraceId::from_hex(trace_id), SpanId::from_hex(span_id))
+        {
+            parent = parent.with_remote_span_context(SpanContext::new(
+                trace_id,
+                span_id,
+                TraceFlags::SAMPLED,
+                true,
+                TraceState::NONE,
+            ));
+        }
+    }
+
+    let name = if ctx.function_name().is_empty() {
+        "invocation".to_string()
+    } else {
+        ctx.function_name().to_string()
+    };
+    let mut attributes = vec![KeyValue::new("faas.name", name.clone())];
+    if let Some(method) = req.method_str() {
+        attributes.push(KeyValue::new("http.request.method", method));
+    }
+    if let Some(path) = req.path() {
+        attributes.push(KeyValue::new("url.path", path));
+    }
+
+    let tracer = opentelemetry::global::tracer("serverless_rs");
+    let span = tracer
+        .span_builder(name)
+        .with_kind(SpanKind::Server)
+        .with_attributes(attributes)
+        .start_with_context(&tracer, &parent);
+    let otel = parent.with_span(span);
+
+    let mut ctx = ctx.clone().with_otel_context(otel.clone());
+    let span_context = otel.span().span_context().clone();
+    if span_context.is_valid() {
+        ctx = ctx
+            .with_trace_id(span_context.trace_id().to_string())
+            .with_span_id(span_context.span_id().to_string());
+    }
+
+    let result = handler(req, &ctx);
+    let span = otel.span();
+    match &result {
+        Ok(resp) => {
+            span.set_attribute(KeyValue::new(
+                "http.response.status_code",
+                resp.status() as i64,
+            ));
+            if resp.status() >= 500 {
+                span.set_status(Status::error(format!("HTTP {}", resp.status())));
+            }
+        }
+        Err(err) => {
+            span.set_attribute(KeyValue::new(
+                "http.response.status_code",
+                err.status() as i64,
+            ));
+            if err.status() >= 500 {
+                span.set_status(Status::error(err.to_string()));
+            }
+        }
+    }
+    span.end();
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_traceparent() {
+        assert_eq!(
+            parse_traceparent("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01"),
+            Some((
+                "4bf92f3577b34da6a3ce929d0e0e4736".to_string(),
+                "00f067aa0ba902b7".to_string()
+            ))
+        );
+        assert!(
+            parse_traceparent("00-00000000000000000000000000000000-00f067aa0ba902b7-01").is_none()
+        );
+        assert!(parse_traceparent("00-4bf92f3577b34da6-00f067aa0ba902b7-01").is_none());
+        assert!(
+            parse_traceparent("ff-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01").is_none()
+        );
+        assert!(parse_traceparent("garbage").is_none());
+    }
+
+    #[test]
+    fn test_parse_xray() {
+        assert_eq!(
+            parse_xray(
+                "Root=1-5759e988-bd862e3fe1be46a994272793;Parent=53995c3f42cd8ad8;Sampled=1"
+            ),
+            Some((
+                "5759e988bd862e3fe1be46a994272793".to_string(),
+                "53995c3f42cd8ad8".to_string()
+            ))
+        );
+        // A root without a parent has no span to continue from
+        assert!(parse_xray("Root=1-5759e988-bd862e3fe1be46a994272793").is_none());
+        assert!(
+            parse_xray("Root=2-5759e988-bd862e3fe1be46a994272793;Parent=53995c3f42cd8ad8")
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_trace_from_request() {
+        let req = Request::new()
+            .with_header(
+                "X-Amzn-Trace-Id",
+                "Root=1-5759e988-bd862e3fe1be46a994272793;Parent=53995c3f42cd8ad8",
+            )
+            .with_header(
+                "traceparent",
+                "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+            );
+        let (trace_id, _) = trace_from_request(&req).unwrap();
+        assert_eq!(trace_id, "4bf92f3577b34da6a3ce929d0e0e4736");
+
+        assert!(trace_from_request(&Request::new()).is_none());
+    }
+
+    #[test]
+    fn test_traced_keeps_incoming_trace() {
+        let req = Request::new().with_method_str("GET").with_path("/orders");
+        let ctx = Context::new()
+            .with_function_name("orders")
+            .with_trace_id("4bf92f3577b34da6a3ce929d0e0e4736")
+            .with_span_id("00f067aa0ba902b7");
+
+        let resp = traced(req, &ctx, |_req, ctx| {
+            #[cfg(feature = "otel")]
+            ctx.current_span().add_event("handled", vec![]);
+            assert_eq!(ctx.trace_id(), Some("4bf92f3577b34da6a3ce929d0e0e4736"));
+            Ok(Response::text("ok"))
+        })
+        .unwrap();
+        assert_eq!(resp.status(), 200);
+    }
+}
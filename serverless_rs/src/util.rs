@@ -0,0 +1,548 @@
+/*!
+Internal helpers for serverless.rs.
+
+This module contains small utilities shared across the request, response,
+and routing code, such as HTTP date formatting. The HTTP date helpers are also
+re-exported for handlers setting date headers themselves.
+*/
+
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BinaryHeap, HashMap};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Condvar, Mutex, OnceLock, Weak};
+use std::task::{Poll, Waker};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+const DAY_NAMES: [&str; 7] = ["Thu", "Fri", "Sat", "Sun", "Mon", "Tue", "Wed"];
+const MONTH_NAMES: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+/// Format a time as an IMF-fixdate, e.g. `Sun, 06 Nov 1994 08:49:37 GMT`
+///
+/// This is the RFC 7231 format of the `Date`, `Last-Modified` and `Expires` headers.
+/// Times before the Unix epoch are clamped to the epoch.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::{Duration, UNIX_EPOCH};
+///
+/// let time = UNIX_EPOCH + Duration::from_secs(784_111_777);
+/// assert_eq!(serverless_rs::http_date(time), "Sun, 06 Nov 1994 08:49:37 GMT");
+/// ```
+pub fn http_date(time: SystemTime) -> String {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or(Duration::ZERO)
+        .as_secs();
+    let days = secs / 86_400;
+    let seconds_of_day = secs % 86_400;
+    let (year, month, day) = civil_from_days(days as i64);
+
+    format!(
+        "{}, {:02} {} {} {:02}:{:02}:{:02} GMT",
+        DAY_NAMES[(days % 7) as usize],
+        day,
+        MONTH_NAMES[(month - 1) as usize],
+        year,
+        seconds_of_day / 3600,
+        (seconds_of_day % 3600) / 60,
+        seconds_of_day % 60
+    )
+}
+
+/// Parse an IMF-fixdate, e.g. `Sun, 06 Nov 1994 08:49:37 GMT`
+///
+/// Use this for headers such as `If-Modified-Since`. Returns `None` if the value
+/// is not a valid IMF-fixdate.
+pub fn parse_http_date(value: &str) -> Option<SystemTime> {
+    let (_, rest) = value.trim().split_once(", ")?;
+    let mut parts = rest.split(' ');
+    let day: u32 = parts.next()?.parse().ok()?;
+    let month_name = parts.next()?;
+    let month = MONTH_NAMES.iter().position(|m| *m == month_name)? as u32 + 1;
+    let year: i64 = parts.next()?.parse().ok()?;
+    let time = parts.next()?;
+    if parts.next()? != "GMT" || parts.next().is_some() {
+        return None;
+    }
+
+    let mut time_parts = time.split(':').map(|p| p.parse::<u64>().ok());
+    let hours = time_parts.next()??;
+    let minutes = time_parts.next()??;
+    let seconds = time_parts.next()??;
+    if time_parts.next().is_some() || hours > 23 || minutes > 59 || seconds > 60 {
+        return None;
+    }
+    if day < 1 || day > days_in_month(year, month) || year < 1970 {
+        return None;
+    }
+
+    let days = days_from_civil(year, month, day);
+    let secs = days as u64 * 86_400 + hours * 3600 + minutes * 60 + seconds;
+    Some(UNIX_EPOCH + Duration::from_secs(secs))
+}
+
+/// Returns the number of days in a month of the proleptic Gregorian calendar
+fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Parse a URL query string into a map, percent-decoding keys and values
+///
+/// `+` is decoded as a space. When a key repeats, the last value wins.
+pub(crate) fn parse_query_string(query: &str) -> HashMap<String, String> {
+    query
+        .trim_start_matches('?')
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (percent_decode(key), percent_decode(value))
+        })
+        .collect()
+}
+
+/// Decode a percent-encoded query component, treating `+` as a space
+///
+/// Invalid escapes are kept as-is and invalid UTF-8 is replaced.
+pub(crate) fn percent_decode(value: &str) -> String {
+    decode(value, true)
+}
+
+/// Decode a percent-encoded path segment, keeping `+` as-is
+pub(crate) fn percent_decode_path(value: &str) -> String {
+    decode(value, false)
+}
+
+/// Decode percent escapes, optionally treating `+` as a space
+fn decode(value: &str, plus_as_space: bool) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' if plus_as_space => decoded.push(b' '),
+            b'%' if i + 2 < bytes.len() => {
+                let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).ok();
+                match hex.and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+                    Some(byte) => {
+                        decoded.push(byte);
+                        i += 2;
+                    }
+                    None => decoded.push(b'%'),
+                }
+            }
+            byte => decoded.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Parse a duration such as `30s`, `500ms`, `2m` or `1h`
+///
+/// A bare number is taken as seconds.
+pub(crate) fn parse_duration(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    let split = value
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number: f64 = number.parse().ok()?;
+    let seconds = match unit.trim() {
+        "ms" => number / 1000.0,
+        "" | "s" => number,
+        "m" => number * 60.0,
+        "h" => number * 3600.0,
+        _ => return None,
+    };
+    Duration::try_from_secs_f64(seconds).ok()
+}
+
+/// Parse a size such as `512MB`, `2GB` or `10240` into megabytes
+///
+/// A bare number is taken as megabytes. Units are case-insensitive, and `GB` is
+/// 1024 MB.
+pub(crate) fn parse_size_mb(value: &str) -> Option<u64> {
+    let value = value.trim();
+    let split = value
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number: f64 = number.parse().ok()?;
+    let megabytes = match unit.trim().to_ascii_lowercase().as_str() {
+        "" | "m" | "mb" | "mib" => number,
+        "g" | "gb" | "gib" => number * 1024.0,
+        _ => return None,
+    };
+    (megabytes.fract() == 0.0).then_some(megabytes as u64)
+}
+
+/// Convert days since the Unix epoch to a (year, month, day) civil date
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// Convert a (year, month, day) civil date to days since the Unix epoch
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let month = month as i64;
+    let doy = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+/// Whether the target lacks a system clock and threads
+///
+/// On `wasm32-unknown-unknown`, reading the clock or spawning a thread panics, so the
+/// crate goes through [`now`], [`stopwatch`] and [`sleep`], which check this first.
+const NO_SYSTEM: bool = cfg!(all(target_arch = "wasm32", target_os = "unknown"));
+
+/// Returns the current time, or `None` on targets without a system clock
+pub(crate) fn now() -> Option<SystemTime> {
+    (!NO_SYSTEM).then(SystemTime::now)
+}
+
+/// Returns an instant to measure elapsed time from, or `None` without a clock
+pub(crate) fn stopwatch() -> Option<Instant> {
+    (!NO_SYSTEM).then(Instant::now)
+}
+
+/// A future that completes once `duration` has passed
+///
+/// The crate doesn't depend on an async runtime, so timers are served by a single
+/// background thread shared by the whole process, started the first time a timer is
+/// polled, which wakes each task when its time is up. Without a clock, as on
+/// `wasm32-unknown-unknown`, the future never completes.
+pub(crate) fn sleep(duration: Duration) -> Sleep {
+    Sleep {
+        deadline: stopwatch().map(|start| start + duration),
+        timer: None,
+    }
+}
+
+/// Future returned by [`sleep`]
+pub(crate) struct Sleep {
+    deadline: Option<Instant>,
+    timer: Option<Arc<Mutex<TimerState>>>,
+}
+
+#[derive(Default)]
+struct TimerState {
+    elapsed: bool,
+    waker: Option<Waker>,
+    callback: Option<Box<dyn FnOnce() + Send>>,
+}
+
+/// Run `callback` on the shared timer thread once `deadline` has passed
+///
+/// The callback is cancelled if the returned [`Timer`] is dropped first. It runs on
+/// the thread that serves every timer in the process, so it should be quick.
+pub(crate) fn call_at<F>(deadline: Instant, callback: F) -> Timer
+where
+    F: FnOnce() + Send + 'static,
+{
+    let state = Arc::new(Mutex::new(TimerState {
+        callback: Some(Box::new(callback)),
+        ..TimerState::default()
+    }));
+    timers().schedule(deadline, &state);
+    Timer { _state: state }
+}
+
+/// A callback scheduled with [`call_at`], cancelled when dropped
+pub(crate) struct Timer {
+    _state: Arc<Mutex<TimerState>>,
+}
+
+impl std::fmt::Debug for Timer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Timer").finish_non_exhaustive()
+    }
+}
+
+impl Future for Sleep {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut std::task::Context<'_>) -> Poll<()> {
+        let Some(deadline) = self.deadline else {
+            return Poll::Pending;
+        };
+        if Instant::now() >= deadline {
+            return Poll::Ready(());
+        }
+
+        let timer = self.timer.get_or_insert_with(|| {
+            let timer = Arc::new(Mutex::new(TimerState::default()));
+            timers().schedule(deadline, &timer);
+            timer
+        });
+
+        match timer.lock() {
+            Ok(state) if state.elapsed => Poll::Ready(()),
+            Ok(mut state) => {
+                state.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+            Err(_) => Poll::Ready(()),
+        }
+    }
+}
+
+/// A timer waiting in the [`TimerQueue`], ordered by deadline
+///
+/// The queue only holds a weak reference, so a [`Sleep`] dropped before its deadline
+/// frees its state straight away.
+struct QueuedTimer {
+    deadline: Instant,
+    state: Weak<Mutex<TimerState>>,
+}
+
+impl PartialEq for QueuedTimer {
+    fn eq(&self, other: &Self) -> bool {
+        self.deadline == other.deadline
+    }
+}
+
+impl Eq for QueuedTimer {}
+
+impl PartialOrd for QueuedTimer {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for QueuedTimer {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.deadline.cmp(&other.deadline)
+    }
+}
+
+/// Pending timers, earliest deadline first, and the thread that fires them
+#[derive(Default)]
+struct TimerQueue {
+    pending: Mutex<BinaryHeap<Reverse<QueuedTimer>>>,
+    changed: Condvar,
+}
+
+impl TimerQueue {
+    fn schedule(&self, deadline: Instant, state: &Arc<Mutex<TimerState>>) {
+        let mut pending = self.pending.lock().unwrap_or_else(|e| e.into_inner());
+        pending.push(Reverse(QueuedTimer {
+            deadline,
+            state: Arc::downgrade(state),
+        }));
+        self.changed.notify_one();
+    }
+
+    /// Fire timers as their deadlines pass, sleeping until the next one is due
+    ///
+    /// Timers fire with the queue unlocked, so a callback can schedule another timer.
+    fn run(&self) {
+        let mut pending = self.pending.lock().unwrap_or_else(|e| e.into_inner());
+        loop {
+            let now = Instant::now();
+            let mut due = Vec::new();
+            while pending
+                .peek()
+                .is_some_and(|Reverse(timer)| timer.deadline <= now)
+            {
+                if let Some(Reverse(timer)) = pending.pop() {
+                    due.push(timer);
+                }
+            }
+            if !due.is_empty() {
+                drop(pending);
+                for timer in due {
+                    fire(&timer.state);
+                }
+                pending = self.pending.lock().unwrap_or_else(|e| e.into_inner());
+                continue;
+            }
+
+            pending = match pending.peek() {
+                Some(Reverse(next)) => {
+                    let wait = next.deadline.saturating_duration_since(now);
+                    self.changed
+                        .wait_timeout(pending, wait)
+                        .unwrap_or_else(|e| e.into_inner())
+                        .0
+                }
+                None => self
+                    .changed
+                    .wait(pending)
+                    .unwrap_or_else(|e| e.into_inner()),
+            };
+        }
+    }
+}
+
+fn fire(state: &Weak<Mutex<TimerState>>) {
+    let Some(state) = state.upgrade() else {
+        return;
+    };
+    let (waker, callback) = match state.lock() {
+        Ok(mut state) => {
+            state.elapsed = true;
+            (state.waker.take(), state.callback.take())
+        }
+        Err(_) => (None, None),
+    };
+    if let Some(waker) = waker {
+        waker.wake();
+    }
+    if let Some(callback) = callback {
+        // A panicking callback mustn't take the timer thread down with it
+        let _ = std::panic::catch_unwind(std::panic::AssertUnwindSafe(callback));
+    }
+}
+
+/// The process-wide timer queue, starting its thread on first use
+fn timers() -> &'static TimerQueue {
+    static TIMERS: OnceLock<TimerQueue> = OnceLock::new();
+    TIMERS.get_or_init(|| {
+        // The thread waits for this initialization to finish before using the queue
+        std::thread::Builder::new()
+            .name("serverless-rs-timer".to_string())
+            .spawn(|| timers().run())
+            .expect("failed to start the timer thread");
+        TimerQueue::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("30s"), Some(Duration::from_secs(30)));
+        assert_eq!(parse_duration("30"), Some(Duration::from_secs(30)));
+        assert_eq!(parse_duration("500ms"), Some(Duration::from_millis(500)));
+        assert_eq!(parse_duration("1.5m"), Some(Duration::from_secs(90)));
+        assert_eq!(parse_duration("2h"), Some(Duration::from_secs(7200)));
+        assert_eq!(parse_duration("soon"), None);
+        assert_eq!(parse_duration("10d"), None);
+        assert_eq!(parse_duration(""), None);
+    }
+
+    #[test]
+    fn test_parse_size_mb() {
+        assert_eq!(parse_size_mb("512MB"), Some(512));
+        assert_eq!(parse_size_mb("512"), Some(512));
+        assert_eq!(parse_size_mb("2GB"), Some(2048));
+        assert_eq!(parse_size_mb("1.5 gb"), Some(1536));
+        assert_eq!(parse_size_mb("0.3MB"), None);
+        assert_eq!(parse_size_mb("10TB"), None);
+        assert_eq!(parse_size_mb("big"), None);
+    }
+
+    #[test]
+    fn test_http_date_round_trip() {
+        let time = UNIX_EPOCH + Duration::from_secs(784_111_777);
+        assert_eq!(http_date(time), "Sun, 06 Nov 1994 08:49:37 GMT");
+        assert_eq!(parse_http_date("Sun, 06 Nov 1994 08:49:37 GMT"), Some(time));
+
+        assert_eq!(http_date(UNIX_EPOCH), "Thu, 01 Jan 1970 00:00:00 GMT");
+        let leap_day = parse_http_date("Thu, 29 Feb 2024 12:00:00 GMT").unwrap();
+        assert_eq!(http_date(leap_day), "Thu, 29 Feb 2024 12:00:00 GMT");
+
+        // Days past the end of the month are rejected rather than rolled over
+        assert_eq!(parse_http_date("Sat, 31 Feb 2024 12:00:00 GMT"), None);
+        assert_eq!(parse_http_date("Sun, 29 Feb 2023 12:00:00 GMT"), None);
+        assert_eq!(parse_http_date("Mon, 29 Feb 2100 12:00:00 GMT"), None);
+        assert_eq!(parse_http_date("Mon, 31 Apr 2024 12:00:00 GMT"), None);
+        assert!(parse_http_date("Tue, 29 Feb 2000 12:00:00 GMT").is_some());
+        assert!(parse_http_date("Wed, 31 Dec 2025 23:59:59 GMT").is_some());
+    }
+
+    #[test]
+    fn test_sleep() {
+        let start = Instant::now();
+        futures::executor::block_on(async {
+            // A timer dropped before its deadline doesn't hold up later ones
+            drop(
+                futures::future::select(
+                    Box::pin(sleep(Duration::from_secs(60))),
+                    Box::pin(sleep(Duration::from_millis(5))),
+                )
+                .await,
+            );
+            futures::future::join(
+                sleep(Duration::from_millis(30)),
+                sleep(Duration::from_millis(10)),
+            )
+            .await;
+        });
+        let elapsed = start.elapsed();
+        assert!(elapsed >= Duration::from_millis(30), "{:?}", elapsed);
+        assert!(elapsed < Duration::from_secs(5), "{:?}", elapsed);
+    }
+
+    #[test]
+    fn test_call_at() {
+        use std::sync::mpsc;
+
+        // A panicking callback leaves the timer thread running for later ones
+        let _panics = call_at(Instant::now(), || panic!("callback failed"));
+
+        // A callback can schedule another one without deadlocking the queue
+        let (fired, receiver) = mpsc::channel();
+        let _outer = call_at(Instant::now() + Duration::from_millis(5), move || {
+            let timer = call_at(Instant::now(), move || fired.send("inner").unwrap());
+            std::mem::forget(timer);
+        });
+        assert_eq!(receiver.recv_timeout(Duration::from_secs(5)), Ok("inner"));
+
+        // Dropping the timer cancels the callback
+        let (fired, receiver) = mpsc::channel::<()>();
+        drop(call_at(
+            Instant::now() + Duration::from_millis(20),
+            move || fired.send(()).unwrap(),
+        ));
+        assert!(receiver.recv_timeout(Duration::from_millis(100)).is_err());
+    }
+
+    #[test]
+    fn test_parse_query_string() {
+        let query = parse_query_string("?name=Jane+Doe&tag=a%26b&empty=&flag");
+        assert_eq!(query.get("name"), Some(&"Jane Doe".to_string()));
+        assert_eq!(query.get("tag"), Some(&"a&b".to_string()));
+        assert_eq!(query.get("empty"), Some(&String::new()));
+        assert_eq!(query.get("flag"), Some(&String::new()));
+        assert!(parse_query_string("").is_empty());
+
+        assert_eq!(percent_decode("100%"), "100%");
+        assert_eq!(percent_decode("%zz"), "%zz");
+        assert_eq!(percent_decode("caf%C3%A9"), "café");
+        assert_eq!(percent_decode_path("a+b%20c"), "a+b c");
+    }
+
+    #[test]
+    fn test_parse_invalid_http_date() {
+        assert!(parse_http_date("").is_none());
+        assert!(parse_http_date("Sunday, 06-Nov-94 08:49:37 GMT").is_none());
+        assert!(parse_http_date("Sun, 06 Nov 1994 08:49:37 UTC").is_none());
+        assert!(parse_http_date("Sun, 06 Foo 1994 08:49:37 GMT").is_none());
+        assert!(parse_http_date("Sun, 06 Nov 1994 25:49:37 GMT").is_none());
+    }
+}
@@ -10,9 +10,10 @@ mod tests {
     #[test]
     fn test_macro_compilation() {
         // Test that the macros compile and work correctly
-        // The actual functionality is tested via the examples
+        // The actual functionality is tested via the trybuild tests in the macros crate
         // This is just to ensure the macros are properly re-exported
-        assert!(true);
+        #[allow(unused_imports)]
+        use serverless_rs::{requirements, route, serverless};
     }
 
     #[test]